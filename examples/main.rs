@@ -12,7 +12,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let tx_manager = Arc::new(TransactionManager::new(Arc::clone(&storage)));
     let wallet = WalletManager::new(Arc::clone(&storage), tx_manager)?;
 
-    println!("Wallet address: {}", wallet.get_address());
+    let (address, _) = wallet.get_address()?;
+    println!("Wallet address: {}", address);
 
     Ok(())
 }
@@ -0,0 +1,63 @@
+use bincode;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::errors::ZipError;
+use crate::storage::ZipStorage;
+
+/// Wallet event the user should be told about (e.g., a double-spent payment).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WalletAlert {
+    pub message: String,
+    pub txid: Option<String>,
+    /// Unix timestamp (seconds) when the alert was raised.
+    pub created_at: i64,
+}
+
+/// Persistent per-user alert queue, drained by the UI.
+#[derive(Clone)]
+pub struct Alerts {
+    storage: Arc<ZipStorage>,
+}
+
+impl Alerts {
+    /// Initializes alert queue with storage.
+    pub fn new(storage: Arc<ZipStorage>) -> Self {
+        Self { storage }
+    }
+
+    fn load(&self, user_id: Uuid) -> Result<Vec<WalletAlert>, ZipError> {
+        let data = self.storage.get_alerts(user_id)?;
+        Ok(data
+            .map(|d| bincode::deserialize(&d).unwrap_or_default())
+            .unwrap_or_default())
+    }
+
+    /// Queues an alert for the user.
+    pub fn push(
+        &self,
+        user_id: Uuid,
+        message: String,
+        txid: Option<String>,
+    ) -> Result<(), ZipError> {
+        let mut alerts = self.load(user_id)?;
+        alerts.push(WalletAlert {
+            message,
+            txid,
+            created_at: chrono::Utc::now().timestamp(),
+        });
+        let serialized =
+            bincode::serialize(&alerts).map_err(|e| ZipError::Blockchain(e.to_string()))?;
+        self.storage.store_alerts(user_id, &serialized)
+    }
+
+    /// Returns and clears all pending alerts for the user.
+    pub fn take(&self, user_id: Uuid) -> Result<Vec<WalletAlert>, ZipError> {
+        let alerts = self.load(user_id)?;
+        if !alerts.is_empty() {
+            self.storage.remove_alerts(user_id)?;
+        }
+        Ok(alerts)
+    }
+}
@@ -0,0 +1,342 @@
+use bincode;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::errors::ZipError;
+use crate::storage::{AliasClaim, ZipStorage};
+use crate::utils::validation::{PrefixViolation, Validation};
+
+/// One change of an alias's owner; `None` means the alias was unowned.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AliasOwnership {
+    pub from: Option<Uuid>,
+    pub to: Option<Uuid>,
+    /// Unix timestamp (seconds) of the change.
+    pub timestamp: i64,
+}
+
+/// Seconds a reserved alias waits for its payment before it is released.
+pub const RESERVATION_TIMEOUT_SECS: i64 = 900;
+
+/// Stage of a paid alias purchase.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum AliasStatus {
+    /// Held for the buyer; nobody else can claim it until the reservation times out.
+    Reserved,
+    /// Paying transaction matched, waiting for the network to accept it.
+    PaymentPending { txid: String },
+    /// Paid and usable.
+    Active { txid: String },
+    /// Reservation lapsed unpaid, or its payment was never accepted; the alias went
+    /// back to the pool.
+    Expired,
+}
+
+/// Purchase of a priced alias, paid to the treasury PayMail.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AliasPurchase {
+    pub alias: String,
+    pub owner: Uuid,
+    /// Pushed in the payment's OP_RETURN output so the transaction matches this purchase.
+    pub reference: String,
+    /// Fiat price at reservation.
+    pub price: Decimal,
+    /// Satoshis due, fixed when payment starts.
+    pub satoshis: u64,
+    /// Treasury output the payment must pay.
+    pub payment_script: Vec<u8>,
+    pub status: AliasStatus,
+    /// Unix timestamps (seconds).
+    pub reserved_at: i64,
+    pub updated_at: i64,
+}
+
+impl AliasPurchase {
+    /// When the purchase lapses unless it moves on: a reservation waits
+    /// `RESERVATION_TIMEOUT_SECS` for its payment, and a matched payment as long again
+    /// for the network to accept it.
+    pub fn deadline(&self) -> Option<i64> {
+        match self.status {
+            AliasStatus::Reserved => Some(self.reserved_at + RESERVATION_TIMEOUT_SECS),
+            AliasStatus::PaymentPending { .. } => Some(self.updated_at + RESERVATION_TIMEOUT_SECS),
+            AliasStatus::Active { .. } | AliasStatus::Expired => None,
+        }
+    }
+
+    /// True when a reservation or its payment has outlived its deadline.
+    pub fn is_lapsed_at(&self, now: i64) -> bool {
+        self.deadline().is_some_and(|deadline| now >= deadline)
+    }
+}
+
+/// Seconds a transfer offer waits for the other side before it lapses.
+pub const TRANSFER_TIMEOUT_SECS: i64 = 7 * 86_400;
+
+/// Seconds a renewal payment waits for the network to accept it before it is dropped.
+pub const RENEWAL_TIMEOUT_SECS: i64 = RESERVATION_TIMEOUT_SECS;
+
+/// Paid term of a premium alias that must be renewed to be kept.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AliasLease {
+    pub alias: String,
+    pub owner: Uuid,
+    /// Unix timestamp (seconds) the paid term ends; the grace period follows.
+    pub paid_until: i64,
+    /// Renewal payments, oldest first.
+    pub renewals: Vec<String>,
+    /// Renewal paid but not yet accepted by the network.
+    pub pending_renewal: Option<String>,
+    /// Unix timestamp (seconds) the pending renewal was paid.
+    pub pending_since: i64,
+    /// Set once the owner has been warned that the term ended.
+    pub grace_notified: bool,
+}
+
+/// `AliasLease` as stored before pending renewals timed out.
+#[derive(Deserialize)]
+struct AliasLeaseV1 {
+    alias: String,
+    owner: Uuid,
+    paid_until: i64,
+    renewals: Vec<String>,
+    pending_renewal: Option<String>,
+    grace_notified: bool,
+}
+
+impl From<AliasLeaseV1> for AliasLease {
+    fn from(v1: AliasLeaseV1) -> Self {
+        Self {
+            alias: v1.alias,
+            owner: v1.owner,
+            paid_until: v1.paid_until,
+            renewals: v1.renewals,
+            pending_renewal: v1.pending_renewal,
+            // Unknown; the renewal gets a full timeout from the first sweep after upgrading
+            pending_since: chrono::Utc::now().timestamp(),
+            grace_notified: v1.grace_notified,
+        }
+    }
+}
+
+impl AliasLease {
+    /// Decodes a stored lease, migrating the format without `pending_since`.
+    pub fn decode(bytes: &[u8]) -> Result<Self, ZipError> {
+        bincode::deserialize(bytes)
+            .or_else(|_| bincode::deserialize::<AliasLeaseV1>(bytes).map(Self::from))
+            .map_err(|e| ZipError::Blockchain(e.to_string()))
+    }
+
+    /// True while a renewal payment is still within `RENEWAL_TIMEOUT_SECS` of being
+    /// paid; the alias is not released under it.
+    pub fn renewal_pending_at(&self, now: i64) -> bool {
+        self.pending_renewal.is_some() && now < self.pending_since + RENEWAL_TIMEOUT_SECS
+    }
+
+    /// True between the end of the paid term and the end of the grace period.
+    pub fn in_grace_at(&self, now: i64, grace: i64) -> bool {
+        now >= self.paid_until && now < self.paid_until + grace
+    }
+
+    /// True once the grace period is over and the alias goes back to the pool.
+    pub fn is_lapsed_at(&self, now: i64, grace: i64) -> bool {
+        now >= self.paid_until + grace
+    }
+}
+
+/// Offer to move an alias between users; it completes once both sides confirm.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AliasTransfer {
+    pub alias: String,
+    pub from: Uuid,
+    pub to: Uuid,
+    pub from_confirmed: bool,
+    pub to_confirmed: bool,
+    /// Unix timestamps (seconds).
+    pub created_at: i64,
+    pub expires_at: i64,
+}
+
+impl AliasTransfer {
+    /// True once both sides have confirmed.
+    pub fn is_confirmed(&self) -> bool {
+        self.from_confirmed && self.to_confirmed
+    }
+
+    /// Records a party's confirmation; anyone else is rejected.
+    pub fn confirm(&mut self, user_id: Uuid) -> Result<(), ZipError> {
+        if user_id == self.from {
+            self.from_confirmed = true;
+        } else if user_id == self.to {
+            self.to_confirmed = true;
+        } else {
+            return Err(ZipError::Validation(format!(
+                "Not a party to the transfer of {}",
+                self.alias
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Global registry of issued PayMail aliases. Every alias has at most one owner, and
+/// each ownership change is applied atomically with its history entry.
+#[derive(Clone)]
+pub struct AliasRegistry {
+    storage: Arc<ZipStorage>,
+}
+
+impl AliasRegistry {
+    /// Initializes the registry with storage.
+    pub fn new(storage: Arc<ZipStorage>) -> Self {
+        Self { storage }
+    }
+
+    /// User owning an alias (e.g., "101@zip.io"), if any.
+    pub fn owner(&self, alias: &str) -> Result<Option<Uuid>, ZipError> {
+        Ok(self
+            .storage
+            .get_alias_owner(&normalize(alias))?
+            .and_then(|id| Uuid::from_slice(&id).ok()))
+    }
+
+    /// Aliases a user owns, sorted.
+    pub fn aliases(&self, user_id: Uuid) -> Result<Vec<String>, ZipError> {
+        let mut aliases = self.storage.get_user_alias_list(user_id)?;
+        aliases.sort();
+        Ok(aliases)
+    }
+
+    /// Ownership changes of an alias, oldest first.
+    pub fn history(&self, alias: &str) -> Result<Vec<AliasOwnership>, ZipError> {
+        Ok(self
+            .storage
+            .get_alias_history(&normalize(alias))?
+            .iter()
+            .filter_map(|event| bincode::deserialize(event).ok())
+            .collect())
+    }
+
+    /// Serialized history entry of an ownership change made now.
+    fn event(from: Option<Uuid>, to: Option<Uuid>) -> Result<Vec<u8>, ZipError> {
+        let event = AliasOwnership {
+            from,
+            to,
+            timestamp: chrono::Utc::now().timestamp(),
+        };
+        bincode::serialize(&event).map_err(|e| ZipError::Blockchain(e.to_string()))
+    }
+
+    /// Moves an alias between owners if `from` still owns it.
+    fn swap(&self, alias: &str, from: Option<Uuid>, to: Option<Uuid>) -> Result<bool, ZipError> {
+        self.storage
+            .swap_alias_owner(alias, from, to, &Self::event(from, to)?)
+    }
+
+    /// Alias on the same domain that reads the same as `alias` (e.g., "j0hn@zip.io" for
+    /// "john@zip.io"), or `alias` itself, owned by anyone but `user_id`.
+    pub fn lookalike(
+        &self,
+        alias: &str,
+        user_id: Option<Uuid>,
+    ) -> Result<Option<String>, ZipError> {
+        self.index_skeletons()?;
+        let skeleton = skeleton(&normalize(alias));
+        for existing in self.storage.get_alias_skeleton(&skeleton)? {
+            let owner = self.owner(&existing)?;
+            if owner.is_some() && owner != user_id {
+                return Ok(Some(existing));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Claims an unowned alias for a user unless another user owns one on the same
+    /// domain that reads the same (e.g., "j0hn@zip.io" for "john@zip.io"); both are
+    /// checked in one transaction.
+    fn claim(&self, alias: &str, user_id: Uuid) -> Result<AliasClaim, ZipError> {
+        self.index_skeletons()?;
+        self.storage.claim_alias(
+            alias,
+            &skeleton(alias),
+            user_id,
+            &Self::event(None, Some(user_id))?,
+        )
+    }
+
+    /// Adds aliases owned before the skeleton index existed to it, once.
+    fn index_skeletons(&self) -> Result<(), ZipError> {
+        if self.storage.alias_skeletons_indexed()? {
+            return Ok(());
+        }
+        for alias in self.storage.get_owned_aliases()? {
+            self.storage
+                .index_alias_skeleton(&alias, &skeleton(&alias))?;
+        }
+        self.storage.set_alias_skeletons_indexed()
+    }
+
+    /// Registers an unowned alias to a user. Registering an alias the user already owns
+    /// is a no-op; an alias owned by anyone else, or that looks like one, is rejected.
+    pub fn register(&self, alias: &str, user_id: Uuid) -> Result<String, ZipError> {
+        let alias = normalize(alias);
+        if self.owner(&alias)? == Some(user_id) {
+            return Ok(alias);
+        }
+        match self.claim(&alias, user_id)? {
+            AliasClaim::Claimed => Ok(alias),
+            AliasClaim::Lookalike(existing) => Err(PrefixViolation::Confusable { existing }.into()),
+            AliasClaim::Taken => Err(ZipError::Validation(format!("{} is already taken", alias))),
+        }
+    }
+
+    /// Returns an alias a user owns to the pool.
+    pub fn release(&self, alias: &str, user_id: Uuid) -> Result<(), ZipError> {
+        let alias = normalize(alias);
+        if self.swap(&alias, Some(user_id), None)? {
+            return Ok(());
+        }
+        Err(ZipError::Validation(format!(
+            "{} is not yours to release",
+            alias
+        )))
+    }
+
+    /// Moves an alias `from` owns to another user.
+    pub fn transfer(&self, alias: &str, from: Uuid, to: Uuid) -> Result<(), ZipError> {
+        let alias = normalize(alias);
+        if self.swap(&alias, Some(from), Some(to))? {
+            return Ok(());
+        }
+        Err(ZipError::Validation(format!(
+            "{} is not yours to transfer",
+            alias
+        )))
+    }
+
+    /// Allocates the next free sequential alias (101, 102, ...) under `domain`, skipping
+    /// numbers already registered as bespoke aliases or taken by look-alikes.
+    pub fn allocate(&self, domain: &str, user_id: Uuid) -> Result<String, ZipError> {
+        loop {
+            let alias = normalize(&format!("{}@{}", self.storage.next_alias_number()?, domain));
+            if self.claim(&alias, user_id)? == AliasClaim::Claimed {
+                return Ok(alias);
+            }
+        }
+    }
+}
+
+/// Aliases are case-insensitive; they are stored lowercased.
+fn normalize(alias: &str) -> String {
+    alias.trim().to_lowercase()
+}
+
+/// Skeleton index key of an alias: its prefix with look-alike characters folded, and
+/// its domain.
+fn skeleton(alias: &str) -> String {
+    match alias.rsplit_once('@') {
+        Some((prefix, domain)) => format!("{}@{}", Validation::confusable_skeleton(prefix), domain),
+        None => Validation::confusable_skeleton(alias),
+    }
+}
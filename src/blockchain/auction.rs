@@ -0,0 +1,176 @@
+use bincode;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::errors::ZipError;
+use crate::storage::ZipStorage;
+
+/// Bid on a premium alias. Its satoshis stay in the bidder's wallet but are held
+/// under `escrow` until the bid is outbid, or, for a winning bid, until it pays for
+/// the alias or the purchase lapses.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Bid {
+    pub bidder: Uuid,
+    pub satoshis: u64,
+    /// Escrow ID holding the bid's coins.
+    pub escrow: String,
+    /// Unix timestamp (seconds).
+    pub placed_at: i64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum AuctionStatus {
+    Open,
+    /// Sold to the highest bidder, who pays through the alias purchase flow.
+    Settled {
+        winner: Uuid,
+        satoshis: u64,
+    },
+    /// Ended without a bid meeting the reserve.
+    Unsold,
+}
+
+/// Auction of a premium alias.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Auction {
+    pub id: Uuid,
+    pub alias: String,
+    /// Lowest opening bid in satoshis.
+    pub reserve: u64,
+    /// Unix timestamps (seconds); `ends_at` moves when a late bid extends the auction.
+    pub opened_at: i64,
+    pub ends_at: i64,
+    /// Accepted bids, lowest first; the last one leads.
+    pub bids: Vec<Bid>,
+    pub status: AuctionStatus,
+}
+
+impl Auction {
+    /// Opens an auction for `alias` running `duration` seconds from `now`.
+    pub fn new(alias: &str, reserve: u64, now: i64, duration: i64) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            alias: alias.to_lowercase(),
+            reserve,
+            opened_at: now,
+            ends_at: now + duration,
+            bids: vec![],
+            status: AuctionStatus::Open,
+        }
+    }
+
+    /// Highest bid so far.
+    pub fn leading(&self) -> Option<&Bid> {
+        self.bids.last()
+    }
+
+    /// True once the leading bid would win.
+    pub fn reserve_met(&self) -> bool {
+        self.leading()
+            .is_some_and(|bid| bid.satoshis >= self.reserve)
+    }
+
+    /// Smallest acceptable next bid: the leading bid raised by `increment_percent`
+    /// (at least one satoshi), or the reserve for the first bid.
+    pub fn minimum_bid(&self, increment_percent: u64) -> u64 {
+        match self.leading() {
+            Some(bid) => {
+                bid.satoshis + (bid.satoshis.saturating_mul(increment_percent) / 100).max(1)
+            }
+            None => self.reserve.max(1),
+        }
+    }
+
+    /// True once bidding has closed.
+    pub fn is_over_at(&self, now: i64) -> bool {
+        self.status != AuctionStatus::Open || now >= self.ends_at
+    }
+
+    /// Accepts a bid and returns the bid it displaced, whose escrow must be released.
+    /// Bids within `extension` seconds of the end push the end back to `extension`
+    /// seconds after the bid.
+    pub fn place_bid(
+        &mut self,
+        bid: Bid,
+        increment_percent: u64,
+        extension: i64,
+    ) -> Result<Option<Bid>, ZipError> {
+        if self.is_over_at(bid.placed_at) {
+            return Err(ZipError::Validation(format!(
+                "Auction for {} has ended",
+                self.alias
+            )));
+        }
+        let minimum = self.minimum_bid(increment_percent);
+        if bid.satoshis < minimum {
+            return Err(ZipError::Validation(format!(
+                "Bid must be at least {} satoshis",
+                minimum
+            )));
+        }
+        if self.ends_at - bid.placed_at < extension {
+            self.ends_at = bid.placed_at + extension;
+        }
+        let displaced = self.leading().cloned();
+        self.bids.push(bid);
+        Ok(displaced)
+    }
+
+    /// Closes an ended auction: the leading bid wins if it meets the reserve.
+    /// Returns the new status, or `None` while bidding is still open.
+    pub fn settle(&mut self, now: i64) -> Option<AuctionStatus> {
+        if self.status != AuctionStatus::Open || now < self.ends_at {
+            return None;
+        }
+        self.status = match self.leading() {
+            Some(bid) if self.reserve_met() => AuctionStatus::Settled {
+                winner: bid.bidder,
+                satoshis: bid.satoshis,
+            },
+            _ => AuctionStatus::Unsold,
+        };
+        Some(self.status.clone())
+    }
+}
+
+/// Auctions by alias; an alias has at most one auction on record, the latest.
+#[derive(Clone)]
+pub struct Auctions {
+    storage: Arc<ZipStorage>,
+}
+
+impl Auctions {
+    /// Initializes the auction book with storage.
+    pub fn new(storage: Arc<ZipStorage>) -> Self {
+        Self { storage }
+    }
+
+    /// Latest auction of an alias.
+    pub fn get(&self, alias: &str) -> Result<Option<Auction>, ZipError> {
+        Ok(self
+            .storage
+            .get_auction(alias)?
+            .and_then(|d| bincode::deserialize(&d).ok()))
+    }
+
+    /// Saves an auction.
+    pub fn store(&self, auction: &Auction) -> Result<(), ZipError> {
+        let serialized =
+            bincode::serialize(auction).map_err(|e| ZipError::Blockchain(e.to_string()))?;
+        self.storage.store_auction(&auction.alias, &serialized)
+    }
+
+    /// Auctions still taking bids or waiting to be settled, soonest ending first.
+    pub fn open(&self) -> Result<Vec<Auction>, ZipError> {
+        let mut auctions: Vec<Auction> = self
+            .storage
+            .get_auctions()?
+            .iter()
+            .filter_map(|d| bincode::deserialize::<Auction>(d).ok())
+            .filter(|auction| auction.status == AuctionStatus::Open)
+            .collect();
+        auctions.sort_by_key(|auction| auction.ends_at);
+        Ok(auctions)
+    }
+}
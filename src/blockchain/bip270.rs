@@ -0,0 +1,188 @@
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use sv::script::Script;
+use sv::transaction::TxOut;
+
+use crate::errors::ZipError;
+
+/// BIP270 network identifier for BSV mainnet.
+pub const BSV_MAINNET: &str = "bitcoin-sv";
+pub const PAYMENT_REQUEST_CONTENT_TYPE: &str = "application/bitcoinsv-paymentrequest";
+pub const PAYMENT_CONTENT_TYPE: &str = "application/bitcoinsv-payment";
+pub const PAYMENT_ACK_CONTENT_TYPE: &str = "application/bitcoinsv-paymentack";
+
+/// Output the merchant requires, with a hex-encoded locking script.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RequestedOutput {
+    pub amount: u64,
+    pub script: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// Merchant payment request fetched from a BIP272 `?r=` URL.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentRequest {
+    pub network: String,
+    pub outputs: Vec<RequestedOutput>,
+    /// Unix timestamp (seconds) when the request was created.
+    pub creation_timestamp: i64,
+    /// Unix timestamp (seconds) after which the merchant rejects payments.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expiration_timestamp: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+    pub payment_url: String,
+    /// Opaque merchant data that must be echoed back in the payment.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub merchant_data: Option<String>,
+}
+
+impl PaymentRequest {
+    /// Total satoshis requested across all outputs.
+    pub fn total(&self) -> Result<u64, ZipError> {
+        self.outputs
+            .iter()
+            .try_fold(0u64, |total, o| total.checked_add(o.amount))
+            .ok_or_else(|| ZipError::Validation("Payment request amount overflow".to_string()))
+    }
+
+    /// Rejects expired, foreign-network, empty or malformed requests.
+    pub fn validate(&self, now: i64) -> Result<(), ZipError> {
+        if self.network != BSV_MAINNET {
+            return Err(ZipError::Validation(format!(
+                "Payment request is for network {}",
+                self.network
+            )));
+        }
+        if let Some(expires) = self.expiration_timestamp {
+            if expires <= now {
+                return Err(ZipError::Validation(
+                    "Payment request has expired".to_string(),
+                ));
+            }
+        }
+        if self.outputs.is_empty() || self.total()? == 0 {
+            return Err(ZipError::Validation(
+                "Payment request has no outputs".to_string(),
+            ));
+        }
+        if !self.payment_url.starts_with("https://") && !self.payment_url.starts_with("http://") {
+            return Err(ZipError::Validation(format!(
+                "Invalid payment URL: {}",
+                self.payment_url
+            )));
+        }
+        self.tx_outputs().map(|_| ())
+    }
+
+    /// Transaction outputs exactly as requested, in order.
+    pub fn tx_outputs(&self) -> Result<Vec<TxOut>, ZipError> {
+        self.outputs
+            .iter()
+            .map(|o| {
+                let script = hex::decode(&o.script)
+                    .map_err(|e| ZipError::Validation(format!("Invalid output script: {}", e)))?;
+                if script.is_empty() {
+                    return Err(ZipError::Validation("Empty output script".to_string()));
+                }
+                Ok(TxOut {
+                    value: o.amount,
+                    script: Script(script),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Payment message posted to the merchant's payment URL.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Payment {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub merchant_data: Option<String>,
+    /// Signed transaction, hex-encoded.
+    pub transaction: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refund_to: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+/// Merchant acknowledgement of a payment; a non-zero `error` means it was refused.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PaymentAck {
+    pub payment: Payment,
+    #[serde(default)]
+    pub memo: Option<String>,
+    #[serde(default)]
+    pub error: Option<i32>,
+}
+
+impl PaymentAck {
+    /// True when the merchant accepted the payment.
+    pub fn is_accepted(&self) -> bool {
+        self.error.unwrap_or(0) == 0
+    }
+}
+
+/// Client for BIP270 merchant payment requests.
+#[derive(Clone, Default)]
+pub struct MerchantClient {
+    client: Client,
+}
+
+impl MerchantClient {
+    /// Initializes merchant client.
+    pub fn new() -> Self {
+        Self {
+            client: Client::new(),
+        }
+    }
+
+    /// Fetches a payment request from a merchant URL.
+    pub async fn fetch_request(&self, url: &str) -> Result<PaymentRequest, ZipError> {
+        let request = self
+            .client
+            .get(url)
+            .header("Accept", PAYMENT_REQUEST_CONTENT_TYPE)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<PaymentRequest>()
+            .await?;
+        Ok(request)
+    }
+
+    /// Posts a signed transaction for a payment request and returns the merchant ACK.
+    pub async fn send_payment(
+        &self,
+        request: &PaymentRequest,
+        tx_hex: &str,
+        memo: Option<String>,
+    ) -> Result<PaymentAck, ZipError> {
+        let payment = Payment {
+            merchant_data: request.merchant_data.clone(),
+            transaction: tx_hex.to_string(),
+            refund_to: None,
+            memo,
+        };
+        let ack = self
+            .client
+            .post(&request.payment_url)
+            .header("Content-Type", PAYMENT_CONTENT_TYPE)
+            .header("Accept", PAYMENT_ACK_CONTENT_TYPE)
+            .json(&payment)
+            .send()
+            .await?
+            .json::<PaymentAck>()
+            .await?;
+        if ack.payment.transaction != payment.transaction {
+            return Err(ZipError::Validation(
+                "Merchant acknowledged a different transaction".to_string(),
+            ));
+        }
+        Ok(ack)
+    }
+}
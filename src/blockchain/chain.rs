@@ -0,0 +1,312 @@
+use bincode;
+use hex;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use sv::util::sha256d;
+
+use crate::errors::ZipError;
+use crate::storage::ZipStorage;
+
+/// Block header as reported by the chain backend.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BlockHeader {
+    pub height: u64,
+    pub hash: String,
+    pub prev_hash: String,
+    pub merkle_root: String,
+    /// Block timestamp (Unix seconds).
+    pub time: u32,
+}
+
+/// `BlockHeader` as stored before block timestamps were kept.
+#[derive(Deserialize)]
+pub(crate) struct BlockHeaderV1 {
+    height: u64,
+    hash: String,
+    prev_hash: String,
+    merkle_root: String,
+}
+
+impl From<BlockHeaderV1> for BlockHeader {
+    /// Timestamps of v1 headers are unknown and read as 0, which keeps time locks
+    /// pending until enough newer headers arrive.
+    fn from(v1: BlockHeaderV1) -> Self {
+        Self {
+            height: v1.height,
+            hash: v1.hash,
+            prev_hash: v1.prev_hash,
+            merkle_root: v1.merkle_root,
+            time: 0,
+        }
+    }
+}
+
+impl BlockHeader {
+    /// Decodes a stored header in the current layout or upgrades a v1 one.
+    pub fn decode(bytes: &[u8]) -> Result<Self, ZipError> {
+        if let Ok(header) = bincode::deserialize::<BlockHeader>(bytes) {
+            return Ok(header);
+        }
+        bincode::deserialize::<BlockHeaderV1>(bytes)
+            .map(Self::from)
+            .map_err(|e| ZipError::Blockchain(e.to_string()))
+    }
+}
+
+/// Size of a serialized block header.
+pub const HEADER_SIZE: usize = 80;
+/// Easiest target mainnet accepts, in compact form.
+pub const POW_LIMIT_BITS: u32 = 0x1d00_ffff;
+
+impl BlockHeader {
+    /// Parses a serialized 80-byte header at a known height. The hash is computed from
+    /// the bytes and must meet both the header's own target and the mainnet limit.
+    pub fn from_raw(height: u64, raw: &[u8]) -> Result<Self, ZipError> {
+        if raw.len() != HEADER_SIZE {
+            return Err(ZipError::Blockchain(format!(
+                "Block header must be {} bytes, got {}",
+                HEADER_SIZE,
+                raw.len()
+            )));
+        }
+        let mut hash = sha256d(raw).0;
+        hash.reverse();
+        let bits = u32::from_le_bytes(raw[72..76].try_into().unwrap());
+        let target = compact_target(bits)?;
+        if target > compact_target(POW_LIMIT_BITS)? || hash > target {
+            return Err(ZipError::Blockchain(
+                "Block header does not meet its proof of work".to_string(),
+            ));
+        }
+        let encode = |bytes: &[u8]| {
+            let mut bytes = bytes.to_vec();
+            bytes.reverse();
+            hex::encode(bytes)
+        };
+        Ok(Self {
+            height,
+            hash: hex::encode(hash),
+            prev_hash: encode(&raw[4..36]),
+            merkle_root: encode(&raw[36..68]),
+            time: u32::from_le_bytes(raw[68..72].try_into().unwrap()),
+        })
+    }
+}
+
+/// Expands a compact ("bits") target into a big-endian 256-bit number.
+fn compact_target(bits: u32) -> Result<[u8; 32], ZipError> {
+    let exponent = (bits >> 24) as usize;
+    let mantissa = bits & 0x007f_ffff;
+    if bits & 0x0080_0000 != 0 || mantissa == 0 || exponent > 32 {
+        return Err(ZipError::Blockchain(format!(
+            "Invalid target bits {:08x}",
+            bits
+        )));
+    }
+    let mut target = [0u8; 32];
+    // The value is mantissa * 256^(exponent - 3); bytes below 256^0 are dropped
+    for (i, byte) in mantissa.to_be_bytes()[1..].iter().enumerate() {
+        if exponent > i {
+            target[32 - exponent + i] = *byte;
+        }
+    }
+    Ok(target)
+}
+
+/// Number of blocks whose timestamps make up the median time past (BIP-113).
+pub const MEDIAN_TIME_SPAN: u64 = 11;
+
+/// Merkle inclusion proof for a transaction in a block.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MerkleProof {
+    pub txid: String,
+    /// Position of the transaction in the block.
+    pub index: u64,
+    /// Sibling hashes from leaf to root; "*" duplicates the current hash.
+    pub nodes: Vec<String>,
+    pub block_hash: String,
+    pub block_height: u64,
+}
+
+impl MerkleProof {
+    /// Computes the Merkle root implied by the proof (display hex).
+    pub fn compute_root(&self) -> Result<String, ZipError> {
+        let mut hash = decode_hash(&self.txid)?;
+        let mut index = self.index;
+        for node in &self.nodes {
+            let sibling = if node == "*" {
+                hash
+            } else {
+                decode_hash(node)?
+            };
+            let mut concat = Vec::with_capacity(64);
+            if index & 1 == 1 {
+                concat.extend_from_slice(&sibling);
+                concat.extend_from_slice(&hash);
+            } else {
+                concat.extend_from_slice(&hash);
+                concat.extend_from_slice(&sibling);
+            }
+            hash = sha256d(&concat).0;
+            index >>= 1;
+        }
+        hash.reverse();
+        Ok(hex::encode(hash))
+    }
+
+    /// Checks the proof against a header: same block and matching Merkle root.
+    pub fn verify(&self, header: &BlockHeader) -> Result<bool, ZipError> {
+        Ok(header.hash == self.block_hash
+            && header.height == self.block_height
+            && self.compute_root()? == header.merkle_root)
+    }
+}
+
+/// Decodes a display-order hash into internal byte order.
+fn decode_hash(hash: &str) -> Result<[u8; 32], ZipError> {
+    let mut bytes: [u8; 32] = hex::decode(hash)
+        .map_err(|e| ZipError::Blockchain(e.to_string()))?
+        .try_into()
+        .map_err(|_| ZipError::Blockchain(format!("Invalid hash length: {}", hash)))?;
+    bytes.reverse();
+    Ok(bytes)
+}
+
+/// Chain switch reported by the header store.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Reorg {
+    /// Height of the last block shared by the old and new chains.
+    pub fork_height: u64,
+    /// Blocks removed from the active chain, lowest first.
+    pub disconnected: Vec<BlockHeader>,
+    /// Blocks added to the active chain, lowest first.
+    pub connected: Vec<BlockHeader>,
+}
+
+/// Active-chain block headers backed by Sled, one key per height.
+#[derive(Clone)]
+pub struct HeaderStore {
+    storage: Arc<ZipStorage>,
+}
+
+impl HeaderStore {
+    /// Initializes header store with storage.
+    pub fn new(storage: Arc<ZipStorage>) -> Self {
+        Self { storage }
+    }
+
+    /// Returns the header at the tip of the active chain.
+    pub fn tip(&self) -> Result<Option<BlockHeader>, ZipError> {
+        match self.storage.get_header_tip()? {
+            Some(height) => self.header_at(height),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the active-chain header at a height.
+    pub fn header_at(&self, height: u64) -> Result<Option<BlockHeader>, ZipError> {
+        let data = self.storage.get_header(height)?;
+        data.map(|d| BlockHeader::decode(&d)).transpose()
+    }
+
+    /// Median timestamp of the last `MEDIAN_TIME_SPAN` active-chain blocks, which
+    /// time-based locks are measured against instead of the local clock.
+    pub fn median_time_past(&self) -> Result<Option<i64>, ZipError> {
+        let Some(tip) = self.tip()? else {
+            return Ok(None);
+        };
+        let mut times = vec![];
+        for height in (0..=tip.height).rev().take(MEDIAN_TIME_SPAN as usize) {
+            if let Some(header) = self.header_at(height)? {
+                times.push(header.time as i64);
+            }
+        }
+        times.sort_unstable();
+        Ok(times.get(times.len() / 2).copied())
+    }
+
+    fn store(&self, header: &BlockHeader) -> Result<(), ZipError> {
+        let serialized =
+            bincode::serialize(header).map_err(|e| ZipError::Blockchain(e.to_string()))?;
+        self.storage.store_header(header.height, &serialized)
+    }
+
+    /// Extends the active chain by one header.
+    pub fn connect(&self, header: BlockHeader) -> Result<Option<Reorg>, ZipError> {
+        self.apply_branch(vec![header])
+    }
+
+    /// Applies a branch of consecutive headers reported by the backend.
+    ///
+    /// Leading headers already on the active chain are skipped; the first new header
+    /// must build on a block in the active chain. A branch that extends the tip is
+    /// appended; a longer competing branch replaces the blocks after the fork point and
+    /// is returned as a `Reorg`. Shorter branches are ignored.
+    pub fn apply_branch(&self, mut branch: Vec<BlockHeader>) -> Result<Option<Reorg>, ZipError> {
+        if branch.is_empty() {
+            return Ok(None);
+        }
+        if branch
+            .windows(2)
+            .any(|w| w[1].prev_hash != w[0].hash || w[1].height != w[0].height + 1)
+        {
+            return Err(ZipError::Blockchain(
+                "Headers do not form a chain".to_string(),
+            ));
+        }
+        let new_tip = branch[branch.len() - 1].height;
+        let tip = match self.tip()? {
+            Some(tip) => tip,
+            None => {
+                // First headers seed the store
+                for header in &branch {
+                    self.store(header)?;
+                }
+                self.storage.store_header_tip(new_tip)?;
+                return Ok(None);
+            }
+        };
+        // Headers resent from the active chain are not part of the fork
+        let mut known = 0;
+        for header in &branch {
+            match self.header_at(header.height)? {
+                Some(stored) if stored.hash == header.hash => known += 1,
+                _ => break,
+            }
+        }
+        branch.drain(..known);
+        let Some(first) = branch.first() else {
+            return Ok(None);
+        };
+        let fork_height = first
+            .height
+            .checked_sub(1)
+            .ok_or_else(|| ZipError::Blockchain("Cannot replace the first header".to_string()))?;
+        match self.header_at(fork_height)? {
+            Some(parent) if parent.hash == first.prev_hash => {}
+            _ => return Err(ZipError::Blockchain("Orphan header".to_string())),
+        }
+        if new_tip <= tip.height && fork_height < tip.height {
+            return Ok(None);
+        }
+        let mut disconnected = vec![];
+        for height in first.height..=tip.height {
+            if let Some(old) = self.header_at(height)? {
+                disconnected.push(old);
+            }
+            self.storage.remove_header(height)?;
+        }
+        for header in &branch {
+            self.store(header)?;
+        }
+        self.storage.store_header_tip(new_tip)?;
+        if disconnected.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Reorg {
+            fork_height,
+            disconnected,
+            connected: branch,
+        }))
+    }
+}
@@ -0,0 +1,216 @@
+use bincode;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::blockchain::chain::{HeaderStore, MerkleProof};
+use crate::errors::ZipError;
+use crate::storage::ZipStorage;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TxDirection {
+    Sent,
+    Received,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TxStatus {
+    /// Built and reserved locally, not yet accepted by the network.
+    Pending,
+    /// Accepted by the network, not yet mined.
+    Broadcast,
+    Confirmed {
+        height: u64,
+        block_hash: String,
+    },
+    /// Inputs were spent by another transaction; this entry no longer counts.
+    Conflicted {
+        competing_txid: String,
+    },
+}
+
+/// Locally indexed wallet transaction.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub txid: String,
+    pub direction: TxDirection,
+    pub amount: u64,
+    pub fee: u64,
+    pub counterparty: String,
+    /// Outpoints spent by this transaction ("txid:vout").
+    pub inputs: Vec<String>,
+    pub status: TxStatus,
+    /// Unix timestamp (seconds) when the entry was recorded.
+    pub timestamp: i64,
+    /// Memo or protocol data attached in an OP_RETURN output.
+    pub memo: Option<String>,
+}
+
+/// `HistoryEntry` as stored before memos were recorded. Bincode has no field
+/// defaults, so histories in this layout are decoded on their own and upgraded.
+#[derive(Deserialize)]
+struct HistoryEntryV1 {
+    txid: String,
+    direction: TxDirection,
+    amount: u64,
+    fee: u64,
+    counterparty: String,
+    inputs: Vec<String>,
+    status: TxStatus,
+    timestamp: i64,
+}
+
+impl From<HistoryEntryV1> for HistoryEntry {
+    fn from(v1: HistoryEntryV1) -> Self {
+        Self {
+            txid: v1.txid,
+            direction: v1.direction,
+            amount: v1.amount,
+            fee: v1.fee,
+            counterparty: v1.counterparty,
+            inputs: v1.inputs,
+            status: v1.status,
+            timestamp: v1.timestamp,
+            memo: None,
+        }
+    }
+}
+
+/// Per-user transaction history index backed by Sled.
+#[derive(Clone)]
+pub struct TxHistory {
+    storage: Arc<ZipStorage>,
+}
+
+impl TxHistory {
+    /// Initializes history index with storage.
+    pub fn new(storage: Arc<ZipStorage>) -> Self {
+        Self { storage }
+    }
+
+    /// Lists all history entries for a user, oldest first, upgrading v1 histories.
+    pub fn list(&self, user_id: Uuid) -> Result<Vec<HistoryEntry>, ZipError> {
+        let Some(data) = self.storage.get_history(user_id)? else {
+            return Ok(vec![]);
+        };
+        if let Ok(entries) = bincode::deserialize::<Vec<HistoryEntry>>(&data) {
+            return Ok(entries);
+        }
+        let v1: Vec<HistoryEntryV1> =
+            bincode::deserialize(&data).map_err(|e| ZipError::Blockchain(e.to_string()))?;
+        Ok(v1.into_iter().map(HistoryEntry::from).collect())
+    }
+
+    fn store(&self, user_id: Uuid, entries: &[HistoryEntry]) -> Result<(), ZipError> {
+        let serialized =
+            bincode::serialize(entries).map_err(|e| ZipError::Blockchain(e.to_string()))?;
+        self.storage.store_history(user_id, &serialized)
+    }
+
+    /// Retrieves a single entry by TXID.
+    pub fn get(&self, user_id: Uuid, txid: &str) -> Result<Option<HistoryEntry>, ZipError> {
+        Ok(self.list(user_id)?.into_iter().find(|e| e.txid == txid))
+    }
+
+    /// Inserts or replaces an entry keyed by TXID.
+    pub fn upsert(&self, user_id: Uuid, entry: HistoryEntry) -> Result<(), ZipError> {
+        let mut entries = self.list(user_id)?;
+        match entries.iter_mut().find(|e| e.txid == entry.txid) {
+            Some(existing) => *existing = entry,
+            None => entries.push(entry),
+        }
+        self.store(user_id, &entries)
+    }
+
+    /// Drops an entry that never reached the network.
+    pub fn remove(&self, user_id: Uuid, txid: &str) -> Result<(), ZipError> {
+        let mut entries = self.list(user_id)?;
+        entries.retain(|e| e.txid != txid);
+        self.store(user_id, &entries)
+    }
+
+    /// Updates an entry's status, returning false if the TXID is unknown.
+    pub fn set_status(
+        &self,
+        user_id: Uuid,
+        txid: &str,
+        status: TxStatus,
+    ) -> Result<bool, ZipError> {
+        let mut entries = self.list(user_id)?;
+        let found = match entries.iter_mut().find(|e| e.txid == txid) {
+            Some(entry) => {
+                entry.status = status;
+                true
+            }
+            None => false,
+        };
+        if found {
+            self.store(user_id, &entries)?;
+        }
+        Ok(found)
+    }
+
+    /// Returns stored Merkle proofs keyed by TXID.
+    pub fn get_proofs(&self, user_id: Uuid) -> Result<HashMap<String, MerkleProof>, ZipError> {
+        let data = self.storage.get_proofs(user_id)?;
+        Ok(data
+            .map(|d| bincode::deserialize(&d).unwrap_or_default())
+            .unwrap_or_default())
+    }
+
+    /// Saves the Merkle proof for a transaction.
+    pub fn store_proof(&self, user_id: Uuid, proof: MerkleProof) -> Result<(), ZipError> {
+        let mut proofs = self.get_proofs(user_id)?;
+        proofs.insert(proof.txid.clone(), proof);
+        let serialized =
+            bincode::serialize(&proofs).map_err(|e| ZipError::Blockchain(e.to_string()))?;
+        self.storage.store_proofs(user_id, &serialized)
+    }
+
+    /// Rewinds entries confirmed above the fork height back to unconfirmed,
+    /// returning the rewound entries.
+    pub fn rewind(&self, user_id: Uuid, fork_height: u64) -> Result<Vec<HistoryEntry>, ZipError> {
+        let mut entries = self.list(user_id)?;
+        let mut rewound = vec![];
+        for entry in entries.iter_mut() {
+            if let TxStatus::Confirmed { height, .. } = entry.status {
+                if height > fork_height {
+                    entry.status = TxStatus::Broadcast;
+                    rewound.push(entry.clone());
+                }
+            }
+        }
+        if !rewound.is_empty() {
+            self.store(user_id, &entries)?;
+        }
+        Ok(rewound)
+    }
+
+    /// Verifies a proof against the active chain and, if valid, marks the entry confirmed.
+    pub fn reverify(
+        &self,
+        user_id: Uuid,
+        proof: MerkleProof,
+        headers: &HeaderStore,
+    ) -> Result<bool, ZipError> {
+        let Some(header) = headers.header_at(proof.block_height)? else {
+            return Ok(false);
+        };
+        if !proof.verify(&header)? {
+            return Ok(false);
+        }
+        let confirmed = self.set_status(
+            user_id,
+            &proof.txid,
+            TxStatus::Confirmed {
+                height: header.height,
+                block_hash: header.hash,
+            },
+        )?;
+        if confirmed {
+            self.store_proof(user_id, proof)?;
+        }
+        Ok(confirmed)
+    }
+}
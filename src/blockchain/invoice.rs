@@ -0,0 +1,308 @@
+use bincode;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::blockchain::WalletManager;
+use crate::errors::ZipError;
+use crate::storage::ZipStorage;
+use crate::utils::payment_uri::{PaymentUri, SATOSHIS_PER_BSV};
+
+/// Amount an invoice asks for, as entered by the issuer.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum InvoiceAmount {
+    Satoshis(u64),
+    /// Fiat amount converted to satoshis at the rate when the invoice is created.
+    Fiat {
+        currency: String,
+        amount: Decimal,
+    },
+}
+
+/// Where the payer sends funds.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum InvoiceDestination {
+    /// Fresh address derived for this invoice only.
+    Address {
+        address: String,
+        derivation_path: String,
+    },
+    /// PayMail handle; incoming P2P payments are matched by `Invoice::reference`.
+    Paymail { handle: String },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum InvoiceStatus {
+    Open,
+    PartiallyPaid,
+    Paid,
+    Expired,
+}
+
+/// Payment matched to an invoice.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct InvoicePayment {
+    pub txid: String,
+    pub amount: u64,
+    pub received_at: i64,
+}
+
+/// Request for payment sent to a customer.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Invoice {
+    pub id: Uuid,
+    /// Short code shown to the payer and sent as the PayMail payment reference.
+    pub reference: String,
+    pub requested: InvoiceAmount,
+    /// Satoshis due, fixed at creation.
+    pub amount: u64,
+    pub memo: String,
+    pub destination: InvoiceDestination,
+    pub created_at: i64,
+    pub expires_at: Option<i64>,
+    pub payments: Vec<InvoicePayment>,
+    pub status: InvoiceStatus,
+}
+
+impl Invoice {
+    /// Creates an open invoice for `amount` satoshis.
+    pub fn new(
+        requested: InvoiceAmount,
+        amount: u64,
+        memo: &str,
+        destination: InvoiceDestination,
+        created_at: i64,
+        expires_at: Option<i64>,
+    ) -> Self {
+        let id = Uuid::new_v4();
+        Self {
+            reference: format!("INV-{}", &id.simple().to_string()[..8].to_uppercase()),
+            id,
+            requested,
+            amount,
+            memo: memo.to_string(),
+            destination,
+            created_at,
+            expires_at,
+            payments: vec![],
+            status: InvoiceStatus::Open,
+        }
+    }
+
+    /// Total satoshis received so far.
+    pub fn received(&self) -> u64 {
+        self.payments.iter().map(|p| p.amount).sum()
+    }
+
+    /// Satoshis still owed.
+    pub fn outstanding(&self) -> u64 {
+        self.amount.saturating_sub(self.received())
+    }
+
+    /// Status at `now`; full payment wins over expiry, partial payments expire.
+    pub fn status_at(&self, now: i64) -> InvoiceStatus {
+        let received = self.received();
+        if received >= self.amount {
+            InvoiceStatus::Paid
+        } else if self.expires_at.is_some_and(|at| at <= now) {
+            InvoiceStatus::Expired
+        } else if received > 0 {
+            InvoiceStatus::PartiallyPaid
+        } else {
+            InvoiceStatus::Open
+        }
+    }
+
+    /// Records a payment once per TXID and returns whether it was new.
+    pub fn apply_payment(&mut self, payment: InvoicePayment) -> bool {
+        if self.payments.iter().any(|p| p.txid == payment.txid) {
+            return false;
+        }
+        let now = payment.received_at;
+        self.payments.push(payment);
+        self.status = self.status_at(now);
+        true
+    }
+
+    /// Payment URI for the outstanding amount, for sharing as a link or QR code.
+    pub fn payment_uri(&self) -> PaymentUri {
+        let uri = match &self.destination {
+            InvoiceDestination::Address { address, .. } => PaymentUri::bitcoin(address),
+            InvoiceDestination::Paymail { handle } => PaymentUri::paymail(handle),
+        };
+        uri.with_amount(self.outstanding())
+            .with_label(&self.reference)
+            .with_message(&self.memo)
+    }
+}
+
+/// Per-user invoice book.
+#[derive(Clone)]
+pub struct Invoices {
+    storage: Arc<ZipStorage>,
+}
+
+impl Invoices {
+    /// Initializes invoice book with storage.
+    pub fn new(storage: Arc<ZipStorage>) -> Self {
+        Self { storage }
+    }
+
+    /// Lists a user's invoices, newest first.
+    pub fn list(&self, user_id: Uuid) -> Result<Vec<Invoice>, ZipError> {
+        let data = self.storage.get_invoices(user_id)?;
+        let mut invoices: Vec<Invoice> = data
+            .map(|d| bincode::deserialize(&d).unwrap_or_default())
+            .unwrap_or_default();
+        invoices.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        Ok(invoices)
+    }
+
+    fn store(&self, user_id: Uuid, invoices: &[Invoice]) -> Result<(), ZipError> {
+        let serialized =
+            bincode::serialize(invoices).map_err(|e| ZipError::Blockchain(e.to_string()))?;
+        self.storage.store_invoices(user_id, &serialized)
+    }
+
+    /// Stores a new invoice.
+    pub fn add(&self, user_id: Uuid, invoice: Invoice) -> Result<(), ZipError> {
+        if invoice.amount == 0 {
+            return Err(ZipError::Validation(
+                "Invoice amount must be positive".to_string(),
+            ));
+        }
+        let mut invoices = self.list(user_id)?;
+        invoices.push(invoice);
+        self.store(user_id, &invoices)
+    }
+
+    /// Deletes an invoice.
+    pub fn remove(&self, user_id: Uuid, invoice_id: Uuid) -> Result<(), ZipError> {
+        let mut invoices = self.list(user_id)?;
+        invoices.retain(|i| i.id != invoice_id);
+        self.store(user_id, &invoices)
+    }
+
+    /// Creates an invoice paid to a fresh derived address, or to a PayMail handle with the
+    /// invoice reference. Fiat amounts are converted at the current BSV price.
+    pub async fn create(
+        &self,
+        wallet: &WalletManager,
+        user_id: Uuid,
+        requested: InvoiceAmount,
+        memo: &str,
+        expires_in: Option<i64>,
+        paymail: Option<&str>,
+    ) -> Result<Invoice, ZipError> {
+        let amount = match &requested {
+            InvoiceAmount::Satoshis(sats) => *sats,
+            InvoiceAmount::Fiat { currency, amount } => {
+                let price = wallet.fetch_price(currency).await?;
+                if price.is_zero() {
+                    return Err(ZipError::Blockchain("Invalid price data".to_string()));
+                }
+                (*amount / price * Decimal::from(SATOSHIS_PER_BSV))
+                    .round()
+                    .try_into()
+                    .map_err(|_| ZipError::Validation("Invalid invoice amount".to_string()))?
+            }
+        };
+        let destination = match paymail {
+            Some(handle) => InvoiceDestination::Paymail {
+                handle: handle.to_string(),
+            },
+            None => {
+                let receive = wallet.invoice_address()?;
+                InvoiceDestination::Address {
+                    address: receive.address,
+                    derivation_path: receive.derivation_path,
+                }
+            }
+        };
+        let now = chrono::Utc::now().timestamp();
+        let invoice = Invoice::new(
+            requested,
+            amount,
+            memo,
+            destination,
+            now,
+            expires_in.map(|secs| now + secs),
+        );
+        self.add(user_id, invoice.clone())?;
+        Ok(invoice)
+    }
+
+    /// Matches an incoming PayMail payment to an open invoice by reference.
+    pub fn record_payment(
+        &self,
+        user_id: Uuid,
+        reference: &str,
+        payment: InvoicePayment,
+    ) -> Result<Option<Invoice>, ZipError> {
+        let mut invoices = self.list(user_id)?;
+        let Some(invoice) = invoices
+            .iter_mut()
+            .find(|i| i.reference.eq_ignore_ascii_case(reference))
+        else {
+            return Ok(None);
+        };
+        if !invoice.apply_payment(payment) {
+            return Ok(None);
+        }
+        let updated = invoice.clone();
+        self.store(user_id, &invoices)?;
+        Ok(Some(updated))
+    }
+
+    /// Checks invoice addresses for incoming funds and expires overdue invoices.
+    /// Returns invoices whose status changed.
+    pub async fn refresh(
+        &self,
+        wallet: &WalletManager,
+        user_id: Uuid,
+        now: i64,
+    ) -> Result<Vec<Invoice>, ZipError> {
+        let mut invoices = self.list(user_id)?;
+        let mut changed = vec![];
+        for invoice in invoices.iter_mut() {
+            let previous = invoice.status.clone();
+            if matches!(previous, InvoiceStatus::Open | InvoiceStatus::PartiallyPaid) {
+                if let InvoiceDestination::Address { address, .. } = &invoice.destination {
+                    // Unique addresses mean every payment to it belongs to this invoice
+                    for (txid, amount) in wallet.received_at(address).await? {
+                        invoice.apply_payment(InvoicePayment {
+                            txid,
+                            amount,
+                            received_at: now,
+                        });
+                    }
+                }
+                invoice.status = invoice.status_at(now);
+            }
+            if invoice.status != previous {
+                changed.push(invoice.clone());
+            }
+        }
+        if !changed.is_empty() {
+            self.store(user_id, &invoices)?;
+            for invoice in changed.iter() {
+                wallet.notify(user_id, invoice_message(invoice))?;
+            }
+        }
+        Ok(changed)
+    }
+}
+
+fn invoice_message(invoice: &Invoice) -> String {
+    match invoice.status {
+        InvoiceStatus::Paid => format!("Invoice {} has been paid", invoice.reference),
+        InvoiceStatus::PartiallyPaid => format!(
+            "Invoice {} partially paid: {} satoshis outstanding",
+            invoice.reference,
+            invoice.outstanding()
+        ),
+        InvoiceStatus::Expired => format!("Invoice {} has expired", invoice.reference),
+        InvoiceStatus::Open => format!("Invoice {} is open", invoice.reference),
+    }
+}
@@ -1,7 +1,48 @@
+pub mod alerts;
+pub mod aliases;
+pub mod auction;
+pub mod bip270;
+pub mod chain;
+pub mod history;
+pub mod invoice;
+pub mod notary;
+pub mod op_return;
 pub mod paymail;
+pub mod policy;
+pub mod profile;
+pub mod reserves;
+pub mod schedule;
+pub mod timelock;
 pub mod transaction;
 pub mod wallet;
 
+pub use alerts::{Alerts, WalletAlert};
+pub use aliases::{
+    AliasLease, AliasOwnership, AliasPurchase, AliasRegistry, AliasStatus, AliasTransfer,
+    RENEWAL_TIMEOUT_SECS, RESERVATION_TIMEOUT_SECS, TRANSFER_TIMEOUT_SECS,
+};
+pub use auction::{Auction, AuctionStatus, Auctions, Bid};
+pub use bip270::{MerchantClient, Payment, PaymentAck, PaymentRequest, RequestedOutput};
+pub use chain::{BlockHeader, HeaderStore, MerkleProof, Reorg};
+pub use history::{HistoryEntry, TxDirection, TxHistory, TxStatus};
+pub use invoice::{
+    Invoice, InvoiceAmount, InvoiceDestination, InvoicePayment, InvoiceStatus, Invoices,
+};
+pub use notary::{Notary, NotaryCheck, NotaryReceipt};
+pub use op_return::DataOutput;
 pub use paymail::PaymailManager;
-pub use transaction::TransactionManager;
-pub use wallet::{WalletData, WalletManager};
+pub use policy::{PolicyViolation, SpendingPolicy};
+pub use profile::{MAX_PROFILE_NAME_LEN, PublicProfile};
+pub use reserves::{AddressProof, ReserveCheck, ReserveReport, verify_reserve_report};
+pub use schedule::{
+    CatchUp, PaymentSchedule, Recurrence, RunLogEntry, RunResult, ScheduleLimits, Scheduler,
+};
+pub use timelock::{LockKind, LockedTx, TimeLocks};
+pub use transaction::{
+    BEEF_V1, BEEF_V2, Beef, BeefTx, MerklePath, Reservation, SIGHASH_ALL_FORKID, SigningKeys,
+    SpendCheck, TransactionManager, Utxo,
+};
+pub use wallet::{
+    BRC42_PATH_PREFIX, NETWORK, PaymentAcceptance, PaymentPreview, ReceiveAddress, WalletData,
+    WalletManager,
+};
@@ -0,0 +1,267 @@
+use bincode;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::blockchain::chain::{BlockHeader, BlockHeaderV1, MerkleProof};
+use crate::blockchain::op_return::DataOutput;
+use crate::blockchain::{TransactionManager, WalletManager};
+use crate::errors::ZipError;
+use crate::storage::ZipStorage;
+
+/// Tag pushed before the document hash so notarisations can be found on-chain.
+pub const NOTARY_TAG: &[u8] = b"zip.notary";
+
+/// Proof that a document hash was written on-chain.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NotaryReceipt {
+    pub id: Uuid,
+    /// Local name only; never written on-chain.
+    pub file_name: String,
+    /// SHA-256 of the document, hex-encoded.
+    pub file_hash: String,
+    pub txid: String,
+    pub tx_hex: String,
+    pub created_at: i64,
+    /// Merkle proof and block header, filled in once the transaction is mined.
+    pub proof: Option<MerkleProof>,
+    pub header: Option<BlockHeader>,
+    /// Serialized 80-byte block header, hex-encoded, so the proof of work and block
+    /// time can be checked offline.
+    pub raw_header: Option<String>,
+}
+
+/// `NotaryReceipt` as stored before raw block headers were kept.
+#[derive(Deserialize)]
+struct NotaryReceiptV2 {
+    id: Uuid,
+    file_name: String,
+    file_hash: String,
+    txid: String,
+    tx_hex: String,
+    created_at: i64,
+    proof: Option<MerkleProof>,
+    header: Option<BlockHeader>,
+}
+
+impl From<NotaryReceiptV2> for NotaryReceipt {
+    /// Without the raw header the receipt reads as unconfirmed until the next refresh.
+    fn from(v2: NotaryReceiptV2) -> Self {
+        Self {
+            id: v2.id,
+            file_name: v2.file_name,
+            file_hash: v2.file_hash,
+            txid: v2.txid,
+            tx_hex: v2.tx_hex,
+            created_at: v2.created_at,
+            proof: v2.proof,
+            header: v2.header,
+            raw_header: None,
+        }
+    }
+}
+
+/// `NotaryReceipt` as stored with v1 block headers.
+#[derive(Deserialize)]
+struct NotaryReceiptV1 {
+    id: Uuid,
+    file_name: String,
+    file_hash: String,
+    txid: String,
+    tx_hex: String,
+    created_at: i64,
+    proof: Option<MerkleProof>,
+    header: Option<BlockHeaderV1>,
+}
+
+impl From<NotaryReceiptV1> for NotaryReceipt {
+    fn from(v1: NotaryReceiptV1) -> Self {
+        Self {
+            id: v1.id,
+            file_name: v1.file_name,
+            file_hash: v1.file_hash,
+            txid: v1.txid,
+            tx_hex: v1.tx_hex,
+            created_at: v1.created_at,
+            proof: v1.proof,
+            header: v1.header.map(BlockHeader::from),
+            raw_header: None,
+        }
+    }
+}
+
+/// Outcome of checking a document against a receipt.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NotaryCheck {
+    /// The document differs from the one notarised.
+    HashMismatch,
+    /// The stored transaction does not match the receipt's TXID or hash.
+    TransactionMismatch,
+    /// The document matches; the transaction is not mined yet.
+    Unconfirmed,
+    /// The document matches and the transaction is proven to be in this block,
+    /// mined at `time` (Unix seconds, from the header).
+    Confirmed {
+        height: u64,
+        block_hash: String,
+        time: u32,
+    },
+    /// The stored Merkle proof does not link the transaction to the stored header.
+    ProofInvalid,
+    /// The stored header is malformed or does not carry valid proof of work.
+    HeaderInvalid,
+}
+
+impl NotaryReceipt {
+    /// SHA-256 of a document, hex-encoded.
+    pub fn hash_file(bytes: &[u8]) -> String {
+        hex::encode(Sha256::digest(bytes))
+    }
+
+    /// OP_RETURN output carrying a document hash.
+    pub fn data_output(file_hash: &str) -> Result<DataOutput, ZipError> {
+        let hash = hex::decode(file_hash).map_err(|e| ZipError::Validation(e.to_string()))?;
+        Ok(DataOutput::new().push(NOTARY_TAG).push(&hash))
+    }
+
+    /// Verifies a document against this receipt without any network access.
+    pub fn verify_file(&self, bytes: &[u8]) -> Result<NotaryCheck, ZipError> {
+        if Self::hash_file(bytes) != self.file_hash {
+            return Ok(NotaryCheck::HashMismatch);
+        }
+        // The hash must be one of the transaction's outputs, not just somewhere in its bytes
+        let script = Self::data_output(&self.file_hash)?.script()?;
+        let Ok(tx) = TransactionManager::from_hex(&self.tx_hex) else {
+            return Ok(NotaryCheck::TransactionMismatch);
+        };
+        if TransactionManager::txid(&tx) != self.txid
+            || !tx.outputs.iter().any(|output| output.script.0 == script.0)
+        {
+            return Ok(NotaryCheck::TransactionMismatch);
+        }
+        let (Some(proof), Some(raw_header)) = (&self.proof, &self.raw_header) else {
+            return Ok(NotaryCheck::Unconfirmed);
+        };
+        // Header fields are taken from the hashed bytes, never from the stored copy
+        let header = match hex::decode(raw_header)
+            .map_err(|e| ZipError::Validation(e.to_string()))
+            .and_then(|raw| BlockHeader::from_raw(proof.block_height, &raw))
+        {
+            Ok(header) => header,
+            Err(_) => return Ok(NotaryCheck::HeaderInvalid),
+        };
+        if proof.txid != self.txid || !proof.verify(&header)? {
+            return Ok(NotaryCheck::ProofInvalid);
+        }
+        Ok(NotaryCheck::Confirmed {
+            height: header.height,
+            block_hash: header.hash,
+            time: header.time,
+        })
+    }
+}
+
+/// Per-user store of notarisation receipts.
+#[derive(Clone)]
+pub struct Notary {
+    storage: Arc<ZipStorage>,
+}
+
+impl Notary {
+    /// Initializes notary with storage.
+    pub fn new(storage: Arc<ZipStorage>) -> Self {
+        Self { storage }
+    }
+
+    /// Lists receipts, newest first.
+    pub fn list(&self, user_id: Uuid) -> Result<Vec<NotaryReceipt>, ZipError> {
+        let data = self.storage.get_notary_receipts(user_id)?;
+        let mut receipts: Vec<NotaryReceipt> = data
+            .map(|d| {
+                bincode::deserialize(&d)
+                    .or_else(|_| {
+                        bincode::deserialize::<Vec<NotaryReceiptV2>>(&d)
+                            .map(|v2| v2.into_iter().map(NotaryReceipt::from).collect())
+                    })
+                    .or_else(|_| {
+                        bincode::deserialize::<Vec<NotaryReceiptV1>>(&d)
+                            .map(|v1| v1.into_iter().map(NotaryReceipt::from).collect())
+                    })
+                    .unwrap_or_default()
+            })
+            .unwrap_or_default();
+        receipts.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        Ok(receipts)
+    }
+
+    fn store(&self, user_id: Uuid, receipts: &[NotaryReceipt]) -> Result<(), ZipError> {
+        let serialized =
+            bincode::serialize(receipts).map_err(|e| ZipError::Blockchain(e.to_string()))?;
+        self.storage.store_notary_receipts(user_id, &serialized)
+    }
+
+    /// Hashes a document, writes the hash on-chain at the minimum fee for its size
+    /// and stores the receipt.
+    pub async fn notarise(
+        &self,
+        wallet: &WalletManager,
+        user_id: Uuid,
+        file_name: &str,
+        bytes: &[u8],
+    ) -> Result<NotaryReceipt, ZipError> {
+        let file_hash = NotaryReceipt::hash_file(bytes);
+        let data = NotaryReceipt::data_output(&file_hash)?;
+        let fee = wallet.data_fee(user_id, &data)?;
+        let tx = wallet.publish_data(user_id, data, fee).await?;
+        let receipt = NotaryReceipt {
+            id: Uuid::new_v4(),
+            file_name: file_name.to_string(),
+            file_hash,
+            txid: TransactionManager::txid(&tx),
+            tx_hex: TransactionManager::to_hex(&tx),
+            created_at: chrono::Utc::now().timestamp(),
+            proof: None,
+            header: None,
+            raw_header: None,
+        };
+        let mut receipts = self.list(user_id)?;
+        receipts.push(receipt.clone());
+        self.store(user_id, &receipts)?;
+        Ok(receipt)
+    }
+
+    /// Attaches Merkle proofs and raw block headers to receipts whose transactions
+    /// have been mined. Returns the receipts that became confirmed.
+    pub async fn refresh_proofs(
+        &self,
+        wallet: &WalletManager,
+        user_id: Uuid,
+    ) -> Result<Vec<NotaryReceipt>, ZipError> {
+        let mut receipts = self.list(user_id)?;
+        let mut confirmed = vec![];
+        for receipt in receipts.iter_mut().filter(|r| r.raw_header.is_none()) {
+            let Some((proof, header)) = wallet.confirmation_proof(&receipt.txid).await? else {
+                continue;
+            };
+            let Some(raw) = wallet.raw_block_header(&header.hash).await? else {
+                continue;
+            };
+            // The served bytes must be the header the proof was checked against
+            if BlockHeader::from_raw(header.height, &raw)?.hash != header.hash {
+                return Err(ZipError::Blockchain(format!(
+                    "Header for block {} does not match the local chain",
+                    header.hash
+                )));
+            }
+            receipt.proof = Some(proof);
+            receipt.header = Some(header);
+            receipt.raw_header = Some(hex::encode(raw));
+            confirmed.push(receipt.clone());
+        }
+        if !confirmed.is_empty() {
+            self.store(user_id, &receipts)?;
+        }
+        Ok(confirmed)
+    }
+}
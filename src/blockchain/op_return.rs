@@ -0,0 +1,211 @@
+use serde::{Deserialize, Serialize};
+use sv::public_key::PublicKey;
+use sv::script::Script;
+use sv::transaction::TxOut;
+
+use crate::errors::ZipError;
+use crate::utils::crypto::Crypto;
+
+pub const OP_FALSE: u8 = 0x00;
+pub const OP_RETURN: u8 = 0x6a;
+const OP_PUSHDATA1: u8 = 0x4c;
+const OP_PUSHDATA2: u8 = 0x4d;
+const OP_PUSHDATA4: u8 = 0x4e;
+
+/// B:// protocol prefix (file data: content, media type, encoding, filename).
+pub const B_PREFIX: &str = "19HxigV4QyBv3tHpQVcUEQyq1pzZVdoAut";
+/// MAP protocol prefix (Magic Attribute Protocol key/value metadata).
+pub const MAP_PREFIX: &str = "1PuQa7K62MiKCtssSLKy1kh56WWU7MtUR5";
+/// Bitcom pipe separating protocols within one output.
+pub const PROTOCOL_SEPARATOR: &str = "|";
+/// Largest data output script the wallet will build.
+pub const MAX_DATA_SCRIPT_SIZE: usize = 100_000;
+/// Longest plain-text memo, in bytes.
+pub const MAX_MEMO_SIZE: usize = 1_000;
+
+/// Provably unspendable `OP_FALSE OP_RETURN` output carrying data pushes.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct DataOutput {
+    pub pushes: Vec<Vec<u8>>,
+}
+
+impl DataOutput {
+    /// Empty data output; add pushes with `push`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Plain-text memo stored as a single push.
+    pub fn memo(text: &str) -> Result<Self, ZipError> {
+        if text.is_empty() || text.len() > MAX_MEMO_SIZE {
+            return Err(ZipError::Validation(format!(
+                "Memo must be 1 to {} bytes",
+                MAX_MEMO_SIZE
+            )));
+        }
+        Ok(Self::new().push(text.as_bytes()))
+    }
+
+    /// Memo encrypted (base64 BIE1) to the recipient's PayMail PKI key, followed by a
+    /// copy encrypted to the sender's own identity key so it shows in their history
+    /// too. `summary` joins the two ciphertexts with a space.
+    pub fn encrypted_memo(
+        recipient: &PublicKey,
+        sender: &PublicKey,
+        text: &str,
+    ) -> Result<Self, ZipError> {
+        if text.is_empty() || text.len() > MAX_MEMO_SIZE {
+            return Err(ZipError::Validation(format!(
+                "Memo must be 1 to {} bytes",
+                MAX_MEMO_SIZE
+            )));
+        }
+        let for_recipient = Crypto::encrypt_message(recipient, text)?;
+        let for_sender = Crypto::encrypt_message(sender, text)?;
+        Ok(Self::new()
+            .push(for_recipient.as_bytes())
+            .push(for_sender.as_bytes()))
+    }
+
+    /// B:// file record.
+    pub fn b(content: &[u8], media_type: &str, encoding: &str, filename: Option<&str>) -> Self {
+        let output = Self::new()
+            .push(B_PREFIX.as_bytes())
+            .push(content)
+            .push(media_type.as_bytes())
+            .push(encoding.as_bytes());
+        match filename {
+            Some(name) => output.push(name.as_bytes()),
+            None => output,
+        }
+    }
+
+    /// MAP `SET` record with key/value pairs.
+    pub fn map_set(pairs: &[(&str, &str)]) -> Self {
+        pairs.iter().fold(
+            Self::new().push(MAP_PREFIX.as_bytes()).push(b"SET"),
+            |output, (key, value)| output.push(key.as_bytes()).push(value.as_bytes()),
+        )
+    }
+
+    /// Appends a data push.
+    pub fn push(mut self, data: &[u8]) -> Self {
+        self.pushes.push(data.to_vec());
+        self
+    }
+
+    /// Chains another protocol after a Bitcom pipe (e.g., B:// followed by MAP).
+    pub fn pipe(mut self, next: DataOutput) -> Self {
+        self.pushes.push(PROTOCOL_SEPARATOR.as_bytes().to_vec());
+        self.pushes.extend(next.pushes);
+        self
+    }
+
+    /// Encodes the `OP_FALSE OP_RETURN <pushes>` locking script, enforcing the size limit.
+    pub fn script(&self) -> Result<Script, ZipError> {
+        if self.pushes.is_empty() {
+            return Err(ZipError::Validation(
+                "Data output has no pushes".to_string(),
+            ));
+        }
+        let mut script = vec![OP_FALSE, OP_RETURN];
+        for data in &self.pushes {
+            match data.len() {
+                0 => script.push(OP_FALSE),
+                len @ 1..=75 => script.push(len as u8),
+                len @ 76..=0xff => script.extend_from_slice(&[OP_PUSHDATA1, len as u8]),
+                len @ 0x100..=0xffff => {
+                    script.push(OP_PUSHDATA2);
+                    script.extend_from_slice(&(len as u16).to_le_bytes());
+                }
+                len => {
+                    script.push(OP_PUSHDATA4);
+                    script.extend_from_slice(&(len as u32).to_le_bytes());
+                }
+            }
+            script.extend_from_slice(data);
+            if script.len() > MAX_DATA_SCRIPT_SIZE {
+                return Err(ZipError::Validation(format!(
+                    "Data output exceeds {} bytes",
+                    MAX_DATA_SCRIPT_SIZE
+                )));
+            }
+        }
+        Ok(Script(script))
+    }
+
+    /// Zero-value transaction output for this data.
+    pub fn tx_out(&self) -> Result<TxOut, ZipError> {
+        Ok(TxOut {
+            value: 0,
+            script: self.script()?,
+        })
+    }
+
+    /// Serialized size of the output: value, script length prefix and script.
+    pub fn size(&self) -> Result<usize, ZipError> {
+        let len = self.script()?.0.len();
+        let prefix = match len {
+            0..=0xfc => 1,
+            0xfd..=0xffff => 3,
+            _ => 5,
+        };
+        Ok(8 + prefix + len)
+    }
+
+    /// Decodes a data output script; `None` for anything but `OP_FALSE OP_RETURN` data.
+    pub fn parse(script: &[u8]) -> Option<Self> {
+        let mut rest = script.strip_prefix(&[OP_FALSE, OP_RETURN])?;
+        let mut pushes = vec![];
+        while let Some((&op, tail)) = rest.split_first() {
+            let (len, tail) = match op {
+                OP_FALSE => (0, tail),
+                1..=75 => (op as usize, tail),
+                OP_PUSHDATA1 => (*tail.first()? as usize, tail.get(1..)?),
+                OP_PUSHDATA2 => (
+                    u16::from_le_bytes(tail.get(..2)?.try_into().ok()?) as usize,
+                    tail.get(2..)?,
+                ),
+                OP_PUSHDATA4 => (
+                    u32::from_le_bytes(tail.get(..4)?.try_into().ok()?) as usize,
+                    tail.get(4..)?,
+                ),
+                _ => return None,
+            };
+            pushes.push(tail.get(..len)?.to_vec());
+            rest = tail.get(len..)?;
+        }
+        Some(Self { pushes })
+    }
+
+    /// Human-readable summary for history: the memo text, or the protocols used.
+    pub fn summary(&self) -> String {
+        let first = self
+            .pushes
+            .first()
+            .map(|p| p.as_slice())
+            .unwrap_or_default();
+        let protocols: Vec<&str> = std::iter::once(first)
+            .chain(
+                self.pushes
+                    .windows(2)
+                    .filter(|w| w[0] == PROTOCOL_SEPARATOR.as_bytes())
+                    .map(|w| w[1].as_slice()),
+            )
+            .filter_map(|prefix| match std::str::from_utf8(prefix).ok()? {
+                B_PREFIX => Some("B://"),
+                MAP_PREFIX => Some("MAP"),
+                _ => None,
+            })
+            .collect();
+        if protocols.is_empty() {
+            self.pushes
+                .iter()
+                .filter_map(|p| std::str::from_utf8(p).ok())
+                .collect::<Vec<_>>()
+                .join(" ")
+        } else {
+            protocols.join(" | ")
+        }
+    }
+}
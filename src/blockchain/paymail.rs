@@ -7,22 +7,55 @@ use secp256k1::Secp256k1;
 use serde_json::Value;
 use std::collections::HashSet;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use sv::public_key::PublicKey;
 use sv::script::Script;
 use tokio::sync::Mutex;
 use uuid::Uuid;
 
+use crate::blockchain::alerts::Alerts;
+use crate::blockchain::aliases::{
+    AliasLease, AliasOwnership, AliasPurchase, AliasRegistry, AliasStatus, AliasTransfer,
+    RESERVATION_TIMEOUT_SECS, TRANSFER_TIMEOUT_SECS,
+};
+use crate::blockchain::auction::{Auction, AuctionStatus, Auctions, Bid};
+use crate::blockchain::op_return::DataOutput;
+use crate::blockchain::profile::{P2P_DESTINATION_BRFC, PUBLIC_PROFILE_BRFC, PublicProfile};
+use crate::blockchain::{PaymentAcceptance, TransactionManager, WalletManager};
 use crate::config::EnvConfig;
 use crate::errors::ZipError;
+use crate::paymail_config::{AliasQuote, PaymailConfig};
 use crate::storage::ZipStorage;
 use crate::utils::rate_limiter::RateLimiter;
 use crate::utils::telemetry::Telemetry;
 
+/// Local part of the PayMail paid aliases are bought from (000@domain).
+pub const TREASURY_PREFIX: &str = "000";
+/// First push of the OP_RETURN output tagging an alias purchase payment.
+pub const ALIAS_PURCHASE_TAG: &str = "zip.alias";
+/// Miner fee paid when a won auction is settled.
+const AUCTION_SETTLEMENT_FEE: u64 = 1000;
+/// Longest wait for another PayMail host to answer.
+const PAYMAIL_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
 #[derive(Clone)]
 pub struct PaymailManager {
     client: Arc<Mutex<PaymailClient>>,
+    /// HTTP client for capability lookups on other hosts, with `PAYMAIL_REQUEST_TIMEOUT`.
+    http: reqwest::Client,
     domain: String,
     storage: Arc<ZipStorage>,
-    next_prefix: Arc<Mutex<u64>>, // Sequential prefix starting from 101
+    registry: AliasRegistry,
+    auctions: Auctions,
+    /// Serializes bids and settlement so escrow always follows the leading bid.
+    auction_lock: Arc<Mutex<()>>,
+    /// Set once the background auction settlement task is running.
+    settling: Arc<AtomicBool>,
+    /// Set once the background expiry sweep is running.
+    sweeping: Arc<AtomicBool>,
+    alerts: Alerts,
+    pricing: PaymailConfig,
     telemetry: Telemetry,
     rate_limiter: RateLimiter,
 }
@@ -34,23 +67,74 @@ impl PaymailManager {
             // TODO: Add #[derive(Default)] to EnvConfig in src/config/env.rs
             panic!("Failed to load config, EnvConfig requires Default implementation")
         });
-        let domain = config.paymail_domain.unwrap_or("zip.io".to_string());
-        let client = PaymailClient::new(
-            reqwest::Client::new(),
-            &domain,
-            sv::network::Network::Mainnet,
-        )
-        .map_err(|e| panic!("Failed to initialize PaymailClient: {}", e))?;
+        let pricing = PaymailConfig::load();
+        let domain = config.paymail_domain.unwrap_or(pricing.domain.clone());
+        let http = reqwest::Client::builder()
+            .timeout(PAYMAIL_REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_else(|e| panic!("Failed to initialize HTTP client: {}", e));
+        let client = PaymailClient::new(http.clone(), &domain, sv::network::Network::Mainnet)
+            .map_err(|e| panic!("Failed to initialize PaymailClient: {}", e))?;
         Self {
             client: Arc::new(Mutex::new(client)),
+            http,
             domain,
+            registry: AliasRegistry::new(Arc::clone(&storage)),
+            auctions: Auctions::new(Arc::clone(&storage)),
+            auction_lock: Arc::new(Mutex::new(())),
+            settling: Arc::new(AtomicBool::new(false)),
+            sweeping: Arc::new(AtomicBool::new(false)),
+            alerts: Alerts::new(Arc::clone(&storage)),
+            pricing,
             storage,
-            next_prefix: Arc::new(Mutex::new(101)),
             telemetry: Telemetry::new(&config),
             rate_limiter: RateLimiter::new(5, 60), // 5 alias ops per minute
         }
     }
 
+    /// Replaces the alias price list, e.g. with one read by `PaymailConfig::from_file`.
+    pub fn with_pricing(mut self, pricing: PaymailConfig) -> Self {
+        self.pricing = pricing;
+        self
+    }
+
+    /// Domain aliases are issued under (e.g., "zip.io").
+    pub fn domain(&self) -> &str {
+        &self.domain
+    }
+
+    /// Registry recording which user owns each alias issued here.
+    pub fn registry(&self) -> &AliasRegistry {
+        &self.registry
+    }
+
+    /// Price list aliases are sold at.
+    pub fn pricing(&self) -> &PaymailConfig {
+        &self.pricing
+    }
+
+    /// Auctions of premium aliases.
+    pub fn auctions(&self) -> &Auctions {
+        &self.auctions
+    }
+
+    /// User owning an active alias (e.g., "101@zip.io"), if it was issued here.
+    /// Aliases still awaiting payment do not resolve.
+    pub fn alias_owner(&self, alias: &str) -> Result<Option<Uuid>, ZipError> {
+        if !self.is_active(alias)? {
+            return Ok(None);
+        }
+        self.registry.owner(alias)
+    }
+
+    /// True unless the alias is a purchase that has not been completed.
+    fn is_active(&self, alias: &str) -> Result<bool, ZipError> {
+        Ok(self
+            .purchase(alias)?
+            .map(|p| matches!(p.status, AliasStatus::Active { .. }))
+            .unwrap_or(true))
+    }
+
     /// Resolves PayMail to payment script and amount.
     pub async fn resolve_paymail(
         &self,
@@ -74,6 +158,181 @@ impl PaymailManager {
         Ok((script, output.amount.unwrap_or(amount)))
     }
 
+    /// Looks up a PayMail's public identity key through its PKI capability.
+    pub async fn resolve_pki(&self, handle: &str) -> Result<PublicKey, ZipError> {
+        let url = self.capability_url(handle, "pki", "PKI").await?;
+        let pki = self.http.get(url).send().await?.json::<Value>().await?;
+        let pubkey = pki["pubkey"]
+            .as_str()
+            .and_then(|key| hex::decode(key).ok())
+            .ok_or_else(|| ZipError::Blockchain("Invalid PKI response".to_string()))?;
+        PublicKey::from_slice(&pubkey).map_err(|e| ZipError::Crypto(e.to_string()))
+    }
+
+    /// Asks a PayMail's host for a BRC-29 destination derived from `sender`, our
+    /// identity key. Returns the script with the derivation prefix and suffix the host
+    /// chose, or `None` when the host only hands out plain destinations.
+    pub async fn request_private_destination(
+        &self,
+        handle: &str,
+        satoshis: u64,
+        sender: &PublicKey,
+    ) -> Result<Option<(Script, String, String)>, ZipError> {
+        let Ok(url) = self
+            .capability_url(handle, P2P_DESTINATION_BRFC, "P2P payment destination")
+            .await
+        else {
+            return Ok(None);
+        };
+        let response = self
+            .http
+            .post(url)
+            .json(&serde_json::json!({
+                "satoshis": satoshis,
+                "senderIdentityKey": hex::encode(sender.to_bytes()),
+            }))
+            .send()
+            .await?
+            .json::<Value>()
+            .await?;
+        let (Some(prefix), Some(suffix)) = (
+            response["derivationPrefix"].as_str(),
+            response["derivationSuffix"].as_str(),
+        ) else {
+            return Ok(None);
+        };
+        let script = response["outputs"][0]["script"]
+            .as_str()
+            .and_then(|script| hex::decode(script).ok())
+            .ok_or_else(|| ZipError::Blockchain("Invalid P2P destination response".to_string()))?;
+        Ok(Some((
+            Script(script),
+            prefix.to_string(),
+            suffix.to_string(),
+        )))
+    }
+
+    /// Looks up the name and avatar a PayMail's owner publishes, so payers can check
+    /// who they are paying. Aliases issued here are answered without a request.
+    pub async fn resolve_profile(&self, handle: &str) -> Result<PublicProfile, ZipError> {
+        let handle = handle.trim().to_lowercase();
+        let is_local = handle
+            .rsplit_once('@')
+            .is_some_and(|(_, domain)| domain == self.domain.to_lowercase());
+        if is_local {
+            return self.alias_profile(&handle);
+        }
+        let url = self
+            .capability_url(&handle, PUBLIC_PROFILE_BRFC, "public profile")
+            .await?;
+        let profile = self.http.get(url).send().await?.json::<Value>().await?;
+        Ok(self.checked_profile(
+            None,
+            PublicProfile::from_response(&handle, &profile),
+            &handle,
+        ))
+    }
+
+    /// Endpoint a PayMail's host advertises for a capability, filled in for `handle`.
+    async fn capability_url(
+        &self,
+        handle: &str,
+        capability: &str,
+        name: &str,
+    ) -> Result<String, ZipError> {
+        let (alias, host) = handle
+            .split_once('@')
+            .ok_or_else(|| ZipError::Validation(format!("Invalid PayMail: {}", handle)))?;
+        let capabilities = self
+            .http
+            .get(format!("https://{}/.well-known/bsvalias", host))
+            .send()
+            .await?
+            .json::<Value>()
+            .await?;
+        let template = capabilities["capabilities"][capability]
+            .as_str()
+            .ok_or_else(|| ZipError::Blockchain(format!("{} has no {} capability", host, name)))?;
+        Ok(template
+            .replace("{alias}", alias)
+            .replace("{domain.tld}", host))
+    }
+
+    /// Profile a user publishes for their aliases, if they set one.
+    pub fn profile(&self, user_id: Uuid) -> Result<Option<PublicProfile>, ZipError> {
+        Ok(self
+            .storage
+            .get_public_profile(user_id)?
+            .and_then(|d| bincode::deserialize(&d).ok()))
+    }
+
+    /// Sets the display name and avatar served for all of a user's aliases. Clearing
+    /// both goes back to showing the alias itself. Names are held to the reserved and
+    /// blocked words of prefixes, and may not read as another user's alias.
+    pub fn set_profile(
+        &self,
+        user_id: Uuid,
+        name: &str,
+        avatar: &str,
+    ) -> Result<PublicProfile, ZipError> {
+        let profile = PublicProfile::new(name, avatar)?;
+        self.check_display_name(Some(user_id), &profile.name)?;
+        if profile == PublicProfile::default() {
+            self.storage.remove_public_profile(user_id)?;
+            return Ok(profile);
+        }
+        let serialized =
+            bincode::serialize(&profile).map_err(|e| ZipError::Blockchain(e.to_string()))?;
+        self.storage.store_public_profile(user_id, &serialized)?;
+        Ok(profile)
+    }
+
+    /// Public profile served for an active alias issued here.
+    pub fn alias_profile(&self, alias: &str) -> Result<PublicProfile, ZipError> {
+        let owner = self
+            .alias_owner(alias)?
+            .ok_or_else(|| ZipError::Validation(format!("Unknown PayMail: {}", alias)))?;
+        let alias = alias.trim().to_lowercase();
+        let profile = self.profile(owner)?.unwrap_or_default().for_alias(&alias);
+        Ok(self.checked_profile(Some(owner), profile, &alias))
+    }
+
+    /// Rejects a display name that reads as a reserved or blocked word, or as an alias
+    /// here owned by anyone but `user_id`.
+    fn check_display_name(&self, user_id: Option<Uuid>, name: &str) -> Result<(), ZipError> {
+        if name.is_empty() {
+            return Ok(());
+        }
+        self.pricing.validate_display_name(name)?;
+        let alias = format!("{}@{}", name, self.domain);
+        if let Some(existing) = self.registry.lookalike(&alias, user_id)? {
+            return Err(ZipError::Validation(format!(
+                "Display name looks too much like {}",
+                existing
+            )));
+        }
+        Ok(())
+    }
+
+    /// Profile shown for `handle`, with the handle's local part standing in for a name
+    /// that fails `check_display_name`; names set before the check, or served by other
+    /// hosts, cannot pass for someone else either.
+    fn checked_profile(
+        &self,
+        user_id: Option<Uuid>,
+        profile: PublicProfile,
+        handle: &str,
+    ) -> PublicProfile {
+        if self.check_display_name(user_id, &profile.name).is_ok() {
+            return profile;
+        }
+        PublicProfile {
+            name: String::new(),
+            ..profile
+        }
+        .for_alias(handle)
+    }
+
     /// Sends transaction P2P if supported, else returns placeholder for node broadcast.
     pub async fn send_p2p_tx(
         &self,
@@ -113,44 +372,19 @@ impl PaymailManager {
         bespoke_prefix: Option<&str>,
     ) -> Result<(String, Decimal), ZipError> {
         self.rate_limiter.check(&user_id.to_string()).await?;
-        let prefix = {
-            let mut next = self.next_prefix.lock().await;
-            let p = *next;
-            *next += 1;
-            p.to_string()
-        };
-        let default_alias = format!("{}@{}", prefix, self.domain);
-        let aliases = self.get_user_aliases(user_id).await?;
-        let is_first = aliases.is_empty();
-        let mut new_aliases = aliases;
-        // Store default alias
-        new_aliases.insert(default_alias.clone());
-        let serialized =
-            bincode::serialize(&new_aliases).map_err(|e| ZipError::Blockchain(e.to_string()))?;
-        self.storage.store_user_data(user_id, &serialized)?;
+        let is_first = self.user_aliases(user_id)?.is_empty();
+        let default_alias = self.registry.allocate(&self.domain, user_id)?;
         let _ = self
             .telemetry
             .track_payment_event(&user_id.to_string(), "create_default_alias", 0, true)
             .await;
-        // Handle bespoke alias (free if first, 5+ digits)
+        // A bespoke alias is priced from the premium list or by length; only a first
+        // alias of "101" is free
         if let Some(prefix) = bespoke_prefix {
-            if prefix.is_empty() || prefix.contains('@') || prefix.contains('.') || prefix.len() < 5
-            {
-                return Err(ZipError::Blockchain("Invalid bespoke prefix".to_string()));
-            }
-            let price = if is_first && prefix == "101" {
-                Decimal::ZERO
-            } else {
-                match prefix.len() {
-                    5 => Decimal::from(10),
-                    _ => Decimal::from(5),
-                }
-            };
-            let bespoke_alias = format!("{}@{}", prefix, self.domain);
-            new_aliases.insert(bespoke_alias.clone());
-            let serialized = bincode::serialize(&new_aliases)
-                .map_err(|e| ZipError::Blockchain(e.to_string()))?;
-            self.storage.store_user_data(user_id, &serialized)?;
+            self.pricing.validate_prefix(prefix)?;
+            self.check_fixed_price(prefix)?;
+            let price = self.pricing.get_prefix_price(prefix, is_first);
+            let bespoke_alias = self.reserve(user_id, prefix, price, 0)?.alias;
             let _ = self
                 .telemetry
                 .track_payment_event(
@@ -166,6 +400,557 @@ impl PaymailManager {
         }
     }
 
+    /// Rejects prefixes that are only sold by auction.
+    fn check_fixed_price(&self, prefix: &str) -> Result<(), ZipError> {
+        if self.pricing.is_auctioned(prefix) {
+            return Err(ZipError::Validation(format!(
+                "{} is sold by auction",
+                prefix.to_lowercase()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Reserves an alias for a user. Free aliases are active at once; priced ones are
+    /// held until paid or until the reservation times out. `satoshis` fixes the amount
+    /// due (e.g., a winning bid); zero leaves it to the quote at payment. Reserving
+    /// again returns the existing purchase.
+    fn reserve(
+        &self,
+        user_id: Uuid,
+        prefix: &str,
+        price: Decimal,
+        satoshis: u64,
+    ) -> Result<AliasPurchase, ZipError> {
+        let now = chrono::Utc::now().timestamp();
+        self.expire_leases(now)?;
+        self.expire_reservations(now)?;
+        let alias = self
+            .registry
+            .register(&format!("{}@{}", prefix, self.domain), user_id)?;
+        if let Some(purchase) = self.purchase(&alias)? {
+            if purchase.owner == user_id && purchase.status != AliasStatus::Expired {
+                return Ok(purchase);
+            }
+        }
+        let purchase = AliasPurchase {
+            alias,
+            owner: user_id,
+            reference: Uuid::new_v4().simple().to_string(),
+            price,
+            satoshis,
+            payment_script: vec![],
+            status: if price.is_zero() && satoshis == 0 {
+                AliasStatus::Active {
+                    txid: String::new(),
+                }
+            } else {
+                AliasStatus::Reserved
+            },
+            reserved_at: now,
+            updated_at: now,
+        };
+        self.store_purchase(&purchase)?;
+        Ok(purchase)
+    }
+
+    /// Purchase state of an alias bought here, if any.
+    pub fn purchase(&self, alias: &str) -> Result<Option<AliasPurchase>, ZipError> {
+        Ok(self
+            .storage
+            .get_alias_purchase(alias)?
+            .and_then(|d| bincode::deserialize(&d).ok()))
+    }
+
+    fn store_purchase(&self, purchase: &AliasPurchase) -> Result<(), ZipError> {
+        let previous = self.storage.get_alias_purchase(&purchase.alias)?;
+        if self.replace_purchase(previous.as_deref(), purchase)? {
+            return Ok(());
+        }
+        Err(ZipError::Blockchain(format!(
+            "{} changed while it was being updated",
+            purchase.alias
+        )))
+    }
+
+    /// Stores a purchase if the stored one still reads `previous`, keeping the deadline
+    /// index in step.
+    fn replace_purchase(
+        &self,
+        previous: Option<&[u8]>,
+        purchase: &AliasPurchase,
+    ) -> Result<bool, ZipError> {
+        let serialized =
+            bincode::serialize(purchase).map_err(|e| ZipError::Blockchain(e.to_string()))?;
+        self.storage.update_alias_purchase(
+            &purchase.alias,
+            previous,
+            &serialized,
+            (Self::deadline_of(previous), purchase.deadline()),
+        )
+    }
+
+    /// Stores a purchase if the stored one still reads `previous` and its buyer still
+    /// owns the alias; with `release`, the alias goes back to the pool along with it.
+    fn settle_purchase(
+        &self,
+        previous: &[u8],
+        purchase: &AliasPurchase,
+        release: bool,
+    ) -> Result<bool, ZipError> {
+        let serialized =
+            bincode::serialize(purchase).map_err(|e| ZipError::Blockchain(e.to_string()))?;
+        let event = AliasOwnership {
+            from: Some(purchase.owner),
+            to: None,
+            timestamp: purchase.updated_at,
+        };
+        let event = bincode::serialize(&event).map_err(|e| ZipError::Blockchain(e.to_string()))?;
+        self.storage.settle_alias_purchase(
+            &purchase.alias,
+            purchase.owner,
+            previous,
+            &serialized,
+            (Self::deadline_of(Some(previous)), purchase.deadline()),
+            release.then_some(event.as_slice()),
+        )
+    }
+
+    fn deadline_of(data: Option<&[u8]>) -> Option<i64> {
+        data.and_then(|d| bincode::deserialize::<AliasPurchase>(d).ok())
+            .and_then(|p| p.deadline())
+    }
+
+    /// Adds purchases stored before the deadline index existed to it, once.
+    fn index_deadlines(&self) -> Result<(), ZipError> {
+        if self.storage.alias_deadlines_indexed()? {
+            return Ok(());
+        }
+        for data in self.storage.get_alias_purchases()? {
+            let Ok(purchase) = bincode::deserialize::<AliasPurchase>(&data) else {
+                continue;
+            };
+            if let Some(deadline) = purchase.deadline() {
+                self.storage
+                    .index_alias_deadline(&purchase.alias, deadline)?;
+            }
+        }
+        self.storage.set_alias_deadlines_indexed()
+    }
+
+    /// Reservation a user holds on an alias.
+    fn owned_purchase(&self, user_id: Uuid, alias: &str) -> Result<AliasPurchase, ZipError> {
+        self.purchase(alias)?
+            .filter(|p| p.owner == user_id && p.status != AliasStatus::Expired)
+            .ok_or_else(|| ZipError::Blockchain("Alias not found".to_string()))
+    }
+
+    /// Releases reservations left unpaid, and payments the network has not accepted,
+    /// `RESERVATION_TIMEOUT_SECS` after they started. Due purchases are read from the
+    /// deadline index, and each is expired in the same transaction that releases it.
+    /// Returns the released aliases.
+    pub fn expire_reservations(&self, now: i64) -> Result<Vec<String>, ZipError> {
+        self.index_deadlines()?;
+        let mut released = vec![];
+        for (deadline, alias) in self.storage.get_due_alias_purchases(now)? {
+            let stored = self.storage.get_alias_purchase(&alias)?;
+            let Some((previous, mut purchase)) = stored.and_then(|d| {
+                let purchase = bincode::deserialize::<AliasPurchase>(&d).ok()?;
+                Some((d, purchase))
+            }) else {
+                self.storage.remove_alias_deadline(&alias, deadline)?;
+                continue;
+            };
+            if purchase.deadline() != Some(deadline) {
+                // Left behind by a purchase written before the index existed
+                self.storage.remove_alias_deadline(&alias, deadline)?;
+                continue;
+            }
+            let pending = matches!(purchase.status, AliasStatus::PaymentPending { .. });
+            purchase.status = AliasStatus::Expired;
+            purchase.updated_at = now;
+            let expired = if self.registry.owner(&alias)? == Some(purchase.owner) {
+                self.settle_purchase(&previous, &purchase, true)?
+            } else {
+                self.replace_purchase(Some(&previous), &purchase)?
+            };
+            if !expired {
+                // Paid or activated meanwhile; its deadline moved with it
+                continue;
+            }
+            if let Some(escrow) = self.won_escrow(purchase.owner, &alias)? {
+                TransactionManager::new(Arc::clone(&self.storage), None)
+                    .release_escrow(purchase.owner, &escrow)?;
+            }
+            if pending {
+                self.alerts.push(
+                    purchase.owner,
+                    format!(
+                        "The network did not accept the payment for {} in time; it has been released",
+                        purchase.alias
+                    ),
+                    None,
+                )?;
+            }
+            released.push(purchase.alias);
+        }
+        Ok(released)
+    }
+
+    /// Pays for a reserved alias: pays the amount due, or else the locked quote, to the
+    /// treasury PayMail with the purchase reference in an OP_RETURN output. Returns the
+    /// payment's TXID.
+    pub async fn pay_alias(
+        &self,
+        wallet: &WalletManager,
+        user_id: Uuid,
+        alias: &str,
+        fee: u64,
+    ) -> Result<String, ZipError> {
+        let mut purchase = self.owned_purchase(user_id, alias)?;
+        if purchase.status != AliasStatus::Reserved {
+            return Err(ZipError::Blockchain(format!(
+                "{} is not awaiting payment",
+                purchase.alias
+            )));
+        }
+        let satoshis = match purchase.satoshis {
+            0 => {
+                let prefix = purchase.alias.split('@').next().unwrap_or_default();
+                self.quote_alias(wallet, user_id, prefix).await?.satoshis
+            }
+            due => due,
+        };
+        let treasury = format!("{}@{}", TREASURY_PREFIX, self.domain);
+        let (script, _) = self.resolve_paymail(&treasury, satoshis).await?;
+        purchase.satoshis = satoshis;
+        purchase.payment_script = script.0.clone();
+        purchase.updated_at = chrono::Utc::now().timestamp();
+        self.store_purchase(&purchase)?;
+        let reference = DataOutput::new()
+            .push(ALIAS_PURCHASE_TAG.as_bytes())
+            .push(purchase.reference.as_bytes());
+        // A won auction is paid with the winning bid's escrowed coins
+        let tx = match self.won_escrow(user_id, &purchase.alias)? {
+            Some(escrow) => {
+                wallet
+                    .send_escrowed(user_id, &escrow, script, satoshis, fee, &[reference])
+                    .await?
+            }
+            None => {
+                wallet
+                    .send_transaction(user_id, script, satoshis, fee, &[reference])
+                    .await?
+            }
+        };
+        self.submit_alias_payment(user_id, &purchase.alias, &TransactionManager::to_hex(&tx))
+    }
+
+    /// Matches a transaction to a reserved alias: it must carry the purchase reference
+    /// and pay at least the amount due to the treasury output chosen for it.
+    pub fn submit_alias_payment(
+        &self,
+        user_id: Uuid,
+        alias: &str,
+        tx_hex: &str,
+    ) -> Result<String, ZipError> {
+        let mut purchase = self.owned_purchase(user_id, alias)?;
+        if purchase.status != AliasStatus::Reserved || purchase.payment_script.is_empty() {
+            return Err(ZipError::Blockchain(format!(
+                "{} is not awaiting payment",
+                purchase.alias
+            )));
+        }
+        let tx = TransactionManager::from_hex(tx_hex)?;
+        let txid = TransactionManager::txid(&tx);
+        let referenced = tx
+            .outputs
+            .iter()
+            .filter_map(|output| DataOutput::parse(&output.script.0))
+            .any(|data| {
+                data.pushes
+                    .iter()
+                    .any(|p| *p == purchase.reference.as_bytes())
+            });
+        if !referenced {
+            return Err(ZipError::Blockchain(format!(
+                "Transaction {} does not reference the purchase of {}",
+                txid, purchase.alias
+            )));
+        }
+        let paid: u64 = tx
+            .outputs
+            .iter()
+            .filter(|output| output.script.0 == purchase.payment_script)
+            .map(|output| output.value)
+            .sum();
+        if paid < purchase.satoshis {
+            return Err(ZipError::Blockchain(format!(
+                "Transaction {} pays {} of {} satoshis due for {}",
+                txid, paid, purchase.satoshis, purchase.alias
+            )));
+        }
+        purchase.status = AliasStatus::PaymentPending { txid: txid.clone() };
+        purchase.updated_at = chrono::Utc::now().timestamp();
+        self.store_purchase(&purchase)?;
+        Ok(txid)
+    }
+
+    /// Opens an auction for a premium prefix, with its fixed price in satoshis at the
+    /// current BSV price as the reserve.
+    pub async fn open_auction(
+        &self,
+        wallet: &WalletManager,
+        prefix: &str,
+    ) -> Result<Auction, ZipError> {
+        let now = chrono::Utc::now().timestamp();
+        let bsv_price = wallet.fetch_price(&self.pricing.currency).await?;
+        let quote = self.pricing.quote(prefix, false, bsv_price, now)?;
+        self.start_auction(prefix, quote.satoshis, now).await
+    }
+
+    /// Opens an auction for a premium prefix with a reserve in satoshis. An auction
+    /// already running for the prefix is returned as is.
+    pub async fn start_auction(
+        &self,
+        prefix: &str,
+        reserve: u64,
+        now: i64,
+    ) -> Result<Auction, ZipError> {
+        self.pricing.validate_prefix(prefix)?;
+        if !self.pricing.is_auctioned(prefix) {
+            return Err(ZipError::Validation(format!(
+                "{} is not sold by auction",
+                prefix.to_lowercase()
+            )));
+        }
+        let alias = format!("{}@{}", prefix, self.domain).to_lowercase();
+        let _guard = self.auction_lock.lock().await;
+        if let Some(auction) = self.auctions.get(&alias)? {
+            if auction.status == AuctionStatus::Open {
+                if auction.is_over_at(now) {
+                    return Err(ZipError::Validation(format!(
+                        "Auction for {} is awaiting settlement",
+                        alias
+                    )));
+                }
+                return Ok(auction);
+            }
+        }
+        if self.registry.owner(&alias)?.is_some() {
+            return Err(ZipError::Validation(format!("{} is already taken", alias)));
+        }
+        let auction = Auction::new(&alias, reserve, now, self.pricing.auction_duration_secs);
+        self.auctions.store(&auction)?;
+        Ok(auction)
+    }
+
+    /// Bids on an open auction. The bid's coins are held in the bidder's wallet until
+    /// the bid is outbid or the auction settles; the bid it displaces is released and
+    /// its bidder alerted.
+    pub async fn bid_alias(
+        &self,
+        wallet: &WalletManager,
+        user_id: Uuid,
+        alias: &str,
+        satoshis: u64,
+        now: i64,
+    ) -> Result<Auction, ZipError> {
+        self.rate_limiter.check(&user_id.to_string()).await?;
+        let _guard = self.auction_lock.lock().await;
+        let mut auction = self
+            .auctions
+            .get(alias)?
+            .filter(|auction| auction.status == AuctionStatus::Open)
+            .ok_or_else(|| ZipError::Validation(format!("No open auction for {}", alias)))?;
+        let escrow = format!("bid:{}:{}", auction.id.simple(), Uuid::new_v4().simple());
+        wallet.escrow(user_id, &escrow, satoshis)?;
+        let bid = Bid {
+            bidder: user_id,
+            satoshis,
+            escrow: escrow.clone(),
+            placed_at: now,
+        };
+        let displaced = match auction.place_bid(
+            bid,
+            self.pricing.bid_increment_percent,
+            self.pricing.auction_extension_secs,
+        ) {
+            Ok(displaced) => displaced,
+            Err(e) => {
+                wallet.release_escrow(user_id, &escrow)?;
+                return Err(e);
+            }
+        };
+        self.auctions.store(&auction)?;
+        if let Some(outbid) = displaced {
+            wallet.release_escrow(outbid.bidder, &outbid.escrow)?;
+            if outbid.bidder != user_id {
+                wallet.notify(
+                    outbid.bidder,
+                    format!(
+                        "Outbid on {}; your {} satoshis are spendable again",
+                        auction.alias, outbid.satoshis
+                    ),
+                )?;
+            }
+        }
+        let _ = self
+            .telemetry
+            .track_payment_event(&user_id.to_string(), "bid_alias", satoshis, true)
+            .await;
+        Ok(auction)
+    }
+
+    /// Satoshis a user has held in escrow by leading bids.
+    pub fn escrowed(&self, user_id: Uuid) -> Result<u64, ZipError> {
+        Ok(self
+            .auctions
+            .open()?
+            .iter()
+            .filter_map(Auction::leading)
+            .filter(|bid| bid.bidder == user_id)
+            .map(|bid| bid.satoshis)
+            .sum())
+    }
+
+    /// Escrow still holding the winning bid of a user's auction for an alias.
+    fn won_escrow(&self, user_id: Uuid, alias: &str) -> Result<Option<String>, ZipError> {
+        Ok(self.auctions.get(alias)?.and_then(|auction| {
+            match (&auction.status, auction.leading()) {
+                (AuctionStatus::Settled { winner, .. }, Some(bid)) if *winner == user_id => {
+                    Some(bid.escrow.clone())
+                }
+                _ => None,
+            }
+        }))
+    }
+
+    /// Starts a background task that settles auctions as they end, so winners pay and
+    /// losing bids are released without anyone visiting an auction. Does nothing if
+    /// the task is already running.
+    pub fn start_auction_settler(&self, wallet: WalletManager, interval: Duration) {
+        if self.settling.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let paymail = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let now = chrono::Utc::now().timestamp();
+                if let Err(e) = paymail.settle_auctions(&wallet, now).await {
+                    tracing::warn!("Auction settlement failed: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Starts a background task that settles renewals and expires lapsed leases and
+    /// reservations every `interval`, so aliases go back to the pool without anyone
+    /// visiting the aliases page. Does nothing if the task is already running.
+    pub fn start_expiry_sweeper(&self, wallet: WalletManager, interval: Duration) {
+        if self.sweeping.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let paymail = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let now = chrono::Utc::now().timestamp();
+                if let Err(e) = paymail.sweep_expired(&wallet, now).await {
+                    tracing::warn!("Alias expiry sweep failed: {}", e);
+                }
+            }
+        });
+    }
+
+    /// One pass of the expiry sweep; renewals are settled first so an accepted one
+    /// keeps its alias.
+    pub async fn sweep_expired(&self, wallet: &WalletManager, now: i64) -> Result<(), ZipError> {
+        self.settle_renewals(wallet, now).await?;
+        self.expire_leases(now)?;
+        self.expire_reservations(now)?;
+        Ok(())
+    }
+
+    /// Closes auctions that have ended. A winner gets the alias reserved at the winning
+    /// bid and pays for it with the bid's escrowed coins straight away, or within the
+    /// reservation timeout if that payment fails; the escrow is held until the payment
+    /// succeeds or the reservation lapses. An unsold auction's bid is released. Returns
+    /// the auctions closed.
+    pub async fn settle_auctions(
+        &self,
+        wallet: &WalletManager,
+        now: i64,
+    ) -> Result<Vec<Auction>, ZipError> {
+        let mut closed = vec![];
+        let mut won = vec![];
+        {
+            let _guard = self.auction_lock.lock().await;
+            for mut auction in self.auctions.open()? {
+                let Some(status) = auction.settle(now) else {
+                    continue;
+                };
+                self.auctions.store(&auction)?;
+                match status {
+                    AuctionStatus::Settled { winner, satoshis } => {
+                        let prefix = auction.alias.split('@').next().unwrap_or_default();
+                        let price = self.pricing.get_prefix_price(prefix, false);
+                        match self.reserve(winner, prefix, price, satoshis) {
+                            Ok(_) => won.push((winner, auction.alias.clone(), satoshis)),
+                            Err(e) => {
+                                tracing::warn!(
+                                    "Auction winner of {} not reserved: {}",
+                                    auction.alias,
+                                    e
+                                );
+                                if let Some(bid) = auction.leading() {
+                                    wallet.release_escrow(bid.bidder, &bid.escrow)?;
+                                }
+                            }
+                        }
+                    }
+                    AuctionStatus::Unsold => {
+                        if let Some(bid) = auction.leading() {
+                            wallet.release_escrow(bid.bidder, &bid.escrow)?;
+                            wallet.notify(
+                                bid.bidder,
+                                format!(
+                                    "Reserve not met for {}; your {} satoshis are spendable again",
+                                    auction.alias, bid.satoshis
+                                ),
+                            )?;
+                        }
+                    }
+                    AuctionStatus::Open => {}
+                }
+                closed.push(auction);
+            }
+        }
+        // Paying waits on the network, so bids are not held up meanwhile
+        for (winner, alias, satoshis) in won {
+            let message = match self
+                .pay_alias(wallet, winner, &alias, AUCTION_SETTLEMENT_FEE)
+                .await
+            {
+                Ok(_) => format!("You won {} for {} satoshis", alias, satoshis),
+                Err(e) => {
+                    tracing::warn!("Auction payment for {} failed: {}", alias, e);
+                    format!(
+                        "You won {} for {} satoshis; pay within {} minutes to keep it",
+                        alias,
+                        satoshis,
+                        RESERVATION_TIMEOUT_SECS / 60
+                    )
+                }
+            };
+            wallet.notify(winner, message)?;
+        }
+        Ok(closed)
+    }
+
     /// Creates a paid PayMail alias, requiring payment to 000@zip.io.
     pub async fn create_paid_alias(
         &self,
@@ -173,26 +958,11 @@ impl PaymailManager {
         prefix: &str,
     ) -> Result<(String, Decimal), ZipError> {
         self.rate_limiter.check(&user_id.to_string()).await?;
-        if prefix.is_empty() || prefix.contains('@') || prefix.contains('.') || prefix.len() < 5 {
-            return Err(ZipError::Blockchain("Invalid prefix".to_string()));
-        }
-        let aliases = self.get_user_aliases(user_id).await?;
-        let is_first = aliases.is_empty();
-        let price = if is_first && prefix == "101" {
-            Decimal::ZERO
-        } else {
-            match prefix.len() {
-                5 => Decimal::from(10),
-                _ => Decimal::from(5),
-            }
-        };
-        let alias = format!("{}@{}", prefix, self.domain);
-        // Store pending alias
-        let mut new_aliases = aliases;
-        new_aliases.insert(alias.clone());
-        let serialized =
-            bincode::serialize(&new_aliases).map_err(|e| ZipError::Blockchain(e.to_string()))?;
-        self.storage.store_user_data(user_id, &serialized)?;
+        self.pricing.validate_prefix(prefix)?;
+        self.check_fixed_price(prefix)?;
+        let is_first = self.user_aliases(user_id)?.is_empty();
+        let price = self.pricing.get_prefix_price(prefix, is_first);
+        let alias = self.reserve(user_id, prefix, price, 0)?.alias;
         let _ = self
             .telemetry
             .track_payment_event(
@@ -205,14 +975,63 @@ impl PaymailManager {
         Ok((alias, price))
     }
 
-    /// Confirms alias after payment to 000@zip.io.
-    pub async fn confirm_alias(&self, user_id: Uuid, alias: &str) -> Result<(), ZipError> {
-        self.rate_limiter.check(&user_id.to_string()).await?;
-        let aliases = self.get_user_aliases(user_id).await?;
-        if !aliases.contains(alias) {
-            return Err(ZipError::Blockchain("Alias not found".to_string()));
+    /// Quotes an available prefix in satoshis at the current BSV price. The quote is
+    /// stored and honoured until it expires, however the price moves meanwhile.
+    pub async fn quote_alias(
+        &self,
+        wallet: &WalletManager,
+        user_id: Uuid,
+        prefix: &str,
+    ) -> Result<AliasQuote, ZipError> {
+        self.pricing.validate_prefix(prefix)?;
+        self.check_fixed_price(prefix)?;
+        let now = chrono::Utc::now().timestamp();
+        self.expire_leases(now)?;
+        self.expire_reservations(now)?;
+        let alias = format!("{}@{}", prefix, self.domain).to_lowercase();
+        if let Some(owner) = self.registry.owner(&alias)? {
+            if owner != user_id {
+                return Err(ZipError::Validation(format!("{} is already taken", alias)));
+            }
         }
-        // Notify PayMail service (placeholder)
+        if let Some(quote) = self.locked_quote(user_id, &alias, now)? {
+            return Ok(quote);
+        }
+        let is_first = self.user_aliases(user_id)?.is_empty();
+        let bsv_price = wallet.fetch_price(&self.pricing.currency).await?;
+        let mut quote = self.pricing.quote(prefix, is_first, bsv_price, now)?;
+        quote.alias = alias;
+        let serialized =
+            bincode::serialize(&quote).map_err(|e| ZipError::Blockchain(e.to_string()))?;
+        self.storage
+            .store_alias_quote(user_id, &quote.alias, &serialized)?;
+        Ok(quote)
+    }
+
+    /// Unexpired quote previously issued to a user for an alias.
+    pub fn locked_quote(
+        &self,
+        user_id: Uuid,
+        alias: &str,
+        now: i64,
+    ) -> Result<Option<AliasQuote>, ZipError> {
+        Ok(self
+            .storage
+            .get_alias_quote(user_id, alias)?
+            .and_then(|d| bincode::deserialize::<AliasQuote>(&d).ok())
+            .filter(|quote| !quote.is_expired_at(now)))
+    }
+
+    /// Activates a paid alias once its payment to 000@zip.io has been accepted by the
+    /// network. Aliases that needed no payment are already active.
+    pub async fn confirm_alias(
+        &self,
+        wallet: &WalletManager,
+        user_id: Uuid,
+        alias: &str,
+    ) -> Result<(), ZipError> {
+        self.rate_limiter.check(&user_id.to_string()).await?;
+        self.activate(wallet, user_id, alias).await?;
         let _ = self
             .telemetry
             .track_payment_event(&user_id.to_string(), "confirm_alias", 0, true)
@@ -220,17 +1039,489 @@ impl PaymailManager {
         Ok(())
     }
 
+    /// Activates a user's aliases whose payments the network has accepted since they
+    /// were matched. Returns the activated aliases.
+    pub async fn activate_pending(
+        &self,
+        wallet: &WalletManager,
+        user_id: Uuid,
+    ) -> Result<Vec<String>, ZipError> {
+        let mut activated = vec![];
+        for alias in self.registry.aliases(user_id)? {
+            if let Some(AliasPurchase {
+                status: AliasStatus::PaymentPending { .. },
+                ..
+            }) = self.purchase(&alias)?
+            {
+                if self.activate(wallet, user_id, &alias).await.is_ok() {
+                    activated.push(alias);
+                }
+            }
+        }
+        Ok(activated)
+    }
+
+    /// Activates a purchase once the wallet proves its payment accepted; a failed
+    /// payment leaves the reservation to be paid again. The purchase only changes if
+    /// nothing else changed it meanwhile and its buyer still owns the alias.
+    async fn activate(
+        &self,
+        wallet: &WalletManager,
+        user_id: Uuid,
+        alias: &str,
+    ) -> Result<(), ZipError> {
+        if self.registry.owner(alias)? != Some(user_id) {
+            return Err(ZipError::Blockchain("Alias not found".to_string()));
+        }
+        let Some(previous) = self.storage.get_alias_purchase(alias)? else {
+            return Ok(());
+        };
+        let mut purchase = bincode::deserialize::<AliasPurchase>(&previous)
+            .map_err(|e| ZipError::Blockchain(e.to_string()))?;
+        let txid = match &purchase.status {
+            AliasStatus::Active { .. } => return Ok(()),
+            AliasStatus::PaymentPending { txid } => txid.clone(),
+            AliasStatus::Reserved | AliasStatus::Expired => {
+                return Err(ZipError::Blockchain(format!(
+                    "{} has not been paid for",
+                    purchase.alias
+                )));
+            }
+        };
+        let now = chrono::Utc::now().timestamp();
+        match wallet.payment_acceptance(user_id, &txid).await? {
+            PaymentAcceptance::Accepted => {
+                purchase.status = AliasStatus::Active { txid: txid.clone() };
+            }
+            PaymentAcceptance::Waiting => {
+                return Err(ZipError::Blockchain(format!(
+                    "Payment {} has not been accepted by the network yet",
+                    txid
+                )));
+            }
+            PaymentAcceptance::Failed => {
+                // Lost or rejected; the reservation can be paid again
+                purchase.status = AliasStatus::Reserved;
+                purchase.reserved_at = now;
+            }
+        }
+        purchase.updated_at = now;
+        if !self.settle_purchase(&previous, &purchase, false)? {
+            return Err(ZipError::Blockchain(format!(
+                "{} changed while its payment was being checked",
+                purchase.alias
+            )));
+        }
+        if purchase.status == AliasStatus::Reserved {
+            return Err(ZipError::Blockchain(format!(
+                "Payment {} for {} failed",
+                txid, purchase.alias
+            )));
+        }
+        self.start_lease(user_id, &purchase.alias, now)
+    }
+
+    /// Paid term of a renewable alias, if it has one.
+    pub fn lease(&self, alias: &str) -> Result<Option<AliasLease>, ZipError> {
+        Ok(self
+            .storage
+            .get_alias_lease(alias)?
+            .and_then(|d| AliasLease::decode(&d).ok()))
+    }
+
+    fn store_lease(&self, lease: &AliasLease) -> Result<(), ZipError> {
+        let serialized =
+            bincode::serialize(lease).map_err(|e| ZipError::Blockchain(e.to_string()))?;
+        self.storage.store_alias_lease(&lease.alias, &serialized)
+    }
+
+    /// Starts the first paid term of a newly active premium alias.
+    fn start_lease(&self, user_id: Uuid, alias: &str, now: i64) -> Result<(), ZipError> {
+        let prefix = alias.split('@').next().unwrap_or_default();
+        if !self.pricing.is_renewable(prefix) || self.lease(alias)?.is_some() {
+            return Ok(());
+        }
+        self.store_lease(&AliasLease {
+            alias: alias.to_lowercase(),
+            owner: user_id,
+            paid_until: now + self.pricing.renewal_period_secs,
+            renewals: vec![],
+            pending_renewal: None,
+            pending_since: 0,
+            grace_notified: false,
+        })
+    }
+
+    /// Extends a lease by one term once the network has accepted its renewal payment
+    /// (see `WalletManager::payment_acceptance`). A rejected payment, or one still
+    /// waiting `RENEWAL_TIMEOUT_SECS` after it was paid, is dropped so the owner can pay
+    /// again.
+    async fn settle_renewal(
+        &self,
+        wallet: &WalletManager,
+        lease: &mut AliasLease,
+        now: i64,
+    ) -> Result<(), ZipError> {
+        let Some(txid) = lease.pending_renewal.clone() else {
+            return Ok(());
+        };
+        match wallet.payment_acceptance(lease.owner, &txid).await? {
+            PaymentAcceptance::Accepted => {
+                lease.paid_until += self.pricing.renewal_period_secs;
+                lease.renewals.push(txid);
+                lease.grace_notified = false;
+            }
+            PaymentAcceptance::Waiting if lease.renewal_pending_at(now) => return Ok(()),
+            PaymentAcceptance::Waiting | PaymentAcceptance::Failed => {
+                self.alerts.push(
+                    lease.owner,
+                    format!(
+                        "The network did not accept the renewal of {}; renew again to keep it",
+                        lease.alias
+                    ),
+                    None,
+                )?;
+            }
+        }
+        lease.pending_renewal = None;
+        Ok(())
+    }
+
+    /// Settles every pending renewal. A lease that changed while its payment was being
+    /// checked is left for the next pass. Returns the renewed aliases.
+    pub async fn settle_renewals(
+        &self,
+        wallet: &WalletManager,
+        now: i64,
+    ) -> Result<Vec<String>, ZipError> {
+        let mut renewed = vec![];
+        for data in self.storage.get_alias_leases()? {
+            let Ok(mut lease) = AliasLease::decode(&data) else {
+                continue;
+            };
+            if lease.pending_renewal.is_none() {
+                continue;
+            }
+            let before = lease.clone();
+            self.settle_renewal(wallet, &mut lease, now).await?;
+            if self.lease(&lease.alias)?.as_ref() != Some(&before) {
+                continue;
+            }
+            self.store_lease(&lease)?;
+            if lease.renewals.len() > before.renewals.len() {
+                renewed.push(lease.alias);
+            }
+        }
+        Ok(renewed)
+    }
+
+    /// Pays for another term of a premium alias at its current price, through the same
+    /// treasury payment as a purchase. The term is added from the end of the current
+    /// one, so renewing early or during the grace period loses nothing.
+    pub async fn renew_alias(
+        &self,
+        wallet: &WalletManager,
+        user_id: Uuid,
+        alias: &str,
+        fee: u64,
+    ) -> Result<AliasLease, ZipError> {
+        self.rate_limiter.check(&user_id.to_string()).await?;
+        let now = chrono::Utc::now().timestamp();
+        let mut lease = self
+            .lease(alias)?
+            .filter(|lease| lease.owner == user_id)
+            .ok_or_else(|| ZipError::Blockchain("Alias not found".to_string()))?;
+        self.settle_renewal(wallet, &mut lease, now).await?;
+        self.store_lease(&lease)?;
+        if lease.is_lapsed_at(now, self.pricing.grace_period_secs) {
+            return Err(ZipError::Validation(format!("{} has expired", lease.alias)));
+        }
+        if lease.pending_renewal.is_some() {
+            return Err(ZipError::Validation(format!(
+                "A renewal of {} is already waiting for the network",
+                lease.alias
+            )));
+        }
+        let prefix = lease.alias.split('@').next().unwrap_or_default();
+        let bsv_price = wallet.fetch_price(&self.pricing.currency).await?;
+        let quote = self.pricing.quote(prefix, false, bsv_price, now)?;
+        let treasury = format!("{}@{}", TREASURY_PREFIX, self.domain);
+        let (script, _) = self.resolve_paymail(&treasury, quote.satoshis).await?;
+        let reference = DataOutput::new()
+            .push(ALIAS_PURCHASE_TAG.as_bytes())
+            .push(format!("renew:{}:{}", lease.alias, lease.paid_until).as_bytes());
+        let txid = wallet
+            .send_payment(user_id, script, quote.satoshis, fee, &[reference])
+            .await?;
+        lease.pending_renewal = Some(txid);
+        lease.pending_since = now;
+        self.settle_renewal(wallet, &mut lease, now).await?;
+        self.store_lease(&lease)?;
+        let _ = self
+            .telemetry
+            .track_payment_event(&user_id.to_string(), "renew_alias", quote.satoshis, true)
+            .await;
+        Ok(lease)
+    }
+
+    /// Warns owners whose term has ended, and returns aliases past their grace period to
+    /// the pool. An alias with a renewal still pending is kept until `settle_renewals`
+    /// applies or drops it. Returns the released aliases.
+    pub fn expire_leases(&self, now: i64) -> Result<Vec<String>, ZipError> {
+        let grace = self.pricing.grace_period_secs;
+        let mut released = vec![];
+        for data in self.storage.get_alias_leases()? {
+            let Ok(mut lease) = AliasLease::decode(&data) else {
+                continue;
+            };
+            if lease.is_lapsed_at(now, grace) && lease.pending_renewal.is_none() {
+                if self.registry.owner(&lease.alias)? == Some(lease.owner) {
+                    self.registry.release(&lease.alias, lease.owner)?;
+                }
+                if let Some(mut purchase) = self.purchase(&lease.alias)? {
+                    purchase.status = AliasStatus::Expired;
+                    purchase.updated_at = now;
+                    self.store_purchase(&purchase)?;
+                }
+                self.storage.remove_alias_transfer(&lease.alias)?;
+                self.storage.remove_alias_lease(&lease.alias)?;
+                self.alerts.push(
+                    lease.owner,
+                    format!("{} was not renewed and has been released", lease.alias),
+                    None,
+                )?;
+                released.push(lease.alias);
+                continue;
+            }
+            if lease.in_grace_at(now, grace) && !lease.grace_notified {
+                self.alerts.push(
+                    lease.owner,
+                    format!(
+                        "{} has expired; renew within {} days to keep it",
+                        lease.alias,
+                        (lease.paid_until + grace - now + 86_399) / 86_400
+                    ),
+                    None,
+                )?;
+                lease.grace_notified = true;
+                self.store_lease(&lease)?;
+            }
+        }
+        Ok(released)
+    }
+
+    fn get_transfer(&self, alias: &str) -> Result<Option<AliasTransfer>, ZipError> {
+        Ok(self
+            .storage
+            .get_alias_transfer(alias)?
+            .and_then(|d| bincode::deserialize(&d).ok()))
+    }
+
+    fn store_transfer(&self, transfer: &AliasTransfer) -> Result<(), ZipError> {
+        let serialized =
+            bincode::serialize(transfer).map_err(|e| ZipError::Blockchain(e.to_string()))?;
+        self.storage
+            .store_alias_transfer(&transfer.alias, &serialized)
+    }
+
+    /// Offers an alias to the user owning `recipient` (one of their aliases). The offer
+    /// counts as the owner's confirmation; the alias moves once the recipient confirms.
+    pub async fn propose_transfer(
+        &self,
+        user_id: Uuid,
+        alias: &str,
+        recipient: &str,
+        now: i64,
+    ) -> Result<AliasTransfer, ZipError> {
+        self.rate_limiter.check(&user_id.to_string()).await?;
+        let alias = alias.trim().to_lowercase();
+        if self.alias_owner(&alias)? != Some(user_id) {
+            return Err(ZipError::Validation(format!(
+                "{} is not yours to transfer",
+                alias
+            )));
+        }
+        let to = self
+            .alias_owner(recipient)?
+            .ok_or_else(|| ZipError::Validation(format!("No user found for {}", recipient)))?;
+        if to == user_id {
+            return Err(ZipError::Validation(
+                "Cannot transfer an alias to yourself".to_string(),
+            ));
+        }
+        if let Some(pending) = self.get_transfer(&alias)? {
+            if pending.expires_at > now {
+                return Err(ZipError::Validation(format!(
+                    "{} already has a pending transfer",
+                    alias
+                )));
+            }
+        }
+        let transfer = AliasTransfer {
+            alias,
+            from: user_id,
+            to,
+            from_confirmed: true,
+            to_confirmed: false,
+            created_at: now,
+            expires_at: now + TRANSFER_TIMEOUT_SECS,
+        };
+        self.store_transfer(&transfer)?;
+        self.alerts.push(
+            to,
+            format!(
+                "You have been offered {}; confirm in Settings to accept it",
+                transfer.alias
+            ),
+            None,
+        )?;
+        Ok(transfer)
+    }
+
+    /// Confirms a pending transfer for either side. Once both sides have confirmed the
+    /// alias, with its paid term, moves to the recipient. A renewal the current owner
+    /// paid is settled first; while the network has yet to accept it the alias stays put.
+    pub async fn confirm_transfer(
+        &self,
+        wallet: &WalletManager,
+        user_id: Uuid,
+        alias: &str,
+        now: i64,
+    ) -> Result<AliasTransfer, ZipError> {
+        self.rate_limiter.check(&user_id.to_string()).await?;
+        let mut transfer = self
+            .get_transfer(alias)?
+            .ok_or_else(|| ZipError::Validation(format!("No pending transfer for {}", alias)))?;
+        if transfer.expires_at <= now {
+            self.storage.remove_alias_transfer(&transfer.alias)?;
+            return Err(ZipError::Validation(format!(
+                "The transfer offer for {} has lapsed",
+                transfer.alias
+            )));
+        }
+        transfer.confirm(user_id)?;
+        if !transfer.is_confirmed() {
+            self.store_transfer(&transfer)?;
+            return Ok(transfer);
+        }
+        let lease = match self.lease(&transfer.alias)? {
+            Some(mut lease) => {
+                self.settle_renewal(wallet, &mut lease, now).await?;
+                self.store_lease(&lease)?;
+                if lease.pending_renewal.is_some() {
+                    return Err(ZipError::Validation(format!(
+                        "A renewal of {} is still waiting for the network; confirm again once it settles",
+                        transfer.alias
+                    )));
+                }
+                Some(lease)
+            }
+            None => None,
+        };
+        self.registry
+            .transfer(&transfer.alias, transfer.from, transfer.to)?;
+        self.storage.remove_alias_transfer(&transfer.alias)?;
+        if let Some(mut purchase) = self.purchase(&transfer.alias)? {
+            purchase.owner = transfer.to;
+            purchase.updated_at = now;
+            self.store_purchase(&purchase)?;
+        }
+        if let Some(mut lease) = lease {
+            lease.owner = transfer.to;
+            self.store_lease(&lease)?;
+        }
+        for (user, message) in [
+            (
+                transfer.from,
+                format!("{} has been transferred", transfer.alias),
+            ),
+            (transfer.to, format!("{} is now yours", transfer.alias)),
+        ] {
+            self.alerts.push(user, message, None)?;
+        }
+        let _ = self
+            .telemetry
+            .track_payment_event(&user_id.to_string(), "transfer_alias", 0, true)
+            .await;
+        Ok(transfer)
+    }
+
+    /// Withdraws or declines a pending transfer; either side may cancel.
+    pub fn cancel_transfer(&self, user_id: Uuid, alias: &str) -> Result<(), ZipError> {
+        let transfer = self
+            .get_transfer(alias)?
+            .filter(|t| t.from == user_id || t.to == user_id)
+            .ok_or_else(|| ZipError::Validation(format!("No pending transfer for {}", alias)))?;
+        self.storage.remove_alias_transfer(&transfer.alias)?;
+        let other = if user_id == transfer.from {
+            transfer.to
+        } else {
+            transfer.from
+        };
+        self.alerts.push(
+            other,
+            format!("The transfer of {} was cancelled", transfer.alias),
+            None,
+        )
+    }
+
+    /// Unlapsed transfers a user is a party to, oldest first.
+    pub fn pending_transfers(
+        &self,
+        user_id: Uuid,
+        now: i64,
+    ) -> Result<Vec<AliasTransfer>, ZipError> {
+        let mut transfers: Vec<AliasTransfer> = self
+            .storage
+            .get_alias_transfers()?
+            .iter()
+            .filter_map(|d| bincode::deserialize::<AliasTransfer>(d).ok())
+            .filter(|t| (t.from == user_id || t.to == user_id) && t.expires_at > now)
+            .collect();
+        transfers.sort_by_key(|t| t.created_at);
+        Ok(transfers)
+    }
+
     /// Retrieves user's PayMail aliases.
     pub async fn get_user_aliases(&self, user_id: Uuid) -> Result<HashSet<String>, ZipError> {
         self.rate_limiter.check(&user_id.to_string()).await?;
-        let data = self.storage.get_user_data(user_id)?;
-        let aliases = data
-            .map(|d| bincode::deserialize(&d).unwrap_or_default())
-            .unwrap_or_default();
+        self.expire_leases(chrono::Utc::now().timestamp())?;
+        let aliases = self.user_aliases(user_id)?;
         let _ = self
             .telemetry
             .track_payment_event(&user_id.to_string(), "get_user_aliases", 0, true)
             .await;
         Ok(aliases)
     }
+
+    /// Aliases a user owns in the registry. Aliases issued before the registry existed
+    /// lived in the user's data blob; they are registered on first read unless another
+    /// user has claimed them since.
+    fn user_aliases(&self, user_id: Uuid) -> Result<HashSet<String>, ZipError> {
+        let aliases = self.registry.aliases(user_id)?;
+        if !aliases.is_empty() {
+            return self.active_only(aliases);
+        }
+        let legacy: HashSet<String> = self
+            .storage
+            .get_user_data(user_id)?
+            .and_then(|d| bincode::deserialize(&d).ok())
+            .unwrap_or_default();
+        for alias in legacy.iter().filter(|a| a.contains('@')) {
+            if let Err(e) = self.registry.register(alias, user_id) {
+                tracing::warn!("Legacy alias not migrated: {}", e);
+            }
+        }
+        self.active_only(self.registry.aliases(user_id)?)
+    }
+
+    fn active_only(&self, aliases: Vec<String>) -> Result<HashSet<String>, ZipError> {
+        let mut active = HashSet::new();
+        for alias in aliases {
+            if self.is_active(&alias)? {
+                active.insert(alias);
+            }
+        }
+        Ok(active)
+    }
 }
@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// User-defined limits applied before any payment leaves the wallet.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SpendingPolicy {
+    /// Largest single payment allowed, in satoshis.
+    pub max_payment: Option<u64>,
+    /// Total satoshis that may be sent per UTC day.
+    pub daily_limit: Option<u64>,
+    /// Payments above this amount must be confirmed with 2FA.
+    pub require_2fa_above: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PolicyViolation {
+    MaxPaymentExceeded {
+        limit: u64,
+        amount: u64,
+    },
+    DailyLimitExceeded {
+        limit: u64,
+        spent_today: u64,
+        amount: u64,
+    },
+}
+
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicyViolation::MaxPaymentExceeded { limit, amount } => write!(
+                f,
+                "Payment of {} satoshis exceeds the per-payment limit of {}",
+                amount, limit
+            ),
+            PolicyViolation::DailyLimitExceeded {
+                limit,
+                spent_today,
+                amount,
+            } => write!(
+                f,
+                "Payment of {} satoshis exceeds the daily limit of {} ({} already sent today)",
+                amount, limit, spent_today
+            ),
+        }
+    }
+}
+
+impl SpendingPolicy {
+    /// Returns every limit the payment would break, given today's spend so far.
+    /// `amount` is the full debit, network fee included.
+    pub fn check(&self, amount: u64, spent_today: u64) -> Vec<PolicyViolation> {
+        let mut violations = vec![];
+        if let Some(limit) = self.max_payment {
+            if amount > limit {
+                violations.push(PolicyViolation::MaxPaymentExceeded { limit, amount });
+            }
+        }
+        if let Some(limit) = self.daily_limit {
+            if spent_today.saturating_add(amount) > limit {
+                violations.push(PolicyViolation::DailyLimitExceeded {
+                    limit,
+                    spent_today,
+                    amount,
+                });
+            }
+        }
+        violations
+    }
+
+    /// Checks whether a payment debiting `amount` (fee included) needs a 2FA code.
+    pub fn requires_2fa(&self, amount: u64) -> bool {
+        self.require_2fa_above
+            .map(|threshold| amount > threshold)
+            .unwrap_or(false)
+    }
+}
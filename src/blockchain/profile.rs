@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::errors::ZipError;
+
+/// BRFC ID of the public profile capability.
+pub const PUBLIC_PROFILE_BRFC: &str = "f12f968c92d6";
+/// BRFC ID of the P2P payment destination capability.
+pub const P2P_DESTINATION_BRFC: &str = "2a40af698840";
+
+/// Longest display name, in characters.
+pub const MAX_PROFILE_NAME_LEN: usize = 100;
+
+/// Name and avatar a PayMail owner publishes through the public profile capability.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PublicProfile {
+    pub name: String,
+    /// HTTPS image URL; empty for none.
+    pub avatar: String,
+}
+
+impl PublicProfile {
+    /// Validated profile: a display name of at most `MAX_PROFILE_NAME_LEN` characters
+    /// without control characters, and an empty or HTTPS avatar URL.
+    pub fn new(name: &str, avatar: &str) -> Result<Self, ZipError> {
+        let name = name.trim();
+        if name.chars().count() > MAX_PROFILE_NAME_LEN {
+            return Err(ZipError::Validation(format!(
+                "Display name must be at most {} characters",
+                MAX_PROFILE_NAME_LEN
+            )));
+        }
+        if name.chars().any(char::is_control) {
+            return Err(ZipError::Validation(
+                "Display name cannot contain control characters".to_string(),
+            ));
+        }
+        let avatar = avatar.trim();
+        if !avatar.is_empty() && !is_avatar_url(avatar) {
+            return Err(ZipError::Validation(
+                "Avatar must be an https:// image URL".to_string(),
+            ));
+        }
+        Ok(Self {
+            name: name.to_string(),
+            avatar: avatar.to_string(),
+        })
+    }
+
+    /// Profile served for `alias`: the local part stands in for a missing name.
+    pub fn for_alias(&self, alias: &str) -> Self {
+        Self {
+            name: if self.name.is_empty() {
+                local_part(alias)
+            } else {
+                self.name.clone()
+            },
+            avatar: self.avatar.clone(),
+        }
+    }
+
+    /// Profile another host returned for `handle`. Names are cut to size and stripped of
+    /// control characters; avatars that are not HTTPS URLs are dropped.
+    pub fn from_response(handle: &str, response: &Value) -> Self {
+        let name: String = response["name"]
+            .as_str()
+            .unwrap_or_default()
+            .chars()
+            .filter(|c| !c.is_control())
+            .take(MAX_PROFILE_NAME_LEN)
+            .collect();
+        let avatar = response["avatar"]
+            .as_str()
+            .map(str::trim)
+            .filter(|url| is_avatar_url(url))
+            .unwrap_or_default();
+        Self {
+            name: name.trim().to_string(),
+            avatar: avatar.to_string(),
+        }
+        .for_alias(handle)
+    }
+}
+
+fn local_part(alias: &str) -> String {
+    alias.split('@').next().unwrap_or_default().to_string()
+}
+
+fn is_avatar_url(url: &str) -> bool {
+    reqwest::Url::parse(url).is_ok_and(|url| url.scheme() == "https" && url.host().is_some())
+}
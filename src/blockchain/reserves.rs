@@ -0,0 +1,150 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use crate::blockchain::WalletManager;
+use crate::blockchain::transaction::Utxo;
+use crate::errors::ZipError;
+use crate::utils::crypto::Crypto;
+
+/// Signature over a challenge by the key behind one address, with the UTXOs it holds.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AddressProof {
+    pub address: String,
+    pub utxos: Vec<Utxo>,
+    /// "Bitcoin Signed Message" signature over the report's challenge.
+    pub signature: String,
+}
+
+impl AddressProof {
+    /// Satoshis held by the listed UTXOs.
+    pub fn balance(&self) -> u64 {
+        self.utxos.iter().map(|u| u.value).sum()
+    }
+
+    /// Checks the signature over `challenge`.
+    pub fn verify(&self, challenge: &str) -> bool {
+        Crypto::verify_bitcoin_message(&self.address, challenge, &self.signature).unwrap_or(false)
+    }
+}
+
+/// Exportable proof that the wallet controls a set of addresses and the funds on them.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReserveReport {
+    /// Text chosen by the counterparty or auditor, signed verbatim so any wallet that
+    /// verifies signed messages can check the signatures too.
+    pub challenge: String,
+    pub created_at: i64,
+    pub addresses: Vec<AddressProof>,
+    /// Claimed total across all addresses, in satoshis.
+    pub total: u64,
+}
+
+/// Outcome of verifying a reserve report.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReserveCheck {
+    /// Every signature is valid; the report proves control of `total` satoshis.
+    Valid { total: u64 },
+    /// The signature for this address does not match the challenge.
+    InvalidSignature(String),
+    /// A UTXO is not locked to the address it is listed under.
+    ForeignUtxo { address: String, outpoint: String },
+    /// The same UTXO is listed more than once.
+    DuplicateUtxo(String),
+    /// The claimed total differs from the sum of the listed UTXOs.
+    TotalMismatch { claimed: u64, computed: u64 },
+}
+
+impl ReserveReport {
+    /// Signs `challenge` with the key of every funded address in the wallet.
+    pub fn create(
+        wallet: &WalletManager,
+        user_id: Uuid,
+        challenge: &str,
+    ) -> Result<Self, ZipError> {
+        if challenge.trim().is_empty() {
+            return Err(ZipError::Validation("Challenge is required".to_string()));
+        }
+        let mut addresses = vec![];
+        for (receive, utxos) in wallet.funded_addresses(user_id)? {
+            addresses.push(AddressProof {
+                signature: wallet.sign_message(user_id, &receive.address, challenge)?,
+                address: receive.address,
+                utxos,
+            });
+        }
+        let total = addresses.iter().map(|a| a.balance()).sum();
+        Ok(Self {
+            challenge: challenge.to_string(),
+            created_at: chrono::Utc::now().timestamp(),
+            addresses,
+            total,
+        })
+    }
+
+    /// Proves ownership of a single wallet address, with or without funds.
+    pub fn prove_address(
+        wallet: &WalletManager,
+        user_id: Uuid,
+        address: &str,
+        challenge: &str,
+    ) -> Result<AddressProof, ZipError> {
+        let utxos = wallet
+            .funded_addresses(user_id)?
+            .into_iter()
+            .find(|(receive, _)| receive.address == address)
+            .map(|(_, utxos)| utxos)
+            .unwrap_or_default();
+        Ok(AddressProof {
+            address: address.to_string(),
+            utxos,
+            signature: wallet.sign_message(user_id, address, challenge)?,
+        })
+    }
+
+    /// Pretty-printed JSON for handing to an auditor.
+    pub fn to_json(&self) -> Result<String, ZipError> {
+        serde_json::to_string_pretty(self).map_err(|e| ZipError::Validation(e.to_string()))
+    }
+
+    /// Parses an exported report.
+    pub fn from_json(json: &str) -> Result<Self, ZipError> {
+        serde_json::from_str(json).map_err(|e| ZipError::Validation(e.to_string()))
+    }
+
+    /// Checks signatures, UTXO ownership and totals without network access. Whether the
+    /// UTXOs are still unspent must be checked separately against the chain.
+    pub fn verify(&self) -> Result<ReserveCheck, ZipError> {
+        let mut seen = HashSet::new();
+        for proof in &self.addresses {
+            if !proof.verify(&self.challenge) {
+                return Ok(ReserveCheck::InvalidSignature(proof.address.clone()));
+            }
+            let script = WalletManager::address_script(&proof.address)?;
+            for utxo in &proof.utxos {
+                if utxo.script != script.0 {
+                    return Ok(ReserveCheck::ForeignUtxo {
+                        address: proof.address.clone(),
+                        outpoint: utxo.outpoint(),
+                    });
+                }
+                if !seen.insert(utxo.outpoint()) {
+                    return Ok(ReserveCheck::DuplicateUtxo(utxo.outpoint()));
+                }
+            }
+        }
+        let computed = self.addresses.iter().map(|a| a.balance()).sum();
+        if computed != self.total {
+            return Ok(ReserveCheck::TotalMismatch {
+                claimed: self.total,
+                computed,
+            });
+        }
+        Ok(ReserveCheck::Valid { total: computed })
+    }
+}
+
+/// Standalone verifier for an exported report.
+pub fn verify_reserve_report(json: &str) -> Result<ReserveCheck, ZipError> {
+    ReserveReport::from_json(json)?.verify()
+}
@@ -0,0 +1,476 @@
+use bincode;
+use chrono::{Datelike, NaiveDate, TimeZone, Utc};
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::blockchain::{PaymailManager, WalletManager};
+use crate::errors::ZipError;
+use crate::storage::ZipStorage;
+
+/// Occurrences due within this many seconds of now count as on time, not missed.
+pub const SCHEDULE_GRACE_SECS: i64 = 3600;
+/// Upper bound on occurrences replayed for a single schedule in one pass.
+pub const MAX_CATCH_UP_RUNS: usize = 31;
+/// Number of run log entries kept per user.
+pub const RUN_LOG_LIMIT: usize = 500;
+
+/// How often a scheduled payment repeats.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Recurrence {
+    /// Fixed number of seconds between payments.
+    Interval {
+        seconds: u64,
+    },
+    Daily,
+    Weekly,
+    /// Same day each month, clamped to the month's last day (e.g., 31 -> 30 in April).
+    Monthly {
+        day: u32,
+    },
+}
+
+impl Recurrence {
+    /// Returns the occurrence after `ts` (Unix seconds).
+    pub fn advance(&self, ts: i64) -> i64 {
+        match self {
+            Recurrence::Interval { seconds } => ts + (*seconds).max(1) as i64,
+            Recurrence::Daily => ts + 86_400,
+            Recurrence::Weekly => ts + 7 * 86_400,
+            Recurrence::Monthly { day } => {
+                let Some(current) = Utc.timestamp_opt(ts, 0).single() else {
+                    return ts + 30 * 86_400;
+                };
+                let (year, month) = match current.month() {
+                    12 => (current.year() + 1, 1),
+                    m => (current.year(), m + 1),
+                };
+                let last_day = NaiveDate::from_ymd_opt(year, month, 1)
+                    .and_then(|d| d.checked_add_months(chrono::Months::new(1)))
+                    .and_then(|d| d.pred_opt())
+                    .map(|d| d.day())
+                    .unwrap_or(28);
+                NaiveDate::from_ymd_opt(year, month, (*day).clamp(1, last_day))
+                    .map(|d| d.and_time(current.time()).and_utc().timestamp())
+                    .unwrap_or(ts + 30 * 86_400)
+            }
+        }
+    }
+}
+
+/// What to do with occurrences missed while the app was offline.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum CatchUp {
+    /// Drop missed occurrences; only pay on-time runs.
+    Skip,
+    /// Pay once for any number of missed occurrences.
+    #[default]
+    RunOnce,
+    /// Pay every missed occurrence (up to `MAX_CATCH_UP_RUNS`).
+    RunAll,
+}
+
+/// Per-schedule limits; the schedule finishes when any is reached.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleLimits {
+    pub max_runs: Option<u32>,
+    /// Total satoshis the schedule may send.
+    pub max_total: Option<u64>,
+    /// No runs after this Unix timestamp.
+    pub end_at: Option<i64>,
+}
+
+/// Recurring PayMail payment.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PaymentSchedule {
+    pub id: Uuid,
+    pub label: String,
+    pub recipient: String,
+    pub amount: u64,
+    pub fee: u64,
+    pub recurrence: Recurrence,
+    pub catch_up: CatchUp,
+    pub limits: ScheduleLimits,
+    /// Unix timestamp of the next occurrence.
+    pub next_run: i64,
+    pub paused: bool,
+    pub runs: u32,
+    pub total_sent: u64,
+}
+
+/// Occurrences to pay and to skip for a schedule at a point in time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RunPlan {
+    pub run: Vec<i64>,
+    pub skipped: Vec<i64>,
+    pub next_run: i64,
+}
+
+impl PaymentSchedule {
+    /// Creates an active schedule whose first payment is due at `first_run`.
+    pub fn new(
+        label: &str,
+        recipient: &str,
+        amount: u64,
+        fee: u64,
+        recurrence: Recurrence,
+        first_run: i64,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            label: label.to_string(),
+            recipient: recipient.to_string(),
+            amount,
+            fee,
+            recurrence,
+            catch_up: CatchUp::default(),
+            limits: ScheduleLimits::default(),
+            next_run: first_run,
+            paused: false,
+            runs: 0,
+            total_sent: 0,
+        }
+    }
+
+    /// True once any limit is reached; finished schedules never run again.
+    pub fn is_finished(&self) -> bool {
+        self.limits.max_runs.is_some_and(|max| self.runs >= max)
+            || self
+                .limits
+                .max_total
+                .is_some_and(|max| self.total_sent.saturating_add(self.amount) > max)
+            || self.limits.end_at.is_some_and(|end| self.next_run > end)
+    }
+
+    /// Works out which occurrences up to `now` should be paid, applying the
+    /// catch-up rule and run limits. Does not modify the schedule.
+    pub fn plan(&self, now: i64) -> RunPlan {
+        let mut occurrences = vec![];
+        let mut next = self.next_run;
+        while next <= now {
+            if self.limits.end_at.is_some_and(|end| next > end) {
+                break;
+            }
+            occurrences.push(next);
+            next = self.recurrence.advance(next);
+        }
+        let (missed, on_time): (Vec<i64>, Vec<i64>) = occurrences
+            .into_iter()
+            .partition(|t| now - t > SCHEDULE_GRACE_SECS);
+        let (mut run, mut skipped) = match self.catch_up {
+            CatchUp::Skip => (on_time, missed),
+            CatchUp::RunOnce => {
+                // One payment covers everything that was missed
+                let mut run = on_time;
+                let mut skipped = missed;
+                if run.is_empty() {
+                    if let Some(latest) = skipped.pop() {
+                        run.push(latest);
+                    }
+                }
+                (run, skipped)
+            }
+            CatchUp::RunAll => {
+                let mut run = missed;
+                run.extend(on_time);
+                (run, vec![])
+            }
+        };
+        let mut allowed = MAX_CATCH_UP_RUNS;
+        if let Some(max) = self.limits.max_runs {
+            allowed = allowed.min(max.saturating_sub(self.runs) as usize);
+        }
+        if let Some(max) = self.limits.max_total {
+            let remaining = max.saturating_sub(self.total_sent);
+            allowed = allowed.min((remaining / self.amount.max(1)) as usize);
+        }
+        if run.len() > allowed {
+            // Pay the oldest occurrences; the rest are skipped
+            skipped.extend(run.split_off(allowed));
+        }
+        skipped.sort();
+        RunPlan {
+            run,
+            skipped,
+            next_run: next,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum RunResult {
+    Sent { txid: String },
+    Failed { error: String },
+    Skipped { reason: String },
+}
+
+/// Record of one scheduled occurrence.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RunLogEntry {
+    pub schedule_id: Uuid,
+    /// Unix timestamp the payment was due.
+    pub scheduled_for: i64,
+    /// Unix timestamp the run was processed.
+    pub executed_at: i64,
+    pub result: RunResult,
+}
+
+/// Persistent recurring payment schedules and their run log.
+#[derive(Clone)]
+pub struct Scheduler {
+    storage: Arc<ZipStorage>,
+    running: Arc<RwLock<HashSet<Uuid>>>,
+    /// Serializes read-modify-write of the schedule list so payment runs never
+    /// overwrite a pause, edit or removal made meanwhile.
+    store_lock: Arc<Mutex<()>>,
+}
+
+impl Scheduler {
+    /// Initializes scheduler with storage.
+    pub fn new(storage: Arc<ZipStorage>) -> Self {
+        Self {
+            storage,
+            running: Arc::new(RwLock::new(HashSet::new())),
+            store_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Lists a user's schedules.
+    pub fn list(&self, user_id: Uuid) -> Result<Vec<PaymentSchedule>, ZipError> {
+        let data = self.storage.get_schedules(user_id)?;
+        Ok(data
+            .map(|d| bincode::deserialize(&d).unwrap_or_default())
+            .unwrap_or_default())
+    }
+
+    fn store(&self, user_id: Uuid, schedules: &[PaymentSchedule]) -> Result<(), ZipError> {
+        let serialized =
+            bincode::serialize(schedules).map_err(|e| ZipError::Blockchain(e.to_string()))?;
+        self.storage.store_schedules(user_id, &serialized)
+    }
+
+    /// Adds a new schedule.
+    pub fn add(&self, user_id: Uuid, schedule: PaymentSchedule) -> Result<(), ZipError> {
+        if schedule.amount == 0 {
+            return Err(ZipError::Validation(
+                "Scheduled amount must be positive".to_string(),
+            ));
+        }
+        let _guard = self.store_lock.lock();
+        let mut schedules = self.list(user_id)?;
+        schedules.push(schedule);
+        self.store(user_id, &schedules)
+    }
+
+    /// Re-reads a schedule and applies `f` to it under the store lock. Returns the
+    /// updated schedule, or `None` if it no longer exists.
+    fn modify(
+        &self,
+        user_id: Uuid,
+        schedule_id: Uuid,
+        f: impl FnOnce(&mut PaymentSchedule),
+    ) -> Result<Option<PaymentSchedule>, ZipError> {
+        let _guard = self.store_lock.lock();
+        let mut schedules = self.list(user_id)?;
+        let Some(schedule) = schedules.iter_mut().find(|s| s.id == schedule_id) else {
+            return Ok(None);
+        };
+        f(schedule);
+        let updated = schedule.clone();
+        self.store(user_id, &schedules)?;
+        Ok(Some(updated))
+    }
+
+    /// Replaces a schedule by ID.
+    pub fn update(&self, user_id: Uuid, schedule: PaymentSchedule) -> Result<(), ZipError> {
+        self.modify(user_id, schedule.id, |existing| *existing = schedule)?
+            .map(|_| ())
+            .ok_or_else(|| ZipError::Validation("Schedule not found".to_string()))
+    }
+
+    /// Deletes a schedule.
+    pub fn remove(&self, user_id: Uuid, schedule_id: Uuid) -> Result<(), ZipError> {
+        let _guard = self.store_lock.lock();
+        let mut schedules = self.list(user_id)?;
+        schedules.retain(|s| s.id != schedule_id);
+        self.store(user_id, &schedules)
+    }
+
+    /// Pauses or resumes a schedule. Resuming does not replay runs missed while paused.
+    pub fn set_paused(
+        &self,
+        user_id: Uuid,
+        schedule_id: Uuid,
+        paused: bool,
+    ) -> Result<(), ZipError> {
+        let now = chrono::Utc::now().timestamp();
+        self.modify(user_id, schedule_id, |schedule| {
+            schedule.paused = paused;
+            if !paused {
+                while schedule.next_run < now - SCHEDULE_GRACE_SECS {
+                    schedule.next_run = schedule.recurrence.advance(schedule.next_run);
+                }
+            }
+        })?
+        .map(|_| ())
+        .ok_or_else(|| ZipError::Validation("Schedule not found".to_string()))
+    }
+
+    /// Returns the run log, oldest first.
+    pub fn run_log(&self, user_id: Uuid) -> Result<Vec<RunLogEntry>, ZipError> {
+        let data = self.storage.get_schedule_log(user_id)?;
+        Ok(data
+            .map(|d| bincode::deserialize(&d).unwrap_or_default())
+            .unwrap_or_default())
+    }
+
+    fn append_log(&self, user_id: Uuid, entries: &[RunLogEntry]) -> Result<(), ZipError> {
+        let mut log = self.run_log(user_id)?;
+        log.extend(entries.iter().cloned());
+        if log.len() > RUN_LOG_LIMIT {
+            log.drain(..log.len() - RUN_LOG_LIMIT);
+        }
+        let serialized =
+            bincode::serialize(&log).map_err(|e| ZipError::Blockchain(e.to_string()))?;
+        self.storage.store_schedule_log(user_id, &serialized)
+    }
+
+    /// Pays every due occurrence through `WalletManager::send_payment` and logs the results.
+    ///
+    /// Each schedule is re-read before every payment, so one paused, finished or removed
+    /// mid-pass stops paying. A payment refused by the wallet's rate limit is not
+    /// consumed: `next_run` stays on it and catch-up resumes on a later pass.
+    pub async fn run_due(
+        &self,
+        wallet: &WalletManager,
+        paymail: &PaymailManager,
+        user_id: Uuid,
+        now: i64,
+    ) -> Result<Vec<RunLogEntry>, ZipError> {
+        let mut entries = vec![];
+        for schedule in self.list(user_id)? {
+            if schedule.paused || schedule.is_finished() || schedule.next_run > now {
+                continue;
+            }
+            let plan = schedule.plan(now);
+            let mut next_run = plan.next_run;
+            let mut runs = vec![];
+            for scheduled_for in &plan.run {
+                let current = self
+                    .list(user_id)?
+                    .into_iter()
+                    .find(|s| s.id == schedule.id);
+                if current.is_none_or(|s| s.paused || s.is_finished()) {
+                    next_run = *scheduled_for;
+                    break;
+                }
+                let result = match paymail
+                    .resolve_paymail(&schedule.recipient, schedule.amount)
+                    .await
+                {
+                    Ok((script, amount)) => {
+                        wallet
+                            .send_payment(user_id, script, amount, schedule.fee, &[])
+                            .await
+                    }
+                    Err(e) => Err(e),
+                };
+                let result = match result {
+                    Ok(txid) => RunResult::Sent { txid },
+                    Err(ZipError::RateLimit(_)) => {
+                        next_run = *scheduled_for;
+                        break;
+                    }
+                    Err(e) => RunResult::Failed {
+                        error: e.to_string(),
+                    },
+                };
+                let sent = matches!(result, RunResult::Sent { .. });
+                let advanced = schedule.recurrence.advance(*scheduled_for);
+                self.modify(user_id, schedule.id, |s| {
+                    if sent {
+                        s.runs += 1;
+                        s.total_sent += s.amount;
+                    }
+                    s.next_run = s.next_run.max(advanced);
+                })?;
+                runs.push(RunLogEntry {
+                    schedule_id: schedule.id,
+                    scheduled_for: *scheduled_for,
+                    executed_at: now,
+                    result,
+                });
+            }
+            let Some(updated) = self.modify(user_id, schedule.id, |s| {
+                s.next_run = s.next_run.max(next_run);
+            })?
+            else {
+                entries.extend(runs);
+                continue;
+            };
+            // Occurrences at or after a stopping point are planned again on a later pass
+            for scheduled_for in plan.skipped.iter().filter(|t| **t < updated.next_run) {
+                entries.push(RunLogEntry {
+                    schedule_id: schedule.id,
+                    scheduled_for: *scheduled_for,
+                    executed_at: now,
+                    result: RunResult::Skipped {
+                        reason: if now - scheduled_for > SCHEDULE_GRACE_SECS {
+                            "Missed while offline".to_string()
+                        } else {
+                            "Schedule limit reached".to_string()
+                        },
+                    },
+                });
+            }
+            entries.extend(runs);
+        }
+        if !entries.is_empty() {
+            self.append_log(user_id, &entries)?;
+        }
+        Ok(entries)
+    }
+
+    /// Starts a background task that runs due payments for the user on an interval.
+    /// Does nothing if one is already running.
+    pub fn start(
+        &self,
+        wallet: WalletManager,
+        paymail: PaymailManager,
+        user_id: Uuid,
+        interval: Duration,
+    ) {
+        if !self.running.write().insert(user_id) {
+            return;
+        }
+        let scheduler = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let now = chrono::Utc::now().timestamp();
+                if let Err(e) = scheduler.run_due(&wallet, &paymail, user_id, now).await {
+                    warn!("Scheduled payments failed: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Starts the background task for every user with stored schedules. Called at app
+    /// launch so payments run without the Schedules page being opened.
+    pub fn start_all(
+        &self,
+        wallet: WalletManager,
+        paymail: PaymailManager,
+        interval: Duration,
+    ) -> Result<(), ZipError> {
+        for user_id in self.storage.get_schedule_users()? {
+            self.start(wallet.clone(), paymail.clone(), user_id, interval);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,124 @@
+use bincode;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::blockchain::transaction::Utxo;
+use crate::errors::ZipError;
+use crate::storage::ZipStorage;
+
+/// nLockTime values below this are block heights, values at or above are Unix timestamps.
+pub const LOCKTIME_THRESHOLD: u32 = 500_000_000;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum LockKind {
+    /// Payment to someone else, sent once the lock expires.
+    Payment,
+    /// Savings locked back to the wallet.
+    Vault,
+}
+
+/// Signed-for-later transaction held locally until its nLockTime passes.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LockedTx {
+    pub txid: String,
+    pub tx_hex: String,
+    pub lock_time: u32,
+    pub kind: LockKind,
+    pub amount: u64,
+    pub fee: u64,
+    pub counterparty: String,
+    /// Outputs that become spendable by the wallet once the transaction is mined.
+    pub wallet_outputs: Vec<Utxo>,
+    /// Unix timestamp (seconds) when the transaction was created.
+    pub created_at: i64,
+}
+
+impl LockedTx {
+    /// Block height the lock expires at, for height-based locks.
+    pub fn unlock_height(&self) -> Option<u32> {
+        (self.lock_time < LOCKTIME_THRESHOLD).then_some(self.lock_time)
+    }
+
+    /// Unix timestamp the lock expires at, for time-based locks.
+    pub fn unlock_time(&self) -> Option<i64> {
+        (self.lock_time >= LOCKTIME_THRESHOLD).then_some(self.lock_time as i64)
+    }
+
+    /// Checks whether the transaction can be mined in the block after `tip_height`.
+    /// Time-based locks must be below the chain's median time past (BIP-113), not the
+    /// local clock, or nodes reject the transaction as non-final.
+    pub fn is_final(&self, tip_height: u64, median_time_past: i64) -> bool {
+        match self.unlock_height() {
+            Some(height) => (height as u64) <= tip_height,
+            None => (self.lock_time as i64) < median_time_past,
+        }
+    }
+}
+
+/// Per-user store of time-locked transactions awaiting broadcast.
+#[derive(Clone)]
+pub struct TimeLocks {
+    storage: Arc<ZipStorage>,
+}
+
+impl TimeLocks {
+    /// Initializes time-lock store with storage.
+    pub fn new(storage: Arc<ZipStorage>) -> Self {
+        Self { storage }
+    }
+
+    /// Lists all pending time-locked transactions, soonest first.
+    pub fn list(&self, user_id: Uuid) -> Result<Vec<LockedTx>, ZipError> {
+        let data = self.storage.get_timelocks(user_id)?;
+        let mut locked: Vec<LockedTx> = data
+            .map(|d| bincode::deserialize(&d).unwrap_or_default())
+            .unwrap_or_default();
+        locked.sort_by_key(|l| (l.unlock_time().is_some(), l.lock_time));
+        Ok(locked)
+    }
+
+    fn store(&self, user_id: Uuid, locked: &[LockedTx]) -> Result<(), ZipError> {
+        let serialized =
+            bincode::serialize(locked).map_err(|e| ZipError::Blockchain(e.to_string()))?;
+        self.storage.store_timelocks(user_id, &serialized)
+    }
+
+    /// Holds a time-locked transaction until it becomes final.
+    pub fn add(&self, user_id: Uuid, locked: LockedTx) -> Result<(), ZipError> {
+        let mut all = self.list(user_id)?;
+        all.push(locked);
+        self.store(user_id, &all)
+    }
+
+    /// Removes a transaction once broadcast or cancelled.
+    pub fn remove(&self, user_id: Uuid, txid: &str) -> Result<(), ZipError> {
+        let mut all = self.list(user_id)?;
+        all.retain(|l| l.txid != txid);
+        self.store(user_id, &all)
+    }
+
+    /// Transactions whose lock has expired and can be broadcast now.
+    pub fn due(
+        &self,
+        user_id: Uuid,
+        tip_height: u64,
+        median_time_past: i64,
+    ) -> Result<Vec<LockedTx>, ZipError> {
+        Ok(self
+            .list(user_id)?
+            .into_iter()
+            .filter(|l| l.is_final(tip_height, median_time_past))
+            .collect())
+    }
+
+    /// Total satoshis held in vaults.
+    pub fn vault_balance(&self, user_id: Uuid) -> Result<u64, ZipError> {
+        Ok(self
+            .list(user_id)?
+            .iter()
+            .filter(|l| l.kind == LockKind::Vault)
+            .map(|l| l.amount)
+            .sum())
+    }
+}
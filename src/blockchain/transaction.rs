@@ -1,221 +1,1205 @@
 use bincode;
 use hex;
-use parking_lot::RwLock;
-use reqwest::Client;
-use rust_decimal::Decimal;
-use secrecy::Secret;
+use secp256k1::SecretKey;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
-use sv::messages::Tx;
 use sv::script::Script;
-use sv::transaction::Transaction;
-use sv::wallet::ExtendedPrivateKey;
+use sv::transaction::{OutPoint, Transaction, TxIn, TxOut};
+use sv::util::{Hash256, sha256d};
 use uuid::Uuid;
 
-use crate::blockchain::TransactionManager;
-use crate::config::EnvConfig;
+use crate::blockchain::chain::{HeaderStore, MerkleProof};
+use crate::blockchain::op_return::DataOutput;
 use crate::errors::ZipError;
 use crate::integrations::rustbus::RustBusIntegrator;
 use crate::storage::ZipStorage;
-use crate::utils::cache::Cache;
 use crate::utils::crypto::Crypto;
-use crate::utils::rate_limiter::RateLimiter;
-use crate::utils::telemetry::Telemetry;
 
-#[derive(Serialize, Deserialize)]
-pub struct WalletData {
-    pub address: String,
-    pub balance: u64,
-    pub currency: String,
-    pub balance_converted: Decimal,
-    pub derivation_path: String,
+/// Estimated size of a signed P2PKH input (outpoint, unlocking script, sequence).
+pub const P2PKH_INPUT_SIZE: usize = 148;
+/// Estimated size of a P2PKH output (value, locking script).
+pub const P2PKH_OUTPUT_SIZE: usize = 34;
+/// Version, input/output counts and lock time.
+pub const TX_OVERHEAD_SIZE: usize = 10;
+/// Minimum relay fee rate in satoshis per byte.
+pub const FEE_RATE: u64 = 1;
+/// Change below this value is left to the miner instead of creating an output.
+pub const DUST_LIMIT: u64 = 1;
+/// Sequence that disables nLockTime.
+pub const SEQUENCE_FINAL: u32 = 0xffff_ffff;
+/// Highest sequence that still enforces nLockTime.
+pub const SEQUENCE_LOCKTIME: u32 = 0xffff_fffe;
+/// Sighash type committing to every input and output, with the BSV fork ID (BIP143
+/// digest).
+pub const SIGHASH_ALL_FORKID: u32 = 0x41;
+/// Version marker opening a BEEF (BRC-62) transaction envelope.
+pub const BEEF_V1: u32 = 0xefbe_0001;
+/// Version marker of the BRC-96 envelope, which may list ancestors by TXID only.
+pub const BEEF_V2: u32 = 0xefbe_0002;
+
+/// Spendable output held by the wallet.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Utxo {
+    pub txid: String,
+    pub vout: u32,
+    pub value: u64,
+    pub script: Vec<u8>,
+}
+
+impl Utxo {
+    /// Outpoint identifier ("txid:vout").
+    pub fn outpoint(&self) -> String {
+        format!("{}:{}", self.txid, self.vout)
+    }
+}
+
+/// Keys that unlock the wallet's coins, by the P2PKH locking script each one unlocks.
+pub type SigningKeys = HashMap<Vec<u8>, SecretKey>;
+
+/// UTXO locked by a transaction that is built but not yet accepted by the network.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Reservation {
+    pub utxo: Utxo,
+    pub txid: String,
+    /// Unix timestamp (seconds) when the UTXO was reserved.
+    pub reserved_at: i64,
+}
+
+/// How an observed spend of a wallet outpoint relates to the wallet's own transactions.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SpendCheck {
+    /// Spent by the wallet's own transaction, or not tracked by this wallet.
+    Expected,
+    /// A UTXO the wallet considered spendable was spent by a transaction it did not build.
+    UnknownSpend { outpoint: String },
+    /// A transaction built by this wallet lost its input to a competing spend.
+    Conflict { our_txid: String, outpoint: String },
+}
+
+/// Inputs, change and size chosen for a payment.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CoinSelection {
+    pub inputs: Vec<Utxo>,
+    pub total_in: u64,
+    pub change: u64,
+    pub estimated_size: usize,
+}
+
+/// Node of a BUMP (BRC-74) Merkle path level.
+#[derive(Clone, Debug, PartialEq)]
+enum PathNode {
+    Hash([u8; 32]),
+    /// Last node of an odd level, paired with itself.
+    Duplicate,
+}
+
+/// Merkle path proving one or more transactions of a block (BRC-74 BUMP).
+#[derive(Clone, Debug, PartialEq)]
+pub struct MerklePath {
+    pub block_height: u64,
+    /// Offsets and hashes per tree level, leaves first.
+    levels: Vec<Vec<(u64, PathNode)>>,
+}
+
+impl MerklePath {
+    /// Hash at `offset` on `height`, computed from the level below when the path
+    /// leaves it out because other leaves already imply it.
+    fn node(&self, height: usize, offset: u64) -> Option<PathNode> {
+        if let Some((_, node)) = self.levels.get(height)?.iter().find(|(o, _)| *o == offset) {
+            return Some(node.clone());
+        }
+        if height == 0 {
+            return None;
+        }
+        let PathNode::Hash(left) = self.node(height - 1, offset * 2)? else {
+            return None;
+        };
+        let right = match self.node(height - 1, offset * 2 + 1)? {
+            PathNode::Hash(right) => right,
+            PathNode::Duplicate => left,
+        };
+        Some(PathNode::Hash(sha256d(&[left, right].concat()).0))
+    }
+
+    /// Merkle proof for `txid` taken from this path, to check against a header.
+    pub fn proof(&self, txid: &str) -> Result<MerkleProof, ZipError> {
+        let leaf = Hash256::decode(txid)
+            .map_err(|e| ZipError::Blockchain(e.to_string()))?
+            .0;
+        let index = self
+            .levels
+            .first()
+            .and_then(|leaves| {
+                leaves
+                    .iter()
+                    .find(|(_, node)| *node == PathNode::Hash(leaf))
+            })
+            .map(|(offset, _)| *offset)
+            .ok_or_else(|| {
+                ZipError::Blockchain(format!("Merkle path does not include {}", txid))
+            })?;
+        let mut nodes = vec![];
+        for height in 0..self.levels.len() {
+            let sibling = (index >> height) ^ 1;
+            nodes.push(match self.node(height, sibling) {
+                Some(PathNode::Hash(hash)) => Hash256(hash).encode(),
+                Some(PathNode::Duplicate) => "*".to_string(),
+                None => {
+                    return Err(ZipError::Blockchain(format!(
+                        "Merkle path for {} is incomplete",
+                        txid
+                    )));
+                }
+            });
+        }
+        Ok(MerkleProof {
+            txid: txid.to_string(),
+            index,
+            nodes,
+            block_hash: String::new(),
+            block_height: self.block_height,
+        })
+    }
+}
+
+/// Transaction listed in a BEEF envelope; BRC-96 ancestors may be a TXID only.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BeefTx {
+    pub txid: String,
+    pub tx: Option<Transaction>,
+    /// Index of the Merkle path proving it was mined.
+    pub bump: Option<usize>,
+}
+
+/// BEEF (BRC-62/BRC-96) envelope: a transaction with the ancestors and Merkle paths
+/// a recipient needs to verify it without a node.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Beef {
+    pub bumps: Vec<MerklePath>,
+    /// Ancestors first; the subject transaction comes last.
+    pub transactions: Vec<BeefTx>,
+}
+
+impl Beef {
+    /// Parses a BEEF envelope.
+    pub fn parse(bytes: &[u8]) -> Result<Self, ZipError> {
+        let mut reader = WireReader::new(bytes);
+        let version = reader.u32()?;
+        if version != BEEF_V1 && version != BEEF_V2 {
+            return Err(ZipError::Blockchain("Not a BEEF transaction".to_string()));
+        }
+        let mut bumps = vec![];
+        for _ in 0..reader.length()? {
+            bumps.push(reader.bump()?);
+        }
+        let mut transactions = vec![];
+        for _ in 0..reader.length()? {
+            let format = if version == BEEF_V2 { reader.u8()? } else { 0 };
+            if format == 2 {
+                // Ancestor known to the recipient by TXID only
+                transactions.push(BeefTx {
+                    txid: Hash256(reader.array()?).encode(),
+                    tx: None,
+                    bump: None,
+                });
+                continue;
+            }
+            let tx = reader.transaction()?;
+            let has_bump = if version == BEEF_V2 {
+                format == 1
+            } else {
+                reader.u8()? == 1
+            };
+            let bump = if has_bump {
+                let index = reader.varint()? as usize;
+                if index >= bumps.len() {
+                    return Err(ZipError::Blockchain(format!(
+                        "BEEF refers to missing Merkle path {}",
+                        index
+                    )));
+                }
+                Some(index)
+            } else {
+                None
+            };
+            transactions.push(BeefTx {
+                txid: TransactionManager::txid(&tx),
+                tx: Some(tx),
+                bump,
+            });
+        }
+        reader.finish()?;
+        Ok(Self {
+            bumps,
+            transactions,
+        })
+    }
+
+    /// The transaction being paid with.
+    pub fn subject(&self) -> Result<&Transaction, ZipError> {
+        self.transactions
+            .last()
+            .and_then(|tx| tx.tx.as_ref())
+            .ok_or_else(|| ZipError::Blockchain("BEEF contains no transaction".to_string()))
+    }
+
+    /// SPV check of the subject: every transaction it depends on is either proven
+    /// mined by its Merkle path against `headers`, or spends existing outputs of
+    /// verified ancestors without creating value.
+    pub fn verify(&self, headers: &HeaderStore) -> Result<(), ZipError> {
+        let mut verified: HashMap<&str, &Transaction> = HashMap::new();
+        for entry in &self.transactions {
+            let Some(tx) = &entry.tx else {
+                continue;
+            };
+            if let Some(bump) = entry.bump {
+                let path = &self.bumps[bump];
+                let proof = path.proof(&entry.txid)?;
+                let header = headers.header_at(path.block_height)?.ok_or_else(|| {
+                    ZipError::Blockchain(format!("Unknown block at height {}", path.block_height))
+                })?;
+                if proof.compute_root()? != header.merkle_root {
+                    return Err(ZipError::Blockchain(format!(
+                        "Merkle path for {} does not match block {}",
+                        entry.txid, header.hash
+                    )));
+                }
+                verified.insert(entry.txid.as_str(), tx);
+                continue;
+            }
+            if tx.inputs.is_empty() {
+                continue;
+            }
+            let mut total_in = 0u64;
+            let mut proven = true;
+            for input in &tx.inputs {
+                let outpoint = &input.previous_output;
+                match verified
+                    .get(outpoint.hash.encode().as_str())
+                    .and_then(|parent| parent.outputs.get(outpoint.index as usize))
+                {
+                    Some(output) => total_in += output.value,
+                    None => {
+                        proven = false;
+                        break;
+                    }
+                }
+            }
+            let total_out: u64 = tx.outputs.iter().map(|output| output.value).sum();
+            if proven && total_in >= total_out {
+                verified.insert(entry.txid.as_str(), tx);
+            }
+        }
+        let subject = self
+            .transactions
+            .last()
+            .ok_or_else(|| ZipError::Blockchain("BEEF contains no transaction".to_string()))?;
+        if !verified.contains_key(subject.txid.as_str()) {
+            return Err(ZipError::Blockchain(format!(
+                "Transaction {} is not proven by its BEEF ancestry",
+                subject.txid
+            )));
+        }
+        Ok(())
+    }
 }
 
 #[derive(Clone)]
-pub struct WalletManager {
+pub struct TransactionManager {
     storage: Arc<ZipStorage>,
-    tx_manager: Arc<TransactionManager>,
     rustbus: Option<Arc<RustBusIntegrator>>,
-    hd_key: Arc<RwLock<ExtendedPrivateKey>>,
-    derivation_index: Arc<RwLock<u32>>,
-    price_cache: Arc<Cache<String, Decimal>>,
-    telemetry: Telemetry,
-    rate_limiter: RateLimiter,
-}
-
-impl Clone for WalletManager {
-    fn clone(&self) -> Self {
-        Self {
-            storage: Arc::clone(&self.storage),
-            tx_manager: Arc::clone(&self.tx_manager),
-            rustbus: self.rustbus.clone().map(Arc::clone),
-            hd_key: Arc::clone(&self.hd_key),
-            derivation_index: Arc::clone(&self.derivation_index),
-            price_cache: Arc::clone(&self.price_cache),
-            telemetry: self.telemetry.clone(),
-            rate_limiter: self.rate_limiter.clone(),
-        }
-    }
-}
-
-impl WalletManager {
-    /// Initializes wallet with HD key or generates new one.
-    pub fn new(
-        storage: Arc<ZipStorage>,
-        tx_manager: Arc<TransactionManager>,
-        rustbus: Option<Arc<RustBusIntegrator>>,
-    ) -> Result<Self, ZipError> {
-        let config = EnvConfig::load()?;
-        let priv_key_bytes = storage.get_private_key().unwrap_or_else(|_| {
-            let private_key = Crypto::generate_private_key()?;
-            let seed = private_key.to_bytes();
-            let hd_key = ExtendedPrivateKey::new_seed(&seed, sv::network::Network::Mainnet)?;
-            let bytes = Secret::new(hd_key.to_bytes());
-            storage.store_private_key(&bytes)?;
-            Secret::new(hd_key.to_bytes())
-        });
-        let hd_key = ExtendedPrivateKey::from_bytes(priv_key_bytes.expose_secret().clone())?;
-        Ok(Self {
-            storage,
-            tx_manager,
-            rustbus,
-            hd_key: Arc::new(RwLock::new(hd_key)),
-            derivation_index: Arc::new(RwLock::new(0)),
-            price_cache: Arc::new(Cache::new(300)), // 5min TTL
-            telemetry: Telemetry::new(&config),
-            rate_limiter: RateLimiter::new(5, 60), // 5 payments per minute
-        })
+}
+
+impl TransactionManager {
+    /// Initializes transaction builder over the cached UTXO set.
+    pub fn new(storage: Arc<ZipStorage>, rustbus: Option<Arc<RustBusIntegrator>>) -> Self {
+        Self { storage, rustbus }
+    }
+
+    /// Returns the cached UTXOs for a user.
+    pub fn get_cached_utxos(&self, user_id: Uuid) -> Result<Vec<Utxo>, ZipError> {
+        let data = self.storage.get_utxos(user_id)?;
+        Ok(data
+            .map(|d| bincode::deserialize(&d).unwrap_or_default())
+            .unwrap_or_default())
     }
 
-    /// Generates a new child address for privacy (no reuse).
-    pub fn get_address(&self) -> Result<String, ZipError> {
-        let index = {
-            let mut idx = self.derivation_index.write();
-            *idx += 1;
-            *idx
+    fn store_utxos(&self, user_id: Uuid, utxos: &[Utxo]) -> Result<(), ZipError> {
+        let serialized =
+            bincode::serialize(utxos).map_err(|e| ZipError::Blockchain(e.to_string()))?;
+        self.storage.cache_utxos(user_id, &serialized)
+    }
+
+    /// Adds outputs the wallet can now spend (e.g., change from a broadcast transaction).
+    /// Outpoints already in the cache are not added twice.
+    pub fn add_utxos(&self, user_id: Uuid, new_utxos: &[Utxo]) -> Result<(), ZipError> {
+        let mut utxos = self.get_cached_utxos(user_id)?;
+        let cached: HashSet<String> = utxos.iter().map(Utxo::outpoint).collect();
+        utxos.extend(
+            new_utxos
+                .iter()
+                .filter(|u| !cached.contains(&u.outpoint()))
+                .cloned(),
+        );
+        self.store_utxos(user_id, &utxos)
+    }
+
+    /// Adds coins the indexer reports unspent to the UTXO cache, skipping any the wallet
+    /// already tracks as spendable, reserved, escrowed or spent. Returns the new coins.
+    pub fn merge_indexed_utxos(
+        &self,
+        user_id: Uuid,
+        found: Vec<Utxo>,
+    ) -> Result<Vec<Utxo>, ZipError> {
+        let mut tracked: HashSet<String> = self.tracked_outpoints(user_id)?.into_iter().collect();
+        let new: Vec<Utxo> = found
+            .into_iter()
+            .filter(|u| tracked.insert(u.outpoint()))
+            .collect();
+        self.add_utxos(user_id, &new)?;
+        Ok(new)
+    }
+
+    /// Splits funds into `count` equal coins paid back to the wallet's own script, so
+    /// payments rarely need more than one input. The transaction is signed with `keys`
+    /// and its outputs are cached like change.
+    pub async fn pre_create_utxos(
+        &self,
+        user_id: Uuid,
+        count: u32,
+        value: u64,
+        fee: u64,
+        keys: &SigningKeys,
+    ) -> Result<Transaction, ZipError> {
+        if count == 0 || value < DUST_LIMIT {
+            return Err(ZipError::Blockchain("Invalid UTXO split".to_string()));
+        }
+        let script = self.wallet_script(user_id)?;
+        let outputs = (0..count)
+            .map(|_| TxOut {
+                value,
+                script: script.clone(),
+            })
+            .collect();
+        let tx = self.build_tx(user_id, outputs, fee, 0, None, keys)?;
+        let txid = Self::txid(&tx);
+        let created: Vec<Utxo> = (0..count)
+            .map(|vout| Utxo {
+                txid: txid.clone(),
+                vout,
+                value,
+                script: script.0.clone(),
+            })
+            .collect();
+        self.add_utxos(user_id, &created)?;
+        Ok(tx)
+    }
+
+    /// Estimates serialized size for a P2PKH transaction.
+    pub fn estimate_size(inputs: usize, outputs: usize) -> usize {
+        TX_OVERHEAD_SIZE + inputs * P2PKH_INPUT_SIZE + outputs * P2PKH_OUTPUT_SIZE
+    }
+
+    /// Selects coins (largest first) for a payment without touching the UTXO cache.
+    pub fn select_coins(
+        &self,
+        user_id: Uuid,
+        amount: u64,
+        fee: u64,
+    ) -> Result<CoinSelection, ZipError> {
+        let mut utxos = self.get_cached_utxos(user_id)?;
+        utxos.sort_by(|a, b| b.value.cmp(&a.value));
+        Self::select_from(utxos, amount, fee)
+    }
+
+    /// Selects coins in the order given until they cover `amount` plus `fee`.
+    fn select_from(utxos: Vec<Utxo>, amount: u64, fee: u64) -> Result<CoinSelection, ZipError> {
+        let target = amount
+            .checked_add(fee)
+            .ok_or_else(|| ZipError::Blockchain("Amount overflow".to_string()))?;
+        let mut inputs = vec![];
+        let mut total_in = 0u64;
+        // A coin cached without its locking script can never be signed for
+        for utxo in utxos.into_iter().filter(|u| !u.script.is_empty()) {
+            if total_in >= target {
+                break;
+            }
+            total_in += utxo.value;
+            inputs.push(utxo);
+        }
+        if total_in < target {
+            return Err(ZipError::Blockchain(format!(
+                "Insufficient funds: need {} satoshis, have {}",
+                target, total_in
+            )));
+        }
+        let change = match total_in - target {
+            c if c < DUST_LIMIT => 0,
+            c => c,
         };
-        let child_key = self
-            .hd_key
-            .read()
-            .derive_private_key(&[sv::wallet::ChildNumber::Normal { index }])?;
-        let pubkey = Crypto::derive_public_key(&child_key);
-        let address = Crypto::generate_address(&pubkey);
-        // Store derivation path
-        let data = WalletData {
-            address: address.clone(),
-            balance: 0,
-            currency: "USD".to_string(),
-            balance_converted: Decimal::ZERO,
-            derivation_path: format!("m/44'/0'/0'/0/{}", index),
+        let outputs = if change > 0 { 2 } else { 1 };
+        Ok(CoinSelection {
+            estimated_size: Self::estimate_size(inputs.len(), outputs),
+            inputs,
+            total_in,
+            change,
+        })
+    }
+
+    /// Builds a payment from cached UTXOs, signed with `keys`, reserving spent inputs
+    /// and caching change. Data outputs follow the payment output and carry no value.
+    pub async fn build_payment_tx(
+        &self,
+        user_id: Uuid,
+        recipient_script: Script,
+        amount: u64,
+        fee: u64,
+        data: &[DataOutput],
+        keys: &SigningKeys,
+    ) -> Result<Transaction, ZipError> {
+        let mut outputs = vec![TxOut {
+            value: amount,
+            script: recipient_script,
+        }];
+        for output in data {
+            outputs.push(output.tx_out()?);
+        }
+        self.build_tx(user_id, outputs, fee, 0, None, keys)
+    }
+
+    /// Builds a payment like `build_payment_tx`, funded by the coins held under
+    /// `escrow_id` first and by cached UTXOs if they fall short. The escrow keeps its
+    /// coins on record until `close_escrow` or `restore_escrow`.
+    pub async fn build_escrow_payment_tx(
+        &self,
+        user_id: Uuid,
+        escrow_id: &str,
+        recipient_script: Script,
+        amount: u64,
+        fee: u64,
+        data: &[DataOutput],
+        keys: &SigningKeys,
+    ) -> Result<Transaction, ZipError> {
+        let mut outputs = vec![TxOut {
+            value: amount,
+            script: recipient_script,
+        }];
+        for output in data {
+            outputs.push(output.tx_out()?);
+        }
+        self.build_tx(user_id, outputs, fee, 0, Some(escrow_id), keys)
+    }
+
+    /// Builds a transaction paying exactly the given outputs, in order, with change last
+    /// (e.g., the outputs of a merchant payment request).
+    pub async fn build_outputs_tx(
+        &self,
+        user_id: Uuid,
+        outputs: Vec<TxOut>,
+        fee: u64,
+        keys: &SigningKeys,
+    ) -> Result<Transaction, ZipError> {
+        if outputs.is_empty() {
+            return Err(ZipError::Blockchain(
+                "Every output needs a locking script".to_string(),
+            ));
+        }
+        self.build_tx(user_id, outputs, fee, 0, None, keys)
+    }
+
+    /// Builds a payment that miners will not accept before `lock_time`
+    /// (a block height below 500,000,000, otherwise a Unix timestamp).
+    /// Without a recipient script the funds are locked back to the wallet.
+    /// Data outputs follow the payment output, as in `build_payment_tx`.
+    pub async fn build_locked_tx(
+        &self,
+        user_id: Uuid,
+        recipient_script: Option<Script>,
+        amount: u64,
+        fee: u64,
+        lock_time: u32,
+        data: &[DataOutput],
+        keys: &SigningKeys,
+    ) -> Result<Transaction, ZipError> {
+        if lock_time == 0 {
+            return Err(ZipError::Blockchain("Lock time must be set".to_string()));
+        }
+        let script = match recipient_script {
+            Some(script) => script,
+            None => self.wallet_script(user_id)?,
         };
-        let serialized = bincode::serialize(&data)
-            .map_err(|e| ZipError::Blockchain(e.to_string()))?;
-        let user_id = Uuid::new_v4();
-        self.storage.store_user_data(user_id, &serialized)?;
-        let _ = self
-            .telemetry
-            .track_payment_event(&user_id.to_string(), "address_generated", 0, true)
-            .await;
-        Ok(address)
-    }
-
-    /// Fetches BSV price in specified currency, caches for 5min.
-    pub async fn fetch_price(&self, currency: &str) -> Result<Decimal, ZipError> {
-        let cache_key = currency.to_string();
-        if let Some(price) = self.price_cache.get(&cache_key).await {
-            return Ok(price);
-        }
-        let client = Client::new();
-        let resp = client
-            .get(format!(
-                "https://api.coingecko.com/api/v3/simple/price?ids=bitcoin-sv&vs_currencies={}",
-                currency.to_lowercase()
-            ))
-            .send()
-            .await?
-            .json::<serde_json::Value>()
-            .await?;
-        let price = resp["bitcoin-sv"][currency.to_lowercase()]
-            .as_f64()
-            .ok_or(ZipError::Blockchain("Invalid price data".to_string()))?;
-        let price = Decimal::from_f64(price)
-            .ok_or_else(|| ZipError::Blockchain("Invalid price conversion".to_string()))?; // Fixed from try_from_f64
-        self.price_cache.insert(cache_key, price).await;
-        Ok(price)
-    }
-
-    /// Updates and caches balance in satoshis and converted currency.
-    pub async fn update_balance(
+        let mut outputs = vec![TxOut {
+            value: amount,
+            script,
+        }];
+        for output in data {
+            outputs.push(output.tx_out()?);
+        }
+        self.build_tx(user_id, outputs, fee, lock_time, None, keys)
+    }
+
+    /// Locking script the wallet pays itself with: that of its largest coin, which coin
+    /// selection spends first and change returns to.
+    fn wallet_script(&self, user_id: Uuid) -> Result<Script, ZipError> {
+        self.get_cached_utxos(user_id)?
+            .into_iter()
+            .filter(|u| !u.script.is_empty())
+            .max_by_key(|u| u.value)
+            .map(|u| Script(u.script))
+            .ok_or_else(|| ZipError::Blockchain("No wallet script to lock funds to".to_string()))
+    }
+
+    /// Builds a transaction paying `outputs` and signs every input with `keys`, then
+    /// reserves its inputs under the signed TXID and caches its change.
+    fn build_tx(
         &self,
         user_id: Uuid,
-        currency: &str,
-    ) -> Result<(u64, Decimal), ZipError> {
-        self.rate_limiter.check(&user_id.to_string()).await?;
-        let address = self.get_address()?;
-        let balance = if let Some(r) = &self.rustbus {
-            r.query_balance(&address).await?
+        mut outputs: Vec<TxOut>,
+        fee: u64,
+        lock_time: u32,
+        escrow_id: Option<&str>,
+        keys: &SigningKeys,
+    ) -> Result<Transaction, ZipError> {
+        let amount = outputs
+            .iter()
+            .try_fold(0u64, |total, o| total.checked_add(o.value))
+            .ok_or_else(|| ZipError::Blockchain("Amount overflow".to_string()))?;
+        // An empty script would burn the output or be mistaken for change
+        if outputs.iter().any(|o| o.script.0.is_empty()) {
+            return Err(ZipError::Blockchain(
+                "Every output needs a locking script".to_string(),
+            ));
+        }
+        let selection = match escrow_id {
+            Some(escrow_id) => {
+                let mut utxos = self.get_escrow(user_id, escrow_id)?;
+                let mut cached = self.get_cached_utxos(user_id)?;
+                cached.sort_by(|a, b| b.value.cmp(&a.value));
+                utxos.extend(cached);
+                Self::select_from(utxos, amount, fee)?
+            }
+            None => self.select_coins(user_id, amount, fee)?,
+        };
+        // nLockTime is only enforced when at least one input is non-final
+        let sequence = if lock_time > 0 {
+            SEQUENCE_LOCKTIME
         } else {
-            0
+            SEQUENCE_FINAL
         };
-        let price = self.fetch_price(currency).await?;
-        let balance_converted = Decimal::from(balance) / Decimal::from(100_000_000) * price;
-        let data = WalletData {
-            address,
-            balance,
-            currency: currency.to_string(),
-            balance_converted,
-            derivation_path: format!("m/44'/0'/0'/0/{}", *self.derivation_index.read()),
+        let inputs = selection
+            .inputs
+            .iter()
+            .map(|utxo| {
+                Ok(TxIn {
+                    previous_output: OutPoint {
+                        hash: Hash256::decode(&utxo.txid)
+                            .map_err(|e| ZipError::Blockchain(e.to_string()))?,
+                        index: utxo.vout,
+                    },
+                    // Unlocking scripts are filled in once the outputs are final
+                    script: Script::default(),
+                    sequence,
+                })
+            })
+            .collect::<Result<Vec<_>, ZipError>>()?;
+        // Change returns to the locking script of the first spent input
+        let change_script = selection
+            .inputs
+            .first()
+            .map(|u| u.script.clone())
+            .unwrap_or_default();
+        let change_vout = outputs.len() as u32;
+        if selection.change > 0 {
+            outputs.push(TxOut {
+                value: selection.change,
+                script: Script(change_script.clone()),
+            });
+        }
+        let mut tx = Transaction {
+            version: 1,
+            inputs,
+            outputs,
+            lock_time,
         };
-        let serialized = bincode::serialize(&data)
-            .map_err(|e| ZipError::Blockchain(e.to_string()))?;
-        self.storage.store_user_data(user_id, &serialized)?;
-        let _ = self
-            .telemetry
-            .track_payment_event(&user_id.to_string(), "balance_update", balance, true)
-            .await;
-        Ok((balance, balance_converted))
-    }
-
-    /// Initiates payment using pre-created UTXOs and PayMail script.
-    pub async fn send_payment(
+        Self::sign_inputs(&mut tx, &selection.inputs, keys)?;
+        let txid = Self::txid(&tx);
+        self.reserve(user_id, &txid, &selection.inputs)?;
+        // Change from a time-locked transaction is unspendable until it is mined
+        if selection.change > 0 && lock_time == 0 {
+            let mut utxos = self.get_cached_utxos(user_id)?;
+            utxos.push(Utxo {
+                txid,
+                vout: change_vout,
+                value: selection.change,
+                script: change_script,
+            });
+            self.store_utxos(user_id, &utxos)?;
+        }
+        Ok(tx)
+    }
+
+    /// Signs every input with the key for the coin it spends (`spent`, in input order)
+    /// and sets its unlocking script to `<signature> <public key>`.
+    pub fn sign_inputs(
+        tx: &mut Transaction,
+        spent: &[Utxo],
+        keys: &SigningKeys,
+    ) -> Result<(), ZipError> {
+        for (index, utxo) in spent.iter().enumerate() {
+            let key = keys.get(&utxo.script).ok_or_else(|| {
+                ZipError::Crypto(format!("No key to sign input {}", utxo.outpoint()))
+            })?;
+            let digest = Self::sighash(tx, index, &utxo.script, utxo.value);
+            let mut signature = Crypto::sign_digest(key, digest);
+            signature.push(SIGHASH_ALL_FORKID as u8);
+            let public_key = Crypto::derive_public_key(key).to_bytes();
+            let mut script = vec![];
+            for push in [&signature, &public_key] {
+                script.push(push.len() as u8);
+                script.extend_from_slice(push);
+            }
+            tx.inputs[index].script = Script(script);
+        }
+        Ok(())
+    }
+
+    /// BIP143 digest input `index` signs under `SIGHASH_ALL_FORKID`, committing to the
+    /// locking script (`script_code`) and value of the coin it spends.
+    pub fn sighash(tx: &Transaction, index: usize, script_code: &[u8], value: u64) -> [u8; 32] {
+        let mut prevouts = vec![];
+        let mut sequences = vec![];
+        for input in &tx.inputs {
+            prevouts.extend_from_slice(&input.previous_output.to_bytes());
+            sequences.extend_from_slice(&input.sequence.to_le_bytes());
+        }
+        let mut outputs = vec![];
+        for output in &tx.outputs {
+            outputs.extend_from_slice(&output.value.to_le_bytes());
+            write_varint(&mut outputs, output.script.0.len() as u64);
+            outputs.extend_from_slice(&output.script.0);
+        }
+        let input = &tx.inputs[index];
+        let mut preimage = vec![];
+        preimage.extend_from_slice(&tx.version.to_le_bytes());
+        preimage.extend_from_slice(&sha256d(&prevouts).0);
+        preimage.extend_from_slice(&sha256d(&sequences).0);
+        preimage.extend_from_slice(&input.previous_output.to_bytes());
+        write_varint(&mut preimage, script_code.len() as u64);
+        preimage.extend_from_slice(script_code);
+        preimage.extend_from_slice(&value.to_le_bytes());
+        preimage.extend_from_slice(&input.sequence.to_le_bytes());
+        preimage.extend_from_slice(&sha256d(&outputs).0);
+        preimage.extend_from_slice(&tx.lock_time.to_le_bytes());
+        preimage.extend_from_slice(&SIGHASH_ALL_FORKID.to_le_bytes());
+        sha256d(&preimage).0
+    }
+
+    /// Returns UTXOs locked by in-flight transactions.
+    pub fn get_reservations(&self, user_id: Uuid) -> Result<Vec<Reservation>, ZipError> {
+        let data = self.storage.get_reservations(user_id)?;
+        Ok(data
+            .map(|d| bincode::deserialize(&d).unwrap_or_default())
+            .unwrap_or_default())
+    }
+
+    fn store_reservations(
         &self,
         user_id: Uuid,
-        recipient_script: Script,
+        reservations: &[Reservation],
+    ) -> Result<(), ZipError> {
+        let serialized =
+            bincode::serialize(reservations).map_err(|e| ZipError::Blockchain(e.to_string()))?;
+        self.storage.store_reservations(user_id, &serialized)
+    }
+
+    /// Returns spent outpoints mapped to the TXID that spent them.
+    pub fn get_spent_outpoints(&self, user_id: Uuid) -> Result<HashMap<String, String>, ZipError> {
+        let data = self.storage.get_spent_outpoints(user_id)?;
+        Ok(data
+            .map(|d| bincode::deserialize(&d).unwrap_or_default())
+            .unwrap_or_default())
+    }
+
+    fn store_spent_outpoints(
+        &self,
+        user_id: Uuid,
+        spent: &HashMap<String, String>,
+    ) -> Result<(), ZipError> {
+        let serialized =
+            bincode::serialize(spent).map_err(|e| ZipError::Blockchain(e.to_string()))?;
+        self.storage.store_spent_outpoints(user_id, &serialized)
+    }
+
+    /// Moves inputs out of the spendable set and locks them to a TXID.
+    fn reserve(&self, user_id: Uuid, txid: &str, inputs: &[Utxo]) -> Result<(), ZipError> {
+        let mut utxos = self.get_cached_utxos(user_id)?;
+        utxos.retain(|u| !inputs.iter().any(|i| i.outpoint() == u.outpoint()));
+        self.store_utxos(user_id, &utxos)?;
+        let mut reservations = self.get_reservations(user_id)?;
+        let reserved_at = chrono::Utc::now().timestamp();
+        reservations.extend(inputs.iter().map(|utxo| Reservation {
+            utxo: utxo.clone(),
+            txid: txid.to_string(),
+            reserved_at,
+        }));
+        self.store_reservations(user_id, &reservations)
+    }
+
+    /// Coins held under an escrow.
+    pub fn get_escrow(&self, user_id: Uuid, escrow_id: &str) -> Result<Vec<Utxo>, ZipError> {
+        let data = self.storage.get_escrow(user_id, escrow_id)?;
+        Ok(data
+            .map(|d| bincode::deserialize(&d).unwrap_or_default())
+            .unwrap_or_default())
+    }
+
+    fn store_escrow(&self, user_id: Uuid, escrow_id: &str, coins: &[Utxo]) -> Result<(), ZipError> {
+        let serialized =
+            bincode::serialize(coins).map_err(|e| ZipError::Blockchain(e.to_string()))?;
+        self.storage.store_escrow(user_id, escrow_id, &serialized)
+    }
+
+    /// Holds coins covering `amount` under `escrow_id`, out of the UTXO cache so they
+    /// cannot be spent until `release_escrow`. Returns the held coins.
+    pub fn escrow(
+        &self,
+        user_id: Uuid,
+        escrow_id: &str,
         amount: u64,
-        fee: u64,
-    ) -> Result<String, ZipError> {
-        self.rate_limiter.check(&user_id.to_string()).await?;
-        let result = self
-            .tx_manager
-            .build_payment_tx(user_id, recipient_script, amount, fee)
-            .await;
-        let success = result.is_ok();
-        let tx_id = result
-            .as_ref()
-            .map(|tx| {
-                // Manual serialization since to_bytes is not available
-                let mut bytes = vec![];
-                bytes.extend_from_slice(&tx.version.to_le_bytes());
-                bytes.extend_from_slice(&(tx.inputs.len() as u32).to_le_bytes());
-                for input in &tx.inputs {
-                    bytes.extend_from_slice(&input.previous_output.to_bytes());
-                    bytes.extend_from_slice(&input.script.0);
-                    bytes.extend_from_slice(&input.sequence.to_le_bytes());
-                }
-                bytes.extend_from_slice(&(tx.outputs.len() as u32).to_le_bytes());
-                for output in &tx.outputs {
-                    bytes.extend_from_slice(&output.value.to_le_bytes());
-                    bytes.extend_from_slice(&output.script.0);
-                }
-                bytes.extend_from_slice(&tx.lock_time.to_le_bytes());
-                hex::encode(bytes)
-            })
-            .unwrap_or(Ok(String::new()))?;
-        let _ = self
-            .telemetry
-            .track_payment_event(&user_id.to_string(), &tx_id, amount, success)
-            .await;
-        result.map(|_| tx_id) // Adjusted to return tx_id on success
+    ) -> Result<Vec<Utxo>, ZipError> {
+        let selection = self.select_coins(user_id, amount, 0)?;
+        let mut utxos = self.get_cached_utxos(user_id)?;
+        utxos.retain(|u| {
+            !selection
+                .inputs
+                .iter()
+                .any(|i| i.outpoint() == u.outpoint())
+        });
+        self.store_escrow(user_id, escrow_id, &selection.inputs)?;
+        self.store_utxos(user_id, &utxos)?;
+        Ok(selection.inputs)
+    }
+
+    /// Returns escrowed coins to the UTXO cache.
+    pub fn release_escrow(&self, user_id: Uuid, escrow_id: &str) -> Result<Vec<Utxo>, ZipError> {
+        let Some(data) = self.storage.get_escrow(user_id, escrow_id)? else {
+            // Escrows used to be held as reservations under their ID
+            return self.release_reservation(user_id, escrow_id);
+        };
+        let coins: Vec<Utxo> = bincode::deserialize(&data).unwrap_or_default();
+        self.storage.remove_escrow(user_id, escrow_id)?;
+        self.add_utxos(user_id, &coins)?;
+        Ok(coins)
+    }
+
+    /// Ends an escrow whose payment was sent: the coins the payment spent stay with it
+    /// and the rest return to the UTXO cache.
+    pub fn close_escrow(&self, user_id: Uuid, escrow_id: &str) -> Result<(), ZipError> {
+        let spent: HashSet<String> = self
+            .get_reservations(user_id)?
+            .iter()
+            .map(|r| r.utxo.outpoint())
+            .chain(self.get_spent_outpoints(user_id)?.into_keys())
+            .collect();
+        let mut unspent = self.get_escrow(user_id, escrow_id)?;
+        unspent.retain(|coin| !spent.contains(&coin.outpoint()));
+        self.storage.remove_escrow(user_id, escrow_id)?;
+        self.add_utxos(user_id, &unspent)
+    }
+
+    /// Takes escrowed coins back out of the UTXO cache after a payment funded by the
+    /// escrow failed and released its inputs.
+    pub fn restore_escrow(&self, user_id: Uuid, escrow_id: &str) -> Result<(), ZipError> {
+        let held: HashSet<String> = self
+            .get_escrow(user_id, escrow_id)?
+            .iter()
+            .map(Utxo::outpoint)
+            .collect();
+        let mut utxos = self.get_cached_utxos(user_id)?;
+        utxos.retain(|u| !held.contains(&u.outpoint()));
+        self.store_utxos(user_id, &utxos)
+    }
+
+    /// Inputs reserved by a transaction.
+    pub fn reserved_inputs(&self, user_id: Uuid, txid: &str) -> Result<Vec<Utxo>, ZipError> {
+        Ok(self
+            .get_reservations(user_id)?
+            .into_iter()
+            .filter(|r| r.txid == txid)
+            .map(|r| r.utxo)
+            .collect())
+    }
+
+    /// Abandons an in-flight transaction: inputs become spendable again and its change is dropped.
+    pub fn release_reservation(&self, user_id: Uuid, txid: &str) -> Result<Vec<Utxo>, ZipError> {
+        let mut reservations = self.get_reservations(user_id)?;
+        let released: Vec<Utxo> = reservations
+            .iter()
+            .filter(|r| r.txid == txid)
+            .map(|r| r.utxo.clone())
+            .collect();
+        reservations.retain(|r| r.txid != txid);
+        self.store_reservations(user_id, &reservations)?;
+        let mut utxos = self.get_cached_utxos(user_id)?;
+        utxos.retain(|u| u.txid != txid);
+        utxos.extend(released.iter().cloned());
+        self.store_utxos(user_id, &utxos)?;
+        Ok(released)
+    }
+
+    /// Records a transaction's inputs as spent once the network accepts it.
+    pub fn mark_spent(&self, user_id: Uuid, txid: &str) -> Result<(), ZipError> {
+        let mut reservations = self.get_reservations(user_id)?;
+        let mut spent = self.get_spent_outpoints(user_id)?;
+        for reservation in reservations.iter().filter(|r| r.txid == txid) {
+            spent.insert(reservation.utxo.outpoint(), txid.to_string());
+        }
+        reservations.retain(|r| r.txid != txid);
+        self.store_spent_outpoints(user_id, &spent)?;
+        self.store_reservations(user_id, &reservations)
+    }
+
+    /// Every outpoint the wallet tracks: spendable, reserved and already spent.
+    pub fn tracked_outpoints(&self, user_id: Uuid) -> Result<Vec<String>, ZipError> {
+        let mut outpoints: Vec<String> = self
+            .get_cached_utxos(user_id)?
+            .iter()
+            .map(Utxo::outpoint)
+            .collect();
+        outpoints.extend(
+            self.get_reservations(user_id)?
+                .iter()
+                .map(|r| r.utxo.outpoint()),
+        );
+        for (_, data) in self.storage.get_escrows(user_id)? {
+            let coins: Vec<Utxo> = bincode::deserialize(&data).unwrap_or_default();
+            outpoints.extend(coins.iter().map(Utxo::outpoint));
+        }
+        outpoints.extend(self.get_spent_outpoints(user_id)?.into_keys());
+        Ok(outpoints)
+    }
+
+    /// Applies an observed spend of a wallet outpoint and reports whether it conflicts
+    /// with the wallet's own transactions.
+    pub fn apply_spend(
+        &self,
+        user_id: Uuid,
+        outpoint: &str,
+        spending_txid: &str,
+    ) -> Result<SpendCheck, ZipError> {
+        let mut utxos = self.get_cached_utxos(user_id)?;
+        let mut reservations = self.get_reservations(user_id)?;
+        let mut spent = self.get_spent_outpoints(user_id)?;
+
+        let check = if let Some(our_txid) = spent.get(outpoint).cloned() {
+            if our_txid == spending_txid {
+                return Ok(SpendCheck::Expected);
+            }
+            // Our broadcast transaction lost the race; its change will never exist
+            utxos.retain(|u| u.txid != our_txid);
+            SpendCheck::Conflict {
+                our_txid,
+                outpoint: outpoint.to_string(),
+            }
+        } else if let Some(reservation) = reservations
+            .iter()
+            .find(|r| r.utxo.outpoint() == outpoint)
+            .cloned()
+        {
+            if reservation.txid == spending_txid {
+                self.mark_spent(user_id, spending_txid)?;
+                return Ok(SpendCheck::Expected);
+            }
+            // Our transaction is now invalid: its other inputs become spendable again
+            let our_txid = reservation.txid;
+            utxos.retain(|u| u.txid != our_txid);
+            utxos.extend(
+                reservations
+                    .iter()
+                    .filter(|r| r.txid == our_txid && r.utxo.outpoint() != outpoint)
+                    .map(|r| r.utxo.clone()),
+            );
+            reservations.retain(|r| r.txid != our_txid);
+            SpendCheck::Conflict {
+                our_txid,
+                outpoint: outpoint.to_string(),
+            }
+        } else if utxos.iter().any(|u| u.outpoint() == outpoint) {
+            utxos.retain(|u| u.outpoint() != outpoint);
+            SpendCheck::UnknownSpend {
+                outpoint: outpoint.to_string(),
+            }
+        } else if let Some(escrow_id) = self.escrow_holding(user_id, outpoint)? {
+            let mut coins = self.get_escrow(user_id, &escrow_id)?;
+            coins.retain(|u| u.outpoint() != outpoint);
+            self.store_escrow(user_id, &escrow_id, &coins)?;
+            SpendCheck::UnknownSpend {
+                outpoint: outpoint.to_string(),
+            }
+        } else {
+            return Ok(SpendCheck::Expected);
+        };
+
+        spent.insert(outpoint.to_string(), spending_txid.to_string());
+        self.store_utxos(user_id, &utxos)?;
+        self.store_reservations(user_id, &reservations)?;
+        self.store_spent_outpoints(user_id, &spent)?;
+        Ok(check)
+    }
+
+    /// Escrow holding an outpoint, if any.
+    fn escrow_holding(&self, user_id: Uuid, outpoint: &str) -> Result<Option<String>, ZipError> {
+        for (escrow_id, data) in self.storage.get_escrows(user_id)? {
+            let coins: Vec<Utxo> = bincode::deserialize(&data).unwrap_or_default();
+            if coins.iter().any(|u| u.outpoint() == outpoint) {
+                return Ok(Some(escrow_id));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Settles a transaction of ours that lost a double spend in one step: the
+    /// `conflicting` inputs are recorded as spent by `competing_txid`, its other reserved
+    /// inputs become spendable again and its change is dropped.
+    pub fn resolve_conflict(
+        &self,
+        user_id: Uuid,
+        our_txid: &str,
+        conflicting: &[String],
+        competing_txid: &str,
+    ) -> Result<(), ZipError> {
+        let mut utxos = self.get_cached_utxos(user_id)?;
+        let mut reservations = self.get_reservations(user_id)?;
+        let mut spent = self.get_spent_outpoints(user_id)?;
+
+        utxos.retain(|u| u.txid != our_txid);
+        for reservation in reservations.iter().filter(|r| r.txid == our_txid) {
+            let outpoint = reservation.utxo.outpoint();
+            if conflicting.contains(&outpoint) {
+                spent.insert(outpoint, competing_txid.to_string());
+            } else {
+                utxos.push(reservation.utxo.clone());
+            }
+        }
+        reservations.retain(|r| r.txid != our_txid);
+        // Inputs already recorded as spent by our transaction were taken by the competitor
+        for outpoint in conflicting {
+            if spent.get(outpoint).is_some_and(|txid| txid == our_txid) {
+                spent.insert(outpoint.clone(), competing_txid.to_string());
+            }
+        }
+
+        self.store_utxos(user_id, &utxos)?;
+        self.store_reservations(user_id, &reservations)?;
+        self.store_spent_outpoints(user_id, &spent)
+    }
+
+    /// Serializes a transaction in wire format.
+    pub fn serialize(tx: &Transaction) -> Vec<u8> {
+        let mut bytes = vec![];
+        bytes.extend_from_slice(&tx.version.to_le_bytes());
+        write_varint(&mut bytes, tx.inputs.len() as u64);
+        for input in &tx.inputs {
+            bytes.extend_from_slice(&input.previous_output.to_bytes());
+            write_varint(&mut bytes, input.script.0.len() as u64);
+            bytes.extend_from_slice(&input.script.0);
+            bytes.extend_from_slice(&input.sequence.to_le_bytes());
+        }
+        write_varint(&mut bytes, tx.outputs.len() as u64);
+        for output in &tx.outputs {
+            bytes.extend_from_slice(&output.value.to_le_bytes());
+            write_varint(&mut bytes, output.script.0.len() as u64);
+            bytes.extend_from_slice(&output.script.0);
+        }
+        bytes.extend_from_slice(&tx.lock_time.to_le_bytes());
+        bytes
+    }
+
+    /// Hex-encodes a transaction for broadcast.
+    pub fn to_hex(tx: &Transaction) -> String {
+        hex::encode(Self::serialize(tx))
+    }
+
+    /// Computes the transaction ID (double SHA-256, byte-reversed hex).
+    pub fn txid(tx: &Transaction) -> String {
+        let mut hash = sha256d(&Self::serialize(tx)).0;
+        hash.reverse();
+        hex::encode(hash)
+    }
+
+    /// Parses a transaction in wire format, rejecting trailing bytes.
+    pub fn deserialize(bytes: &[u8]) -> Result<Transaction, ZipError> {
+        let mut reader = WireReader::new(bytes);
+        let tx = reader.transaction()?;
+        reader.finish()?;
+        Ok(tx)
+    }
+
+    /// Extracts the subject (last) transaction from a BEEF envelope without verifying
+    /// its ancestry (see `Beef::verify`).
+    pub fn from_beef(bytes: &[u8]) -> Result<Transaction, ZipError> {
+        Beef::parse(bytes)?.subject().cloned()
+    }
+
+    /// True when hex-encoded bytes open with a BEEF version marker.
+    pub fn is_beef(bytes: &[u8]) -> bool {
+        [BEEF_V1, BEEF_V2]
+            .iter()
+            .any(|version| bytes.starts_with(&version.to_le_bytes()))
+    }
+
+    /// Decodes a hex transaction, accepting either raw wire format or BEEF.
+    pub fn from_hex(tx_hex: &str) -> Result<Transaction, ZipError> {
+        let bytes = hex::decode(tx_hex.trim()).map_err(|e| ZipError::Blockchain(e.to_string()))?;
+        if Self::is_beef(&bytes) {
+            Self::from_beef(&bytes)
+        } else {
+            Self::deserialize(&bytes)
+        }
+    }
+}
+
+/// Cursor over wire-format bytes.
+struct WireReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> WireReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], ZipError> {
+        let end = self
+            .pos
+            .checked_add(n)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| ZipError::Blockchain("Truncated transaction data".to_string()))?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], ZipError> {
+        let mut array = [0u8; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    fn u8(&mut self) -> Result<u8, ZipError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, ZipError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, ZipError> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    fn varint(&mut self) -> Result<u64, ZipError> {
+        Ok(match self.u8()? {
+            0xfd => u16::from_le_bytes(self.array()?) as u64,
+            0xfe => self.u32()? as u64,
+            0xff => self.u64()?,
+            n => n as u64,
+        })
+    }
+
+    /// Length prefix for a list or script, bounded by the bytes left so a forged
+    /// count fails fast instead of looping.
+    fn length(&mut self) -> Result<usize, ZipError> {
+        let n = self.varint()?;
+        if n > (self.bytes.len() - self.pos) as u64 {
+            return Err(ZipError::Blockchain(
+                "Truncated transaction data".to_string(),
+            ));
+        }
+        Ok(n as usize)
+    }
+
+    fn transaction(&mut self) -> Result<Transaction, ZipError> {
+        let version = self.u32()?;
+        let mut inputs = vec![];
+        for _ in 0..self.length()? {
+            let hash = Hash256(self.array()?);
+            let index = self.u32()?;
+            let script_len = self.length()?;
+            let script = Script(self.take(script_len)?.to_vec());
+            inputs.push(TxIn {
+                previous_output: OutPoint { hash, index },
+                script,
+                sequence: self.u32()?,
+            });
+        }
+        let mut outputs = vec![];
+        for _ in 0..self.length()? {
+            let value = self.u64()?;
+            let script_len = self.length()?;
+            outputs.push(TxOut {
+                value,
+                script: Script(self.take(script_len)?.to_vec()),
+            });
+        }
+        Ok(Transaction {
+            version,
+            inputs,
+            outputs,
+            lock_time: self.u32()?,
+        })
+    }
+
+    /// Reads a BUMP (BRC-74) Merkle path.
+    fn bump(&mut self) -> Result<MerklePath, ZipError> {
+        let block_height = self.varint()?;
+        let tree_height = self.u8()?;
+        let mut levels = vec![];
+        for _ in 0..tree_height {
+            let mut level = vec![];
+            for _ in 0..self.length()? {
+                let offset = self.varint()?;
+                // Flag 1 marks a duplicated sibling, which carries no hash
+                let node = match self.u8()? {
+                    1 => PathNode::Duplicate,
+                    _ => PathNode::Hash(self.array()?),
+                };
+                level.push((offset, node));
+            }
+            levels.push(level);
+        }
+        Ok(MerklePath {
+            block_height,
+            levels,
+        })
+    }
+
+    fn finish(&self) -> Result<(), ZipError> {
+        if self.pos != self.bytes.len() {
+            return Err(ZipError::Blockchain(
+                "Unexpected bytes after transaction".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, n: u64) {
+    match n {
+        0..=0xfc => bytes.push(n as u8),
+        0xfd..=0xffff => {
+            bytes.push(0xfd);
+            bytes.extend_from_slice(&(n as u16).to_le_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            bytes.push(0xfe);
+            bytes.extend_from_slice(&(n as u32).to_le_bytes());
+        }
+        _ => {
+            bytes.push(0xff);
+            bytes.extend_from_slice(&n.to_le_bytes());
+        }
     }
 }
@@ -5,24 +5,50 @@ use reqwest::Client;
 use rust_decimal::Decimal;
 use secrecy::Secret;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::Duration;
+use sv::address::{AddressType, addr_decode};
 use sv::messages::Tx;
+use sv::network::Network;
+use sv::public_key::PublicKey;
 use sv::script::Script;
-use sv::transaction::Transaction;
+use sv::transaction::{Transaction, TxOut, p2pkh};
+use sv::util::hash160;
 use sv::wallet::ExtendedPrivateKey;
+use tracing::warn;
 use uuid::Uuid;
 
-use crate::blockchain::TransactionManager;
+use crate::blockchain::alerts::{Alerts, WalletAlert};
+use crate::blockchain::bip270::{MerchantClient, PaymentAck, PaymentRequest};
+use crate::blockchain::chain::{BlockHeader, HeaderStore, MerkleProof, Reorg};
+use crate::blockchain::history::{HistoryEntry, TxDirection, TxHistory, TxStatus};
+use crate::blockchain::op_return::DataOutput;
+use crate::blockchain::policy::{PolicyViolation, SpendingPolicy};
+use crate::blockchain::timelock::{LOCKTIME_THRESHOLD, LockKind, LockedTx, TimeLocks};
+use crate::blockchain::transaction::{
+    Beef, CoinSelection, FEE_RATE, SigningKeys, SpendCheck, Utxo,
+};
+use crate::blockchain::{PaymailManager, TransactionManager};
 use crate::config::EnvConfig;
 use crate::errors::ZipError;
 use crate::integrations::rustbus::RustBusIntegrator;
+use crate::integrations::{ArcCallback, ArcClient};
 use crate::storage::ZipStorage;
 use crate::utils::cache::Cache;
 use crate::utils::crypto::Crypto;
+use crate::utils::payment_uri::{ParsedPayment, PaymentTarget};
 use crate::utils::rate_limiter::RateLimiter;
 use crate::utils::telemetry::Telemetry;
 
+/// Network the wallet's keys, addresses and payments belong to.
+pub const NETWORK: Network = Network::Mainnet;
+
+/// Derivation path prefix for BRC-42 counterparty keys: "brc42:<sender pubkey>:<invoice>".
+pub const BRC42_PATH_PREFIX: &str = "brc42:";
+/// Seconds a 2FA confirmation stays valid for the payment it was given for.
+pub const TWO_FA_APPROVAL_SECS: i64 = 300;
+
 #[derive(Serialize, Deserialize)]
 pub struct WalletData {
     pub address: String,
@@ -30,6 +56,91 @@ pub struct WalletData {
     pub currency: String,
     pub balance_converted: Decimal,
     pub derivation_path: String,
+    /// Received satoshis whose confirmation was undone by a reorg.
+    pub unconfirmed_balance: u64,
+}
+
+/// `WalletData` as stored before `unconfirmed_balance` existed. Bincode has no field
+/// defaults, so blobs in this layout are decoded on their own and upgraded.
+#[derive(Deserialize)]
+struct WalletDataV1 {
+    address: String,
+    balance: u64,
+    currency: String,
+    balance_converted: Decimal,
+    derivation_path: String,
+}
+
+impl WalletData {
+    /// Decodes stored wallet data in the current layout or upgrades a v1 blob.
+    pub fn decode(bytes: &[u8]) -> Result<Self, ZipError> {
+        if let Ok(data) = bincode::deserialize::<WalletData>(bytes) {
+            return Ok(data);
+        }
+        let v1: WalletDataV1 =
+            bincode::deserialize(bytes).map_err(|e| ZipError::Blockchain(e.to_string()))?;
+        Ok(Self {
+            address: v1.address,
+            balance: v1.balance,
+            currency: v1.currency,
+            balance_converted: v1.balance_converted,
+            derivation_path: v1.derivation_path,
+            unconfirmed_balance: 0,
+        })
+    }
+}
+
+/// Address shown on the receive screen; replaced once it has received funds.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReceiveAddress {
+    pub address: String,
+    pub derivation_path: String,
+    pub used: bool,
+    /// Unix timestamp (seconds) when the address was handed out.
+    pub created_at: i64,
+}
+
+/// What the network has made of one of the wallet's payments.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PaymentAcceptance {
+    /// Proven in a block on our header chain, or accepted by ARC.
+    Accepted,
+    /// Not proven or accepted yet.
+    Waiting,
+    /// Rejected, double spent, or unknown to the wallet.
+    Failed,
+}
+
+/// Everything a payment would do, computed without reserving, signing or broadcasting.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PaymentPreview {
+    pub recipient: String,
+    pub recipient_script: Vec<u8>,
+    pub amount: u64,
+    pub fee: u64,
+    pub estimated_size: usize,
+    pub inputs: Vec<Utxo>,
+    pub total_in: u64,
+    pub change: u64,
+    pub policy_violations: Vec<PolicyViolation>,
+    pub requires_2fa: bool,
+    /// The merchant payment request being previewed; confirming pays exactly this one.
+    pub payment_request: Option<PaymentRequest>,
+    /// True when the PayMail destination is a BRC-29 key derived between our identity
+    /// key and the recipient's, checked against their PKI.
+    pub private: bool,
+}
+
+impl PaymentPreview {
+    /// Total satoshis leaving the wallet (amount plus fee).
+    pub fn total_debit(&self) -> u64 {
+        self.amount + self.fee
+    }
+
+    /// True when no spending policy blocks the payment.
+    pub fn is_allowed(&self) -> bool {
+        self.policy_violations.is_empty()
+    }
 }
 
 #[derive(Clone)]
@@ -37,8 +148,16 @@ pub struct WalletManager {
     storage: Arc<ZipStorage>,
     tx_manager: Arc<TransactionManager>,
     rustbus: Option<Arc<RustBusIntegrator>>,
+    arc: Option<Arc<ArcClient>>,
+    merchant: MerchantClient,
+    history: TxHistory,
+    alerts: Alerts,
+    headers: HeaderStore,
+    timelocks: TimeLocks,
+    timelock_watchers: Arc<RwLock<HashSet<Uuid>>>,
+    /// One-shot 2FA confirmations: the largest debit approved and when it lapses.
+    two_fa_approvals: Arc<RwLock<HashMap<Uuid, (u64, i64)>>>,
     hd_key: Arc<RwLock<ExtendedPrivateKey>>,
-    derivation_index: Arc<RwLock<u32>>,
     price_cache: Arc<Cache<String, Decimal>>,
     telemetry: Telemetry,
     rate_limiter: RateLimiter,
@@ -50,8 +169,15 @@ impl Clone for WalletManager {
             storage: Arc::clone(&self.storage),
             tx_manager: Arc::clone(&self.tx_manager),
             rustbus: self.rustbus.clone().map(Arc::clone),
+            arc: self.arc.clone(),
+            merchant: self.merchant.clone(),
+            history: self.history.clone(),
+            alerts: self.alerts.clone(),
+            headers: self.headers.clone(),
+            timelocks: self.timelocks.clone(),
+            timelock_watchers: Arc::clone(&self.timelock_watchers),
+            two_fa_approvals: Arc::clone(&self.two_fa_approvals),
             hd_key: Arc::clone(&self.hd_key),
-            derivation_index: Arc::clone(&self.derivation_index),
             price_cache: Arc::clone(&self.price_cache),
             telemetry: self.telemetry.clone(),
             rate_limiter: self.rate_limiter.clone(),
@@ -70,31 +196,36 @@ impl WalletManager {
         let priv_key_bytes = storage.get_private_key().unwrap_or_else(|_| {
             let private_key = Crypto::generate_private_key()?;
             let seed = private_key.to_bytes();
-            let hd_key = ExtendedPrivateKey::new_seed(&seed, sv::network::Network::Mainnet)?;
+            let hd_key = ExtendedPrivateKey::new_seed(&seed, NETWORK)?;
             let bytes = Secret::new(hd_key.to_bytes());
             storage.store_private_key(&bytes)?;
             Secret::new(hd_key.to_bytes())
         });
         let hd_key = ExtendedPrivateKey::from_bytes(priv_key_bytes.expose_secret().clone())?;
         Ok(Self {
+            history: TxHistory::new(Arc::clone(&storage)),
+            alerts: Alerts::new(Arc::clone(&storage)),
+            headers: HeaderStore::new(Arc::clone(&storage)),
+            timelocks: TimeLocks::new(Arc::clone(&storage)),
+            timelock_watchers: Arc::new(RwLock::new(HashSet::new())),
+            two_fa_approvals: Arc::new(RwLock::new(HashMap::new())),
+            arc: ArcClient::from_config(&config).map(Arc::new),
+            merchant: MerchantClient::new(),
             storage,
             tx_manager,
             rustbus,
             hd_key: Arc::new(RwLock::new(hd_key)),
-            derivation_index: Arc::new(RwLock::new(0)),
             price_cache: Arc::new(Cache::new(300)), // 5min TTL
             telemetry: Telemetry::new(&config),
             rate_limiter: RateLimiter::new(5, 60), // 5 payments per minute
         })
     }
 
-    /// Generates a new child address for privacy (no reuse).
-    pub fn get_address(&self) -> Result<String, ZipError> {
-        let index = {
-            let mut idx = self.derivation_index.write();
-            *idx += 1;
-            *idx
-        };
+    /// Generates a new child address for privacy (no reuse) and returns it with its
+    /// derivation index. Indexes come from a persistent counter, so they are never
+    /// reissued across restarts or concurrent callers.
+    pub fn get_address(&self) -> Result<(String, u32), ZipError> {
+        let index = self.storage.next_derivation_index()?;
         let child_key = self
             .hd_key
             .read()
@@ -108,6 +239,7 @@ impl WalletManager {
             currency: "USD".to_string(),
             balance_converted: Decimal::ZERO,
             derivation_path: format!("m/44'/0'/0'/0/{}", index),
+            unconfirmed_balance: 0,
         };
         let serialized =
             bincode::serialize(&data).map_err(|e| ZipError::Blockchain(e.to_string()))?;
@@ -117,7 +249,495 @@ impl WalletManager {
             .telemetry
             .track_payment_event(&user_id.to_string(), "address_generated", 0, true)
             .await;
-        Ok(address)
+        Ok((address, index))
+    }
+
+    /// Lists addresses handed out for receiving, oldest first.
+    pub fn get_receive_addresses(&self, user_id: Uuid) -> Result<Vec<ReceiveAddress>, ZipError> {
+        let data = self.storage.get_receive_addresses(user_id)?;
+        Ok(data
+            .map(|d| bincode::deserialize(&d).unwrap_or_default())
+            .unwrap_or_default())
+    }
+
+    fn store_receive_addresses(
+        &self,
+        user_id: Uuid,
+        addresses: &[ReceiveAddress],
+    ) -> Result<(), ZipError> {
+        let serialized =
+            bincode::serialize(addresses).map_err(|e| ZipError::Blockchain(e.to_string()))?;
+        self.storage.store_receive_addresses(user_id, &serialized)
+    }
+
+    /// Returns the current unused receive address, deriving a fresh one if needed.
+    pub fn receive_address(&self, user_id: Uuid) -> Result<ReceiveAddress, ZipError> {
+        let mut addresses = self.get_receive_addresses(user_id)?;
+        // Counterparty keys belong to one sender and are never shown to anyone else
+        if let Some(unused) = addresses
+            .iter()
+            .rev()
+            .find(|a| !a.used && !a.derivation_path.starts_with(BRC42_PATH_PREFIX))
+        {
+            return Ok(unused.clone());
+        }
+        let (address, index) = self.get_address()?;
+        let receive = ReceiveAddress {
+            address,
+            derivation_path: format!("m/44'/0'/0'/0/{}", index),
+            used: false,
+            created_at: chrono::Utc::now().timestamp(),
+        };
+        addresses.push(receive.clone());
+        self.store_receive_addresses(user_id, &addresses)?;
+        Ok(receive)
+    }
+
+    /// Derives an address for a single invoice; it is never shown on the receive screen.
+    pub fn invoice_address(&self) -> Result<ReceiveAddress, ZipError> {
+        let (address, index) = self.get_address()?;
+        Ok(ReceiveAddress {
+            address,
+            derivation_path: format!("m/44'/0'/0'/0/{}", index),
+            used: false,
+            created_at: chrono::Utc::now().timestamp(),
+        })
+    }
+
+    /// Every payment an address has received, as (TXID, satoshis) per transaction,
+    /// whether or not the outputs were spent since. Empty when no indexer is configured.
+    pub async fn received_at(&self, address: &str) -> Result<Vec<(String, u64)>, ZipError> {
+        let Some(rustbus) = &self.rustbus else {
+            return Ok(vec![]);
+        };
+        let mut received: Vec<(String, u64)> = vec![];
+        for output in rustbus.query_address_outputs(address).await? {
+            match received.iter_mut().find(|(txid, _)| *txid == output.txid) {
+                Some((_, amount)) => *amount += output.value,
+                None => received.push((output.txid, output.value)),
+            }
+        }
+        Ok(received)
+    }
+
+    /// Caches the unspent outputs the indexer holds for every wallet address, with the
+    /// address's own locking script, so coin selection only spends coins the wallet can
+    /// sign for. Returns the newly found coins; none when no indexer is configured.
+    pub async fn refresh_utxos(&self, user_id: Uuid) -> Result<Vec<Utxo>, ZipError> {
+        let Some(rustbus) = &self.rustbus else {
+            return Ok(vec![]);
+        };
+        let mut found = vec![];
+        for receive in self.controlled_addresses(user_id)? {
+            let script = Self::address_script(&receive.address)?.0;
+            for output in rustbus.query_address_outputs(&receive.address).await? {
+                if rustbus
+                    .query_spender(&output.txid, output.vout)
+                    .await?
+                    .is_none()
+                {
+                    found.push(Utxo {
+                        txid: output.txid,
+                        vout: output.vout,
+                        value: output.value,
+                        script: script.clone(),
+                    });
+                }
+            }
+        }
+        self.tx_manager.merge_indexed_utxos(user_id, found)
+    }
+
+    /// Signs a message with the key behind one of the user's receive addresses, in the
+    /// "Bitcoin Signed Message" format.
+    pub fn sign_message(
+        &self,
+        user_id: Uuid,
+        address: &str,
+        message: &str,
+    ) -> Result<String, ZipError> {
+        let receive = self
+            .controlled_addresses(user_id)?
+            .into_iter()
+            .find(|a| a.address == address)
+            .ok_or_else(|| ZipError::Validation(format!("{} is not a wallet address", address)))?;
+        let child_key = self.address_key(&receive)?;
+        // Guard against a stale derivation path signing for the wrong key
+        if Crypto::generate_address(&Crypto::derive_public_key(&child_key)) != address {
+            return Err(ZipError::Crypto(
+                "Derived key does not match address".to_string(),
+            ));
+        }
+        Crypto::sign_bitcoin_message(&child_key, message)
+    }
+
+    /// Every address the wallet holds the key for: the user's receive addresses
+    /// (including BRC-42 counterparty keys), then each HD child handed out so far for
+    /// invoices and PayMail destinations.
+    pub fn controlled_addresses(&self, user_id: Uuid) -> Result<Vec<ReceiveAddress>, ZipError> {
+        let mut addresses = self.get_receive_addresses(user_id)?;
+        let known: HashSet<String> = addresses.iter().map(|a| a.address.clone()).collect();
+        for index in 1..=self.storage.derivation_index()? {
+            let child_key = self
+                .hd_key
+                .read()
+                .derive_private_key(&[sv::wallet::ChildNumber::Normal { index }])?;
+            let address = Crypto::generate_address(&Crypto::derive_public_key(&child_key));
+            if !known.contains(&address) {
+                addresses.push(ReceiveAddress {
+                    address,
+                    derivation_path: format!("m/44'/0'/0'/0/{}", index),
+                    used: true,
+                    created_at: 0,
+                });
+            }
+        }
+        Ok(addresses)
+    }
+
+    /// Keys of every address the wallet controls, by locking script, for signing the
+    /// coins a transaction spends.
+    fn signing_keys(&self, user_id: Uuid) -> Result<SigningKeys, ZipError> {
+        self.controlled_addresses(user_id)?
+            .iter()
+            .map(|receive| {
+                Ok((
+                    Self::address_script(&receive.address)?.0,
+                    self.address_key(receive)?,
+                ))
+            })
+            .collect()
+    }
+
+    /// Private key behind a receive address: an HD child, or a BRC-42 counterparty key.
+    fn address_key(&self, receive: &ReceiveAddress) -> Result<secp256k1::SecretKey, ZipError> {
+        if let Some(brc42) = receive.derivation_path.strip_prefix(BRC42_PATH_PREFIX) {
+            let (sender, invoice_number) = brc42
+                .split_once(':')
+                .ok_or_else(|| ZipError::Crypto("Invalid derivation path".to_string()))?;
+            let sender = hex::decode(sender)
+                .ok()
+                .and_then(|key| PublicKey::from_slice(&key).ok())
+                .ok_or_else(|| ZipError::Crypto("Invalid counterparty key".to_string()))?;
+            return Crypto::derive_child_private_key(
+                &self.identity_key()?,
+                &sender,
+                invoice_number,
+            );
+        }
+        let index = receive
+            .derivation_path
+            .rsplit('/')
+            .next()
+            .and_then(|i| i.parse().ok())
+            .ok_or_else(|| ZipError::Crypto("Invalid derivation path".to_string()))?;
+        Ok(self
+            .hd_key
+            .read()
+            .derive_private_key(&[sv::wallet::ChildNumber::Normal { index }])?)
+    }
+
+    /// Locking script for paying a contact privately: a BRC-42 child of the recipient's
+    /// identity key, unique to this prefix/suffix pair and unlinkable to their PayMail.
+    pub fn private_payment_script(
+        &self,
+        recipient: &PublicKey,
+        prefix: &str,
+        suffix: &str,
+    ) -> Result<Script, ZipError> {
+        let child = Crypto::derive_child_public_key(
+            &self.identity_key()?,
+            recipient,
+            &Crypto::payment_invoice_number(prefix, suffix),
+        )?;
+        Self::address_script(&Crypto::generate_address(&child))
+    }
+
+    /// Registers the address a sender derived with `private_payment_script`, so funds
+    /// sent to it are tracked, spendable and signable like any receive address.
+    pub fn accept_private_payment(
+        &self,
+        user_id: Uuid,
+        sender: &PublicKey,
+        prefix: &str,
+        suffix: &str,
+    ) -> Result<ReceiveAddress, ZipError> {
+        let invoice_number = Crypto::payment_invoice_number(prefix, suffix);
+        let child_key =
+            Crypto::derive_child_private_key(&self.identity_key()?, sender, &invoice_number)?;
+        let address = Crypto::generate_address(&Crypto::derive_public_key(&child_key));
+        let mut addresses = self.get_receive_addresses(user_id)?;
+        if let Some(existing) = addresses.iter().find(|a| a.address == address) {
+            return Ok(existing.clone());
+        }
+        let receive = ReceiveAddress {
+            address,
+            derivation_path: format!(
+                "{}{}:{}",
+                BRC42_PATH_PREFIX,
+                hex::encode(sender.to_bytes()),
+                invoice_number
+            ),
+            used: false,
+            created_at: chrono::Utc::now().timestamp(),
+        };
+        addresses.push(receive.clone());
+        self.store_receive_addresses(user_id, &addresses)?;
+        Ok(receive)
+    }
+
+    /// Key published through PayMail PKI. It sits on a hardened branch so it never
+    /// doubles as a receive address key.
+    fn identity_key(&self) -> Result<secp256k1::SecretKey, ZipError> {
+        Ok(self
+            .hd_key
+            .read()
+            .derive_private_key(&[sv::wallet::ChildNumber::Hardened { index: 0 }])?)
+    }
+
+    /// Public identity key that senders encrypt memos to.
+    pub fn identity_public_key(&self) -> Result<PublicKey, ZipError> {
+        Ok(Crypto::derive_public_key(&self.identity_key()?))
+    }
+
+    /// Decrypts a BIE1 memo encrypted to this wallet's identity key. Memos carry one
+    /// ciphertext per party, so whichever copy opens with our key is used.
+    pub fn decrypt_memo(&self, memo: &str) -> Result<String, ZipError> {
+        let identity = self.identity_key()?;
+        memo.split_whitespace()
+            .find_map(|part| Crypto::decrypt_message(&identity, part).ok())
+            .ok_or_else(|| ZipError::Crypto("Memo is not encrypted to this wallet".to_string()))
+    }
+
+    /// BRC-43 derived public key for an invoice number. `counterparty` defaults to this
+    /// wallet's own identity key; `for_self` returns our child key instead of theirs.
+    pub fn derived_public_key(
+        &self,
+        invoice_number: &str,
+        counterparty: Option<&PublicKey>,
+        for_self: bool,
+    ) -> Result<PublicKey, ZipError> {
+        let identity = self.identity_key()?;
+        let counterparty = match counterparty {
+            Some(key) => key.clone(),
+            None => Crypto::derive_public_key(&identity),
+        };
+        if for_self {
+            let child = Crypto::derive_child_private_key(&identity, &counterparty, invoice_number)?;
+            Ok(Crypto::derive_public_key(&child))
+        } else {
+            Crypto::derive_child_public_key(&identity, &counterparty, invoice_number)
+        }
+    }
+
+    /// Signs a SHA-256 digest with our BRC-43 derived key for an invoice number
+    /// (DER-encoded ECDSA).
+    pub fn derived_signature(
+        &self,
+        invoice_number: &str,
+        counterparty: Option<&PublicKey>,
+        digest: [u8; 32],
+    ) -> Result<Vec<u8>, ZipError> {
+        let identity = self.identity_key()?;
+        let counterparty = match counterparty {
+            Some(key) => key.clone(),
+            None => Crypto::derive_public_key(&identity),
+        };
+        let child = Crypto::derive_child_private_key(&identity, &counterparty, invoice_number)?;
+        Ok(Crypto::sign_digest(&child, digest))
+    }
+
+    /// True if the wallet holds, or has handed out an address for, funds locked to this
+    /// key: one of its addresses, or a cached UTXO whose script names the key or its hash.
+    pub fn holds_funds_for(&self, user_id: Uuid, public_key: &PublicKey) -> Result<bool, ZipError> {
+        let address = Crypto::generate_address(public_key);
+        if self
+            .controlled_addresses(user_id)?
+            .iter()
+            .any(|a| a.address == address)
+        {
+            return Ok(true);
+        }
+        let key = public_key.to_bytes();
+        let hash = hash160(&key);
+        Ok(self.tx_manager.get_cached_utxos(user_id)?.iter().any(|u| {
+            u.script.windows(hash.0.len()).any(|w| w == hash.0)
+                || u.script.windows(key.len()).any(|w| w == key.as_slice())
+        }))
+    }
+
+    /// Wallet addresses holding cached UTXOs, each with the UTXOs locked to it. Covers
+    /// receive, invoice and PayMail destination addresses, and change sent back to them.
+    pub fn funded_addresses(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<(ReceiveAddress, Vec<Utxo>)>, ZipError> {
+        let utxos = self.tx_manager.get_cached_utxos(user_id)?;
+        let mut funded = vec![];
+        for receive in self.controlled_addresses(user_id)? {
+            let script = Self::address_script(&receive.address)?;
+            let held: Vec<Utxo> = utxos
+                .iter()
+                .filter(|u| u.script == script.0)
+                .cloned()
+                .collect();
+            if !held.is_empty() {
+                funded.push((receive, held));
+            }
+        }
+        Ok(funded)
+    }
+
+    /// Raises a user-facing alert.
+    pub fn notify(&self, user_id: Uuid, message: String) -> Result<(), ZipError> {
+        self.alerts.push(user_id, message, None)
+    }
+
+    /// Accepts a transaction (raw hex or BEEF) a sender handed us directly. It must pay
+    /// `address` and be proven before anything is credited: a BEEF's ancestry is checked
+    /// against the header store, and raw transactions need ARC to accept them. Then the
+    /// outputs become spendable, it is recorded in history and the user is alerted.
+    /// Returns the satoshis received; resubmissions of an already recorded transaction
+    /// are accepted without side effects.
+    pub async fn receive_transaction(
+        &self,
+        user_id: Uuid,
+        tx_hex: &str,
+        address: &str,
+        counterparty: String,
+        memo: Option<String>,
+    ) -> Result<u64, ZipError> {
+        let bytes = hex::decode(tx_hex.trim()).map_err(|e| ZipError::Blockchain(e.to_string()))?;
+        let (tx, beef) = if TransactionManager::is_beef(&bytes) {
+            let beef = Beef::parse(&bytes)?;
+            (beef.subject()?.clone(), Some(beef))
+        } else {
+            (TransactionManager::deserialize(&bytes)?, None)
+        };
+        let txid = TransactionManager::txid(&tx);
+        let script = Self::address_script(address)?;
+        let utxos: Vec<Utxo> = tx
+            .outputs
+            .iter()
+            .enumerate()
+            .filter(|(_, output)| output.script.0 == script.0)
+            .map(|(vout, output)| Utxo {
+                txid: txid.clone(),
+                vout: vout as u32,
+                value: output.value,
+                script: output.script.0.clone(),
+            })
+            .collect();
+        let amount: u64 = utxos.iter().map(|u| u.value).sum();
+        if amount == 0 {
+            return Err(ZipError::Blockchain(format!(
+                "Transaction {} does not pay {}",
+                txid, address
+            )));
+        }
+        if self.history.get(user_id, &txid)?.is_some() {
+            return Ok(amount);
+        }
+        let proven = match &beef {
+            Some(beef) => {
+                beef.verify(&self.headers)?;
+                true
+            }
+            None => false,
+        };
+        let status = match &self.arc {
+            Some(arc) => {
+                let response = arc.broadcast(&TransactionManager::to_hex(&tx)).await?;
+                if response.is_double_spend() {
+                    return Err(ZipError::Blockchain(format!(
+                        "Transaction {} conflicts with an existing transaction",
+                        txid
+                    )));
+                }
+                if response.is_rejected() {
+                    return Err(ZipError::Blockchain(format!(
+                        "Transaction rejected: {}",
+                        response.extra_info.unwrap_or(response.tx_status)
+                    )));
+                }
+                if response.is_accepted() {
+                    TxStatus::Broadcast
+                } else if proven {
+                    TxStatus::Pending
+                } else {
+                    return Err(ZipError::Blockchain(format!(
+                        "Transaction {} was not accepted by the network ({})",
+                        txid, response.tx_status
+                    )));
+                }
+            }
+            None if proven => TxStatus::Pending,
+            None => {
+                return Err(ZipError::Blockchain(format!(
+                    "Transaction {} cannot be verified without BEEF ancestry",
+                    txid
+                )));
+            }
+        };
+        self.tx_manager.add_utxos(user_id, &utxos)?;
+        self.history.upsert(
+            user_id,
+            HistoryEntry {
+                txid: txid.clone(),
+                direction: TxDirection::Received,
+                amount,
+                fee: 0,
+                counterparty: counterparty.clone(),
+                inputs: vec![],
+                status,
+                timestamp: chrono::Utc::now().timestamp(),
+                memo,
+            },
+        )?;
+        let mut addresses = self.get_receive_addresses(user_id)?;
+        if let Some(receive) = addresses.iter_mut().find(|a| a.address == address) {
+            receive.used = true;
+            self.store_receive_addresses(user_id, &addresses)?;
+        }
+        self.alerts.push(
+            user_id,
+            format!("Received {} satoshis from {}", amount, counterparty),
+            Some(txid),
+        )?;
+        let _ = self
+            .telemetry
+            .track_payment_event(&user_id.to_string(), "p2p_received", amount, true)
+            .await;
+        Ok(amount)
+    }
+
+    /// Checks unused receive addresses for incoming funds and marks funded ones as used,
+    /// so the receive screen never shows an address twice. Returns newly used addresses.
+    pub async fn refresh_receive_addresses(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<ReceiveAddress>, ZipError> {
+        let Some(rustbus) = &self.rustbus else {
+            return Ok(vec![]);
+        };
+        let mut addresses = self.get_receive_addresses(user_id)?;
+        let mut newly_used = vec![];
+        for receive in addresses.iter_mut().filter(|a| !a.used) {
+            let balance = rustbus.query_balance(&receive.address).await.unwrap_or(0);
+            if balance > 0 {
+                receive.used = true;
+                newly_used.push(receive.clone());
+                self.alerts.push(
+                    user_id,
+                    format!("Received {} satoshis at {}", balance, receive.address),
+                    None,
+                )?;
+            }
+        }
+        if !newly_used.is_empty() {
+            self.store_receive_addresses(user_id, &addresses)?;
+        }
+        Ok(newly_used)
     }
 
     /// Fetches BSV price in specified currency, caches for 5min.
@@ -152,7 +772,8 @@ impl WalletManager {
         currency: &str,
     ) -> Result<(u64, Decimal), ZipError> {
         self.rate_limiter.check(&user_id.to_string()).await?;
-        let address = self.get_address()?;
+        self.refresh_utxos(user_id).await?;
+        let (address, index) = self.get_address()?;
         let balance = if let Some(r) = &self.rustbus {
             r.query_balance(&address).await?
         } else {
@@ -160,12 +781,18 @@ impl WalletManager {
         };
         let price = self.fetch_price(currency).await?;
         let balance_converted = Decimal::from(balance) / Decimal::from(100_000_000) * price;
+        // Receipts a reorg undid stay unconfirmed until they confirm again
+        let unconfirmed_balance = match self.storage.get_user_data(user_id)? {
+            Some(data) => WalletData::decode(&data).map_or(0, |d| d.unconfirmed_balance),
+            None => 0,
+        };
         let data = WalletData {
             address,
             balance,
             currency: currency.to_string(),
             balance_converted,
-            derivation_path: format!("m/44'/0'/0'/0/{}", *self.derivation_index.read()),
+            derivation_path: format!("m/44'/0'/0'/0/{}", index),
+            unconfirmed_balance,
         };
         let serialized =
             bincode::serialize(&data).map_err(|e| ZipError::Blockchain(e.to_string()))?;
@@ -177,45 +804,1235 @@ impl WalletManager {
         Ok((balance, balance_converted))
     }
 
-    /// Initiates payment using pre-created UTXOs and PayMail script.
+    /// Loads the user's spending policy (no limits if none is stored).
+    pub fn get_spending_policy(&self, user_id: Uuid) -> Result<SpendingPolicy, ZipError> {
+        let data = self.storage.get_policy(user_id)?;
+        Ok(data
+            .map(|d| bincode::deserialize(&d).unwrap_or_default())
+            .unwrap_or_default())
+    }
+
+    /// Saves the user's spending policy.
+    pub fn set_spending_policy(
+        &self,
+        user_id: Uuid,
+        policy: &SpendingPolicy,
+    ) -> Result<(), ZipError> {
+        let serialized =
+            bincode::serialize(policy).map_err(|e| ZipError::Blockchain(e.to_string()))?;
+        self.storage.store_policy(user_id, &serialized)
+    }
+
+    fn today() -> String {
+        chrono::Utc::now().format("%Y-%m-%d").to_string()
+    }
+
+    /// Rejects a payment that breaks the spending policy, returning today's spend key.
+    /// `debit` is everything the payment takes from the wallet, fee included.
+    fn enforce_policy(&self, user_id: Uuid, debit: u64) -> Result<String, ZipError> {
+        let day = Self::today();
+        let spent_today = self.storage.get_daily_spend(user_id, &day)?;
+        if let Some(violation) = self
+            .get_spending_policy(user_id)?
+            .check(debit, spent_today)
+            .into_iter()
+            .next()
+        {
+            return Err(ZipError::Validation(violation.to_string()));
+        }
+        Ok(day)
+    }
+
+    /// Records that the user confirmed, with a valid 2FA code, a payment debiting up to
+    /// `debit` satoshis. The approval covers one payment within `TWO_FA_APPROVAL_SECS`.
+    pub fn approve_2fa(&self, user_id: Uuid, debit: u64) {
+        let expires_at = chrono::Utc::now().timestamp() + TWO_FA_APPROVAL_SECS;
+        self.two_fa_approvals
+            .write()
+            .insert(user_id, (debit, expires_at));
+    }
+
+    /// Rejects a payment the policy wants confirmed with 2FA unless an approval covers
+    /// it; the approval is used up either way.
+    fn require_2fa(&self, user_id: Uuid, debit: u64) -> Result<(), ZipError> {
+        if !self.get_spending_policy(user_id)?.requires_2fa(debit) {
+            return Ok(());
+        }
+        let now = chrono::Utc::now().timestamp();
+        match self.two_fa_approvals.write().remove(&user_id) {
+            Some((approved, expires_at)) if debit <= approved && now < expires_at => Ok(()),
+            _ => Err(ZipError::Auth(format!(
+                "Payment of {} satoshis must be confirmed with 2FA",
+                debit
+            ))),
+        }
+    }
+
+    /// Builds a P2PKH locking script for a mainnet address.
+    pub fn address_script(address: &str) -> Result<Script, ZipError> {
+        let (hash, addr_type) =
+            addr_decode(address, NETWORK).map_err(|e| ZipError::Validation(e.to_string()))?;
+        if addr_type != AddressType::P2PKH {
+            return Err(ZipError::Validation(
+                "Only P2PKH addresses are supported".to_string(),
+            ));
+        }
+        Ok(p2pkh::create_lock_script(&hash))
+    }
+
+    /// Previews a payment to a PayMail, `$handle`, address or payment URI: resolves the
+    /// destination, selects coins, estimates size and change, and checks spending
+    /// policies without reserving UTXOs. Data outputs count towards the size, and the
+    /// fee is raised to the relay minimum for that size if needed.
+    pub async fn simulate_payment(
+        &self,
+        paymail: &PaymailManager,
+        user_id: Uuid,
+        recipient: &str,
+        amount: u64,
+        fee: u64,
+        data: &[DataOutput],
+    ) -> Result<PaymentPreview, ZipError> {
+        let parsed = ParsedPayment::parse(recipient)?;
+        parsed.validate_network(NETWORK)?;
+        let mut payment_request = None;
+        let mut private = false;
+        let (script, resolved_amount) = match &parsed.target {
+            PaymentTarget::Paymail(handle) => {
+                match self.private_paymail_script(paymail, handle, amount).await? {
+                    Some(script) => {
+                        private = true;
+                        (script, amount)
+                    }
+                    None => paymail.resolve_paymail(handle, amount).await?,
+                }
+            }
+            PaymentTarget::Address(address) => (Self::address_script(address)?, amount),
+            // The merchant sets the amount; the preview shows the first requested output
+            PaymentTarget::PaymentRequest(url) => {
+                let request = self.fetch_payment_request(url).await?;
+                let outputs = request.tx_outputs()?;
+                let total = request.total()?;
+                payment_request = Some(request);
+                (outputs[0].script.clone(), total)
+            }
+        };
+        let (selection, estimated_size, fee) =
+            self.select_with_fee(user_id, resolved_amount, fee, data)?;
+        let policy = self.get_spending_policy(user_id)?;
+        let spent_today = self.storage.get_daily_spend(user_id, &Self::today())?;
+        let debit = resolved_amount + fee;
+        let preview = PaymentPreview {
+            recipient: recipient.to_string(),
+            recipient_script: script.0,
+            amount: resolved_amount,
+            fee,
+            estimated_size,
+            inputs: selection.inputs,
+            total_in: selection.total_in,
+            change: selection.change,
+            policy_violations: policy.check(debit, spent_today),
+            requires_2fa: policy.requires_2fa(debit),
+            payment_request,
+            private,
+        };
+        let _ = self
+            .telemetry
+            .track_payment_event(&user_id.to_string(), "payment_simulated", amount, true)
+            .await;
+        Ok(preview)
+    }
+
+    /// BRC-29 script for paying a PayMail whose host supports it. The host derives the
+    /// destination from our identity key; it is only used if it matches the key we
+    /// derive from the recipient's PKI, so the host cannot swap in another address.
+    async fn private_paymail_script(
+        &self,
+        paymail: &PaymailManager,
+        handle: &str,
+        amount: u64,
+    ) -> Result<Option<Script>, ZipError> {
+        let Some((script, prefix, suffix)) = paymail
+            .request_private_destination(handle, amount, &self.identity_public_key()?)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let recipient = paymail.resolve_pki(handle).await?;
+        if self.private_payment_script(&recipient, &prefix, &suffix)?.0 != script.0 {
+            return Err(ZipError::Crypto(format!(
+                "{} returned a destination not derived from its identity key",
+                handle
+            )));
+        }
+        Ok(Some(script))
+    }
+
+    /// Selects coins for a payment plus data outputs, raising the fee until it covers
+    /// the estimated size at `FEE_RATE`. Returns the selection, size and fee.
+    fn select_with_fee(
+        &self,
+        user_id: Uuid,
+        amount: u64,
+        fee: u64,
+        data: &[DataOutput],
+    ) -> Result<(CoinSelection, usize, u64), ZipError> {
+        let mut data_size = 0;
+        for output in data {
+            data_size += output.size()?;
+        }
+        // More inputs may be needed once the fee covers the data outputs
+        let mut fee = fee;
+        loop {
+            let selection = self.tx_manager.select_coins(user_id, amount, fee)?;
+            let estimated_size = selection.estimated_size + data_size;
+            let required = estimated_size as u64 * FEE_RATE;
+            if required <= fee {
+                return Ok((selection, estimated_size, fee));
+            }
+            fee = required;
+        }
+    }
+
+    /// Minimum fee for publishing a data-only transaction (see `publish_data`).
+    pub fn data_fee(&self, user_id: Uuid, data: &DataOutput) -> Result<u64, ZipError> {
+        let (_, _, fee) = self.select_with_fee(user_id, 0, 0, std::slice::from_ref(data))?;
+        Ok(fee)
+    }
+
+    /// Initiates payment using pre-created UTXOs and PayMail script, with optional
+    /// OP_RETURN data outputs (memos, B://, MAP). Returns the payment's TXID.
     pub async fn send_payment(
         &self,
         user_id: Uuid,
         recipient_script: Script,
         amount: u64,
         fee: u64,
+        data: &[DataOutput],
     ) -> Result<String, ZipError> {
-        self.rate_limiter.check(&user_id.to_string()).await?;
+        let tx = self
+            .send_transaction(user_id, recipient_script, amount, fee, data)
+            .await?;
+        Ok(TransactionManager::txid(&tx))
+    }
+
+    /// Same as `send_payment`, returning the sent transaction itself (e.g., for a
+    /// recipient that matches the payment against its outputs).
+    pub async fn send_transaction(
+        &self,
+        user_id: Uuid,
+        recipient_script: Script,
+        amount: u64,
+        fee: u64,
+        data: &[DataOutput],
+    ) -> Result<Transaction, ZipError> {
+        self.send_funded(user_id, recipient_script, amount, fee, data, None)
+            .await
+    }
+
+    /// Sends a payment like `send_transaction`, funded by the coins held under
+    /// `escrow_id` (e.g., a winning bid) first. The escrow ends only once the payment
+    /// is sent; if it fails, its coins stay held.
+    pub async fn send_escrowed(
+        &self,
+        user_id: Uuid,
+        escrow_id: &str,
+        recipient_script: Script,
+        amount: u64,
+        fee: u64,
+        data: &[DataOutput],
+    ) -> Result<Transaction, ZipError> {
         let result = self
-            .tx_manager
-            .build_payment_tx(user_id, recipient_script, amount, fee)
+            .send_funded(
+                user_id,
+                recipient_script,
+                amount,
+                fee,
+                data,
+                Some(escrow_id),
+            )
             .await;
+        match &result {
+            Ok(_) => self.tx_manager.close_escrow(user_id, escrow_id)?,
+            Err(_) => self.tx_manager.restore_escrow(user_id, escrow_id)?,
+        }
+        result
+    }
+
+    async fn send_funded(
+        &self,
+        user_id: Uuid,
+        recipient_script: Script,
+        amount: u64,
+        fee: u64,
+        data: &[DataOutput],
+        escrow_id: Option<&str>,
+    ) -> Result<Transaction, ZipError> {
+        self.rate_limiter.check(&user_id.to_string()).await?;
+        let day = self.enforce_policy(user_id, amount + fee)?;
+        self.require_2fa(user_id, amount + fee)?;
+        let counterparty = hex::encode(&recipient_script.0);
+        let memo = data.first().map(DataOutput::summary);
+        let built = match escrow_id {
+            Some(escrow_id) => {
+                self.tx_manager
+                    .build_escrow_payment_tx(
+                        user_id,
+                        escrow_id,
+                        recipient_script,
+                        amount,
+                        fee,
+                        data,
+                        &self.signing_keys(user_id)?,
+                    )
+                    .await
+            }
+            None => {
+                self.tx_manager
+                    .build_payment_tx(
+                        user_id,
+                        recipient_script,
+                        amount,
+                        fee,
+                        data,
+                        &self.signing_keys(user_id)?,
+                    )
+                    .await
+            }
+        };
+        let result = match built {
+            Ok(tx) => self
+                .record_and_broadcast(user_id, &tx, counterparty, memo, amount, fee)
+                .await
+                .map(|_| tx),
+            Err(e) => Err(e),
+        };
         let success = result.is_ok();
-        let tx_id = result
-            .as_ref()
-            .map(|tx| {
-                // Manual serialization since to_bytes is not available
-                let mut bytes = vec![];
-                bytes.extend_from_slice(&tx.version.to_le_bytes());
-                bytes.extend_from_slice(&(tx.inputs.len() as u32).to_le_bytes());
-                for input in &tx.inputs {
-                    bytes.extend_from_slice(&input.previous_output.to_bytes());
-                    bytes.extend_from_slice(&input.script.0);
-                    bytes.extend_from_slice(&input.sequence.to_le_bytes());
-                }
-                bytes.extend_from_slice(&(tx.outputs.len() as u32).to_le_bytes());
-                for output in &tx.outputs {
-                    bytes.extend_from_slice(&output.value.to_le_bytes());
-                    bytes.extend_from_slice(&output.script.0);
+        if success {
+            self.storage.add_daily_spend(user_id, &day, amount + fee)?;
+        }
+        let _ = self
+            .telemetry
+            .track_payment_event(&user_id.to_string(), "payment_sent", amount, success)
+            .await;
+        result
+    }
+
+    /// Writes a data-only OP_RETURN transaction (e.g., a notarised hash); only the fee is
+    /// spent. Returns the transaction.
+    pub async fn publish_data(
+        &self,
+        user_id: Uuid,
+        data: DataOutput,
+        fee: u64,
+    ) -> Result<Transaction, ZipError> {
+        self.rate_limiter.check(&user_id.to_string()).await?;
+        let day = self.enforce_policy(user_id, fee)?;
+        self.require_2fa(user_id, fee)?;
+        let tx = self
+            .tx_manager
+            .build_outputs_tx(
+                user_id,
+                vec![data.tx_out()?],
+                fee,
+                &self.signing_keys(user_id)?,
+            )
+            .await?;
+        self.record_and_broadcast(
+            user_id,
+            &tx,
+            "OP_RETURN".to_string(),
+            Some(data.summary()),
+            0,
+            fee,
+        )
+        .await?;
+        self.storage.add_daily_spend(user_id, &day, fee)?;
+        Ok(tx)
+    }
+
+    /// Splits the wallet's funds into `count` coins of `value` satoshis paid back to
+    /// itself and broadcasts the split; only the fee is spent. Returns the TXID.
+    pub async fn split_utxos(
+        &self,
+        user_id: Uuid,
+        count: u32,
+        value: u64,
+        fee: u64,
+    ) -> Result<String, ZipError> {
+        self.rate_limiter.check(&user_id.to_string()).await?;
+        let day = self.enforce_policy(user_id, fee)?;
+        self.require_2fa(user_id, fee)?;
+        let tx = self
+            .tx_manager
+            .pre_create_utxos(user_id, count, value, fee, &self.signing_keys(user_id)?)
+            .await?;
+        let txid = self
+            .record_and_broadcast(user_id, &tx, "UTXO split".to_string(), None, 0, fee)
+            .await?;
+        self.storage.add_daily_spend(user_id, &day, fee)?;
+        Ok(txid)
+    }
+
+    /// Builds a transaction paying the given outputs, reserving its inputs, after checking
+    /// the spending policy. Submit it with `submit_prepared` or release it with
+    /// `abort_prepared`.
+    pub async fn prepare_outputs(
+        &self,
+        user_id: Uuid,
+        outputs: Vec<TxOut>,
+        fee: u64,
+    ) -> Result<Transaction, ZipError> {
+        self.rate_limiter.check(&user_id.to_string()).await?;
+        let amount: u64 = outputs.iter().map(|o| o.value).sum();
+        self.enforce_policy(user_id, amount + fee)?;
+        self.require_2fa(user_id, amount + fee)?;
+        self.tx_manager
+            .build_outputs_tx(user_id, outputs, fee, &self.signing_keys(user_id)?)
+            .await
+    }
+
+    /// Records and broadcasts a transaction from `prepare_outputs`; `amount` excludes
+    /// change. Returns the TXID.
+    pub async fn submit_prepared(
+        &self,
+        user_id: Uuid,
+        tx: &Transaction,
+        memo: Option<String>,
+        amount: u64,
+        fee: u64,
+    ) -> Result<String, ZipError> {
+        let day = self.enforce_policy(user_id, amount + fee)?;
+        let counterparty = tx
+            .outputs
+            .first()
+            .map(|o| hex::encode(&o.script.0))
+            .unwrap_or_default();
+        let txid = self
+            .record_and_broadcast(user_id, tx, counterparty, memo, amount, fee)
+            .await?;
+        self.storage.add_daily_spend(user_id, &day, amount + fee)?;
+        let _ = self
+            .telemetry
+            .track_payment_event(&user_id.to_string(), "outputs_sent", amount, true)
+            .await;
+        Ok(txid)
+    }
+
+    /// Releases the inputs reserved for a prepared transaction that will not be sent.
+    pub fn abort_prepared(&self, user_id: Uuid, txid: &str) -> Result<(), ZipError> {
+        self.tx_manager.release_reservation(user_id, txid)?;
+        Ok(())
+    }
+
+    /// Holds coins covering `amount` (e.g., an auction bid) until `release_escrow`.
+    /// Returns the satoshis held, which may exceed `amount`.
+    pub fn escrow(&self, user_id: Uuid, escrow_id: &str, amount: u64) -> Result<u64, ZipError> {
+        let held = self.tx_manager.escrow(user_id, escrow_id, amount)?;
+        Ok(held.iter().map(|u| u.value).sum())
+    }
+
+    /// Makes escrowed coins spendable again.
+    pub fn release_escrow(&self, user_id: Uuid, escrow_id: &str) -> Result<(), ZipError> {
+        self.tx_manager.release_escrow(user_id, escrow_id)?;
+        Ok(())
+    }
+
+    /// Spendable outputs held by the wallet.
+    pub fn list_outputs(&self, user_id: Uuid) -> Result<Vec<Utxo>, ZipError> {
+        self.tx_manager.get_cached_utxos(user_id)
+    }
+
+    /// Fetches a transaction's Merkle proof and checks it against the local header chain.
+    pub async fn confirmation_proof(
+        &self,
+        txid: &str,
+    ) -> Result<Option<(MerkleProof, BlockHeader)>, ZipError> {
+        let Some(rustbus) = &self.rustbus else {
+            return Ok(None);
+        };
+        let Some(proof) = rustbus.query_merkle_proof(txid).await? else {
+            return Ok(None);
+        };
+        let Some(header) = self.headers.header_at(proof.block_height)? else {
+            return Ok(None);
+        };
+        Ok(proof.verify(&header)?.then_some((proof, header)))
+    }
+
+    /// Whether the network has accepted one of a user's payments. Only a Merkle proof
+    /// checked against the header chain, or ARC's status of the transaction, counts;
+    /// the wallet's own history saying it was broadcast does not.
+    pub async fn payment_acceptance(
+        &self,
+        user_id: Uuid,
+        txid: &str,
+    ) -> Result<PaymentAcceptance, ZipError> {
+        match self.history.get(user_id, txid)?.map(|e| e.status) {
+            Some(TxStatus::Conflicted { .. }) | None => return Ok(PaymentAcceptance::Failed),
+            Some(TxStatus::Pending | TxStatus::Broadcast | TxStatus::Confirmed { .. }) => {}
+        }
+        if let Some(proof) = self.history.get_proofs(user_id)?.remove(txid) {
+            if self.history.reverify(user_id, proof, &self.headers)? {
+                self.settle_reorged_receipts(user_id)?;
+                return Ok(PaymentAcceptance::Accepted);
+            }
+        }
+        if let Some((proof, _)) = self.confirmation_proof(txid).await? {
+            self.history.reverify(user_id, proof, &self.headers)?;
+            self.settle_reorged_receipts(user_id)?;
+            return Ok(PaymentAcceptance::Accepted);
+        }
+        if let Some(arc) = &self.arc {
+            let response = arc.tx_status(txid).await?;
+            if response.is_accepted() {
+                return Ok(PaymentAcceptance::Accepted);
+            }
+            if response.is_rejected() || response.is_double_spend() {
+                return Ok(PaymentAcceptance::Failed);
+            }
+        }
+        Ok(PaymentAcceptance::Waiting)
+    }
+
+    /// Fetches the serialized 80-byte header of a block from RustBus.
+    pub async fn raw_block_header(&self, block_hash: &str) -> Result<Option<Vec<u8>>, ZipError> {
+        match &self.rustbus {
+            Some(rustbus) => rustbus.query_block_header(block_hash).await,
+            None => Ok(None),
+        }
+    }
+
+    /// Records a built payment in history as pending and returns its TXID.
+    fn record_pending(
+        &self,
+        user_id: Uuid,
+        tx: &Transaction,
+        counterparty: String,
+        memo: Option<String>,
+        amount: u64,
+        fee: u64,
+    ) -> Result<String, ZipError> {
+        let txid = TransactionManager::txid(tx);
+        let inputs = self
+            .tx_manager
+            .reserved_inputs(user_id, &txid)?
+            .iter()
+            .map(Utxo::outpoint)
+            .collect();
+        self.history.upsert(
+            user_id,
+            HistoryEntry {
+                txid: txid.clone(),
+                direction: TxDirection::Sent,
+                amount,
+                fee,
+                counterparty,
+                inputs,
+                status: TxStatus::Pending,
+                timestamp: chrono::Utc::now().timestamp(),
+                memo,
+            },
+        )?;
+        Ok(txid)
+    }
+
+    /// Fetches a BIP270 payment request and rejects expired or foreign-network requests.
+    pub async fn fetch_payment_request(&self, url: &str) -> Result<PaymentRequest, ZipError> {
+        let request = self.merchant.fetch_request(url).await?;
+        request.validate(chrono::Utc::now().timestamp())?;
+        Ok(request)
+    }
+
+    /// Pays the merchant payment request the user reviewed: builds the requested outputs
+    /// exactly, posts the payment to the merchant (who broadcasts it) and applies the ACK.
+    /// A refused payment releases its reserved inputs. When the post fails the merchant
+    /// may already hold the transaction, so it stays pending with its inputs reserved.
+    pub async fn pay_payment_request(
+        &self,
+        user_id: Uuid,
+        request: &PaymentRequest,
+        fee: u64,
+        memo: Option<String>,
+    ) -> Result<PaymentAck, ZipError> {
+        self.rate_limiter.check(&user_id.to_string()).await?;
+        request.validate(chrono::Utc::now().timestamp())?;
+        let amount = request.total()?;
+        let day = self.enforce_policy(user_id, amount + fee)?;
+        self.require_2fa(user_id, amount + fee)?;
+        let tx = self
+            .tx_manager
+            .build_outputs_tx(
+                user_id,
+                request.tx_outputs()?,
+                fee,
+                &self.signing_keys(user_id)?,
+            )
+            .await?;
+        let counterparty = request
+            .memo
+            .clone()
+            .unwrap_or_else(|| request.payment_url.clone());
+        let txid = self.record_pending(user_id, &tx, counterparty, memo.clone(), amount, fee)?;
+        let result = match self
+            .merchant
+            .send_payment(request, &TransactionManager::to_hex(&tx), memo)
+            .await
+        {
+            Ok(ack) if ack.is_accepted() => {
+                self.tx_manager.mark_spent(user_id, &txid)?;
+                self.history
+                    .set_status(user_id, &txid, TxStatus::Broadcast)?;
+                self.storage.add_daily_spend(user_id, &day, amount + fee)?;
+                Ok(ack)
+            }
+            Ok(ack) => {
+                self.tx_manager.release_reservation(user_id, &txid)?;
+                self.history.remove(user_id, &txid)?;
+                Err(ZipError::Blockchain(format!(
+                    "Merchant refused payment: {}",
+                    ack.memo
+                        .unwrap_or_else(|| format!("error {}", ack.error.unwrap_or(0)))
+                )))
+            }
+            // Count it against the limits until the network shows whether it was sent
+            Err(e) => {
+                self.storage.add_daily_spend(user_id, &day, amount + fee)?;
+                self.alerts.push(
+                    user_id,
+                    format!(
+                        "Payment {} may have reached the merchant and stays pending until it is seen on-chain",
+                        txid
+                    ),
+                    Some(txid.clone()),
+                )?;
+                Err(e)
+            }
+        };
+        let _ = self
+            .telemetry
+            .track_payment_event(
+                &user_id.to_string(),
+                "merchant_payment",
+                amount,
+                result.is_ok(),
+            )
+            .await;
+        result
+    }
+
+    /// Records a built payment in history and, when ARC is configured, broadcasts it.
+    /// Rejected or double-spent payments release their reserved inputs, and so does a
+    /// broadcast that fails outright, taking its history entry with it; callers only
+    /// count the payment against the daily limit once this succeeds. Returns the TXID.
+    async fn record_and_broadcast(
+        &self,
+        user_id: Uuid,
+        tx: &Transaction,
+        counterparty: String,
+        memo: Option<String>,
+        amount: u64,
+        fee: u64,
+    ) -> Result<String, ZipError> {
+        let txid = self.record_pending(user_id, tx, counterparty, memo, amount, fee)?;
+        let tx_hex = TransactionManager::to_hex(tx);
+        let Some(arc) = &self.arc else {
+            return Ok(txid);
+        };
+        let response = match arc.broadcast(&tx_hex).await {
+            Ok(response) => response,
+            Err(e) => {
+                self.tx_manager.release_reservation(user_id, &txid)?;
+                self.history.remove(user_id, &txid)?;
+                return Err(e);
+            }
+        };
+        if response.is_double_spend() {
+            let competing = response
+                .competing_txs
+                .as_ref()
+                .and_then(|txs| txs.first().cloned())
+                .unwrap_or_default();
+            self.resolve_double_spend(user_id, &txid, &competing)
+                .await?;
+            return Err(ZipError::Blockchain(format!(
+                "Payment {} conflicts with an existing transaction",
+                txid
+            )));
+        }
+        if response.is_rejected() {
+            self.tx_manager.release_reservation(user_id, &txid)?;
+            self.history.remove(user_id, &txid)?;
+            return Err(ZipError::Blockchain(format!(
+                "Payment rejected: {}",
+                response.extra_info.unwrap_or(response.tx_status)
+            )));
+        }
+        self.tx_manager.mark_spent(user_id, &txid)?;
+        self.history
+            .set_status(user_id, &txid, TxStatus::Broadcast)?;
+        Ok(txid)
+    }
+
+    /// Settles a payment the network reports as double-spent by `competing_txid`. Inputs
+    /// the competitor took are marked spent and the rest become spendable again, all in
+    /// one update, before the payment is rolled back.
+    async fn resolve_double_spend(
+        &self,
+        user_id: Uuid,
+        our_txid: &str,
+        competing_txid: &str,
+    ) -> Result<(), ZipError> {
+        let inputs = self
+            .history
+            .get(user_id, our_txid)?
+            .map(|e| e.inputs)
+            .unwrap_or_default();
+        let conflicting = self.conflicting_inputs(&inputs, competing_txid).await;
+        self.tx_manager
+            .resolve_conflict(user_id, our_txid, &conflicting, competing_txid)?;
+        self.roll_back(user_id, our_txid, competing_txid).await
+    }
+
+    /// Inputs of a double-spent payment that are no longer ours to spend. RustBus is
+    /// asked about each one when configured; without it, or when a lookup fails, every
+    /// input is treated as taken so it is not offered for another doomed payment.
+    async fn conflicting_inputs(&self, inputs: &[String], competing_txid: &str) -> Vec<String> {
+        let Some(rustbus) = &self.rustbus else {
+            return inputs.to_vec();
+        };
+        let mut conflicting = vec![];
+        for outpoint in inputs {
+            let spender = match outpoint
+                .split_once(':')
+                .and_then(|(txid, vout)| Some((txid, vout.parse::<u32>().ok()?)))
+            {
+                Some((txid, vout)) => rustbus.query_spender(txid, vout).await,
+                None => Ok(Some(competing_txid.to_string())),
+            };
+            // Unspent inputs go back to the wallet; anything spent or unknown is lost
+            if !matches!(spender, Ok(None)) {
+                conflicting.push(outpoint.clone());
+            }
+        }
+        conflicting
+    }
+
+    /// Marks a payment as conflicted, alerts the user and records telemetry.
+    async fn roll_back(
+        &self,
+        user_id: Uuid,
+        our_txid: &str,
+        competing_txid: &str,
+    ) -> Result<(), ZipError> {
+        let amount = self
+            .history
+            .get(user_id, our_txid)?
+            .map(|e| e.amount)
+            .unwrap_or(0);
+        self.history.set_status(
+            user_id,
+            our_txid,
+            TxStatus::Conflicted {
+                competing_txid: competing_txid.to_string(),
+            },
+        )?;
+        self.alerts.push(
+            user_id,
+            format!(
+                "Payment {} was double-spent by {} and has been rolled back",
+                our_txid, competing_txid
+            ),
+            Some(our_txid.to_string()),
+        )?;
+        let _ = self
+            .telemetry
+            .track_payment_event(&user_id.to_string(), "double_spend_detected", amount, false)
+            .await;
+        Ok(())
+    }
+
+    /// Applies an observed spend of a wallet outpoint, rolling back any payment it invalidates.
+    pub async fn handle_spend(
+        &self,
+        user_id: Uuid,
+        outpoint: &str,
+        spending_txid: &str,
+    ) -> Result<SpendCheck, ZipError> {
+        let check = self
+            .tx_manager
+            .apply_spend(user_id, outpoint, spending_txid)?;
+        match &check {
+            SpendCheck::Conflict { our_txid, .. } => {
+                self.roll_back(user_id, our_txid, spending_txid).await?;
+            }
+            SpendCheck::UnknownSpend { outpoint } => {
+                self.alerts.push(
+                    user_id,
+                    format!(
+                        "Coin {} was spent by {} outside this wallet",
+                        outpoint, spending_txid
+                    ),
+                    Some(spending_txid.to_string()),
+                )?;
+            }
+            SpendCheck::Expected => {}
+        }
+        Ok(check)
+    }
+
+    /// Asks RustBus who spent each tracked outpoint and reports any conflicts found.
+    pub async fn check_conflicts(&self, user_id: Uuid) -> Result<Vec<SpendCheck>, ZipError> {
+        let Some(rustbus) = &self.rustbus else {
+            return Ok(vec![]);
+        };
+        let mut conflicts = vec![];
+        for outpoint in self.tx_manager.tracked_outpoints(user_id)? {
+            let Some((txid, vout)) = outpoint.split_once(':') else {
+                continue;
+            };
+            let vout = vout
+                .parse::<u32>()
+                .map_err(|e| ZipError::Blockchain(e.to_string()))?;
+            if let Some(spender) = rustbus.query_spender(txid, vout).await? {
+                let check = self.handle_spend(user_id, &outpoint, &spender).await?;
+                if check != SpendCheck::Expected {
+                    conflicts.push(check);
                 }
-                bytes.extend_from_slice(&tx.lock_time.to_le_bytes());
-                hex::encode(bytes)
+            }
+        }
+        Ok(conflicts)
+    }
+
+    /// Handles an ARC status callback for one of the wallet's transactions.
+    pub async fn handle_arc_callback(
+        &self,
+        user_id: Uuid,
+        callback: &ArcCallback,
+    ) -> Result<(), ZipError> {
+        if callback.is_double_spend() {
+            let competing = callback
+                .competing_txs
+                .as_ref()
+                .and_then(|txs| txs.first().cloned())
+                .unwrap_or_default();
+            return self
+                .resolve_double_spend(user_id, &callback.txid, &competing)
+                .await;
+        }
+        if callback.is_mined() {
+            self.tx_manager.mark_spent(user_id, &callback.txid)?;
+            self.history.set_status(
+                user_id,
+                &callback.txid,
+                TxStatus::Confirmed {
+                    height: callback.block_height.unwrap_or(0),
+                    block_hash: callback.block_hash.clone().unwrap_or_default(),
+                },
+            )?;
+            self.settle_reorged_receipts(user_id)?;
+        } else if callback.tx_status == "SEEN_ON_NETWORK" {
+            self.tx_manager.mark_spent(user_id, &callback.txid)?;
+            self.history
+                .set_status(user_id, &callback.txid, TxStatus::Broadcast)?;
+        }
+        Ok(())
+    }
+
+    /// Builds a time-locked payment and holds it locally until `lock_time` passes.
+    /// The daily spend is charged when the payment is broadcast, not when scheduled.
+    pub async fn schedule_locked_payment(
+        &self,
+        user_id: Uuid,
+        recipient_script: Script,
+        amount: u64,
+        fee: u64,
+        lock_time: u32,
+        data: &[DataOutput],
+    ) -> Result<LockedTx, ZipError> {
+        self.rate_limiter.check(&user_id.to_string()).await?;
+        self.enforce_policy(user_id, amount + fee)?;
+        self.require_2fa(user_id, amount + fee)?;
+        let counterparty = hex::encode(&recipient_script.0);
+        let memo = data.first().map(DataOutput::summary);
+        let tx = self
+            .tx_manager
+            .build_locked_tx(
+                user_id,
+                Some(recipient_script),
+                amount,
+                fee,
+                lock_time,
+                data,
+                &self.signing_keys(user_id)?,
+            )
+            .await?;
+        let locked = self.hold_locked_tx(
+            user_id,
+            &tx,
+            LockKind::Payment,
+            counterparty,
+            amount,
+            fee,
+            memo,
+        )?;
+        let _ = self
+            .telemetry
+            .track_payment_event(
+                &user_id.to_string(),
+                "locked_payment_scheduled",
+                amount,
+                true,
+            )
+            .await;
+        Ok(locked)
+    }
+
+    /// Locks savings back to the wallet until a block height.
+    pub async fn create_vault(
+        &self,
+        user_id: Uuid,
+        amount: u64,
+        fee: u64,
+        unlock_height: u32,
+    ) -> Result<LockedTx, ZipError> {
+        if unlock_height >= LOCKTIME_THRESHOLD {
+            return Err(ZipError::Validation(
+                "Vault unlock must be a block height".to_string(),
+            ));
+        }
+        let tip = self.headers.tip()?.map(|h| h.height).unwrap_or(0);
+        if (unlock_height as u64) <= tip {
+            return Err(ZipError::Validation(format!(
+                "Unlock height {} is not above the current height {}",
+                unlock_height, tip
+            )));
+        }
+        let tx = self
+            .tx_manager
+            .build_locked_tx(
+                user_id,
+                None,
+                amount,
+                fee,
+                unlock_height,
+                &[],
+                &self.signing_keys(user_id)?,
+            )
+            .await?;
+        let locked = self.hold_locked_tx(
+            user_id,
+            &tx,
+            LockKind::Vault,
+            "Vault".to_string(),
+            amount,
+            fee,
+            None,
+        )?;
+        let _ = self
+            .telemetry
+            .track_payment_event(&user_id.to_string(), "vault_created", amount, true)
+            .await;
+        Ok(locked)
+    }
+
+    fn hold_locked_tx(
+        &self,
+        user_id: Uuid,
+        tx: &Transaction,
+        kind: LockKind,
+        counterparty: String,
+        amount: u64,
+        fee: u64,
+        memo: Option<String>,
+    ) -> Result<LockedTx, ZipError> {
+        let txid = TransactionManager::txid(tx);
+        // Vault outputs and change come back to the wallet once mined; data outputs never do
+        let wallet_outputs = tx
+            .outputs
+            .iter()
+            .enumerate()
+            .filter(|(vout, _)| *vout > 0 || kind == LockKind::Vault)
+            .filter(|(_, out)| out.value > 0 && DataOutput::parse(&out.script.0).is_none())
+            .map(|(vout, out)| Utxo {
+                txid: txid.clone(),
+                vout: vout as u32,
+                value: out.value,
+                script: out.script.0.clone(),
             })
-            .unwrap_or(Ok(String::new()))?;
+            .collect();
+        let locked = LockedTx {
+            txid: txid.clone(),
+            tx_hex: TransactionManager::to_hex(tx),
+            lock_time: tx.lock_time,
+            kind,
+            amount,
+            fee,
+            counterparty: counterparty.clone(),
+            wallet_outputs,
+            created_at: chrono::Utc::now().timestamp(),
+        };
+        let inputs = self
+            .tx_manager
+            .reserved_inputs(user_id, &txid)?
+            .iter()
+            .map(Utxo::outpoint)
+            .collect();
+        self.history.upsert(
+            user_id,
+            HistoryEntry {
+                txid,
+                direction: TxDirection::Sent,
+                amount,
+                fee,
+                counterparty,
+                inputs,
+                status: TxStatus::Pending,
+                timestamp: locked.created_at,
+                memo,
+            },
+        )?;
+        self.timelocks.add(user_id, locked.clone())?;
+        Ok(locked)
+    }
+
+    /// Cancels a scheduled payment that has not been broadcast yet, freeing its inputs.
+    /// Its daily spend is only charged on broadcast, so nothing is refunded.
+    pub fn cancel_locked_payment(&self, user_id: Uuid, txid: &str) -> Result<(), ZipError> {
+        let scheduled = self
+            .timelocks
+            .list(user_id)?
+            .iter()
+            .any(|l| l.txid == txid && l.kind == LockKind::Payment);
+        if !scheduled {
+            return Err(ZipError::Validation(format!(
+                "No scheduled payment {}",
+                txid
+            )));
+        }
+        self.timelocks.remove(user_id, txid)?;
+        self.tx_manager.release_reservation(user_id, txid)?;
+        self.history.remove(user_id, txid)?;
+        Ok(())
+    }
+
+    /// Lists time-locked payments and vaults that have not been broadcast yet.
+    pub fn locked_funds(&self, user_id: Uuid) -> Result<Vec<LockedTx>, ZipError> {
+        self.timelocks.list(user_id)
+    }
+
+    /// Total satoshis held in vaults, excluding scheduled payments.
+    pub fn vault_balance(&self, user_id: Uuid) -> Result<u64, ZipError> {
+        self.timelocks.vault_balance(user_id)
+    }
+
+    /// Broadcasts every time-locked transaction whose lock has expired, returning their TXIDs.
+    pub async fn broadcast_due_locked(&self, user_id: Uuid) -> Result<Vec<String>, ZipError> {
+        let tip = self.headers.tip()?.map(|h| h.height).unwrap_or(0);
+        let median_time_past = self.headers.median_time_past()?.unwrap_or(0);
+        let due = self.timelocks.due(user_id, tip, median_time_past)?;
+        if due.is_empty() {
+            return Ok(vec![]);
+        }
+        let arc = self
+            .arc
+            .as_ref()
+            .ok_or_else(|| ZipError::Config("ARC_ENDPOINT is not set".to_string()))?;
+        let mut broadcast = vec![];
+        for locked in due {
+            let response = match arc.broadcast(&locked.tx_hex).await {
+                Ok(response) => response,
+                // Network errors are retried on the next pass
+                Err(_) => continue,
+            };
+            if response.is_double_spend() {
+                let competing = response
+                    .competing_txs
+                    .as_ref()
+                    .and_then(|txs| txs.first().cloned())
+                    .unwrap_or_default();
+                self.timelocks.remove(user_id, &locked.txid)?;
+                self.resolve_double_spend(user_id, &locked.txid, &competing)
+                    .await?;
+                continue;
+            }
+            // Rejections such as non-final or fee errors can clear up; retry on the next pass
+            if response.is_rejected() {
+                warn!(
+                    "Time-locked transaction {} rejected: {}",
+                    locked.txid,
+                    response.extra_info.unwrap_or(response.tx_status)
+                );
+                continue;
+            }
+            self.tx_manager.mark_spent(user_id, &locked.txid)?;
+            self.tx_manager.add_utxos(user_id, &locked.wallet_outputs)?;
+            self.history
+                .set_status(user_id, &locked.txid, TxStatus::Broadcast)?;
+            self.timelocks.remove(user_id, &locked.txid)?;
+            // Vaults pay the wallet itself; only payments count toward the daily limit
+            if locked.kind == LockKind::Payment {
+                self.storage.add_daily_spend(
+                    user_id,
+                    &Self::today(),
+                    locked.amount + locked.fee,
+                )?;
+            }
+            broadcast.push(locked.txid);
+        }
+        Ok(broadcast)
+    }
+
+    /// Starts a background task that broadcasts time-locked transactions as they
+    /// become final. Does nothing if a watcher is already running for the user.
+    pub fn start_timelock_watcher(&self, user_id: Uuid, interval: Duration) {
+        if !self.timelock_watchers.write().insert(user_id) {
+            return;
+        }
+        let wallet = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = wallet.broadcast_due_locked(user_id).await {
+                    warn!("Time-locked broadcast failed: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Applies headers from the chain backend, handling any reorg they cause.
+    pub async fn connect_headers(
+        &self,
+        user_id: Uuid,
+        branch: Vec<BlockHeader>,
+    ) -> Result<Option<Reorg>, ZipError> {
+        let reorg = self.headers.apply_branch(branch)?;
+        if let Some(reorg) = &reorg {
+            self.handle_reorg(user_id, reorg).await?;
+        }
+        Ok(reorg)
+    }
+
+    /// Rewinds transactions confirmed in disconnected blocks, re-verifies their
+    /// Merkle proofs on the new chain, and returns those left unconfirmed.
+    pub async fn handle_reorg(
+        &self,
+        user_id: Uuid,
+        reorg: &Reorg,
+    ) -> Result<Vec<HistoryEntry>, ZipError> {
+        let rewound = self.history.rewind(user_id, reorg.fork_height)?;
+        let stored_proofs = self.history.get_proofs(user_id)?;
+        let mut unconfirmed = vec![];
+        for entry in rewound {
+            // Prefer a fresh proof from the backend; the stored one may point at an orphaned block
+            let proof = match &self.rustbus {
+                Some(r) => r.query_merkle_proof(&entry.txid).await.ok().flatten(),
+                None => None,
+            }
+            .or_else(|| stored_proofs.get(&entry.txid).cloned());
+            let confirmed = match proof {
+                Some(proof) => self.history.reverify(user_id, proof, &self.headers)?,
+                None => false,
+            };
+            if !confirmed {
+                unconfirmed.push(entry);
+            }
+        }
+        let received: u64 = unconfirmed
+            .iter()
+            .filter(|e| e.direction == TxDirection::Received)
+            .map(|e| e.amount)
+            .sum();
+        if received > 0 {
+            if let Some(data) = self.storage.get_user_data(user_id)? {
+                // Rewriting also migrates v1 blobs to the current layout
+                let mut wallet_data = WalletData::decode(&data)?;
+                wallet_data.balance = wallet_data.balance.saturating_sub(received);
+                wallet_data.unconfirmed_balance += received;
+                let serialized = bincode::serialize(&wallet_data)
+                    .map_err(|e| ZipError::Blockchain(e.to_string()))?;
+                self.storage.store_user_data(user_id, &serialized)?;
+                let mut receipts = self.reorged_receipts(user_id)?;
+                receipts.extend(
+                    unconfirmed
+                        .iter()
+                        .filter(|e| e.direction == TxDirection::Received)
+                        .map(|e| (e.txid.clone(), e.amount)),
+                );
+                self.store_reorged_receipts(user_id, &receipts)?;
+            }
+        }
         let _ = self
             .telemetry
-            .track_payment_event(&user_id.to_string(), &tx_id, amount, success)
+            .track_payment_event(
+                &user_id.to_string(),
+                "chain_reorg",
+                reorg.disconnected.len() as u64,
+                unconfirmed.is_empty(),
+            )
             .await;
-        result.map(|_| tx_id) // Adjusted to return tx_id on success
+        Ok(unconfirmed)
+    }
+
+    /// Receipts a reorg moved to the unconfirmed balance, as (TXID, satoshis).
+    fn reorged_receipts(&self, user_id: Uuid) -> Result<Vec<(String, u64)>, ZipError> {
+        let data = self.storage.get_reorged_receipts(user_id)?;
+        Ok(data
+            .map(|d| bincode::deserialize(&d).unwrap_or_default())
+            .unwrap_or_default())
+    }
+
+    fn store_reorged_receipts(
+        &self,
+        user_id: Uuid,
+        receipts: &[(String, u64)],
+    ) -> Result<(), ZipError> {
+        let serialized =
+            bincode::serialize(receipts).map_err(|e| ZipError::Blockchain(e.to_string()))?;
+        self.storage.store_reorged_receipts(user_id, &serialized)
+    }
+
+    /// Moves receipts a reorg left unconfirmed back to the confirmed balance once they
+    /// confirm again. Receipts that lost a double spend, or are gone from history, only
+    /// leave the unconfirmed balance.
+    fn settle_reorged_receipts(&self, user_id: Uuid) -> Result<(), ZipError> {
+        let receipts = self.reorged_receipts(user_id)?;
+        if receipts.is_empty() {
+            return Ok(());
+        }
+        let (mut confirmed, mut dropped) = (0u64, 0u64);
+        let mut waiting = vec![];
+        for (txid, amount) in receipts {
+            match self.history.get(user_id, &txid)?.map(|e| e.status) {
+                Some(TxStatus::Confirmed { .. }) => confirmed += amount,
+                Some(TxStatus::Conflicted { .. }) | None => dropped += amount,
+                Some(TxStatus::Pending | TxStatus::Broadcast) => waiting.push((txid, amount)),
+            }
+        }
+        if confirmed + dropped == 0 {
+            return Ok(());
+        }
+        if let Some(data) = self.storage.get_user_data(user_id)? {
+            let mut wallet_data = WalletData::decode(&data)?;
+            wallet_data.balance += confirmed;
+            wallet_data.unconfirmed_balance = wallet_data
+                .unconfirmed_balance
+                .saturating_sub(confirmed + dropped);
+            let serialized = bincode::serialize(&wallet_data)
+                .map_err(|e| ZipError::Blockchain(e.to_string()))?;
+            self.storage.store_user_data(user_id, &serialized)?;
+        }
+        self.store_reorged_receipts(user_id, &waiting)
+    }
+
+    /// Lists the user's transaction history, oldest first.
+    pub fn get_history(&self, user_id: Uuid) -> Result<Vec<HistoryEntry>, ZipError> {
+        self.history.list(user_id)
+    }
+
+    /// Returns and clears pending wallet alerts.
+    pub fn take_alerts(&self, user_id: Uuid) -> Result<Vec<WalletAlert>, ZipError> {
+        self.alerts.take(user_id)
     }
 }
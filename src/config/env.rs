@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use std::env;
+use std::net::IpAddr;
 
 use crate::errors::ZipError;
 
@@ -13,6 +14,12 @@ pub struct EnvConfig {
     pub rustbus_endpoint: String,
     pub log_level: String,
     pub paymail_domain: Option<String>,
+    /// Public URL of the self-hosted PayMail server; unset disables hosting.
+    pub paymail_host_url: Option<String>,
+    /// Reverse proxies in front of the PayMail host whose `X-Forwarded-For` is trusted.
+    pub paymail_trusted_proxies: Vec<IpAddr>,
+    pub arc_endpoint: Option<String>,
+    pub arc_api_key: Option<String>,
 }
 
 impl EnvConfig {
@@ -41,6 +48,14 @@ impl EnvConfig {
                 .map_err(|_| ZipError::Auth("Missing LOG_LEVEL".to_string()))
                 .unwrap_or("info".to_string()),
             paymail_domain: env::var("PAYMAIL_DOMAIN").ok(),
+            paymail_host_url: env::var("PAYMAIL_HOST_URL").ok(),
+            paymail_trusted_proxies: env::var("PAYMAIL_TRUSTED_PROXIES")
+                .unwrap_or_default()
+                .split(',')
+                .filter_map(|proxy| proxy.trim().parse().ok())
+                .collect(),
+            arc_endpoint: env::var("ARC_ENDPOINT").ok(),
+            arc_api_key: env::var("ARC_API_KEY").ok(),
         })
     }
 }
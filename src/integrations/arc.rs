@@ -0,0 +1,111 @@
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+use crate::config::EnvConfig;
+use crate::errors::ZipError;
+
+/// Transaction status reported by ARC, both in responses and in callbacks.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArcResponse {
+    pub txid: String,
+    pub tx_status: String,
+    #[serde(default)]
+    pub block_hash: Option<String>,
+    #[serde(default)]
+    pub block_height: Option<u64>,
+    #[serde(default)]
+    pub competing_txs: Option<Vec<String>>,
+    #[serde(default)]
+    pub extra_info: Option<String>,
+}
+
+/// ARC posts the same payload to the callback URL on every status change.
+pub type ArcCallback = ArcResponse;
+
+impl ArcResponse {
+    /// True when ARC has seen a competing transaction spending the same inputs.
+    pub fn is_double_spend(&self) -> bool {
+        self.tx_status == "DOUBLE_SPEND_ATTEMPTED"
+            || self
+                .competing_txs
+                .as_ref()
+                .map(|txs| !txs.is_empty())
+                .unwrap_or(false)
+    }
+
+    /// True when the transaction will never be mined as submitted.
+    pub fn is_rejected(&self) -> bool {
+        self.tx_status == "REJECTED"
+    }
+
+    /// True once the transaction is in a block.
+    pub fn is_mined(&self) -> bool {
+        self.tx_status == "MINED"
+    }
+
+    /// True when the network has taken the transaction without a competing spend.
+    pub fn is_accepted(&self) -> bool {
+        matches!(
+            self.tx_status.as_str(),
+            "ACCEPTED_BY_NETWORK" | "SEEN_ON_NETWORK" | "MINED"
+        ) && !self.is_double_spend()
+    }
+}
+
+/// Client for an ARC transaction processor.
+#[derive(Clone)]
+pub struct ArcClient {
+    client: Client,
+    endpoint: String,
+    api_key: Option<String>,
+}
+
+impl ArcClient {
+    /// Initializes ARC client for an endpoint (e.g., https://arc.taal.com).
+    pub fn new(endpoint: &str, api_key: Option<String>) -> Self {
+        Self {
+            client: Client::new(),
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            api_key,
+        }
+    }
+
+    /// Builds a client from ARC_ENDPOINT/ARC_API_KEY, if configured.
+    pub fn from_config(config: &EnvConfig) -> Option<Self> {
+        config
+            .arc_endpoint
+            .as_ref()
+            .map(|endpoint| Self::new(endpoint, config.arc_api_key.clone()))
+    }
+
+    fn request(&self, builder: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.api_key {
+            Some(key) => builder.bearer_auth(key),
+            None => builder,
+        }
+    }
+
+    /// Submits a raw transaction and returns its initial status.
+    pub async fn broadcast(&self, tx_hex: &str) -> Result<ArcResponse, ZipError> {
+        let resp = self
+            .request(self.client.post(format!("{}/v1/tx", self.endpoint)))
+            .json(&serde_json::json!({ "rawTx": tx_hex }))
+            .send()
+            .await?
+            .json::<ArcResponse>()
+            .await?;
+        Ok(resp)
+    }
+
+    /// Queries the current status of a transaction.
+    pub async fn tx_status(&self, txid: &str) -> Result<ArcResponse, ZipError> {
+        let resp = self
+            .request(self.client.get(format!("{}/v1/tx/{}", self.endpoint, txid)))
+            .send()
+            .await?
+            .json::<ArcResponse>()
+            .await?;
+        Ok(resp)
+    }
+}
@@ -1,9 +1,11 @@
+mod arc;
 #[cfg(feature = "advanced")]
 mod nprint;
 #[cfg(feature = "advanced")]
 mod rustbus;
 
+pub use arc::{ArcCallback, ArcClient, ArcResponse};
 #[cfg(feature = "advanced")]
 pub use nprint::NPrintIntegrator;
 #[cfg(feature = "advanced")]
-pub use rustbus::RustBusIntegrator;
+pub use rustbus::{AddressOutput, RustBusIntegrator};
@@ -3,6 +3,7 @@ use std::sync::Arc;
 use tokio::sync::Mutex;
 use uuid::Uuid;
 
+use crate::blockchain::chain::MerkleProof;
 use crate::config::EnvConfig;
 use crate::errors::ZipError;
 
@@ -31,6 +32,22 @@ impl Query {
     fn tx_history(_user_id: String) -> Self {
         Self
     }
+
+    fn spender(_txid: &str, _vout: u32) -> Self {
+        Self
+    }
+
+    fn merkle_proof(_txid: &str) -> Self {
+        Self
+    }
+
+    fn address_outputs(_address: &str) -> Self {
+        Self
+    }
+
+    fn block_header(_block_hash: &str) -> Self {
+        Self
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -43,6 +60,35 @@ struct TxHistoryResponse {
     txs: Vec<String>,
 }
 
+#[derive(Serialize, Deserialize)]
+struct SpenderResponse {
+    spending_txid: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct MerkleProofResponse {
+    proof: Option<MerkleProof>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BlockHeaderResponse {
+    /// Serialized 80-byte header, hex-encoded.
+    header: Option<String>,
+}
+
+/// Output paying an address, spent or not.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AddressOutput {
+    pub txid: String,
+    pub vout: u32,
+    pub value: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AddressOutputsResponse {
+    outputs: Vec<AddressOutput>,
+}
+
 #[derive(Clone)]
 pub struct RustBusIntegrator {
     client: Arc<Mutex<Client>>,
@@ -81,4 +127,54 @@ impl RustBusIntegrator {
             .map_err(|e| ZipError::Blockchain(e))?;
         Ok(response.txs)
     }
+
+    /// Queries which transaction (if any) spent an outpoint.
+    pub async fn query_spender(&self, txid: &str, vout: u32) -> Result<Option<String>, ZipError> {
+        let guard = self.client.lock().await;
+        let query = Query::spender(txid, vout);
+        let response: SpenderResponse = guard
+            .execute(query)
+            .await
+            .map_err(|e| ZipError::Blockchain(e))?;
+        Ok(response.spending_txid)
+    }
+
+    /// Fetches the Merkle proof for a transaction on the backend's active chain.
+    pub async fn query_merkle_proof(&self, txid: &str) -> Result<Option<MerkleProof>, ZipError> {
+        let guard = self.client.lock().await;
+        let query = Query::merkle_proof(txid);
+        let response: MerkleProofResponse = guard
+            .execute(query)
+            .await
+            .map_err(|e| ZipError::Blockchain(e))?;
+        Ok(response.proof)
+    }
+
+    /// Lists every output ever paid to an address, including spent ones.
+    pub async fn query_address_outputs(
+        &self,
+        address: &str,
+    ) -> Result<Vec<AddressOutput>, ZipError> {
+        let guard = self.client.lock().await;
+        let query = Query::address_outputs(address);
+        let response: AddressOutputsResponse = guard
+            .execute(query)
+            .await
+            .map_err(|e| ZipError::Blockchain(e))?;
+        Ok(response.outputs)
+    }
+
+    /// Fetches the serialized 80-byte header of a block.
+    pub async fn query_block_header(&self, block_hash: &str) -> Result<Option<Vec<u8>>, ZipError> {
+        let guard = self.client.lock().await;
+        let query = Query::block_header(block_hash);
+        let response: BlockHeaderResponse = guard
+            .execute(query)
+            .await
+            .map_err(|e| ZipError::Blockchain(e))?;
+        response
+            .header
+            .map(|h| hex::decode(h).map_err(|e| ZipError::Blockchain(e.to_string())))
+            .transpose()
+    }
 }
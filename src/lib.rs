@@ -3,6 +3,8 @@ pub mod blockchain;
 pub mod config;
 pub mod errors;
 pub mod integrations;
+pub mod paymail_config;
+pub mod server;
 pub mod storage;
 pub mod ui;
 pub mod utils;
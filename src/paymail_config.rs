@@ -2,18 +2,174 @@ use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::env;
+use std::path::Path;
 
 use crate::errors::ZipError;
+use crate::utils::payment_uri::SATOSHIS_PER_BSV;
+use crate::utils::validation::{PrefixViolation, Validation};
 
-#[derive(Serialize, Deserialize)]
+/// Shortest prefix issued on request; sequential aliases are exempt.
+pub const MIN_PREFIX_LENGTH: usize = 3;
+/// Seconds a quoted alias price stays fixed in satoshis.
+pub const QUOTE_LOCK_SECS: i64 = 300;
+/// Default length of a premium alias auction (seven days).
+pub const AUCTION_DURATION_SECS: i64 = 7 * 86_400;
+/// Bids this close to the end push it back by the same amount, so nobody can snipe.
+pub const AUCTION_EXTENSION_SECS: i64 = 600;
+/// Minimum raise over the leading bid, in percent.
+pub const BID_INCREMENT_PERCENT: u64 = 5;
+/// Term a premium alias renewal pays for (one year).
+pub const RENEWAL_PERIOD_SECS: i64 = 365 * 86_400;
+/// Time after a term ends during which the owner can still renew.
+pub const GRACE_PERIOD_SECS: i64 = 30 * 86_400;
+
+/// Alias pricing and limits. Prices are in `currency` and converted to satoshis when
+/// a quote is issued.
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PaymailConfig {
+    #[serde(default = "default_domain")]
     pub domain: String,
+    /// Fiat currency prices are set in.
+    #[serde(default = "default_currency")]
+    pub currency: String,
+    /// Premium prefixes with their own price, regardless of length.
+    #[serde(default)]
     pub excluded_prefixes: HashMap<String, Decimal>,
+    /// Price by prefix length for prefixes not on the premium list.
+    #[serde(default = "default_length_prices")]
+    pub length_prices: HashMap<usize, Decimal>,
+    /// Price of prefixes longer than any entry in `length_prices`.
+    #[serde(default = "default_price")]
+    pub default_price: Decimal,
+    /// Seconds a quote stays locked.
+    #[serde(default = "default_quote_lock_secs")]
+    pub quote_lock_secs: i64,
+    /// Sell premium prefixes by auction instead of at their fixed price, which then
+    /// becomes the auction's reserve.
+    #[serde(default)]
+    pub premium_auctions: bool,
+    #[serde(default = "default_auction_duration_secs")]
+    pub auction_duration_secs: i64,
+    #[serde(default = "default_auction_extension_secs")]
+    pub auction_extension_secs: i64,
+    #[serde(default = "default_bid_increment_percent")]
+    pub bid_increment_percent: u64,
+    /// Premium aliases expire after `renewal_period_secs` unless renewed at their
+    /// current price; unrenewed ones return to the pool after `grace_period_secs`.
+    #[serde(default)]
+    pub premium_renewals: bool,
+    #[serde(default = "default_renewal_period_secs")]
+    pub renewal_period_secs: i64,
+    #[serde(default = "default_grace_period_secs")]
+    pub grace_period_secs: i64,
+    #[serde(default = "default_min_prefix_length")]
+    pub min_prefix_length: usize,
+    /// Prefixes nobody may take, including look-alikes (e.g., "admin", "adm1n").
+    #[serde(default = "default_reserved_words")]
+    pub reserved_words: Vec<String>,
+    /// Words that may not appear anywhere in a prefix, including look-alikes.
+    #[serde(default = "default_blocked_words")]
+    pub blocked_words: Vec<String>,
 }
 
-impl PaymailConfig {
-    /// Loads PayMail configuration with excluded prefixes and prices.
-    pub fn load() -> Self {
+/// Alias price fixed in satoshis until `expires_at`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AliasQuote {
+    pub alias: String,
+    /// Fiat price in `currency`.
+    pub price: Decimal,
+    pub currency: String,
+    pub satoshis: u64,
+    /// Unix timestamps (seconds).
+    pub quoted_at: i64,
+    pub expires_at: i64,
+}
+
+impl AliasQuote {
+    /// True once the locked price may no longer be honoured.
+    pub fn is_expired_at(&self, now: i64) -> bool {
+        now >= self.expires_at
+    }
+}
+
+fn default_domain() -> String {
+    env::var("PAYMAIL_DOMAIN").unwrap_or("zip.io".to_string())
+}
+
+fn default_currency() -> String {
+    "USD".to_string()
+}
+
+fn default_length_prices() -> HashMap<usize, Decimal> {
+    HashMap::from([(3, Decimal::from(250)), (4, Decimal::from(25))])
+}
+
+fn default_price() -> Decimal {
+    Decimal::from(10)
+}
+
+fn default_quote_lock_secs() -> i64 {
+    QUOTE_LOCK_SECS
+}
+
+fn default_auction_duration_secs() -> i64 {
+    AUCTION_DURATION_SECS
+}
+
+fn default_auction_extension_secs() -> i64 {
+    AUCTION_EXTENSION_SECS
+}
+
+fn default_bid_increment_percent() -> u64 {
+    BID_INCREMENT_PERCENT
+}
+
+fn default_renewal_period_secs() -> i64 {
+    RENEWAL_PERIOD_SECS
+}
+
+fn default_grace_period_secs() -> i64 {
+    GRACE_PERIOD_SECS
+}
+
+fn default_min_prefix_length() -> usize {
+    MIN_PREFIX_LENGTH
+}
+
+fn default_reserved_words() -> Vec<String> {
+    [
+        "000",
+        "abuse",
+        "admin",
+        "administrator",
+        "billing",
+        "help",
+        "hostmaster",
+        "noreply",
+        "official",
+        "paymail",
+        "postmaster",
+        "root",
+        "security",
+        "support",
+        "system",
+        "treasury",
+        "wallet",
+        "webmaster",
+    ]
+    .map(String::from)
+    .to_vec()
+}
+
+fn default_blocked_words() -> Vec<String> {
+    ["bitch", "cunt", "fuck", "nazi", "shit"]
+        .map(String::from)
+        .to_vec()
+}
+
+impl Default for PaymailConfig {
+    /// Built-in price list with the premium prefixes.
+    fn default() -> Self {
         let mut excluded_prefixes = HashMap::new();
         // 3-digit prefixes (alphabetically sorted)
         excluded_prefixes.insert("ace".to_string(), Decimal::from(1000));
@@ -68,36 +224,150 @@ impl PaymailConfig {
         excluded_prefixes.insert("tech".to_string(), Decimal::from(1000));
 
         Self {
-            domain: env::var("PAYMAIL_DOMAIN")
-                .map_err(|_| ZipError::Blockchain("Missing PAYMAIL_DOMAIN".to_string()))
-                .unwrap_or("zip.io".to_string()),
+            domain: default_domain(),
+            currency: default_currency(),
             excluded_prefixes,
+            length_prices: default_length_prices(),
+            default_price: default_price(),
+            quote_lock_secs: default_quote_lock_secs(),
+            premium_auctions: false,
+            auction_duration_secs: default_auction_duration_secs(),
+            auction_extension_secs: default_auction_extension_secs(),
+            bid_increment_percent: default_bid_increment_percent(),
+            premium_renewals: false,
+            renewal_period_secs: default_renewal_period_secs(),
+            grace_period_secs: default_grace_period_secs(),
+            min_prefix_length: default_min_prefix_length(),
+            reserved_words: default_reserved_words(),
+            blocked_words: default_blocked_words(),
         }
     }
+}
+
+impl PaymailConfig {
+    /// Loads PayMail configuration from the JSON file named by `PAYMAIL_CONFIG`, falling
+    /// back to the built-in price list.
+    pub fn load() -> Self {
+        match env::var("PAYMAIL_CONFIG") {
+            Ok(path) => Self::from_file(&path).unwrap_or_else(|e| {
+                tracing::warn!("Using built-in PayMail config: {}", e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Reads configuration from a JSON file; omitted fields take their defaults.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ZipError> {
+        let data = std::fs::read_to_string(path.as_ref())
+            .map_err(|e| ZipError::Config(format!("{}: {}", path.as_ref().display(), e)))?;
+        serde_json::from_str(&data).map_err(|e| ZipError::Config(e.to_string()))
+    }
 
     /// Determines price for a PayMail prefix based on length and exclusion list.
     pub fn get_prefix_price(&self, prefix: &str, is_first: bool) -> Decimal {
         if is_first && prefix == "101" {
             return Decimal::ZERO;
         }
-        if let Some(price) = self.excluded_prefixes.get(prefix) {
+        let prefix = prefix.to_lowercase();
+        if let Some(price) = self.excluded_prefixes.get(&prefix) {
             return *price;
         }
-        match prefix.len() {
-            3 => Decimal::from(250),
-            4 => Decimal::from(25),
-            _ => Decimal::from(10),
+        self.length_prices
+            .get(&prefix.chars().count())
+            .copied()
+            .unwrap_or(self.default_price)
+    }
+
+    /// True when a prefix is only sold by auction.
+    pub fn is_auctioned(&self, prefix: &str) -> bool {
+        self.premium_auctions && self.excluded_prefixes.contains_key(&prefix.to_lowercase())
+    }
+
+    /// True when a prefix must be renewed every `renewal_period_secs`.
+    pub fn is_renewable(&self, prefix: &str) -> bool {
+        self.premium_renewals && self.excluded_prefixes.contains_key(&prefix.to_lowercase())
+    }
+
+    /// Quotes a prefix at the current BSV price in `currency`, locking the satoshi
+    /// amount for `quote_lock_secs`.
+    pub fn quote(
+        &self,
+        prefix: &str,
+        is_first: bool,
+        bsv_price: Decimal,
+        now: i64,
+    ) -> Result<AliasQuote, ZipError> {
+        self.validate_prefix(prefix)?;
+        if bsv_price <= Decimal::ZERO {
+            return Err(ZipError::Blockchain("Invalid price data".to_string()));
         }
+        let price = self.get_prefix_price(prefix, is_first);
+        let satoshis = (price / bsv_price * Decimal::from(SATOSHIS_PER_BSV))
+            .round()
+            .try_into()
+            .map_err(|_| ZipError::Validation("Invalid alias price".to_string()))?;
+        Ok(AliasQuote {
+            alias: format!("{}@{}", prefix.to_lowercase(), self.domain),
+            price,
+            currency: self.currency.clone(),
+            satoshis,
+            quoted_at: now,
+            expires_at: now + self.quote_lock_secs,
+        })
     }
 
-    /// Validates a PayMail prefix.
+    /// Validates a prefix requested for a new alias: PayMail syntax, then the minimum
+    /// length and the reserved and blocked word lists. Look-alike spellings of listed
+    /// words are refused too.
     pub fn validate_prefix(&self, prefix: &str) -> Result<(), ZipError> {
-        if prefix.is_empty() || prefix.contains('@') || prefix.contains('.') {
-            return Err(ZipError::Blockchain("Invalid PayMail prefix".to_string()));
+        Validation::validate_paymail_prefix(prefix)?;
+        if prefix.chars().count() < self.min_prefix_length {
+            return Err(PrefixViolation::TooShort {
+                min: self.min_prefix_length,
+            }
+            .into());
+        }
+        let skeleton = Validation::confusable_skeleton(prefix);
+        if self.is_reserved(&skeleton) {
+            return Err(PrefixViolation::Reserved(prefix.to_lowercase()).into());
+        }
+        if self.is_blocked(&skeleton) {
+            return Err(PrefixViolation::Blocked.into());
+        }
+        Ok(())
+    }
+
+    /// Validates a profile display name against the reserved and blocked word lists,
+    /// look-alike spellings included, the same way as a prefix.
+    pub fn validate_display_name(&self, name: &str) -> Result<(), ZipError> {
+        let skeleton = Validation::confusable_skeleton(name.trim());
+        if self.is_reserved(&skeleton) {
+            return Err(ZipError::Validation(format!(
+                "Display name '{}' is reserved",
+                name.trim()
+            )));
         }
-        if prefix.len() < 3 {
-            return Err(ZipError::Blockchain("Prefix too short".to_string()));
+        if self.is_blocked(&skeleton) {
+            return Err(ZipError::Validation(
+                "Display name contains a blocked word".to_string(),
+            ));
         }
         Ok(())
     }
+
+    /// True when a skeleton reads as a reserved word.
+    fn is_reserved(&self, skeleton: &str) -> bool {
+        self.reserved_words
+            .iter()
+            .any(|word| Validation::confusable_skeleton(word) == skeleton)
+    }
+
+    /// True when a skeleton contains a blocked word.
+    fn is_blocked(&self, skeleton: &str) -> bool {
+        self.blocked_words
+            .iter()
+            .filter(|word| !word.is_empty())
+            .any(|word| skeleton.contains(&Validation::confusable_skeleton(word)))
+    }
 }
@@ -0,0 +1,940 @@
+use axum::extract::{Path, Request, State};
+use axum::http::{HeaderMap, HeaderValue, Method, StatusCode, header};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use bincode;
+use secp256k1::SecretKey;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use sv::network::Network;
+use sv::public_key::PublicKey;
+use sv::script::Script;
+use sv::transaction::{Transaction, TxOut};
+use tokio::sync::{Mutex, oneshot};
+use uuid::Uuid;
+
+use crate::blockchain::{NETWORK, TransactionManager, WalletManager};
+use crate::errors::ZipError;
+use crate::storage::ZipStorage;
+use crate::utils::crypto::{BRC29_PROTOCOL_ID, Crypto};
+
+/// Port BRC-100 apps expect the wallet's HTTP interface on.
+pub const BRC100_PORT: u16 = 3321;
+/// How long a request waits for the user to answer a permission prompt.
+pub const PROMPT_TIMEOUT: Duration = Duration::from_secs(60);
+/// Fee rate for app-created transactions, in satoshis per byte.
+pub const ACTION_FEE_RATE: u64 = 1;
+/// How long a two-phase action keeps its inputs and allowance reserved before it is
+/// aborted automatically.
+pub const ACTION_TTL: Duration = Duration::from_secs(300);
+/// Protocols whose keys lock wallet funds (BRC-29 payments); apps may never sign with
+/// them. Names starting with "admin" are reserved for the wallet by BRC-100.
+pub const RESERVED_PROTOCOLS: &[&str] = &[BRC29_PROTOCOL_ID];
+
+/// Access granted to an app, identified by its origin (e.g., "https://app.example").
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AppPermission {
+    pub origin: String,
+    /// Satoshis the app may spend without asking again.
+    pub allowance: u64,
+    pub spent: u64,
+    pub granted_at: i64,
+    /// Protocols ("<security level>-<protocol>") the app may sign with.
+    pub protocols: Vec<String>,
+}
+
+/// `AppPermission` as stored before per-protocol grants.
+#[derive(Deserialize)]
+struct AppPermissionV1 {
+    origin: String,
+    allowance: u64,
+    spent: u64,
+    granted_at: i64,
+}
+
+impl From<AppPermissionV1> for AppPermission {
+    fn from(v1: AppPermissionV1) -> Self {
+        Self {
+            origin: v1.origin,
+            allowance: v1.allowance,
+            spent: v1.spent,
+            granted_at: v1.granted_at,
+            protocols: vec![],
+        }
+    }
+}
+
+impl AppPermission {
+    /// Satoshis left in the allowance.
+    pub fn remaining(&self) -> u64 {
+        self.allowance.saturating_sub(self.spent)
+    }
+}
+
+/// What an app is asking the user for.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PromptKind {
+    /// First contact: use the wallet at all.
+    Connect,
+    /// A transaction beyond the app's remaining allowance.
+    Spend { satoshis: u64, description: String },
+    /// Signing with keys of a BRC-43 protocol for the first time.
+    Protocol {
+        security_level: u8,
+        protocol: String,
+    },
+}
+
+/// Permission request waiting for the user.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PermissionPrompt {
+    pub id: Uuid,
+    pub origin: String,
+    pub kind: PromptKind,
+}
+
+/// Per-user store of app permissions.
+#[derive(Clone)]
+pub struct AppPermissions {
+    storage: Arc<ZipStorage>,
+}
+
+impl AppPermissions {
+    /// Initializes permissions with storage.
+    pub fn new(storage: Arc<ZipStorage>) -> Self {
+        Self { storage }
+    }
+
+    /// Lists apps with access.
+    pub fn list(&self, user_id: Uuid) -> Result<Vec<AppPermission>, ZipError> {
+        let data = self.storage.get_app_permissions(user_id)?;
+        Ok(data
+            .map(|d| {
+                bincode::deserialize(&d)
+                    .or_else(|_| {
+                        bincode::deserialize::<Vec<AppPermissionV1>>(&d)
+                            .map(|v1| v1.into_iter().map(AppPermission::from).collect())
+                    })
+                    .unwrap_or_default()
+            })
+            .unwrap_or_default())
+    }
+
+    fn store(&self, user_id: Uuid, permissions: &[AppPermission]) -> Result<(), ZipError> {
+        let serialized =
+            bincode::serialize(permissions).map_err(|e| ZipError::Blockchain(e.to_string()))?;
+        self.storage.store_app_permissions(user_id, &serialized)
+    }
+
+    /// Access granted to an origin, if any.
+    pub fn get(&self, user_id: Uuid, origin: &str) -> Result<Option<AppPermission>, ZipError> {
+        Ok(self.list(user_id)?.into_iter().find(|p| p.origin == origin))
+    }
+
+    /// Grants an origin access with a spending allowance, replacing any previous grant.
+    pub fn grant(
+        &self,
+        user_id: Uuid,
+        origin: &str,
+        allowance: u64,
+    ) -> Result<AppPermission, ZipError> {
+        let mut permissions = self.list(user_id)?;
+        permissions.retain(|p| p.origin != origin);
+        let permission = AppPermission {
+            origin: origin.to_string(),
+            allowance,
+            spent: 0,
+            granted_at: chrono::Utc::now().timestamp(),
+            protocols: vec![],
+        };
+        permissions.push(permission.clone());
+        self.store(user_id, &permissions)?;
+        Ok(permission)
+    }
+
+    /// Revokes an origin's access.
+    pub fn revoke(&self, user_id: Uuid, origin: &str) -> Result<(), ZipError> {
+        let mut permissions = self.list(user_id)?;
+        permissions.retain(|p| p.origin != origin);
+        self.store(user_id, &permissions)
+    }
+
+    /// Lets an app sign with a protocol's keys without asking again.
+    pub fn allow_protocol(
+        &self,
+        user_id: Uuid,
+        origin: &str,
+        protocol: &str,
+    ) -> Result<(), ZipError> {
+        let mut permissions = self.list(user_id)?;
+        if let Some(permission) = permissions.iter_mut().find(|p| p.origin == origin) {
+            if !permission.protocols.iter().any(|p| p == protocol) {
+                permission.protocols.push(protocol.to_string());
+            }
+        }
+        self.store(user_id, &permissions)
+    }
+
+    /// Counts satoshis spent by an app against its allowance.
+    pub fn record_spend(&self, user_id: Uuid, origin: &str, amount: u64) -> Result<(), ZipError> {
+        let mut permissions = self.list(user_id)?;
+        if let Some(permission) = permissions.iter_mut().find(|p| p.origin == origin) {
+            permission.spent += amount;
+        }
+        self.store(user_id, &permissions)
+    }
+
+    /// Returns satoshis reserved for an action that was never broadcast.
+    pub fn refund(&self, user_id: Uuid, origin: &str, amount: u64) -> Result<(), ZipError> {
+        let mut permissions = self.list(user_id)?;
+        if let Some(permission) = permissions.iter_mut().find(|p| p.origin == origin) {
+            permission.spent = permission.spent.saturating_sub(amount);
+        }
+        self.store(user_id, &permissions)
+    }
+}
+
+/// Transaction built by `createAction` and held until `signAction` or `abortAction`.
+#[derive(Clone)]
+struct PendingAction {
+    user_id: Uuid,
+    origin: String,
+    tx: Transaction,
+    description: String,
+    amount: u64,
+    fee: u64,
+    /// Charged to the app's allowance when created (false when the user approved it
+    /// in a prompt instead).
+    charged: bool,
+}
+
+type Prompts = HashMap<Uuid, (PermissionPrompt, oneshot::Sender<Option<u64>>)>;
+
+#[derive(Deserialize)]
+struct KeyArgs {
+    #[serde(default, rename = "identityKey")]
+    identity_key: bool,
+    #[serde(rename = "protocolID")]
+    protocol_id: Option<(u8, String)>,
+    #[serde(rename = "keyID")]
+    key_id: Option<String>,
+    counterparty: Option<String>,
+    #[serde(default, rename = "forSelf")]
+    for_self: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SignatureArgs {
+    #[serde(flatten)]
+    key: KeyArgs,
+    data: Option<Vec<u8>>,
+    hash_to_directly_sign: Option<Vec<u8>>,
+    hash_to_directly_verify: Option<Vec<u8>>,
+    #[serde(default)]
+    signature: Vec<u8>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ActionOutput {
+    locking_script: String,
+    satoshis: u64,
+    #[serde(default)]
+    output_description: String,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ActionOptions {
+    sign_and_process: Option<bool>,
+}
+
+#[derive(Deserialize)]
+struct CreateActionArgs {
+    description: String,
+    #[serde(default)]
+    inputs: Vec<Value>,
+    #[serde(default)]
+    outputs: Vec<ActionOutput>,
+    #[serde(default)]
+    options: ActionOptions,
+}
+
+#[derive(Deserialize)]
+struct ListOutputsArgs {
+    limit: Option<usize>,
+    #[serde(default)]
+    offset: usize,
+}
+
+/// BRC-100 wallet interface for third-party apps, served over HTTP on localhost.
+/// Every app is identified by its origin and must be approved by the user; spending
+/// beyond an app's allowance prompts again.
+#[derive(Clone)]
+pub struct WalletInterface {
+    wallet: WalletManager,
+    tx_manager: Arc<TransactionManager>,
+    storage: Arc<ZipStorage>,
+    permissions: AppPermissions,
+    /// Fixed wallet user; `None` serves whoever is signed in.
+    user_id: Option<Uuid>,
+    prompts: Arc<Mutex<Prompts>>,
+    actions: Arc<Mutex<HashMap<String, PendingAction>>>,
+    /// Serializes allowance checks so concurrent actions cannot both spend the same
+    /// remaining allowance.
+    allowance_lock: Arc<Mutex<()>>,
+}
+
+impl WalletInterface {
+    /// Initializes the interface for one wallet user.
+    pub fn new(
+        wallet: WalletManager,
+        tx_manager: Arc<TransactionManager>,
+        storage: Arc<ZipStorage>,
+        user_id: Uuid,
+    ) -> Self {
+        Self::with_user(wallet, tx_manager, storage, Some(user_id))
+    }
+
+    /// Initializes the interface for the user signed in on this device; calls are
+    /// refused while nobody is signed in.
+    pub fn for_session(
+        wallet: WalletManager,
+        tx_manager: Arc<TransactionManager>,
+        storage: Arc<ZipStorage>,
+    ) -> Self {
+        Self::with_user(wallet, tx_manager, storage, None)
+    }
+
+    fn with_user(
+        wallet: WalletManager,
+        tx_manager: Arc<TransactionManager>,
+        storage: Arc<ZipStorage>,
+        user_id: Option<Uuid>,
+    ) -> Self {
+        Self {
+            wallet,
+            tx_manager,
+            permissions: AppPermissions::new(Arc::clone(&storage)),
+            storage,
+            user_id,
+            prompts: Arc::new(Mutex::new(HashMap::new())),
+            actions: Arc::new(Mutex::new(HashMap::new())),
+            allowance_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Wallet user the interface acts for.
+    fn user_id(&self) -> Result<Uuid, ZipError> {
+        match self.user_id {
+            Some(user_id) => Ok(user_id),
+            None => self
+                .storage
+                .get_session_user()?
+                .ok_or_else(|| ZipError::Auth("No wallet user is signed in".to_string())),
+        }
+    }
+
+    /// Whether the wallet user is signed in on this device, so apps may call it.
+    fn session_unlocked(&self) -> Result<bool, ZipError> {
+        Ok(match (self.storage.get_session_user()?, self.user_id) {
+            (Some(signed_in), Some(user_id)) => signed_in == user_id,
+            (signed_in, None) => signed_in.is_some(),
+            (None, Some(_)) => false,
+        })
+    }
+
+    /// Whether the user already connected `origin` to their wallet.
+    fn is_granted(&self, origin: &str) -> bool {
+        self.user_id()
+            .and_then(|user_id| self.permissions.get(user_id, origin))
+            .is_ok_and(|permission| permission.is_some())
+    }
+
+    /// Stored app permissions, for listing and revoking in settings.
+    pub fn permissions(&self) -> &AppPermissions {
+        &self.permissions
+    }
+
+    /// Prompts waiting for the user.
+    pub async fn pending_prompts(&self) -> Vec<PermissionPrompt> {
+        self.prompts
+            .lock()
+            .await
+            .values()
+            .map(|(prompt, _)| prompt.clone())
+            .collect()
+    }
+
+    /// Answers a prompt. `None` denies it. For `Connect`, `Some(allowance)` grants access
+    /// with that spending allowance; for `Spend`, it approves that one transaction.
+    pub async fn respond(&self, prompt_id: Uuid, answer: Option<u64>) -> Result<(), ZipError> {
+        let (_, reply) = self
+            .prompts
+            .lock()
+            .await
+            .remove(&prompt_id)
+            .ok_or_else(|| ZipError::Validation("Prompt is no longer pending".to_string()))?;
+        let _ = reply.send(answer);
+        Ok(())
+    }
+
+    /// Routes for the BRC-100 HTTP wallet interface (`POST /<method>` with JSON args),
+    /// reachable from browser apps through CORS.
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/:method", post(handle))
+            .layer(middleware::from_fn_with_state(self.clone(), cors))
+            .with_state(self.clone())
+    }
+
+    /// Serves the interface on a loopback address (port 0 picks a free port) and
+    /// returns the bound address.
+    pub async fn serve(&self, port: u16) -> Result<SocketAddr, ZipError> {
+        let listener = tokio::net::TcpListener::bind(("127.0.0.1", port))
+            .await
+            .map_err(|e| ZipError::Config(e.to_string()))?;
+        let addr = listener
+            .local_addr()
+            .map_err(|e| ZipError::Config(e.to_string()))?;
+        let router = self.router();
+        tokio::spawn(async move {
+            let _ = axum::serve(listener, router).await;
+        });
+        Ok(addr)
+    }
+
+    /// Raises a prompt and waits for the user; unanswered prompts are denied.
+    async fn ask(&self, user_id: Uuid, origin: &str, kind: PromptKind) -> Option<u64> {
+        let (reply, answer) = oneshot::channel();
+        let prompt = PermissionPrompt {
+            id: Uuid::new_v4(),
+            origin: origin.to_string(),
+            kind,
+        };
+        let message = match &prompt.kind {
+            PromptKind::Connect => format!("{} wants to connect to your wallet", origin),
+            PromptKind::Spend {
+                satoshis,
+                description,
+            } => format!(
+                "{} wants to spend {} satoshis: {}",
+                origin, satoshis, description
+            ),
+            PromptKind::Protocol {
+                security_level,
+                protocol,
+            } => format!(
+                "{} wants to sign with your \"{}\" keys (level {})",
+                origin, protocol, security_level
+            ),
+        };
+        self.prompts
+            .lock()
+            .await
+            .insert(prompt.id, (prompt.clone(), reply));
+        let _ = self.wallet.notify(user_id, message);
+        let answer = tokio::time::timeout(PROMPT_TIMEOUT, answer).await;
+        self.prompts.lock().await.remove(&prompt.id);
+        answer.ok().and_then(|a| a.ok()).flatten()
+    }
+
+    /// Returns the origin's permission, prompting the user on first contact.
+    async fn authorize(&self, user_id: Uuid, origin: &str) -> Result<AppPermission, ZipError> {
+        if let Some(permission) = self.permissions.get(user_id, origin)? {
+            return Ok(permission);
+        }
+        match self.ask(user_id, origin, PromptKind::Connect).await {
+            Some(allowance) => self.permissions.grant(user_id, origin, allowance),
+            None => Err(ZipError::Auth(format!(
+                "{} was not allowed to connect",
+                origin
+            ))),
+        }
+    }
+
+    /// Dispatches a BRC-100 call from an app.
+    pub async fn call(&self, origin: &str, method: &str, args: Value) -> Result<Value, ZipError> {
+        match method {
+            "getVersion" => {
+                return Ok(json!({ "version": format!("zip-{}", env!("CARGO_PKG_VERSION")) }));
+            }
+            "getNetwork" => {
+                let network = match NETWORK {
+                    Network::Mainnet => "mainnet",
+                    _ => "testnet",
+                };
+                return Ok(json!({ "network": network }));
+            }
+            "isAuthenticated" => {
+                return Ok(json!({ "authenticated": self.session_unlocked()? }));
+            }
+            "waitForAuthentication" => {
+                let deadline = tokio::time::Instant::now() + PROMPT_TIMEOUT;
+                while !self.session_unlocked()? {
+                    if tokio::time::Instant::now() >= deadline {
+                        return Err(ZipError::Auth("The wallet is locked".to_string()));
+                    }
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
+                return Ok(json!({ "authenticated": true }));
+            }
+            _ => {}
+        }
+        let user_id = self.user_id()?;
+        let permission = self.authorize(user_id, origin).await?;
+        match method {
+            "getPublicKey" => self.get_public_key(parse(args)?),
+            "createSignature" => {
+                self.create_signature(user_id, &permission, parse(args)?)
+                    .await
+            }
+            "verifySignature" => self.verify_signature(parse(args)?),
+            "listOutputs" => self.list_outputs(user_id, parse(args)?),
+            "createAction" => self.create_action(user_id, &permission, parse(args)?).await,
+            "signAction" => self.sign_action(user_id, origin, &reference(&args)?).await,
+            "abortAction" => self.abort_action(user_id, origin, &reference(&args)?).await,
+            _ => Err(ZipError::Validation(format!(
+                "Unsupported method: {}",
+                method
+            ))),
+        }
+    }
+
+    fn get_public_key(&self, args: KeyArgs) -> Result<Value, ZipError> {
+        let public_key = if args.identity_key {
+            self.wallet.identity_public_key()?
+        } else {
+            let counterparty = counterparty_key(args.counterparty.as_deref().unwrap_or("self"))?;
+            self.wallet.derived_public_key(
+                &invoice_number(&args)?,
+                counterparty.as_ref(),
+                args.for_self,
+            )?
+        };
+        Ok(json!({ "publicKey": hex::encode(public_key.to_bytes()) }))
+    }
+
+    /// Signs with a BRC-43 derived key. Reserved protocols are refused, each other
+    /// protocol above level 0 needs the user's approval once per app, and keys the
+    /// wallet holds funds under are never used: a signature over `data` is also a
+    /// transaction signature whenever `data` is the first SHA-256 of a sighash preimage.
+    async fn create_signature(
+        &self,
+        user_id: Uuid,
+        permission: &AppPermission,
+        args: SignatureArgs,
+    ) -> Result<Value, ZipError> {
+        let invoice_number = invoice_number(&args.key)?;
+        let Some((level, protocol)) = &args.key.protocol_id else {
+            return Err(ZipError::Validation("protocolID is required".to_string()));
+        };
+        let protocol = protocol.trim().to_lowercase();
+        if RESERVED_PROTOCOLS.contains(&protocol.as_str()) || protocol.starts_with("admin") {
+            return Err(ZipError::Auth(format!(
+                "Protocol \"{}\" is reserved for the wallet",
+                protocol
+            )));
+        }
+        let grant = format!("{}-{}", level, protocol);
+        if *level > 0 && !permission.protocols.contains(&grant) {
+            let kind = PromptKind::Protocol {
+                security_level: *level,
+                protocol: protocol.clone(),
+            };
+            if self.ask(user_id, &permission.origin, kind).await.is_none() {
+                return Err(ZipError::Auth(format!(
+                    "Signing with \"{}\" was not approved",
+                    protocol
+                )));
+            }
+            self.permissions
+                .allow_protocol(user_id, &permission.origin, &grant)?;
+        }
+        let digest = digest(args.hash_to_directly_sign, args.data)?;
+        let counterparty = counterparty_key(args.key.counterparty.as_deref().unwrap_or("anyone"))?;
+        let signing_key =
+            self.wallet
+                .derived_public_key(&invoice_number, counterparty.as_ref(), true)?;
+        if self.wallet.holds_funds_for(user_id, &signing_key)? {
+            return Err(ZipError::Auth(
+                "This key controls wallet funds and cannot sign for apps".to_string(),
+            ));
+        }
+        let signature =
+            self.wallet
+                .derived_signature(&invoice_number, counterparty.as_ref(), digest)?;
+        Ok(json!({ "signature": signature }))
+    }
+
+    fn verify_signature(&self, args: SignatureArgs) -> Result<Value, ZipError> {
+        let digest = digest(args.hash_to_directly_verify, args.data)?;
+        let counterparty = counterparty_key(args.key.counterparty.as_deref().unwrap_or("self"))?;
+        let public_key = self.wallet.derived_public_key(
+            &invoice_number(&args.key)?,
+            counterparty.as_ref(),
+            args.key.for_self,
+        )?;
+        Ok(json!({ "valid": Crypto::verify_digest(&public_key, digest, &args.signature) }))
+    }
+
+    fn list_outputs(&self, user_id: Uuid, args: ListOutputsArgs) -> Result<Value, ZipError> {
+        let utxos = self.wallet.list_outputs(user_id)?;
+        let outputs: Vec<Value> = utxos
+            .iter()
+            .skip(args.offset)
+            .take(args.limit.unwrap_or(10).min(10_000))
+            .map(|utxo| {
+                json!({
+                    "outpoint": format!("{}.{}", utxo.txid, utxo.vout),
+                    "satoshis": utxo.value,
+                    "lockingScript": hex::encode(&utxo.script),
+                    "spendable": true,
+                })
+            })
+            .collect();
+        Ok(json!({ "totalOutputs": utxos.len(), "outputs": outputs }))
+    }
+
+    async fn create_action(
+        &self,
+        user_id: Uuid,
+        permission: &AppPermission,
+        args: CreateActionArgs,
+    ) -> Result<Value, ZipError> {
+        if !args.inputs.is_empty() {
+            return Err(ZipError::Validation(
+                "Actions with app-supplied inputs are not supported".to_string(),
+            ));
+        }
+        let outputs = args
+            .outputs
+            .iter()
+            .map(|output| {
+                let script = hex::decode(&output.locking_script)
+                    .map_err(|e| ZipError::Validation(e.to_string()))?;
+                Ok(TxOut {
+                    value: output.satoshis,
+                    script: Script(script),
+                })
+            })
+            .collect::<Result<Vec<TxOut>, ZipError>>()?;
+        let amount: u64 = outputs.iter().map(|o| o.value).sum();
+        let selection = self.tx_manager.select_coins(user_id, amount, 0)?;
+        let fee = TransactionManager::estimate_size(selection.inputs.len(), outputs.len() + 1)
+            as u64
+            * ACTION_FEE_RATE;
+        let origin = permission.origin.clone();
+        // Reserve the allowance now, so pending two-phase actions count against it
+        let charged = {
+            let _guard = self.allowance_lock.lock().await;
+            let remaining = self
+                .permissions
+                .get(user_id, &origin)?
+                .map(|p| p.remaining())
+                .unwrap_or(0);
+            let within = amount + fee <= remaining;
+            if within {
+                self.permissions
+                    .record_spend(user_id, &origin, amount + fee)?;
+            }
+            within
+        };
+        if !charged {
+            let description = args
+                .outputs
+                .iter()
+                .map(|o| o.output_description.as_str())
+                .find(|d| !d.is_empty())
+                .unwrap_or(&args.description)
+                .to_string();
+            let kind = PromptKind::Spend {
+                satoshis: amount + fee,
+                description,
+            };
+            if self.ask(user_id, &origin, kind).await.is_none() {
+                return Err(ZipError::Auth("Spend was not approved".to_string()));
+            }
+        }
+        let tx = match self.wallet.prepare_outputs(user_id, outputs, fee).await {
+            Ok(tx) => tx,
+            Err(e) => {
+                if charged {
+                    self.permissions.refund(user_id, &origin, amount + fee)?;
+                }
+                return Err(e);
+            }
+        };
+        let action = PendingAction {
+            user_id,
+            origin,
+            tx: tx.clone(),
+            description: args.description,
+            amount,
+            fee,
+            charged,
+        };
+        if args.options.sign_and_process == Some(false) {
+            let reference = Uuid::new_v4().to_string();
+            self.actions.lock().await.insert(reference.clone(), action);
+            self.expire_action(reference.clone());
+            return Ok(json!({
+                "signableTransaction": {
+                    "tx": TransactionManager::serialize(&tx),
+                    "reference": reference,
+                }
+            }));
+        }
+        self.process(action).await
+    }
+
+    /// Aborts a two-phase action left unsigned for `ACTION_TTL`.
+    fn expire_action(&self, reference: String) {
+        let interface = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(ACTION_TTL).await;
+            let expired = interface.actions.lock().await.remove(&reference);
+            if let Some(action) = expired {
+                let _ = interface.release(&action);
+            }
+        });
+    }
+
+    async fn sign_action(
+        &self,
+        user_id: Uuid,
+        origin: &str,
+        reference: &str,
+    ) -> Result<Value, ZipError> {
+        let action = self.take_action(user_id, origin, reference).await?;
+        self.process(action).await
+    }
+
+    async fn abort_action(
+        &self,
+        user_id: Uuid,
+        origin: &str,
+        reference: &str,
+    ) -> Result<Value, ZipError> {
+        let action = self.take_action(user_id, origin, reference).await?;
+        self.release(&action)?;
+        Ok(json!({ "aborted": true }))
+    }
+
+    async fn take_action(
+        &self,
+        user_id: Uuid,
+        origin: &str,
+        reference: &str,
+    ) -> Result<PendingAction, ZipError> {
+        let mut actions = self.actions.lock().await;
+        match actions.get(reference) {
+            Some(action) if action.origin == origin && action.user_id == user_id => {
+                Ok(actions.remove(reference).unwrap())
+            }
+            _ => Err(ZipError::Validation(format!(
+                "Unknown action: {}",
+                reference
+            ))),
+        }
+    }
+
+    /// Releases an unbroadcast action's inputs and returns its allowance.
+    fn release(&self, action: &PendingAction) -> Result<(), ZipError> {
+        self.wallet
+            .abort_prepared(action.user_id, &TransactionManager::txid(&action.tx))?;
+        if action.charged {
+            self.permissions
+                .refund(action.user_id, &action.origin, action.amount + action.fee)?;
+        }
+        Ok(())
+    }
+
+    /// Broadcasts an action; its allowance was charged when it was created and is
+    /// returned if the wallet refused it before recording it.
+    async fn process(&self, action: PendingAction) -> Result<Value, ZipError> {
+        let result = self
+            .wallet
+            .submit_prepared(
+                action.user_id,
+                &action.tx,
+                Some(action.description.clone()),
+                action.amount,
+                action.fee,
+            )
+            .await;
+        let txid = match result {
+            Ok(txid) => txid,
+            Err(e) => {
+                let txid = TransactionManager::txid(&action.tx);
+                let recorded = self
+                    .wallet
+                    .get_history(action.user_id)?
+                    .iter()
+                    .any(|entry| entry.txid == txid);
+                if !recorded {
+                    self.release(&action)?;
+                }
+                return Err(e);
+            }
+        };
+        let tx = TransactionManager::serialize(&action.tx);
+        Ok(json!({ "txid": txid, "tx": tx }))
+    }
+}
+
+/// Adds CORS headers for browser apps the user already connected and answers preflight
+/// requests. Other origins get no CORS headers, so browsers keep their pages from
+/// reading the wallet's responses.
+async fn cors(State(interface): State<WalletInterface>, request: Request, next: Next) -> Response {
+    let origin = request
+        .headers()
+        .get(header::ORIGIN)
+        .filter(|origin| interface.is_granted(origin.to_str().unwrap_or_default()))
+        .cloned();
+    let mut response = if request.method() == Method::OPTIONS {
+        StatusCode::NO_CONTENT.into_response()
+    } else {
+        next.run(request).await
+    };
+    let headers = response.headers_mut();
+    headers.insert(header::VARY, HeaderValue::from_static("origin"));
+    let Some(origin) = origin else {
+        return response;
+    };
+    headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+    headers.insert(
+        header::ACCESS_CONTROL_ALLOW_METHODS,
+        HeaderValue::from_static("POST, OPTIONS"),
+    );
+    headers.insert(
+        header::ACCESS_CONTROL_ALLOW_HEADERS,
+        HeaderValue::from_static("content-type"),
+    );
+    headers.insert(
+        "access-control-allow-private-network",
+        HeaderValue::from_static("true"),
+    );
+    response
+}
+
+async fn handle(
+    State(interface): State<WalletInterface>,
+    Path(method): Path<String>,
+    headers: HeaderMap,
+    body: String,
+) -> (StatusCode, Json<Value>) {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .map(|value| value.to_str().unwrap_or_default().to_string())
+    };
+    // Browsers set Origin themselves while any page can claim an `originator`, so it
+    // only names apps that send no Origin and must agree with Origin otherwise
+    let origin = match (header("origin"), header("originator")) {
+        (Some(origin), Some(originator)) if originator != origin => {
+            let error = ZipError::Auth("originator does not match Origin".to_string());
+            return (StatusCode::FORBIDDEN, error_body(&error));
+        }
+        (Some(origin), _) | (None, Some(origin)) => Some(origin).filter(|o| !o.is_empty()),
+        (None, None) => None,
+    };
+    let result = match origin {
+        Some(origin) => match body.trim() {
+            "" => interface.call(&origin, &method, json!({})).await,
+            body => match serde_json::from_str(body) {
+                Ok(args) => interface.call(&origin, &method, args).await,
+                Err(e) => Err(ZipError::Validation(e.to_string())),
+            },
+        },
+        None => Err(ZipError::Auth("Missing Origin header".to_string())),
+    };
+    match result {
+        Ok(value) => (StatusCode::OK, Json(value)),
+        Err(e) => (StatusCode::BAD_REQUEST, error_body(&e)),
+    }
+}
+
+fn error_body(error: &ZipError) -> Json<Value> {
+    Json(json!({
+        "status": "error",
+        "code": error_code(error),
+        "description": error.to_string(),
+    }))
+}
+
+fn error_code(error: &ZipError) -> &'static str {
+    match error {
+        ZipError::Auth(_) => "ERR_UNAUTHORIZED",
+        ZipError::Validation(_) => "ERR_INVALID_PARAMETER",
+        ZipError::RateLimit(_) => "ERR_RATE_LIMITED",
+        ZipError::Blockchain(message) if message.starts_with("Insufficient funds") => {
+            "ERR_INSUFFICIENT_FUNDS"
+        }
+        _ => "ERR_INTERNAL",
+    }
+}
+
+fn parse<T: for<'de> Deserialize<'de>>(args: Value) -> Result<T, ZipError> {
+    serde_json::from_value(args).map_err(|e| ZipError::Validation(e.to_string()))
+}
+
+fn reference(args: &Value) -> Result<String, ZipError> {
+    args["reference"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| ZipError::Validation("Missing reference".to_string()))
+}
+
+/// BRC-43 invoice number: "<security level>-<protocol>-<key ID>".
+fn invoice_number(args: &KeyArgs) -> Result<String, ZipError> {
+    let (Some((level, protocol)), Some(key_id)) = (&args.protocol_id, &args.key_id) else {
+        return Err(ZipError::Validation(
+            "protocolID and keyID are required".to_string(),
+        ));
+    };
+    let protocol = protocol.trim().to_lowercase();
+    if *level > 2 || protocol.len() < 5 || key_id.is_empty() {
+        return Err(ZipError::Validation(
+            "Invalid protocolID or keyID".to_string(),
+        ));
+    }
+    Ok(format!("{}-{}-{}", level, protocol, key_id))
+}
+
+/// "self" is the wallet's own identity key (`None`); "anyone" is the public key of the
+/// private key 1, so anyone can derive the same child keys.
+fn counterparty_key(counterparty: &str) -> Result<Option<PublicKey>, ZipError> {
+    match counterparty {
+        "self" => Ok(None),
+        "anyone" => {
+            let mut one = [0u8; 32];
+            one[31] = 1;
+            let key = SecretKey::from_slice(&one).map_err(|e| ZipError::Crypto(e.to_string()))?;
+            Ok(Some(Crypto::derive_public_key(&key)))
+        }
+        hex_key => hex::decode(hex_key)
+            .ok()
+            .and_then(|key| PublicKey::from_slice(&key).ok())
+            .map(Some)
+            .ok_or_else(|| ZipError::Validation("Invalid counterparty".to_string())),
+    }
+}
+
+fn digest(hash: Option<Vec<u8>>, data: Option<Vec<u8>>) -> Result<[u8; 32], ZipError> {
+    match (hash, data) {
+        (Some(hash), _) => hash
+            .try_into()
+            .map_err(|_| ZipError::Validation("Hash must be 32 bytes".to_string())),
+        (None, Some(data)) => Ok(Sha256::digest(&data).into()),
+        (None, None) => Err(ZipError::Validation("data is required".to_string())),
+    }
+}
@@ -0,0 +1,9 @@
+pub mod brc100;
+pub mod paymail;
+
+pub use brc100::{
+    AppPermission, AppPermissions, BRC100_PORT, PermissionPrompt, PromptKind, WalletInterface,
+};
+pub use paymail::{
+    DESTINATION_TTL_SECS, MAX_OPEN_DESTINATIONS, PAYMAIL_PORT, PaymailHost, PaymentDestination,
+};
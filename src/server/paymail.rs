@@ -0,0 +1,673 @@
+use axum::extract::{ConnectInfo, Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use bincode;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use sv::public_key::PublicKey;
+use uuid::Uuid;
+
+use crate::blockchain::profile::{P2P_DESTINATION_BRFC, PUBLIC_PROFILE_BRFC};
+use crate::blockchain::{
+    Invoice, InvoiceDestination, InvoicePayment, InvoiceStatus, Invoices, PaymailManager,
+    TransactionManager, WalletManager,
+};
+use crate::errors::ZipError;
+use crate::storage::ZipStorage;
+use crate::utils::rate_limiter::RateLimiter;
+
+/// Port the PayMail host listens on behind the public URL.
+pub const PAYMAIL_PORT: u16 = 3322;
+
+/// BRFC ID of the P2P receive transaction capability.
+pub const P2P_RECEIVE_BRFC: &str = "5f1323cddf31";
+/// BRFC ID of the P2P receive capability that takes BEEF instead of raw hex.
+pub const P2P_BEEF_BRFC: &str = "5c55a7fdb7bb";
+/// BRFC ID of the sender validation capability.
+pub const SENDER_VALIDATION_BRFC: &str = "6745385c3fc0";
+
+/// Seconds an unpaid destination stays valid; later submissions for it are refused.
+pub const DESTINATION_TTL_SECS: i64 = 24 * 60 * 60;
+/// Unpaid destinations a single client address may hold open; it gets no more until
+/// some are paid or expire.
+pub const MAX_OPEN_DESTINATIONS: usize = 100;
+/// Requests a single client address may make to the destination and P2P endpoints
+/// per minute.
+const REQUESTS_PER_IP: u32 = 30;
+/// Destination requests accepted per alias per minute.
+const REQUESTS_PER_ALIAS: u32 = 60;
+/// P2P transaction submissions accepted per alias per minute, counted apart from
+/// destination requests so those cannot hold up payments.
+const SUBMISSIONS_PER_ALIAS: u32 = 60;
+
+/// BRC-29 derivation suffix; each destination holds a single output, and its
+/// reference serves as the derivation prefix.
+const BRC29_DERIVATION_SUFFIX: &str = "1";
+
+/// Script handed out for a PayMail payment; each request gets a fresh address.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PaymentDestination {
+    pub reference: String,
+    pub alias: String,
+    pub address: String,
+    pub derivation_path: String,
+    /// Satoshis due: the invoice's outstanding amount, else what the sender announced.
+    pub satoshis: u64,
+    pub created_at: i64,
+    /// Set once a P2P sender submits the paying transaction.
+    pub txid: Option<String>,
+    /// Reference of the invoice this destination was issued for.
+    pub invoice: Option<String>,
+}
+
+/// `PaymentDestination` as stored before destinations were issued for invoices.
+#[derive(Deserialize)]
+struct PaymentDestinationV1 {
+    reference: String,
+    alias: String,
+    address: String,
+    derivation_path: String,
+    satoshis: u64,
+    created_at: i64,
+    txid: Option<String>,
+}
+
+impl From<PaymentDestinationV1> for PaymentDestination {
+    fn from(v1: PaymentDestinationV1) -> Self {
+        Self {
+            reference: v1.reference,
+            alias: v1.alias,
+            address: v1.address,
+            derivation_path: v1.derivation_path,
+            satoshis: v1.satoshis,
+            created_at: v1.created_at,
+            txid: v1.txid,
+            invoice: None,
+        }
+    }
+}
+
+impl PaymentDestination {
+    /// Decodes a stored destination, migrating the pre-invoice format.
+    fn decode(bytes: &[u8]) -> Result<Self, ZipError> {
+        bincode::deserialize(bytes)
+            .or_else(|_| bincode::deserialize::<PaymentDestinationV1>(bytes).map(Self::from))
+            .map_err(|e| ZipError::Blockchain(e.to_string()))
+    }
+
+    /// True once an unpaid destination is older than `DESTINATION_TTL_SECS`.
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.txid.is_none() && now - self.created_at > DESTINATION_TTL_SECS
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DestinationRequest {
+    #[serde(default)]
+    satoshis: u64,
+    #[serde(default)]
+    amount: u64,
+    /// Hex identity key of a BRC-29 sender; the destination is then derived from it.
+    #[serde(default)]
+    sender_identity_key: Option<String>,
+    /// Reference of an invoice issued here that the payment settles.
+    #[serde(default)]
+    invoice: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ReceiveRequest {
+    #[serde(default)]
+    hex: Option<String>,
+    /// Sent instead of `hex` to the BEEF capability.
+    #[serde(default)]
+    beef: Option<String>,
+    reference: String,
+    #[serde(default)]
+    metadata: Value,
+}
+
+/// Destinations issued to each client address, as (user, reference, issued at).
+type OpenDestinations = HashMap<String, Vec<(Uuid, String, i64)>>;
+
+/// PayMail host serving every alias issued under the wallet's domain: capability
+/// discovery, PKI, public profile, payment destinations and P2P transactions.
+#[derive(Clone)]
+pub struct PaymailHost {
+    paymail: PaymailManager,
+    wallet: WalletManager,
+    invoices: Invoices,
+    storage: Arc<ZipStorage>,
+    /// Public URL the host is reachable at (e.g., "https://zip.io").
+    base_url: String,
+    /// Reverse proxies whose `X-Forwarded-For` names the client.
+    trusted_proxies: Vec<IpAddr>,
+    ip_limiter: RateLimiter,
+    alias_limiter: RateLimiter,
+    receive_limiter: RateLimiter,
+    open_destinations: Arc<Mutex<OpenDestinations>>,
+}
+
+impl PaymailHost {
+    /// Initializes the host for the aliases `paymail` issues.
+    pub fn new(
+        paymail: PaymailManager,
+        wallet: WalletManager,
+        storage: Arc<ZipStorage>,
+        base_url: &str,
+    ) -> Self {
+        Self {
+            paymail,
+            wallet,
+            invoices: Invoices::new(Arc::clone(&storage)),
+            storage,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            trusted_proxies: vec![],
+            ip_limiter: RateLimiter::new(REQUESTS_PER_IP, 60),
+            alias_limiter: RateLimiter::new(REQUESTS_PER_ALIAS, 60),
+            receive_limiter: RateLimiter::new(SUBMISSIONS_PER_ALIAS, 60),
+            open_destinations: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Trusts `X-Forwarded-For` on requests relayed by these reverse proxies.
+    pub fn with_trusted_proxies(mut self, proxies: Vec<IpAddr>) -> Self {
+        self.trusted_proxies = proxies;
+        self
+    }
+
+    /// Routes for `.well-known/bsvalias` and the capability endpoints it advertises.
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/.well-known/bsvalias", get(capabilities))
+            .route("/api/v1/bsvalias/id/:handle", get(pki))
+            .route(
+                "/api/v1/bsvalias/public-profile/:handle",
+                get(public_profile),
+            )
+            .route(
+                "/api/v1/bsvalias/address/:handle",
+                post(payment_destination),
+            )
+            .route(
+                "/api/v1/bsvalias/p2p-payment-destination/:handle",
+                post(p2p_payment_destination),
+            )
+            .route(
+                "/api/v1/bsvalias/receive-transaction/:handle",
+                post(receive_transaction),
+            )
+            .route("/api/v1/bsvalias/beef/:handle", post(receive_transaction))
+            .with_state(self.clone())
+    }
+
+    /// Serves the host on all interfaces and returns the bound address.
+    pub async fn serve(&self, port: u16) -> Result<SocketAddr, ZipError> {
+        let listener = tokio::net::TcpListener::bind(("0.0.0.0", port))
+            .await
+            .map_err(|e| ZipError::Config(e.to_string()))?;
+        let addr = listener
+            .local_addr()
+            .map_err(|e| ZipError::Config(e.to_string()))?;
+        let router = self.router();
+        tokio::spawn(async move {
+            let _ = axum::serve(
+                listener,
+                router.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .await;
+        });
+        Ok(addr)
+    }
+
+    /// Capability document advertising this host's endpoints.
+    pub fn capabilities(&self) -> Value {
+        let endpoint = |path: &str| {
+            format!(
+                "{}/api/v1/bsvalias/{}/{{alias}}@{{domain.tld}}",
+                self.base_url, path
+            )
+        };
+        json!({
+            "bsvalias": "1.0",
+            "capabilities": {
+                "pki": endpoint("id"),
+                "paymentDestination": endpoint("address"),
+                PUBLIC_PROFILE_BRFC: endpoint("public-profile"),
+                P2P_DESTINATION_BRFC: endpoint("p2p-payment-destination"),
+                P2P_RECEIVE_BRFC: endpoint("receive-transaction"),
+                P2P_BEEF_BRFC: endpoint("beef"),
+                SENDER_VALIDATION_BRFC: false,
+            }
+        })
+    }
+
+    /// Destinations issued for a user's aliases, newest first.
+    pub fn destinations(&self, user_id: Uuid) -> Result<Vec<PaymentDestination>, ZipError> {
+        self.migrate_destinations(user_id)?;
+        let mut destinations: Vec<PaymentDestination> = self
+            .storage
+            .get_user_paymail_destinations(user_id)?
+            .iter()
+            .filter_map(|d| PaymentDestination::decode(d).ok())
+            .collect();
+        destinations.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        Ok(destinations)
+    }
+
+    /// Destination issued to a user under `reference`.
+    fn destination(
+        &self,
+        user_id: Uuid,
+        reference: &str,
+    ) -> Result<Option<PaymentDestination>, ZipError> {
+        self.migrate_destinations(user_id)?;
+        Ok(self
+            .storage
+            .get_paymail_destination(user_id, reference)?
+            .and_then(|d| PaymentDestination::decode(&d).ok()))
+    }
+
+    fn store_destination(
+        &self,
+        user_id: Uuid,
+        destination: &PaymentDestination,
+    ) -> Result<(), ZipError> {
+        let serialized =
+            bincode::serialize(destination).map_err(|e| ZipError::Blockchain(e.to_string()))?;
+        self.storage
+            .store_paymail_destination(user_id, &destination.reference, &serialized)
+    }
+
+    /// Moves destinations stored as one list per user to one entry per reference.
+    fn migrate_destinations(&self, user_id: Uuid) -> Result<(), ZipError> {
+        let Some(data) = self.storage.get_legacy_paymail_destinations(user_id)? else {
+            return Ok(());
+        };
+        let destinations: Vec<PaymentDestination> =
+            bincode::deserialize::<Vec<PaymentDestinationV1>>(&data)
+                .map(|list| list.into_iter().map(PaymentDestination::from).collect())
+                .unwrap_or_default();
+        for destination in &destinations {
+            self.store_destination(user_id, destination)?;
+        }
+        self.storage.remove_legacy_paymail_destinations(user_id)
+    }
+
+    /// Drops unpaid destinations past `DESTINATION_TTL_SECS`; paid ones are kept.
+    fn prune_destinations(&self, user_id: Uuid, now: i64) -> Result<(), ZipError> {
+        for destination in self.destinations(user_id)? {
+            if destination.is_expired(now) {
+                self.storage
+                    .remove_paymail_destination(user_id, &destination.reference)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether a destination issued to a client is still open: neither paid nor expired.
+    fn is_open(&self, user_id: Uuid, reference: &str, now: i64) -> bool {
+        self.destination(user_id, reference)
+            .ok()
+            .flatten()
+            .is_some_and(|d| d.txid.is_none() && !d.is_expired(now))
+    }
+
+    /// Applies the per-client limit and the alias's limit in `alias_limiter`.
+    async fn throttle(
+        &self,
+        client: &str,
+        handle: &str,
+        alias_limiter: &RateLimiter,
+    ) -> Result<(), ZipError> {
+        self.ip_limiter.check(client).await?;
+        alias_limiter.check(&handle.trim().to_lowercase()).await
+    }
+
+    /// Client a request is counted against: the connecting peer, or the last
+    /// `X-Forwarded-For` hop when the peer is a trusted proxy that appended it.
+    fn client_address(&self, headers: &HeaderMap, peer: Option<ConnectInfo<SocketAddr>>) -> String {
+        let Some(ConnectInfo(peer)) = peer else {
+            return "unknown".to_string();
+        };
+        if !self.trusted_proxies.contains(&peer.ip()) {
+            return peer.ip().to_string();
+        }
+        headers
+            .get("x-forwarded-for")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.rsplit(',').next())
+            .map(|hop| hop.trim().to_string())
+            .filter(|hop| !hop.is_empty())
+            .unwrap_or_else(|| peer.ip().to_string())
+    }
+
+    /// Owner of a handle on this domain; unknown handles are rejected.
+    fn owner(&self, handle: &str) -> Result<(String, Uuid), ZipError> {
+        let handle = handle.trim().to_lowercase();
+        let domain = handle
+            .split_once('@')
+            .map(|(_, domain)| domain)
+            .unwrap_or_default();
+        if domain != self.paymail.domain().to_lowercase() {
+            return Err(ZipError::Validation(format!("Unknown PayMail: {}", handle)));
+        }
+        match self.paymail.alias_owner(&handle)? {
+            Some(user_id) => Ok((handle, user_id)),
+            None => Err(ZipError::Validation(format!("Unknown PayMail: {}", handle))),
+        }
+    }
+
+    /// Derives a fresh address for a payment to `handle` and records it under a new
+    /// reference. With a BRC-29 `sender` the address is derived between the sender's
+    /// identity key and ours, using the reference as derivation prefix. Naming an open
+    /// invoice for the alias links the destination to it, and its outstanding amount
+    /// replaces the sender's `satoshis`. A `client` holding `MAX_OPEN_DESTINATIONS`
+    /// open destinations is refused.
+    pub fn issue_destination(
+        &self,
+        client: &str,
+        handle: &str,
+        satoshis: u64,
+        sender: Option<&PublicKey>,
+        invoice: Option<&str>,
+    ) -> Result<PaymentDestination, ZipError> {
+        let (alias, user_id) = self.owner(handle)?;
+        let invoice = match invoice {
+            Some(reference) => Some(self.open_invoice(user_id, &alias, reference)?),
+            None => None,
+        };
+        let now = chrono::Utc::now().timestamp();
+        let mut open_destinations = self.open_destinations.lock();
+        open_destinations.retain(|_, issued| {
+            issued.retain(|(_, _, issued_at)| now - issued_at <= DESTINATION_TTL_SECS);
+            !issued.is_empty()
+        });
+        let issued = open_destinations.entry(client.to_string()).or_default();
+        issued.retain(|(user_id, reference, _)| self.is_open(*user_id, reference, now));
+        if issued.len() >= MAX_OPEN_DESTINATIONS {
+            return Err(ZipError::RateLimit(format!(
+                "{} has too many unpaid payment destinations",
+                client
+            )));
+        }
+        let reference = Uuid::new_v4().simple().to_string();
+        let receive = match sender {
+            Some(sender) => self.wallet.accept_private_payment(
+                user_id,
+                sender,
+                &reference,
+                BRC29_DERIVATION_SUFFIX,
+            )?,
+            None => self.wallet.invoice_address()?,
+        };
+        let destination = PaymentDestination {
+            reference,
+            alias,
+            address: receive.address,
+            derivation_path: receive.derivation_path,
+            satoshis: invoice.as_ref().map_or(satoshis, Invoice::outstanding),
+            created_at: now,
+            txid: None,
+            invoice: invoice.map(|i| i.reference),
+        };
+        self.store_destination(user_id, &destination)?;
+        issued.push((user_id, destination.reference.clone(), now));
+        self.prune_destinations(user_id, now)?;
+        Ok(destination)
+    }
+
+    /// Open invoice of a user that asks to be paid to `alias`.
+    fn open_invoice(
+        &self,
+        user_id: Uuid,
+        alias: &str,
+        reference: &str,
+    ) -> Result<Invoice, ZipError> {
+        self.invoices
+            .list(user_id)?
+            .into_iter()
+            .find(|invoice| {
+                invoice.reference.eq_ignore_ascii_case(reference)
+                    && matches!(
+                        invoice.status,
+                        InvoiceStatus::Open | InvoiceStatus::PartiallyPaid
+                    )
+                    && matches!(
+                        &invoice.destination,
+                        InvoiceDestination::Paymail { handle } if handle.eq_ignore_ascii_case(alias)
+                    )
+            })
+            .ok_or_else(|| ZipError::Validation(format!("Unknown invoice: {}", reference)))
+    }
+
+    /// Accepts a transaction (raw hex or BEEF) a P2P sender submits for a reference we
+    /// issued: it must pay the destination's address, and at least the outstanding
+    /// amount when the destination was issued for an invoice. The wallet verifies and
+    /// records it, then that invoice is marked paid.
+    pub async fn accept_transaction(
+        &self,
+        handle: &str,
+        tx_hex: &str,
+        reference: &str,
+        metadata: &Value,
+    ) -> Result<String, ZipError> {
+        let (alias, user_id) = self.owner(handle)?;
+        let tx = TransactionManager::from_hex(tx_hex)?;
+        let txid = TransactionManager::txid(&tx);
+        let mut destination = self
+            .destination(user_id, reference)?
+            .filter(|d| d.alias == alias)
+            .filter(|d| !d.is_expired(chrono::Utc::now().timestamp()))
+            .ok_or_else(|| ZipError::Validation(format!("Unknown reference: {}", reference)))?;
+        match &destination.txid {
+            Some(paid) if *paid == txid => return Ok(txid),
+            Some(paid) => {
+                return Err(ZipError::Blockchain(format!(
+                    "Reference {} was already paid by {}",
+                    reference, paid
+                )));
+            }
+            None => {}
+        }
+        let script = WalletManager::address_script(&destination.address)?;
+        let paid: u64 = tx
+            .outputs
+            .iter()
+            .filter(|output| output.script.0 == script.0)
+            .map(|output| output.value)
+            .sum();
+        // Only invoice amounts are ours; a sender's own announcement binds nothing
+        let due = match destination.invoice {
+            Some(_) => destination.satoshis,
+            None => 0,
+        };
+        if paid == 0 || paid < due {
+            return Err(ZipError::Blockchain(format!(
+                "Transaction {} pays {} of {} satoshis due for reference {}",
+                txid, paid, due, reference
+            )));
+        }
+        let sender = metadata["sender"]
+            .as_str()
+            .unwrap_or("Unknown sender")
+            .to_string();
+        let note = metadata["note"].as_str().filter(|note| !note.is_empty());
+        self.wallet
+            .receive_transaction(
+                user_id,
+                tx_hex,
+                &destination.address,
+                sender,
+                note.map(str::to_string),
+            )
+            .await?;
+        destination.txid = Some(txid.clone());
+        self.store_destination(user_id, &destination)?;
+        if let Some(invoice) = &destination.invoice {
+            let payment = InvoicePayment {
+                txid: txid.clone(),
+                amount: paid,
+                received_at: chrono::Utc::now().timestamp(),
+            };
+            self.invoices.record_payment(user_id, invoice, payment)?;
+        }
+        Ok(txid)
+    }
+}
+
+type Reply = (StatusCode, Json<Value>);
+
+fn reply(result: Result<Value, ZipError>) -> Reply {
+    match result {
+        Ok(value) => (StatusCode::OK, Json(value)),
+        Err(e @ ZipError::Validation(_)) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "message": e.to_string() })),
+        ),
+        Err(e @ ZipError::RateLimit(_)) => (
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(json!({ "message": e.to_string() })),
+        ),
+        // Transactions that fail verification or are rejected by the network
+        Err(e @ ZipError::Blockchain(_)) => (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "message": e.to_string() })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "message": e.to_string() })),
+        ),
+    }
+}
+
+async fn capabilities(State(host): State<PaymailHost>) -> Json<Value> {
+    Json(host.capabilities())
+}
+
+async fn pki(State(host): State<PaymailHost>, Path(handle): Path<String>) -> Reply {
+    reply(host.owner(&handle).and_then(|(alias, _)| {
+        let pubkey = host.wallet.identity_public_key()?;
+        Ok(json!({
+            "bsvalias": "1.0",
+            "handle": alias,
+            "pubkey": hex::encode(pubkey.to_bytes()),
+        }))
+    }))
+}
+
+async fn public_profile(State(host): State<PaymailHost>, Path(handle): Path<String>) -> Reply {
+    reply(host.owner(&handle).and_then(|(alias, _)| {
+        let profile = host.paymail.alias_profile(&alias)?;
+        Ok(json!({ "name": profile.name, "avatar": profile.avatar }))
+    }))
+}
+
+async fn payment_destination(
+    State(host): State<PaymailHost>,
+    Path(handle): Path<String>,
+    peer: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
+    body: String,
+) -> Reply {
+    let client = host.client_address(&headers, peer);
+    if let Err(e) = host.throttle(&client, &handle, &host.alias_limiter).await {
+        return reply(Err(e));
+    }
+    let request: DestinationRequest = serde_json::from_str(&body).unwrap_or_default();
+    reply(
+        host.issue_destination(
+            &client,
+            &handle,
+            request.amount,
+            None,
+            request.invoice.as_deref(),
+        )
+        .and_then(|destination| {
+            let script = WalletManager::address_script(&destination.address)?;
+            Ok(json!({ "output": hex::encode(&script.0) }))
+        }),
+    )
+}
+
+async fn p2p_payment_destination(
+    State(host): State<PaymailHost>,
+    Path(handle): Path<String>,
+    peer: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
+    body: String,
+) -> Reply {
+    let client = host.client_address(&headers, peer);
+    if let Err(e) = host.throttle(&client, &handle, &host.alias_limiter).await {
+        return reply(Err(e));
+    }
+    let request: DestinationRequest = serde_json::from_str(&body).unwrap_or_default();
+    let sender = match request.sender_identity_key.as_deref().map(|key| {
+        hex::decode(key)
+            .ok()
+            .and_then(|key| PublicKey::from_slice(&key).ok())
+            .ok_or_else(|| ZipError::Blockchain("Invalid sender identity key".to_string()))
+    }) {
+        Some(Ok(sender)) => Some(sender),
+        Some(Err(e)) => return reply(Err(e)),
+        None => None,
+    };
+    reply(
+        host.issue_destination(
+            &client,
+            &handle,
+            request.satoshis,
+            sender.as_ref(),
+            request.invoice.as_deref(),
+        )
+        .and_then(|destination| {
+            let script = WalletManager::address_script(&destination.address)?;
+            let mut response = json!({
+                "outputs": [{
+                    "script": hex::encode(&script.0),
+                    "satoshis": destination.satoshis,
+                }],
+                "reference": destination.reference,
+            });
+            // BRC-29 senders check the script against our PKI key with these
+            if sender.is_some() {
+                response["derivationPrefix"] = json!(destination.reference);
+                response["derivationSuffix"] = json!(BRC29_DERIVATION_SUFFIX);
+            }
+            Ok(response)
+        }),
+    )
+}
+
+async fn receive_transaction(
+    State(host): State<PaymailHost>,
+    Path(handle): Path<String>,
+    peer: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
+    body: String,
+) -> Reply {
+    let client = host.client_address(&headers, peer);
+    if let Err(e) = host.throttle(&client, &handle, &host.receive_limiter).await {
+        return reply(Err(e));
+    }
+    let request: ReceiveRequest = match serde_json::from_str(&body) {
+        Ok(request) => request,
+        Err(e) => return reply(Err(ZipError::Blockchain(e.to_string()))),
+    };
+    let Some(tx_hex) = request.beef.as_ref().or(request.hex.as_ref()) else {
+        return reply(Err(ZipError::Blockchain(
+            "Missing transaction hex or BEEF".to_string(),
+        )));
+    };
+    reply(
+        host.accept_transaction(&handle, tx_hex, &request.reference, &request.metadata)
+            .await
+            .map(|txid| json!({ "txid": txid, "note": "Payment received" })),
+    )
+}
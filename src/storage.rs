@@ -1,11 +1,24 @@
 use keyring::Entry;
 use secrecy::{ExposeSecret, Secret};
 use sled::Db;
+use sled::transaction::{
+    ConflictableTransactionResult, TransactionError, TransactionResult, TransactionalTree,
+};
 use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::errors::ZipError;
 
+/// Outcome of `ZipStorage::claim_alias`.
+#[derive(Debug, PartialEq)]
+pub enum AliasClaim {
+    Claimed,
+    /// Someone already owns the alias.
+    Taken,
+    /// Another user owns this alias, which shares the claimed alias's skeleton.
+    Lookalike(String),
+}
+
 #[derive(Clone)]
 pub struct ZipStorage {
     db: Arc<Db>,   // Embedded Sled KV store
@@ -60,4 +73,938 @@ impl ZipStorage {
         let key = format!("utxo:{}", user_id);
         Ok(self.db.get(key.as_bytes())?)
     }
+
+    /// Stores a user's spending policy.
+    pub fn store_policy(&self, user_id: Uuid, policy: &[u8]) -> Result<(), ZipError> {
+        let key = format!("policy:{}", user_id);
+        self.db.insert(key.as_bytes(), policy)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Retrieves a user's spending policy.
+    pub fn get_policy(&self, user_id: Uuid) -> Result<Option<sled::IVec>, ZipError> {
+        let key = format!("policy:{}", user_id);
+        Ok(self.db.get(key.as_bytes())?)
+    }
+
+    /// Adds to the satoshis spent on a given day (YYYY-MM-DD) and returns the new total.
+    pub fn add_daily_spend(&self, user_id: Uuid, day: &str, amount: u64) -> Result<u64, ZipError> {
+        let key = format!("spent:{}:{}", user_id, day);
+        let updated = self.db.update_and_fetch(key.as_bytes(), |old| {
+            let current = old
+                .and_then(|b| b.try_into().ok())
+                .map(u64::from_le_bytes)
+                .unwrap_or(0);
+            Some(current.saturating_add(amount).to_le_bytes().to_vec())
+        })?;
+        self.db.flush()?;
+        Ok(updated
+            .and_then(|b| b.as_ref().try_into().ok())
+            .map(u64::from_le_bytes)
+            .unwrap_or(0))
+    }
+
+    /// Retrieves the satoshis spent on a given day (YYYY-MM-DD).
+    pub fn get_daily_spend(&self, user_id: Uuid, day: &str) -> Result<u64, ZipError> {
+        let key = format!("spent:{}:{}", user_id, day);
+        Ok(self
+            .db
+            .get(key.as_bytes())?
+            .and_then(|b| b.as_ref().try_into().ok())
+            .map(u64::from_le_bytes)
+            .unwrap_or(0))
+    }
+
+    /// Stores a user's transaction history index.
+    pub fn store_history(&self, user_id: Uuid, data: &[u8]) -> Result<(), ZipError> {
+        let key = format!("history:{}", user_id);
+        self.db.insert(key.as_bytes(), data)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Retrieves a user's transaction history index.
+    pub fn get_history(&self, user_id: Uuid) -> Result<Option<sled::IVec>, ZipError> {
+        let key = format!("history:{}", user_id);
+        Ok(self.db.get(key.as_bytes())?)
+    }
+
+    /// Stores a user's pending wallet alerts.
+    pub fn store_alerts(&self, user_id: Uuid, data: &[u8]) -> Result<(), ZipError> {
+        let key = format!("alerts:{}", user_id);
+        self.db.insert(key.as_bytes(), data)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Retrieves a user's pending wallet alerts.
+    pub fn get_alerts(&self, user_id: Uuid) -> Result<Option<sled::IVec>, ZipError> {
+        let key = format!("alerts:{}", user_id);
+        Ok(self.db.get(key.as_bytes())?)
+    }
+
+    /// Removes a user's pending wallet alerts.
+    pub fn remove_alerts(&self, user_id: Uuid) -> Result<(), ZipError> {
+        let key = format!("alerts:{}", user_id);
+        self.db.remove(key.as_bytes())?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Stores UTXOs reserved by in-flight transactions.
+    pub fn store_reservations(&self, user_id: Uuid, data: &[u8]) -> Result<(), ZipError> {
+        let key = format!("reserved:{}", user_id);
+        self.db.insert(key.as_bytes(), data)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Retrieves UTXOs reserved by in-flight transactions.
+    pub fn get_reservations(&self, user_id: Uuid) -> Result<Option<sled::IVec>, ZipError> {
+        let key = format!("reserved:{}", user_id);
+        Ok(self.db.get(key.as_bytes())?)
+    }
+
+    /// Stores the coins held under an escrow (e.g., an auction bid).
+    pub fn store_escrow(
+        &self,
+        user_id: Uuid,
+        escrow_id: &str,
+        data: &[u8],
+    ) -> Result<(), ZipError> {
+        let key = format!("escrow:{}:{}", user_id, escrow_id);
+        self.db.insert(key.as_bytes(), data)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Retrieves the coins held under an escrow.
+    pub fn get_escrow(
+        &self,
+        user_id: Uuid,
+        escrow_id: &str,
+    ) -> Result<Option<sled::IVec>, ZipError> {
+        let key = format!("escrow:{}:{}", user_id, escrow_id);
+        Ok(self.db.get(key.as_bytes())?)
+    }
+
+    /// Retrieves every escrow a user holds, keyed by escrow ID.
+    pub fn get_escrows(&self, user_id: Uuid) -> Result<Vec<(String, sled::IVec)>, ZipError> {
+        let prefix = format!("escrow:{}:", user_id);
+        self.db
+            .scan_prefix(prefix.as_bytes())
+            .map(|entry| {
+                let (key, value) = entry?;
+                Ok((
+                    String::from_utf8_lossy(&key[prefix.len()..]).to_string(),
+                    value,
+                ))
+            })
+            .collect()
+    }
+
+    /// Removes an escrow.
+    pub fn remove_escrow(&self, user_id: Uuid, escrow_id: &str) -> Result<(), ZipError> {
+        let key = format!("escrow:{}:{}", user_id, escrow_id);
+        self.db.remove(key.as_bytes())?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Stores outpoints known to be spent, keyed to the spending TXID.
+    pub fn store_spent_outpoints(&self, user_id: Uuid, data: &[u8]) -> Result<(), ZipError> {
+        let key = format!("spent_outpoints:{}", user_id);
+        self.db.insert(key.as_bytes(), data)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Retrieves outpoints known to be spent.
+    pub fn get_spent_outpoints(&self, user_id: Uuid) -> Result<Option<sled::IVec>, ZipError> {
+        let key = format!("spent_outpoints:{}", user_id);
+        Ok(self.db.get(key.as_bytes())?)
+    }
+
+    /// Stores receipts a reorg left unconfirmed, as (TXID, satoshis).
+    pub fn store_reorged_receipts(&self, user_id: Uuid, data: &[u8]) -> Result<(), ZipError> {
+        let key = format!("reorged_receipts:{}", user_id);
+        self.db.insert(key.as_bytes(), data)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Retrieves receipts a reorg left unconfirmed.
+    pub fn get_reorged_receipts(&self, user_id: Uuid) -> Result<Option<sled::IVec>, ZipError> {
+        let key = format!("reorged_receipts:{}", user_id);
+        Ok(self.db.get(key.as_bytes())?)
+    }
+
+    /// Stores a block header at a height on the active chain.
+    pub fn store_header(&self, height: u64, header: &[u8]) -> Result<(), ZipError> {
+        let key = format!("header:{}", height);
+        self.db.insert(key.as_bytes(), header)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Retrieves the block header at a height on the active chain.
+    pub fn get_header(&self, height: u64) -> Result<Option<sled::IVec>, ZipError> {
+        let key = format!("header:{}", height);
+        Ok(self.db.get(key.as_bytes())?)
+    }
+
+    /// Removes a block header disconnected by a reorg.
+    pub fn remove_header(&self, height: u64) -> Result<(), ZipError> {
+        let key = format!("header:{}", height);
+        self.db.remove(key.as_bytes())?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Stores the height of the active chain tip.
+    pub fn store_header_tip(&self, height: u64) -> Result<(), ZipError> {
+        self.db.insert(b"header_tip", &height.to_le_bytes())?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Retrieves the height of the active chain tip, if any headers are stored.
+    pub fn get_header_tip(&self) -> Result<Option<u64>, ZipError> {
+        Ok(self
+            .db
+            .get(b"header_tip")?
+            .and_then(|b| b.as_ref().try_into().ok())
+            .map(u64::from_le_bytes))
+    }
+
+    /// Stores Merkle proofs for a user's confirmed transactions.
+    pub fn store_proofs(&self, user_id: Uuid, data: &[u8]) -> Result<(), ZipError> {
+        let key = format!("proofs:{}", user_id);
+        self.db.insert(key.as_bytes(), data)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Retrieves Merkle proofs for a user's confirmed transactions.
+    pub fn get_proofs(&self, user_id: Uuid) -> Result<Option<sled::IVec>, ZipError> {
+        let key = format!("proofs:{}", user_id);
+        Ok(self.db.get(key.as_bytes())?)
+    }
+
+    /// Stores a user's time-locked transactions awaiting broadcast.
+    pub fn store_timelocks(&self, user_id: Uuid, data: &[u8]) -> Result<(), ZipError> {
+        let key = format!("timelocks:{}", user_id);
+        self.db.insert(key.as_bytes(), data)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Retrieves a user's time-locked transactions awaiting broadcast.
+    pub fn get_timelocks(&self, user_id: Uuid) -> Result<Option<sled::IVec>, ZipError> {
+        let key = format!("timelocks:{}", user_id);
+        Ok(self.db.get(key.as_bytes())?)
+    }
+
+    /// Stores a user's recurring payment schedules.
+    pub fn store_schedules(&self, user_id: Uuid, data: &[u8]) -> Result<(), ZipError> {
+        let key = format!("schedules:{}", user_id);
+        self.db.insert(key.as_bytes(), data)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Retrieves a user's recurring payment schedules.
+    pub fn get_schedules(&self, user_id: Uuid) -> Result<Option<sled::IVec>, ZipError> {
+        let key = format!("schedules:{}", user_id);
+        Ok(self.db.get(key.as_bytes())?)
+    }
+
+    /// Lists users that have stored payment schedules.
+    pub fn get_schedule_users(&self) -> Result<Vec<Uuid>, ZipError> {
+        let prefix = "schedules:";
+        Ok(self
+            .db
+            .scan_prefix(prefix.as_bytes())
+            .keys()
+            .filter_map(|key| {
+                let key = key.ok()?;
+                Uuid::parse_str(&String::from_utf8_lossy(&key[prefix.len()..])).ok()
+            })
+            .collect())
+    }
+
+    /// Stores the run log for a user's scheduled payments.
+    pub fn store_schedule_log(&self, user_id: Uuid, data: &[u8]) -> Result<(), ZipError> {
+        let key = format!("schedule_log:{}", user_id);
+        self.db.insert(key.as_bytes(), data)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Retrieves the run log for a user's scheduled payments.
+    pub fn get_schedule_log(&self, user_id: Uuid) -> Result<Option<sled::IVec>, ZipError> {
+        let key = format!("schedule_log:{}", user_id);
+        Ok(self.db.get(key.as_bytes())?)
+    }
+
+    /// Stores addresses handed out for receiving funds.
+    pub fn store_receive_addresses(&self, user_id: Uuid, data: &[u8]) -> Result<(), ZipError> {
+        let key = format!("addresses:{}", user_id);
+        self.db.insert(key.as_bytes(), data)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Retrieves addresses handed out for receiving funds.
+    pub fn get_receive_addresses(&self, user_id: Uuid) -> Result<Option<sled::IVec>, ZipError> {
+        let key = format!("addresses:{}", user_id);
+        Ok(self.db.get(key.as_bytes())?)
+    }
+
+    /// Stores invoices issued by a user.
+    pub fn store_invoices(&self, user_id: Uuid, data: &[u8]) -> Result<(), ZipError> {
+        let key = format!("invoices:{}", user_id);
+        self.db.insert(key.as_bytes(), data)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Retrieves invoices issued by a user.
+    pub fn get_invoices(&self, user_id: Uuid) -> Result<Option<sled::IVec>, ZipError> {
+        let key = format!("invoices:{}", user_id);
+        Ok(self.db.get(key.as_bytes())?)
+    }
+
+    /// Stores document notarisation receipts.
+    pub fn store_notary_receipts(&self, user_id: Uuid, data: &[u8]) -> Result<(), ZipError> {
+        let key = format!("notary:{}", user_id);
+        self.db.insert(key.as_bytes(), data)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Retrieves document notarisation receipts.
+    pub fn get_notary_receipts(&self, user_id: Uuid) -> Result<Option<sled::IVec>, ZipError> {
+        let key = format!("notary:{}", user_id);
+        Ok(self.db.get(key.as_bytes())?)
+    }
+
+    /// Records which user is signed in on this device; `None` after sign-out.
+    pub fn store_session_user(&self, user_id: Option<Uuid>) -> Result<(), ZipError> {
+        match user_id {
+            Some(user_id) => self.db.insert("session_user", user_id.as_bytes())?,
+            None => self.db.remove("session_user")?,
+        };
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// User signed in on this device, if any.
+    pub fn get_session_user(&self) -> Result<Option<Uuid>, ZipError> {
+        Ok(self
+            .db
+            .get("session_user")?
+            .and_then(|b| Uuid::from_slice(&b).ok()))
+    }
+
+    /// Stores permissions granted to third-party apps.
+    pub fn store_app_permissions(&self, user_id: Uuid, data: &[u8]) -> Result<(), ZipError> {
+        let key = format!("app_permissions:{}", user_id);
+        self.db.insert(key.as_bytes(), data)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Retrieves permissions granted to third-party apps.
+    pub fn get_app_permissions(&self, user_id: Uuid) -> Result<Option<sled::IVec>, ZipError> {
+        let key = format!("app_permissions:{}", user_id);
+        Ok(self.db.get(key.as_bytes())?)
+    }
+
+    /// Atomically moves an alias from owner `from` to owner `to` (`None` meaning
+    /// unowned), keeping the per-user alias index in step and appending `event` to the
+    /// alias's ownership history. Returns false, changing nothing, when `from` is not
+    /// the current owner.
+    pub fn swap_alias_owner(
+        &self,
+        alias: &str,
+        from: Option<Uuid>,
+        to: Option<Uuid>,
+        event: &[u8],
+    ) -> Result<bool, ZipError> {
+        let alias = alias.to_lowercase();
+        let result: TransactionResult<bool, ZipError> = self
+            .db
+            .transaction(|tx| Self::swap_owner_in(tx, &alias, from, to, event));
+        let swapped = result.map_err(Self::transaction_error)?;
+        self.db.flush()?;
+        Ok(swapped)
+    }
+
+    /// Ownership swap of `swap_alias_owner` within an open transaction.
+    fn swap_owner_in(
+        tx: &TransactionalTree,
+        alias: &str,
+        from: Option<Uuid>,
+        to: Option<Uuid>,
+        event: &[u8],
+    ) -> ConflictableTransactionResult<bool, ZipError> {
+        let owner_key = format!("alias_owner:{}", alias);
+        let current = tx
+            .get(owner_key.as_bytes())?
+            .and_then(|id| Uuid::from_slice(&id).ok());
+        if current != from {
+            return Ok(false);
+        }
+        if let Some(previous) = from {
+            tx.remove(format!("user_alias:{}:{}", previous, alias).as_bytes())?;
+        }
+        match to {
+            Some(owner) => {
+                tx.insert(owner_key.as_bytes(), owner.as_bytes())?;
+                tx.insert(
+                    format!("user_alias:{}:{}", owner, alias).as_bytes(),
+                    sled::IVec::default(),
+                )?;
+            }
+            None => {
+                tx.remove(owner_key.as_bytes())?;
+            }
+        }
+        let history_key = format!("alias_history:{}:{:020}", alias, tx.generate_id()?);
+        tx.insert(history_key.as_bytes(), event)?;
+        Ok(true)
+    }
+
+    /// Atomically registers an unowned alias to `owner` unless another user owns an
+    /// alias with the same `skeleton` (the alias with look-alike characters folded, see
+    /// `Validation::confusable_skeleton`). Aliases are indexed by skeleton so the check
+    /// is a single lookup within the claiming transaction; index entries of aliases
+    /// released since are dropped as they are met.
+    pub fn claim_alias(
+        &self,
+        alias: &str,
+        skeleton: &str,
+        owner: Uuid,
+        event: &[u8],
+    ) -> Result<AliasClaim, ZipError> {
+        let alias = alias.to_lowercase();
+        let skeleton_key = format!("alias_skeleton:{}", skeleton);
+        let result: TransactionResult<AliasClaim, ZipError> = self.db.transaction(|tx| {
+            let mut holders = vec![];
+            for existing in Self::skeleton_holders(tx.get(skeleton_key.as_bytes())?) {
+                if existing == alias {
+                    continue;
+                }
+                let holder = tx
+                    .get(format!("alias_owner:{}", existing).as_bytes())?
+                    .and_then(|id| Uuid::from_slice(&id).ok());
+                match holder {
+                    None => {}
+                    Some(holder) if holder == owner => holders.push(existing),
+                    Some(_) => return Ok(AliasClaim::Lookalike(existing)),
+                }
+            }
+            if !Self::swap_owner_in(tx, &alias, None, Some(owner), event)? {
+                return Ok(AliasClaim::Taken);
+            }
+            holders.push(alias.clone());
+            tx.insert(skeleton_key.as_bytes(), holders.join("\n").as_bytes())?;
+            Ok(AliasClaim::Claimed)
+        });
+        let claim = result.map_err(Self::transaction_error)?;
+        self.db.flush()?;
+        Ok(claim)
+    }
+
+    /// Aliases recorded under a skeleton; entries are newline-separated, which no
+    /// alias can contain.
+    fn skeleton_holders(entry: Option<sled::IVec>) -> Vec<String> {
+        entry
+            .map(|holders| {
+                String::from_utf8_lossy(&holders)
+                    .lines()
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Aliases recorded under a skeleton, including any released since.
+    pub fn get_alias_skeleton(&self, skeleton: &str) -> Result<Vec<String>, ZipError> {
+        let key = format!("alias_skeleton:{}", skeleton);
+        Ok(Self::skeleton_holders(self.db.get(key.as_bytes())?))
+    }
+
+    /// Adds an owned alias to the skeleton index, for aliases claimed before the index
+    /// existed.
+    pub fn index_alias_skeleton(&self, alias: &str, skeleton: &str) -> Result<(), ZipError> {
+        let alias = alias.to_lowercase();
+        let skeleton_key = format!("alias_skeleton:{}", skeleton);
+        let result: TransactionResult<(), ZipError> = self.db.transaction(|tx| {
+            let mut holders = Self::skeleton_holders(tx.get(skeleton_key.as_bytes())?);
+            if !holders.contains(&alias) {
+                holders.push(alias.clone());
+                tx.insert(skeleton_key.as_bytes(), holders.join("\n").as_bytes())?;
+            }
+            Ok(())
+        });
+        result.map_err(Self::transaction_error)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Whether aliases owned before the skeleton index existed have been indexed.
+    pub fn alias_skeletons_indexed(&self) -> Result<bool, ZipError> {
+        Ok(self.db.contains_key("alias_skeletons_indexed")?)
+    }
+
+    /// Records that owned aliases have been added to the skeleton index.
+    pub fn set_alias_skeletons_indexed(&self) -> Result<(), ZipError> {
+        self.db
+            .insert("alias_skeletons_indexed", sled::IVec::default())?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    fn transaction_error(e: TransactionError<ZipError>) -> ZipError {
+        match e {
+            TransactionError::Abort(e) => e,
+            TransactionError::Storage(e) => ZipError::Storage(e),
+        }
+    }
+
+    /// Retrieves the owner of a PayMail alias.
+    pub fn get_alias_owner(&self, alias: &str) -> Result<Option<sled::IVec>, ZipError> {
+        let key = format!("alias_owner:{}", alias.to_lowercase());
+        Ok(self.db.get(key.as_bytes())?)
+    }
+
+    /// Lists every alias that has an owner.
+    pub fn get_owned_aliases(&self) -> Result<Vec<String>, ZipError> {
+        let prefix = "alias_owner:";
+        self.db
+            .scan_prefix(prefix.as_bytes())
+            .keys()
+            .map(|key| Ok(String::from_utf8_lossy(&key?[prefix.len()..]).to_string()))
+            .collect()
+    }
+
+    /// Lists the aliases a user owns.
+    pub fn get_user_alias_list(&self, user_id: Uuid) -> Result<Vec<String>, ZipError> {
+        let prefix = format!("user_alias:{}:", user_id);
+        self.db
+            .scan_prefix(prefix.as_bytes())
+            .keys()
+            .map(|key| Ok(String::from_utf8_lossy(&key?[prefix.len()..]).to_string()))
+            .collect()
+    }
+
+    /// Retrieves an alias's ownership history, oldest first.
+    pub fn get_alias_history(&self, alias: &str) -> Result<Vec<sled::IVec>, ZipError> {
+        let prefix = format!("alias_history:{}:", alias.to_lowercase());
+        self.db
+            .scan_prefix(prefix.as_bytes())
+            .values()
+            .map(|value| Ok(value?))
+            .collect()
+    }
+
+    /// Hands out the next sequential alias number, starting from 101. The counter
+    /// survives restarts so numbers are never reissued.
+    pub fn next_alias_number(&self) -> Result<u64, ZipError> {
+        let updated = self.db.update_and_fetch("alias_counter", |old| {
+            let next = old
+                .and_then(|b| b.try_into().ok())
+                .map(u64::from_le_bytes)
+                .map(|n| n + 1)
+                .unwrap_or(101);
+            Some(next.to_le_bytes().to_vec())
+        })?;
+        self.db.flush()?;
+        Ok(updated
+            .and_then(|b| b.as_ref().try_into().ok())
+            .map(u64::from_le_bytes)
+            .unwrap_or(101))
+    }
+
+    /// Hands out the next HD receive index, starting from 1. The counter survives
+    /// restarts so no address is derived twice.
+    pub fn next_derivation_index(&self) -> Result<u32, ZipError> {
+        let updated = self.db.update_and_fetch("derivation_index", |old| {
+            let next = old
+                .and_then(|b| b.try_into().ok())
+                .map(u32::from_le_bytes)
+                .map(|n| n + 1)
+                .unwrap_or(1);
+            Some(next.to_le_bytes().to_vec())
+        })?;
+        self.db.flush()?;
+        Ok(updated
+            .and_then(|b| b.as_ref().try_into().ok())
+            .map(u32::from_le_bytes)
+            .unwrap_or(1))
+    }
+
+    /// Highest HD receive index handed out so far; 0 before the first.
+    pub fn derivation_index(&self) -> Result<u32, ZipError> {
+        Ok(self
+            .db
+            .get("derivation_index")?
+            .and_then(|b| b.as_ref().try_into().ok())
+            .map(u32::from_le_bytes)
+            .unwrap_or(0))
+    }
+
+    /// Stores a payment destination issued for one of a user's PayMail aliases, keyed
+    /// by its reference.
+    pub fn store_paymail_destination(
+        &self,
+        user_id: Uuid,
+        reference: &str,
+        data: &[u8],
+    ) -> Result<(), ZipError> {
+        let key = format!("paymail_destination:{}:{}", user_id, reference);
+        self.db.insert(key.as_bytes(), data)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Retrieves the payment destination issued under a reference.
+    pub fn get_paymail_destination(
+        &self,
+        user_id: Uuid,
+        reference: &str,
+    ) -> Result<Option<sled::IVec>, ZipError> {
+        let key = format!("paymail_destination:{}:{}", user_id, reference);
+        Ok(self.db.get(key.as_bytes())?)
+    }
+
+    /// Retrieves every payment destination issued for a user's PayMail aliases.
+    pub fn get_user_paymail_destinations(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<sled::IVec>, ZipError> {
+        let prefix = format!("paymail_destination:{}:", user_id);
+        self.db
+            .scan_prefix(prefix.as_bytes())
+            .values()
+            .map(|value| Ok(value?))
+            .collect()
+    }
+
+    /// Removes an expired payment destination.
+    pub fn remove_paymail_destination(
+        &self,
+        user_id: Uuid,
+        reference: &str,
+    ) -> Result<(), ZipError> {
+        let key = format!("paymail_destination:{}:{}", user_id, reference);
+        self.db.remove(key.as_bytes())?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Retrieves the destinations list older versions kept in one entry per user.
+    pub fn get_legacy_paymail_destinations(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Option<sled::IVec>, ZipError> {
+        let key = format!("paymail_destinations:{}", user_id);
+        Ok(self.db.get(key.as_bytes())?)
+    }
+
+    /// Removes a user's legacy destinations list once it has been migrated.
+    pub fn remove_legacy_paymail_destinations(&self, user_id: Uuid) -> Result<(), ZipError> {
+        let key = format!("paymail_destinations:{}", user_id);
+        self.db.remove(key.as_bytes())?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Stores the locked price quote for an alias a user is buying.
+    pub fn store_alias_quote(
+        &self,
+        user_id: Uuid,
+        alias: &str,
+        data: &[u8],
+    ) -> Result<(), ZipError> {
+        let key = format!("alias_quote:{}:{}", user_id, alias.to_lowercase());
+        self.db.insert(key.as_bytes(), data)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Retrieves the locked price quote for an alias a user is buying.
+    pub fn get_alias_quote(
+        &self,
+        user_id: Uuid,
+        alias: &str,
+    ) -> Result<Option<sled::IVec>, ZipError> {
+        let key = format!("alias_quote:{}:{}", user_id, alias.to_lowercase());
+        Ok(self.db.get(key.as_bytes())?)
+    }
+
+    /// Stores the purchase state of a paid alias, leaving the deadline index as it is;
+    /// `update_alias_purchase` keeps both in step.
+    pub fn store_alias_purchase(&self, alias: &str, data: &[u8]) -> Result<(), ZipError> {
+        let key = format!("alias_purchase:{}", alias.to_lowercase());
+        self.db.insert(key.as_bytes(), data)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Retrieves the purchase state of a paid alias.
+    pub fn get_alias_purchase(&self, alias: &str) -> Result<Option<sled::IVec>, ZipError> {
+        let key = format!("alias_purchase:{}", alias.to_lowercase());
+        Ok(self.db.get(key.as_bytes())?)
+    }
+
+    /// Atomically replaces the purchase state of an alias, provided it still reads
+    /// `previous` (`None` meaning no purchase), moving its entry in the deadline index
+    /// from the first of `deadlines` to the second. Returns false, changing nothing,
+    /// when the purchase changed in between.
+    pub fn update_alias_purchase(
+        &self,
+        alias: &str,
+        previous: Option<&[u8]>,
+        data: &[u8],
+        deadlines: (Option<i64>, Option<i64>),
+    ) -> Result<bool, ZipError> {
+        let alias = alias.to_lowercase();
+        let result: TransactionResult<bool, ZipError> = self
+            .db
+            .transaction(|tx| Self::put_purchase_in(tx, &alias, previous, data, deadlines));
+        let updated = result.map_err(Self::transaction_error)?;
+        self.db.flush()?;
+        Ok(updated)
+    }
+
+    /// Like `update_alias_purchase`, but only while `owner` still owns the alias. With
+    /// `release`, the alias also goes back to the pool in the same transaction, the
+    /// event being appended to its ownership history.
+    pub fn settle_alias_purchase(
+        &self,
+        alias: &str,
+        owner: Uuid,
+        previous: &[u8],
+        data: &[u8],
+        deadlines: (Option<i64>, Option<i64>),
+        release: Option<&[u8]>,
+    ) -> Result<bool, ZipError> {
+        let alias = alias.to_lowercase();
+        let owner_key = format!("alias_owner:{}", alias);
+        let purchase_key = format!("alias_purchase:{}", alias);
+        let result: TransactionResult<bool, ZipError> = self.db.transaction(|tx| {
+            let owned = tx
+                .get(owner_key.as_bytes())?
+                .and_then(|id| Uuid::from_slice(&id).ok())
+                == Some(owner);
+            if !owned || tx.get(purchase_key.as_bytes())?.as_deref() != Some(previous) {
+                return Ok(false);
+            }
+            if let Some(event) = release {
+                Self::swap_owner_in(tx, &alias, Some(owner), None, event)?;
+            }
+            Self::put_purchase_in(tx, &alias, Some(previous), data, deadlines)
+        });
+        let settled = result.map_err(Self::transaction_error)?;
+        self.db.flush()?;
+        Ok(settled)
+    }
+
+    fn put_purchase_in(
+        tx: &TransactionalTree,
+        alias: &str,
+        previous: Option<&[u8]>,
+        data: &[u8],
+        (from, to): (Option<i64>, Option<i64>),
+    ) -> ConflictableTransactionResult<bool, ZipError> {
+        let key = format!("alias_purchase:{}", alias);
+        if tx.get(key.as_bytes())?.as_deref() != previous {
+            return Ok(false);
+        }
+        tx.insert(key.as_bytes(), data)?;
+        if from != to {
+            if let Some(deadline) = from {
+                tx.remove(Self::alias_deadline_key(alias, deadline).as_bytes())?;
+            }
+            if let Some(deadline) = to {
+                tx.insert(
+                    Self::alias_deadline_key(alias, deadline).as_bytes(),
+                    sled::IVec::default(),
+                )?;
+            }
+        }
+        Ok(true)
+    }
+
+    fn alias_deadline_key(alias: &str, deadline: i64) -> String {
+        format!("alias_deadline:{:020}:{}", deadline.max(0), alias)
+    }
+
+    /// Adds an alias to the purchase deadline index, e.g. for purchases stored before
+    /// the index existed.
+    pub fn index_alias_deadline(&self, alias: &str, deadline: i64) -> Result<(), ZipError> {
+        let key = Self::alias_deadline_key(&alias.to_lowercase(), deadline);
+        self.db.insert(key.as_bytes(), sled::IVec::default())?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Removes an entry from the purchase deadline index.
+    pub fn remove_alias_deadline(&self, alias: &str, deadline: i64) -> Result<(), ZipError> {
+        let key = Self::alias_deadline_key(&alias.to_lowercase(), deadline);
+        self.db.remove(key.as_bytes())?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Purchases whose deadline is at or before `now`, as (deadline, alias), earliest
+    /// first.
+    pub fn get_due_alias_purchases(&self, now: i64) -> Result<Vec<(i64, String)>, ZipError> {
+        let prefix = "alias_deadline:";
+        // ';' sorts right after ':', so the bound covers every deadline up to `now`
+        let end = format!("{}{:020};", prefix, now.max(0));
+        self.db
+            .range(prefix.as_bytes()..end.as_bytes())
+            .keys()
+            .map(|key| {
+                let key = key?;
+                let entry = String::from_utf8_lossy(&key[prefix.len()..]).to_string();
+                let (deadline, alias) = entry.split_once(':').unwrap_or_default();
+                Ok((deadline.parse().unwrap_or_default(), alias.to_string()))
+            })
+            .collect()
+    }
+
+    /// Whether purchases stored before the deadline index existed have been indexed.
+    pub fn alias_deadlines_indexed(&self) -> Result<bool, ZipError> {
+        Ok(self.db.contains_key("alias_deadlines_indexed")?)
+    }
+
+    /// Records that existing purchases have been added to the deadline index.
+    pub fn set_alias_deadlines_indexed(&self) -> Result<(), ZipError> {
+        self.db
+            .insert("alias_deadlines_indexed", sled::IVec::default())?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Retrieves the purchase state of every paid alias.
+    pub fn get_alias_purchases(&self) -> Result<Vec<sled::IVec>, ZipError> {
+        self.db
+            .scan_prefix("alias_purchase:")
+            .values()
+            .map(|value| Ok(value?))
+            .collect()
+    }
+
+    /// Stores the latest auction of an alias.
+    pub fn store_auction(&self, alias: &str, data: &[u8]) -> Result<(), ZipError> {
+        let key = format!("auction:{}", alias.to_lowercase());
+        self.db.insert(key.as_bytes(), data)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Retrieves the latest auction of an alias.
+    pub fn get_auction(&self, alias: &str) -> Result<Option<sled::IVec>, ZipError> {
+        let key = format!("auction:{}", alias.to_lowercase());
+        Ok(self.db.get(key.as_bytes())?)
+    }
+
+    /// Retrieves every stored auction.
+    pub fn get_auctions(&self) -> Result<Vec<sled::IVec>, ZipError> {
+        self.db
+            .scan_prefix("auction:")
+            .values()
+            .map(|value| Ok(value?))
+            .collect()
+    }
+
+    /// Stores the paid term of a renewable alias.
+    pub fn store_alias_lease(&self, alias: &str, data: &[u8]) -> Result<(), ZipError> {
+        let key = format!("alias_lease:{}", alias.to_lowercase());
+        self.db.insert(key.as_bytes(), data)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Retrieves the paid term of a renewable alias.
+    pub fn get_alias_lease(&self, alias: &str) -> Result<Option<sled::IVec>, ZipError> {
+        let key = format!("alias_lease:{}", alias.to_lowercase());
+        Ok(self.db.get(key.as_bytes())?)
+    }
+
+    /// Retrieves the paid terms of every renewable alias.
+    pub fn get_alias_leases(&self) -> Result<Vec<sled::IVec>, ZipError> {
+        self.db
+            .scan_prefix("alias_lease:")
+            .values()
+            .map(|value| Ok(value?))
+            .collect()
+    }
+
+    /// Removes the paid term of an alias.
+    pub fn remove_alias_lease(&self, alias: &str) -> Result<(), ZipError> {
+        let key = format!("alias_lease:{}", alias.to_lowercase());
+        self.db.remove(key.as_bytes())?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Stores the pending transfer of an alias.
+    pub fn store_alias_transfer(&self, alias: &str, data: &[u8]) -> Result<(), ZipError> {
+        let key = format!("alias_transfer:{}", alias.to_lowercase());
+        self.db.insert(key.as_bytes(), data)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Retrieves the pending transfer of an alias.
+    pub fn get_alias_transfer(&self, alias: &str) -> Result<Option<sled::IVec>, ZipError> {
+        let key = format!("alias_transfer:{}", alias.to_lowercase());
+        Ok(self.db.get(key.as_bytes())?)
+    }
+
+    /// Retrieves every pending alias transfer.
+    pub fn get_alias_transfers(&self) -> Result<Vec<sled::IVec>, ZipError> {
+        self.db
+            .scan_prefix("alias_transfer:")
+            .values()
+            .map(|value| Ok(value?))
+            .collect()
+    }
+
+    /// Removes the pending transfer of an alias.
+    pub fn remove_alias_transfer(&self, alias: &str) -> Result<(), ZipError> {
+        let key = format!("alias_transfer:{}", alias.to_lowercase());
+        self.db.remove(key.as_bytes())?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Stores the public profile a user publishes for their PayMail aliases.
+    pub fn store_public_profile(&self, user_id: Uuid, data: &[u8]) -> Result<(), ZipError> {
+        let key = format!("public_profile:{}", user_id);
+        self.db.insert(key.as_bytes(), data)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Retrieves a user's public profile.
+    pub fn get_public_profile(&self, user_id: Uuid) -> Result<Option<sled::IVec>, ZipError> {
+        let key = format!("public_profile:{}", user_id);
+        Ok(self.db.get(key.as_bytes())?)
+    }
+
+    /// Removes a user's public profile.
+    pub fn remove_public_profile(&self, user_id: Uuid) -> Result<(), ZipError> {
+        let key = format!("public_profile:{}", user_id);
+        self.db.remove(key.as_bytes())?;
+        self.db.flush()?;
+        Ok(())
+    }
 }
@@ -0,0 +1,108 @@
+use dioxus::prelude::*;
+use dioxus_motion::use_animated;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::errors::ZipError;
+use crate::server::{AppPermission, PermissionPrompt, PromptKind, WalletInterface};
+use crate::ui::components::{ErrorDisplay, Notification};
+use crate::ui::styles::global_styles;
+
+#[component]
+pub fn ConnectedApps() -> Element {
+    let interface = use_context::<WalletInterface>();
+    let user_id = use_signal(|| Uuid::new_v4());
+    let prompts = use_signal(|| Vec::<PermissionPrompt>::new());
+    let apps = use_signal(|| Vec::<AppPermission>::new());
+    let allowance = use_signal(|| 10_000u64);
+    let error = use_signal(|| None::<ZipError>);
+    let notification = use_signal(|| None::<String>);
+    let animated = use_animated(|style| style.opacity(1.0).duration(0.5));
+
+    let reload = move || match interface.permissions().list(*user_id.read()) {
+        Ok(list) => apps.set(list),
+        Err(e) => error.set(Some(e)),
+    };
+
+    use_effect(move || async move {
+        // Apps wait on these prompts, so keep them fresh
+        loop {
+            prompts.set(interface.pending_prompts().await);
+            reload();
+            tokio::time::sleep(Duration::from_secs(2)).await;
+        }
+    });
+
+    let on_answer = move |prompt: PermissionPrompt, answer: Option<u64>| async move {
+        match interface.respond(prompt.id, answer).await {
+            Ok(()) => {
+                let verb = if answer.is_some() { "Allowed" } else { "Denied" };
+                notification.set(Some(format!("{} {}", verb, prompt.origin)));
+                prompts.set(interface.pending_prompts().await);
+            }
+            Err(e) => error.set(Some(e)),
+        }
+    };
+
+    let on_revoke = move |origin: String| {
+        match interface.permissions().revoke(*user_id.read(), &origin) {
+            Ok(()) => reload(),
+            Err(e) => error.set(Some(e)),
+        }
+    };
+
+    rsx! {
+        div {
+            class: "connected-apps",
+            style: format!("{{{global_styles()}}} {}", animated),
+            h2 { class: "title", "Connected Apps" }
+            for prompt in prompts.read().iter().cloned() {
+                div { class: "prompt",
+                    match &prompt.kind {
+                        PromptKind::Connect => rsx! {
+                            div { "{prompt.origin} wants to connect to your wallet" }
+                            input {
+                                r#type: "number",
+                                placeholder: "Spending allowance in satoshis",
+                                value: "{allowance}",
+                                oninput: move |evt: Event<FormData>| allowance.set(evt.value().parse().unwrap_or(0)),
+                            }
+                        },
+                        PromptKind::Spend { satoshis, description } => rsx! {
+                            div { "{prompt.origin} wants to spend {satoshis} satoshis" }
+                            div { class: "warning", "{description}" }
+                        },
+                        PromptKind::Protocol { security_level, protocol } => rsx! {
+                            div { "{prompt.origin} wants to sign with your \"{protocol}\" keys" }
+                            div { class: "warning",
+                                if *security_level == 2 { "Separate keys per counterparty" } else { "One key for every counterparty" }
+                            }
+                        },
+                    }
+                    button {
+                        onclick: {
+                            let prompt = prompt.clone();
+                            move |_| on_answer(prompt.clone(), Some(*allowance.read()))
+                        },
+                        "Allow"
+                    }
+                    button { onclick: move |_| on_answer(prompt.clone(), None), "Deny" }
+                }
+            }
+            div { class: "app-list",
+                div { class: "header", "App" }
+                div { class: "header", "Allowance" }
+                div { class: "header", "Remaining" }
+                div { class: "header", "" }
+                for app in apps.read().iter().cloned() {
+                    div { "{app.origin}" }
+                    div { "{app.allowance} satoshis" }
+                    div { "{app.remaining()} satoshis" }
+                    div { button { onclick: move |_| on_revoke(app.origin.clone()), "Revoke" } }
+                }
+            }
+            ErrorDisplay { error: *error.read() }
+            Notification { message: *notification.read(), is_success: true }
+        }
+    }
+}
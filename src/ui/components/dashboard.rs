@@ -4,6 +4,7 @@ use rust_decimal::Decimal;
 use uuid::Uuid;
 
 use crate::blockchain::WalletManager;
+use crate::ui::components::Notification;
 use crate::ui::router::Route;
 use crate::ui::styles::global_styles;
 use crate::ui::transitions::fade_in;
@@ -15,6 +16,7 @@ pub fn Dashboard(cx: Scope) -> Element {
     let balance = use_signal(|| 0u64);
     let balance_converted = use_signal(|| Decimal::ZERO);
     let currency = use_signal(|| "USD".to_string());
+    let alert = use_signal(|| None::<String>);
 
     use_effect(move || async move {
         match wallet
@@ -30,6 +32,14 @@ pub fn Dashboard(cx: Scope) -> Element {
                 balance_converted.set(Decimal::ZERO);
             }
         }
+        // Surface double-spends and other wallet events detected in the background
+        let _ = wallet.check_conflicts(*user_id.read()).await;
+        if let Ok(alerts) = wallet.take_alerts(*user_id.read()) {
+            if !alerts.is_empty() {
+                let messages: Vec<String> = alerts.into_iter().map(|a| a.message).collect();
+                alert.set(Some(messages.join("\n")));
+            }
+        }
     });
 
     fade_in(
@@ -42,8 +52,12 @@ pub fn Dashboard(cx: Scope) -> Element {
                 div { class: "balance-main", "${balance_converted:.2} {currency}" }
                 div { class: "balance-sub", "{balance} satoshis (BSV)" }
                 Link { to: Route::Payment, "Send Payment" }
+                Link { to: Route::ReceiveRoute, "Receive" }
                 Link { to: Route::HistoryRoute, "Transaction History" }
                 Link { to: Route::SettingsRoute, "Settings" }
+                if let Some(msg) = alert.read().as_ref() {
+                    Notification { message: Some(msg.clone()), is_success: false }
+                }
             }
         },
     )
@@ -12,6 +12,7 @@ use crate::blockchain::WalletManager;
 use crate::integrations::rustbus::RustBusIntegrator;
 use crate::ui::styles::global_styles;
 use crate::ui::transitions::fade_in;
+use crate::utils::crypto::Crypto;
 
 #[derive(Clone, Debug)]
 struct Tx {
@@ -22,6 +23,7 @@ struct Tx {
     txid: String,
     timestamp: String,
     from_to: String,
+    memo: String,
 }
 
 #[component]
@@ -68,6 +70,23 @@ pub fn History(cx: Scope) -> Element {
                 Ok(txs) => txs,
                 Err(_) => vec![],
             };
+            // Memos are only known for transactions this wallet built. Encrypted memos
+            // stay encrypted in storage and are decrypted for display only.
+            let memos: HashMap<String, String> = wallet
+                .get_history(*user_id.read())
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|entry| entry.memo.map(|memo| (entry.txid, memo)))
+                .map(|(txid, memo)| {
+                    if !memo.split_whitespace().any(Crypto::is_encrypted_message) {
+                        return (txid, memo);
+                    }
+                    let text = wallet
+                        .decrypt_memo(&memo)
+                        .unwrap_or_else(|_| "Encrypted memo".to_string());
+                    (txid, text)
+                })
+                .collect();
             let mut updated_txs = txs.read().clone();
             for txid in new_txs.into_iter().skip(*page.read() * 20).take(20) {
                 let tx_details = match fetch_tx_details(&txid).await {
@@ -119,7 +138,7 @@ pub fn History(cx: Scope) -> Element {
                     amount_usd,
                     current_value_usd,
                     delta_percent,
-                    txid,
+                    txid: txid.clone(),
                     timestamp: dt
                         .format(
                             &time::format_description::parse(
@@ -129,6 +148,7 @@ pub fn History(cx: Scope) -> Element {
                         )
                         .unwrap_or_default(),
                     from_to: tx_details["from"].as_str().unwrap_or("Unknown").to_string(),
+                    memo: memos.get(&txid).cloned().unwrap_or_default(),
                 });
             }
             txs.set(updated_txs);
@@ -142,13 +162,14 @@ pub fn History(cx: Scope) -> Element {
         rsx! {
             div {
                 class: "history-grid",
-                style: "{{{global_styles()}}} .history-grid {{ display: grid; grid-template-columns: 100px 120px 140px 200px 140px 200px; gap: 10px; overflow-y: auto; max-height: 80vh; font-size: 14px; padding: 10px; }} .history-grid > div {{ padding: 8px; border-bottom: 1px solid #ddd; }} .header {{ font-weight: bold; background-color: #f0f0f0; }} .delta-positive {{ color: green; }} .delta-negative {{ color: red; }} .txid-link {{ color: #007bff; text-decoration: none; }} .txid-link:hover {{ text-decoration: underline; }} @media (max-width: 600px) {{ .history-grid {{ grid-template-columns: 1fr; }} .history-grid > div {{ font-size: 12px; }} }}",
+                style: "{{{global_styles()}}} .history-grid {{ display: grid; grid-template-columns: 100px 120px 140px 200px 140px 200px 1fr; gap: 10px; overflow-y: auto; max-height: 80vh; font-size: 14px; padding: 10px; }} .history-grid > div {{ padding: 8px; border-bottom: 1px solid #ddd; }} .header {{ font-weight: bold; background-color: #f0f0f0; }} .delta-positive {{ color: green; }} .delta-negative {{ color: red; }} .txid-link {{ color: #007bff; text-decoration: none; }} .txid-link:hover {{ text-decoration: underline; }} @media (max-width: 600px) {{ .history-grid {{ grid-template-columns: 1fr; }} .history-grid > div {{ font-size: 12px; }} }}",
                 div { class: "header", "Token" }
                 div { class: "header", "Amount ({currency})" }
                 div { class: "header", "Value ({currency})" }
                 div { class: "header", "TXID" }
                 div { class: "header", "Timestamp" }
                 div { class: "header", "From/To" }
+                div { class: "header", "Memo" }
                 for tx in txs.read().iter() {
                     div { "{tx.token}" }
                     div { "{tx.amount_usd:.2}" }
@@ -156,9 +177,10 @@ pub fn History(cx: Scope) -> Element {
                     div { a { class: "txid-link", href: "https://whatsonchain.com/tx/{tx.txid}", target: "_blank", "🔗 {tx.txid}" } }
                     div { "{tx.timestamp}" }
                     div { "{tx.from_to}" }
+                    div { "{tx.memo}" }
                 }
                 if *loading.read() {
-                    div { style: "grid-column: span 6; text-align: center;", "Loading..." }
+                    div { style: "grid-column: span 7; text-align: center;", "Loading..." }
                 }
             }
         },
@@ -0,0 +1,220 @@
+use chrono::{TimeZone, Utc};
+use dioxus::prelude::*;
+use dioxus_motion::use_animated;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::blockchain::{
+    Invoice, InvoiceAmount, InvoiceStatus, Invoices as InvoiceBook, PaymailManager, WalletManager,
+};
+use crate::errors::ZipError;
+use crate::ui::components::{ErrorDisplay, Notification};
+use crate::ui::styles::global_styles;
+use crate::utils::Qr;
+
+#[component]
+pub fn Invoices() -> Element {
+    let wallet = use_context::<WalletManager>();
+    let paymail = use_context::<PaymailManager>();
+    let invoice_book = use_context::<InvoiceBook>();
+    let user_id = use_signal(|| Uuid::new_v4());
+    let invoices = use_signal(|| Vec::<Invoice>::new());
+    let selected = use_signal(|| None::<Invoice>);
+    let amount = use_signal(|| String::new());
+    let currency = use_signal(|| "SATS".to_string());
+    let memo = use_signal(|| String::new());
+    let expiry_hours = use_signal(|| Some(24i64));
+    let use_paymail = use_signal(|| false);
+    let alias = use_signal(|| None::<String>);
+    let error = use_signal(|| None::<ZipError>);
+    let notification = use_signal(|| None::<String>);
+    let animated = use_animated(|style| style.opacity(1.0).duration(0.5));
+
+    let reload = move || match invoice_book.list(*user_id.read()) {
+        Ok(list) => invoices.set(list),
+        Err(e) => error.set(Some(e)),
+    };
+
+    use_effect(move || async move {
+        if let Ok(aliases) = paymail.get_user_aliases(*user_id.read()).await {
+            alias.set(aliases.into_iter().next());
+        }
+        reload();
+        // Match incoming payments and expire overdue invoices
+        loop {
+            let now = Utc::now().timestamp();
+            if let Ok(changed) = invoice_book.refresh(&wallet, *user_id.read(), now).await {
+                if let Some(invoice) = changed.first() {
+                    notification.set(Some(format!(
+                        "Invoice {} is now {}",
+                        invoice.reference,
+                        status_label(&invoice.status)
+                    )));
+                    reload();
+                }
+            }
+            tokio::time::sleep(Duration::from_secs(30)).await;
+        }
+    });
+
+    let on_create = move |_| async move {
+        let requested = match currency.read().as_str() {
+            "SATS" => amount.read().parse().ok().map(InvoiceAmount::Satoshis),
+            fiat => Decimal::from_str(&amount.read())
+                .ok()
+                .map(|value| InvoiceAmount::Fiat {
+                    currency: fiat.to_string(),
+                    amount: value,
+                }),
+        };
+        let Some(requested) = requested else {
+            error.set(Some(ZipError::Validation("Invalid amount".to_string())));
+            return;
+        };
+        let handle = if *use_paymail.read() {
+            alias.read().clone()
+        } else {
+            None
+        };
+        match invoice_book
+            .create(
+                &wallet,
+                *user_id.read(),
+                requested,
+                &memo.read(),
+                expiry_hours.read().map(|h| h * 3600),
+                handle.as_deref(),
+            )
+            .await
+        {
+            Ok(invoice) => {
+                notification.set(Some(format!("Invoice {} created", invoice.reference)));
+                selected.set(Some(invoice));
+                reload();
+            }
+            Err(e) => error.set(Some(e)),
+        }
+    };
+
+    let on_remove = move |invoice_id: Uuid| match invoice_book.remove(*user_id.read(), invoice_id) {
+        Ok(()) => {
+            selected.set(None);
+            reload();
+        }
+        Err(e) => error.set(Some(e)),
+    };
+
+    rsx! {
+        div {
+            class: "invoices",
+            style: format!("{{{global_styles()}}} {}", animated),
+            h2 { class: "title", "Invoices" }
+            div { class: "section",
+                h3 { "New Invoice" }
+                input {
+                    r#type: "text",
+                    placeholder: "Amount",
+                    oninput: move |evt: Event<FormData>| amount.set(evt.value()),
+                }
+                select {
+                    onchange: move |evt: Event<FormData>| currency.set(evt.value()),
+                    option { value: "SATS", "satoshis" }
+                    option { value: "USD", "USD" }
+                    option { value: "EUR", "EUR" }
+                    option { value: "GBP", "GBP" }
+                }
+                input {
+                    r#type: "text",
+                    placeholder: "Memo (e.g., Website design, March)",
+                    oninput: move |evt: Event<FormData>| memo.set(evt.value()),
+                }
+                input {
+                    r#type: "number",
+                    placeholder: "Expires after N hours (empty for never)",
+                    value: "24",
+                    oninput: move |evt: Event<FormData>| expiry_hours.set(evt.value().parse().ok()),
+                }
+                if alias.read().is_some() {
+                    label {
+                        input {
+                            r#type: "checkbox",
+                            onchange: move |evt: Event<FormData>| use_paymail.set(evt.value() == "true"),
+                        }
+                        "Pay to my PayMail instead of a new address"
+                    }
+                }
+                button { onclick: on_create, "Create Invoice" }
+            }
+            if let Some(invoice) = selected.read().as_ref() {
+                div { class: "qr-card",
+                    h3 { "{invoice.reference}" }
+                    div { class: "qr", dangerous_inner_html: Qr::svg(&invoice.payment_uri().to_string(), 200).unwrap_or_default() }
+                    div { class: "qr-uri", "{invoice.payment_uri()}" }
+                }
+            }
+            div { class: "invoice-list",
+                div { class: "header", "Reference" }
+                div { class: "header", "Memo" }
+                div { class: "header", "Amount" }
+                div { class: "header", "Expires" }
+                div { class: "header", "Status" }
+                div { class: "header", "" }
+                for invoice in invoices.read().iter().cloned() {
+                    div { "{invoice.reference}" }
+                    div { "{invoice.memo}" }
+                    div {
+                        "{invoice.amount} satoshis"
+                        if let InvoiceAmount::Fiat { currency, amount } = &invoice.requested {
+                            " ({amount} {currency})"
+                        }
+                    }
+                    div { "{expiry_label(invoice.expires_at)}" }
+                    div { class: status_class(&invoice.status),
+                        "{status_label(&invoice.status)}"
+                        if invoice.status == InvoiceStatus::PartiallyPaid {
+                            " ({invoice.outstanding()} due)"
+                        }
+                    }
+                    div {
+                        button {
+                            onclick: {
+                                let invoice = invoice.clone();
+                                move |_| selected.set(Some(invoice.clone()))
+                            },
+                            "Show"
+                        }
+                        button { onclick: move |_| on_remove(invoice.id), "Delete" }
+                    }
+                }
+            }
+            ErrorDisplay { error: *error.read() }
+            Notification { message: *notification.read(), is_success: true }
+        }
+    }
+}
+
+fn status_label(status: &InvoiceStatus) -> &'static str {
+    match status {
+        InvoiceStatus::Open => "Open",
+        InvoiceStatus::PartiallyPaid => "Partially paid",
+        InvoiceStatus::Paid => "Paid",
+        InvoiceStatus::Expired => "Expired",
+    }
+}
+
+fn status_class(status: &InvoiceStatus) -> &'static str {
+    match status {
+        InvoiceStatus::Paid => "status-paid",
+        InvoiceStatus::Expired => "status-expired",
+        _ => "",
+    }
+}
+
+fn expiry_label(expires_at: Option<i64>) -> String {
+    expires_at
+        .and_then(|ts| Utc.timestamp_opt(ts, 0).single())
+        .map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_else(|| "Never".to_string())
+}
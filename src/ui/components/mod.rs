@@ -5,6 +5,8 @@ pub mod auth;
 #[cfg(feature = "ui")]
 pub mod auth_callback;
 #[cfg(feature = "ui")]
+pub mod connected_apps;
+#[cfg(feature = "ui")]
 pub mod dashboard;
 #[cfg(feature = "ui")]
 pub mod error;
@@ -13,26 +15,38 @@ pub mod history;
 #[cfg(feature = "ui")]
 pub mod home;
 #[cfg(feature = "ui")]
+pub mod invoices;
+#[cfg(feature = "ui")]
 pub mod loading;
 #[cfg(feature = "ui")]
 pub mod logout;
 #[cfg(feature = "ui")]
 pub mod nav;
 #[cfg(feature = "ui")]
+pub mod notary;
+#[cfg(feature = "ui")]
 pub mod notifications;
 #[cfg(feature = "ui")]
 pub mod payment_form;
 #[cfg(feature = "ui")]
 pub mod profile;
 #[cfg(feature = "ui")]
+pub mod receive;
+#[cfg(feature = "ui")]
+pub mod schedules;
+#[cfg(feature = "ui")]
 pub mod settings;
 #[cfg(feature = "ui")]
+pub mod sign_message;
+#[cfg(feature = "ui")]
 pub mod swipe_button;
 #[cfg(feature = "ui")]
 pub mod theme;
 #[cfg(feature = "ui")]
 pub mod theme_switcher;
 #[cfg(feature = "ui")]
+pub mod vault;
+#[cfg(feature = "ui")]
 pub mod wallet;
 
 #[cfg(feature = "ui")]
@@ -42,6 +56,8 @@ pub use auth::Auth;
 #[cfg(feature = "ui")]
 pub use auth_callback::AuthCallback;
 #[cfg(feature = "ui")]
+pub use connected_apps::ConnectedApps;
+#[cfg(feature = "ui")]
 pub use dashboard::Dashboard;
 #[cfg(feature = "ui")]
 pub use error::ErrorDisplay;
@@ -50,24 +66,36 @@ pub use history::History;
 #[cfg(feature = "ui")]
 pub use home::Home;
 #[cfg(feature = "ui")]
+pub use invoices::Invoices;
+#[cfg(feature = "ui")]
 pub use loading::Loading;
 #[cfg(feature = "ui")]
 pub use logout::Logout;
 #[cfg(feature = "ui")]
 pub use nav::NavBar;
 #[cfg(feature = "ui")]
+pub use notary::Notary;
+#[cfg(feature = "ui")]
 pub use notifications::Notification;
 #[cfg(feature = "ui")]
 pub use payment_form::PaymentForm;
 #[cfg(feature = "ui")]
 pub use profile::Profile;
 #[cfg(feature = "ui")]
+pub use receive::Receive;
+#[cfg(feature = "ui")]
+pub use schedules::Schedules;
+#[cfg(feature = "ui")]
 pub use settings::Settings;
 #[cfg(feature = "ui")]
+pub use sign_message::SignMessage;
+#[cfg(feature = "ui")]
 pub use swipe_button::SwipeButton;
 #[cfg(feature = "ui")]
 pub use theme::{Theme, ThemeProvider};
 #[cfg(feature = "ui")]
 pub use theme_switcher::ThemeSwitcher;
 #[cfg(feature = "ui")]
+pub use vault::Vault;
+#[cfg(feature = "ui")]
 pub use wallet::WalletOverview;
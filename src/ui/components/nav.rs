@@ -23,7 +23,14 @@ pub fn NavBar(cx: Scope) -> Element {
                 rsx! {
                     Link { to: Route::DashboardRoute, class: "nav-link", "Wallet" }
                     Link { to: Route::Payment, class: "nav-link", "Send" }
+                    Link { to: Route::ReceiveRoute, class: "nav-link", "Receive" }
+                    Link { to: Route::InvoicesRoute, class: "nav-link", "Invoices" }
                     Link { to: Route::HistoryRoute, class: "nav-link", "History" }
+                    Link { to: Route::VaultRoute, class: "nav-link", "Vault" }
+                    Link { to: Route::SchedulesRoute, class: "nav-link", "Recurring" }
+                    Link { to: Route::NotaryRoute, class: "nav-link", "Notary" }
+                    Link { to: Route::SignMessageRoute, class: "nav-link", "Sign" }
+                    Link { to: Route::ConnectedAppsRoute, class: "nav-link", "Apps" }
                     Link { to: Route::ProfileRoute, class: "nav-link", "Profile" }
                     Link { to: Route::SettingsRoute, class: "nav-link", "Settings" }
                     Link { to: Route::LogoutRoute, class: "nav-link", "Logout" }
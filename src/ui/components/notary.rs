@@ -0,0 +1,161 @@
+use chrono::{TimeZone, Utc};
+use dioxus::prelude::*;
+use dioxus_motion::use_animated;
+use uuid::Uuid;
+
+use crate::blockchain::{Notary as NotaryBook, NotaryCheck, NotaryReceipt, WalletManager};
+use crate::errors::ZipError;
+use crate::ui::components::{ErrorDisplay, Loading, Notification};
+use crate::ui::styles::global_styles;
+
+#[component]
+pub fn Notary() -> Element {
+    let wallet = use_context::<WalletManager>();
+    let notary = use_context::<NotaryBook>();
+    let user_id = use_signal(|| Uuid::new_v4());
+    let receipts = use_signal(|| Vec::<NotaryReceipt>::new());
+    let file = use_signal(|| None::<(String, Vec<u8>)>);
+    let check = use_signal(|| None::<(String, NotaryCheck)>);
+    let is_loading = use_signal(|| false);
+    let error = use_signal(|| None::<ZipError>);
+    let notification = use_signal(|| None::<String>);
+    let animated = use_animated(|style| style.opacity(1.0).duration(0.5));
+
+    let reload = move || match notary.list(*user_id.read()) {
+        Ok(list) => receipts.set(list),
+        Err(e) => error.set(Some(e)),
+    };
+
+    use_effect(move || async move {
+        // Attach Merkle proofs to receipts mined since the last visit
+        if let Ok(confirmed) = notary.refresh_proofs(&wallet, *user_id.read()).await {
+            if !confirmed.is_empty() {
+                notification.set(Some(format!("{} receipt(s) confirmed", confirmed.len())));
+            }
+        }
+        reload();
+    });
+
+    let on_file = move |evt: Event<FormData>| async move {
+        let Some(engine) = evt.files() else {
+            return;
+        };
+        if let Some(name) = engine.files().into_iter().next() {
+            if let Some(bytes) = engine.read_file(&name).await {
+                check.set(None);
+                file.set(Some((name, bytes)));
+            }
+        }
+    };
+
+    let on_notarise = move |_| async move {
+        let Some((name, bytes)) = file.read().clone() else {
+            error.set(Some(ZipError::Validation("Choose a file first".to_string())));
+            return;
+        };
+        is_loading.set(true);
+        match notary
+            .notarise(&wallet, *user_id.read(), &name, &bytes)
+            .await
+        {
+            Ok(receipt) => {
+                notification.set(Some(format!("{} notarised: TXID {}", name, receipt.txid)));
+                reload();
+            }
+            Err(e) => error.set(Some(e)),
+        }
+        is_loading.set(false);
+    };
+
+    let on_verify = move |receipt: NotaryReceipt| {
+        let Some((name, bytes)) = file.read().clone() else {
+            error.set(Some(ZipError::Validation("Choose a file to verify".to_string())));
+            return;
+        };
+        match receipt.verify_file(&bytes) {
+            Ok(result) => check.set(Some((name, result))),
+            Err(e) => error.set(Some(e)),
+        }
+    };
+
+    rsx! {
+        div {
+            class: "notary",
+            style: format!("{{{global_styles()}}} {}", animated),
+            h2 { class: "title", "Notarise" }
+            p { "Only the file's SHA-256 hash is written on-chain; the file never leaves this device." }
+            input { r#type: "file", onchange: on_file }
+            if let Some((name, bytes)) = file.read().as_ref() {
+                div { class: "qr-text", "{name}: {NotaryReceipt::hash_file(bytes)}" }
+            }
+            button { onclick: on_notarise, disabled: *is_loading.read(), "Notarise File" }
+            if let Some((name, result)) = check.read().as_ref() {
+                div { class: if matches!(result, NotaryCheck::Confirmed { .. } | NotaryCheck::Unconfirmed) { "status-paid" } else { "error" },
+                    "{name}: {check_label(result)}"
+                }
+            }
+            div { class: "receipt-list",
+                div { class: "header", "File" }
+                div { class: "header", "Hash" }
+                div { class: "header", "TXID" }
+                div { class: "header", "Status" }
+                div { class: "header", "" }
+                for receipt in receipts.read().iter().cloned() {
+                    div { "{receipt.file_name}" }
+                    div { class: "qr-text", "{receipt.file_hash}" }
+                    div { a { class: "txid-link", href: "https://whatsonchain.com/tx/{receipt.txid}", target: "_blank", "{receipt.txid}" } }
+                    div { "{receipt_status(&receipt)}" }
+                    div {
+                        button {
+                            onclick: move |_| on_verify(receipt.clone()),
+                            "Verify Selected File"
+                        }
+                    }
+                }
+            }
+            ErrorDisplay { error: *error.read() }
+            Notification { message: *notification.read(), is_success: true }
+            if *is_loading.read() {
+                Loading { message: "Writing hash on-chain".to_string() }
+            }
+        }
+    }
+}
+
+fn receipt_status(receipt: &NotaryReceipt) -> String {
+    match &receipt.header {
+        Some(header) => format!("Block {}", header.height),
+        None => Utc
+            .timestamp_opt(receipt.created_at, 0)
+            .single()
+            .map(|t| format!("Pending since {}", t.format("%Y-%m-%d %H:%M UTC")))
+            .unwrap_or_else(|| "Pending".to_string()),
+    }
+}
+
+fn check_label(result: &NotaryCheck) -> String {
+    match result {
+        NotaryCheck::HashMismatch => "does not match this receipt".to_string(),
+        NotaryCheck::TransactionMismatch => "receipt transaction is corrupted".to_string(),
+        NotaryCheck::Unconfirmed => "matches; transaction not yet mined".to_string(),
+        NotaryCheck::Confirmed {
+            height,
+            block_hash,
+            time,
+        } => {
+            let mined = Utc
+                .timestamp_opt(*time as i64, 0)
+                .single()
+                .map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+                .unwrap_or_default();
+            format!(
+                "matches; proven in block {} ({}), mined {}",
+                height, block_hash, mined
+            )
+        }
+        NotaryCheck::ProofInvalid => "matches, but the Merkle proof is invalid".to_string(),
+        NotaryCheck::HeaderInvalid => {
+            "matches, but the block header fails proof of work".to_string()
+        }
+    }
+}
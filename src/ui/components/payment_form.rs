@@ -1,17 +1,25 @@
+use chrono::{Local, NaiveDateTime};
 use dioxus::prelude::*;
 use dioxus_motion::use_animated;
 use rust_decimal::Decimal;
+use std::time::Duration;
+use sv::script::Script;
 use uuid::Uuid;
 
-use crate::blockchain::{PaymailManager, WalletManager};
+use crate::blockchain::{
+    DataOutput, NETWORK, PaymailManager, PaymentPreview, PublicProfile, WalletManager,
+};
 use crate::errors::ZipError;
 use crate::ui::components::{ErrorDisplay, Loading, Notification, SwipeButton};
 use crate::ui::styles::global_styles;
+use crate::utils::auth::AuthUtils;
+use crate::utils::payment_uri::{ParsedPayment, PaymentTarget};
 
 #[component]
 pub fn PaymentForm() -> Element {
     let paymail = use_context::<PaymailManager>();
     let wallet = use_context::<WalletManager>();
+    let auth_utils = use_context::<AuthUtils>();
     let user_id = use_signal(|| Uuid::new_v4());
     let recipient = use_signal(|| String::new());
     let amount = use_signal(|| 0u64);
@@ -19,40 +27,194 @@ pub fn PaymentForm() -> Element {
     let error = use_signal(|| None::<ZipError>);
     let notification = use_signal(|| None::<String>);
     let is_loading = use_signal(|| false);
+    let preview = use_signal(|| None::<PaymentPreview>);
+    let recipient_profile = use_signal(|| None::<PublicProfile>);
+    let send_at = use_signal(|| None::<i64>);
+    let memo = use_signal(|| String::new());
+    let encrypt_memo = use_signal(|| false);
+    let data_outputs = use_signal(|| Vec::<DataOutput>::new());
+    let two_fa_code = use_signal(|| String::new());
     let animated = use_animated(|style| style.opacity(1.0).duration(0.5));
 
-    let on_submit = move |_| async move {
-        if recipient.read().is_empty() || *amount.read() == 0 {
+    let on_review = move |_| async move {
+        // Merchant payment requests carry their own amount
+        let is_request = matches!(
+            ParsedPayment::parse(&recipient.read()).map(|p| p.target),
+            Ok(PaymentTarget::PaymentRequest(_))
+        );
+        if recipient.read().is_empty() || (*amount.read() == 0 && !is_request) {
             error.set(Some(ZipError::Blockchain(
                 "Invalid recipient or amount".to_string(),
             )));
             return;
         }
         is_loading.set(true);
-        match paymail
-            .resolve_paymail(&recipient.read(), *amount.read())
+        // Show who is being paid; hosts without the public profile capability show the handle only
+        let handle = match ParsedPayment::parse(&recipient.read()).map(|p| p.target) {
+            Ok(PaymentTarget::Paymail(handle)) => Some(handle),
+            _ => None,
+        };
+        recipient_profile.set(match handle {
+            Some(handle) => paymail.resolve_profile(&handle).await.ok(),
+            None => None,
+        });
+        // The memo is written on-chain in an OP_RETURN output, optionally encrypted
+        // to the recipient's PayMail identity key; merchants receive it off-chain
+        let memo_text = Some(memo.read().clone()).filter(|m| !m.is_empty());
+        let data = match memo_text {
+            _ if is_request => Ok(None),
+            Some(text) if *encrypt_memo.read() => {
+                match paymail.resolve_pki(&recipient.read()).await {
+                    Ok(key) => wallet
+                        .identity_public_key()
+                        .and_then(|own| DataOutput::encrypted_memo(&key, &own, &text))
+                        .map(Some),
+                    Err(e) => Err(e),
+                }
+            }
+            text => text.as_deref().map(DataOutput::memo).transpose(),
+        };
+        let data = match data {
+            Ok(data) => data.into_iter().collect::<Vec<_>>(),
+            Err(e) => {
+                error.set(Some(e));
+                is_loading.set(false);
+                return;
+            }
+        };
+        match wallet
+            .simulate_payment(
+                &paymail,
+                *user_id.read(),
+                &recipient.read(),
+                *amount.read(),
+                1000,
+                &data,
+            )
             .await
         {
-            Ok((script, resolved_amount)) => {
-                match wallet
-                    .send_payment(*user_id.read(), script, resolved_amount, 1000)
-                    .await
-                {
-                    Ok(txid) => {
-                        notification.set(Some(format!("Payment sent: TXID {}", txid)));
-                        recipient.set(String::new());
-                        amount.set(0);
-                    }
-                    Err(e) => error.set(Some(e)),
+            Ok(result) => {
+                data_outputs.set(data);
+                preview.set(Some(result));
+            }
+            Err(e) => error.set(Some(e)),
+        }
+        is_loading.set(false);
+    };
+
+    let on_confirm = move || async move {
+        let Some(confirmed) = preview.read().clone() else {
+            return;
+        };
+        // The wallet refuses payments over the 2FA threshold without a fresh approval
+        if confirmed.requires_2fa {
+            match auth_utils
+                .validate_totp(*user_id.read(), &two_fa_code.read())
+                .await
+            {
+                Ok(true) => wallet.approve_2fa(*user_id.read(), confirmed.total_debit()),
+                Ok(false) | Err(_) => {
+                    error.set(Some(ZipError::Auth("Invalid 2FA code".to_string())));
+                    return;
                 }
             }
+        }
+        is_loading.set(true);
+        let script = Script(confirmed.recipient_script.clone());
+        let memo_text = Some(memo.read().clone()).filter(|m| !m.is_empty());
+        let data = data_outputs.read().clone();
+        let result = match (*send_at.read(), confirmed.payment_request.clone()) {
+            // The reviewed merchant request is posted to the merchant, who broadcasts it
+            (_, Some(request)) => wallet
+                .pay_payment_request(*user_id.read(), &request, confirmed.fee, memo_text)
+                .await
+                .map(|ack| match ack.memo {
+                    Some(message) => format!("Payment accepted: {}", message),
+                    None => "Payment accepted by merchant".to_string(),
+                }),
+            // Future-dated payments are held locally with nLockTime until due
+            (Some(timestamp), None) => wallet
+                .schedule_locked_payment(
+                    *user_id.read(),
+                    script,
+                    confirmed.amount,
+                    confirmed.fee,
+                    timestamp as u32,
+                    &data,
+                )
+                .await
+                .map(|locked| {
+                    wallet.start_timelock_watcher(*user_id.read(), Duration::from_secs(60));
+                    format!("Payment scheduled: TXID {}", locked.txid)
+                }),
+            (None, None) => wallet
+                .send_payment(
+                    *user_id.read(),
+                    script,
+                    confirmed.amount,
+                    confirmed.fee,
+                    &data,
+                )
+                .await
+                .map(|txid| format!("Payment sent: TXID {}", txid)),
+        };
+        match result {
+            Ok(message) => {
+                notification.set(Some(message));
+                recipient.set(String::new());
+                amount.set(0);
+                memo.set(String::new());
+                encrypt_memo.set(false);
+                send_at.set(None);
+            }
             Err(e) => error.set(Some(e)),
         }
+        preview.set(None);
+        data_outputs.set(vec![]);
+        recipient_profile.set(None);
+        two_fa_code.set(String::new());
         is_loading.set(false);
     };
 
+    let on_cancel = move |_| {
+        preview.set(None);
+        data_outputs.set(vec![]);
+        recipient_profile.set(None);
+        two_fa_code.set(String::new());
+    };
+
+    // Accepts typed handles as well as pasted or scanned URIs and QR payloads
     let on_recipient_change = move |evt: Event<FormData>| {
-        recipient.set(evt.value().clone());
+        let input = evt.value();
+        match ParsedPayment::parse(&input) {
+            Ok(parsed) => {
+                if let Err(e) = parsed.validate_network(NETWORK) {
+                    // Keep what was typed so the user can correct it
+                    recipient.set(input);
+                    error.set(Some(e));
+                    return;
+                }
+                recipient.set(match parsed.target {
+                    PaymentTarget::Address(address) => address,
+                    PaymentTarget::Paymail(handle) => handle,
+                    PaymentTarget::PaymentRequest(url) => url,
+                });
+                if let Some(requested) = parsed.amount {
+                    amount.set(requested);
+                }
+                // Only the URI message becomes the memo; the label names the recipient
+                // and is never written on-chain
+                if let Some(text) = parsed.memo {
+                    memo.set(text);
+                }
+            }
+            // Keep partial input so the user can finish typing
+            Err(_) => recipient.set(input),
+        }
+    };
+
+    let on_memo_change = move |evt: Event<FormData>| {
+        memo.set(evt.value());
     };
 
     let on_amount_change = move |evt: Event<FormData>| {
@@ -60,6 +222,16 @@ pub fn PaymentForm() -> Element {
         amount.set(value);
     };
 
+    let on_send_at_change = move |evt: Event<FormData>| {
+        let timestamp = NaiveDateTime::parse_from_str(&evt.value(), "%Y-%m-%dT%H:%M")
+            .ok()
+            // datetime-local inputs are in the user's time zone
+            .and_then(|dt| dt.and_local_timezone(Local).earliest())
+            .map(|dt| dt.timestamp())
+            .filter(|ts| *ts > chrono::Utc::now().timestamp());
+        send_at.set(timestamp);
+    };
+
     rsx! {
         div {
             class: "payment-form",
@@ -67,22 +239,95 @@ pub fn PaymentForm() -> Element {
             h2 { class: "title", "Send Payment" }
             input {
                 r#type: "text",
-                placeholder: "Recipient PayMail (e.g., user@zip.io)",
+                placeholder: "PayMail, $handle, address or payment link",
+                value: "{recipient}",
                 oninput: on_recipient_change,
                 disabled: *is_loading.read()
             }
             input {
                 r#type: "number",
                 placeholder: "Amount in satoshis",
+                value: if *amount.read() > 0 { amount.read().to_string() } else { String::new() },
                 oninput: on_amount_change,
                 disabled: *is_loading.read()
             }
-            button { onclick: on_submit, disabled: *is_loading.read(), "Submit" }
-            if *amount.read() > 0 && !recipient.read().is_empty() {
-                SwipeButton {
-                    recipient: recipient.read().clone(),
-                    amount: *amount.read(),
-                    "Pay {amount} satoshis to {recipient}"
+            input {
+                r#type: "text",
+                placeholder: "Memo (optional)",
+                value: "{memo}",
+                oninput: on_memo_change,
+                disabled: *is_loading.read()
+            }
+            if recipient.read().contains('@') && !memo.read().is_empty() {
+                label {
+                    input {
+                        r#type: "checkbox",
+                        checked: *encrypt_memo.read(),
+                        onchange: move |evt: Event<FormData>| encrypt_memo.set(evt.value() == "true"),
+                        disabled: *is_loading.read()
+                    }
+                    "Encrypt memo so only the recipient and you can read it"
+                }
+            }
+            input {
+                r#type: "datetime-local",
+                title: "Send later (optional)",
+                oninput: on_send_at_change,
+                disabled: *is_loading.read()
+            }
+            button { onclick: on_review, disabled: *is_loading.read(), "Review Payment" }
+            if let Some(sheet) = preview.read().as_ref() {
+                div { class: "confirmation-sheet",
+                    h3 { "Confirm Payment" }
+                    if let Some(profile) = recipient_profile.read().as_ref() {
+                        div { class: "recipient-profile",
+                            if !profile.avatar.is_empty() {
+                                img { class: "avatar", src: "{profile.avatar}", alt: "{profile.name}", width: "48", height: "48" }
+                            }
+                            span { "{profile.name}" }
+                        }
+                    }
+                    div { "Recipient: {sheet.recipient}" }
+                    if sheet.private {
+                        div { class: "private", "Private address derived from the recipient's identity key (BRC-29)" }
+                    }
+                    if !memo.read().is_empty() {
+                        div {
+                            "Memo: {memo}"
+                            if *encrypt_memo.read() { " (encrypted)" }
+                        }
+                    }
+                    div { "Amount: {sheet.amount} satoshis" }
+                    div { "Network fee: {sheet.fee} satoshis (~{sheet.estimated_size} bytes)" }
+                    div { "Inputs: {sheet.inputs.len()} ({sheet.total_in} satoshis)" }
+                    div { "Change: {sheet.change} satoshis" }
+                    div { class: "total", "Total: {sheet.total_debit()} satoshis" }
+                    if send_at.read().is_some() {
+                        div { class: "warning", "Funds are reserved now and sent automatically at the scheduled time" }
+                    }
+                    if sheet.requires_2fa {
+                        div { class: "warning", "This payment requires 2FA confirmation" }
+                        input {
+                            r#type: "text",
+                            placeholder: "2FA code",
+                            value: "{two_fa_code}",
+                            oninput: move |evt: Event<FormData>| two_fa_code.set(evt.value()),
+                            disabled: *is_loading.read()
+                        }
+                    }
+                    for violation in sheet.policy_violations.iter() {
+                        div { class: "error", "{violation}" }
+                    }
+                    button { onclick: move |_| on_confirm(), disabled: *is_loading.read() || !sheet.is_allowed(), "Confirm & Send" }
+                    if sheet.is_allowed() && !*is_loading.read() {
+                        SwipeButton {
+                            recipient: sheet.recipient.clone(),
+                            amount: sheet.amount,
+                            on_swipe: move |_| on_confirm(),
+                            "Swipe to pay {sheet.total_debit()} satoshis to {sheet.recipient}"
+                        }
+                    }
+                    button { onclick: on_cancel, disabled: *is_loading.read(), "Cancel" }
                 }
             }
             ErrorDisplay { error: *error.read() }
@@ -0,0 +1,99 @@
+use dioxus::prelude::*;
+use dioxus_motion::use_animated;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::blockchain::{PaymailManager, ReceiveAddress, WalletManager};
+use crate::errors::ZipError;
+use crate::ui::components::{ErrorDisplay, Notification};
+use crate::ui::styles::global_styles;
+use crate::utils::{PaymentUri, Qr};
+
+#[component]
+pub fn Receive() -> Element {
+    let wallet = use_context::<WalletManager>();
+    let paymail = use_context::<PaymailManager>();
+    let user_id = use_signal(|| Uuid::new_v4());
+    let address = use_signal(|| None::<ReceiveAddress>);
+    let alias = use_signal(|| None::<String>);
+    let amount = use_signal(|| 0u64);
+    let label = use_signal(|| String::new());
+    let error = use_signal(|| None::<ZipError>);
+    let notification = use_signal(|| None::<String>);
+    let animated = use_animated(|style| style.opacity(1.0).duration(0.5));
+
+    use_effect(move || async move {
+        match paymail.get_user_aliases(*user_id.read()).await {
+            Ok(aliases) => alias.set(aliases.into_iter().next()),
+            Err(e) => error.set(Some(e)),
+        }
+        match wallet.receive_address(*user_id.read()) {
+            Ok(current) => address.set(Some(current)),
+            Err(e) => error.set(Some(e)),
+        }
+        // Swap in a fresh address as soon as the shown one receives funds
+        loop {
+            tokio::time::sleep(Duration::from_secs(30)).await;
+            if let Ok(used) = wallet.refresh_receive_addresses(*user_id.read()).await {
+                if !used.is_empty() {
+                    let total = used.len();
+                    notification.set(Some(format!("Funds received on {} address(es)", total)));
+                    if let Ok(fresh) = wallet.receive_address(*user_id.read()) {
+                        address.set(Some(fresh));
+                    }
+                }
+            }
+        }
+    });
+
+    let address_uri = address.read().as_ref().map(|a| {
+        PaymentUri::bitcoin(&a.address)
+            .with_amount(*amount.read())
+            .with_label(&label.read())
+            .to_string()
+    });
+    let paymail_uri = alias.read().as_ref().map(|handle| {
+        PaymentUri::paymail(handle)
+            .with_amount(*amount.read())
+            .with_label(&label.read())
+            .to_string()
+    });
+
+    rsx! {
+        div {
+            class: "receive",
+            style: format!("{{{global_styles()}}} {}", animated),
+            h2 { class: "title", "Receive" }
+            input {
+                r#type: "number",
+                placeholder: "Request amount in satoshis (optional)",
+                oninput: move |evt: Event<FormData>| amount.set(evt.value().parse().unwrap_or(0)),
+            }
+            input {
+                r#type: "text",
+                placeholder: "Label (optional)",
+                oninput: move |evt: Event<FormData>| label.set(evt.value()),
+            }
+            div { class: "receive-codes",
+                if let Some(uri) = paymail_uri {
+                    div { class: "qr-card",
+                        h3 { "PayMail" }
+                        div { class: "qr", dangerous_inner_html: Qr::svg(&uri, 200).unwrap_or_default() }
+                        div { class: "qr-text", "{alias.read().clone().unwrap_or_default()}" }
+                        div { class: "qr-uri", "{uri}" }
+                    }
+                }
+                if let Some(uri) = address_uri {
+                    div { class: "qr-card",
+                        h3 { "Address" }
+                        div { class: "qr", dangerous_inner_html: Qr::svg(&uri, 200).unwrap_or_default() }
+                        div { class: "qr-text", "{address.read().as_ref().map(|a| a.address.clone()).unwrap_or_default()}" }
+                        div { class: "qr-uri", "{uri}" }
+                    }
+                }
+            }
+            ErrorDisplay { error: *error.read() }
+            Notification { message: *notification.read(), is_success: true }
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use chrono::{Datelike, TimeZone, Utc};
+use dioxus::prelude::*;
+use dioxus_motion::use_animated;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::blockchain::{
+    CatchUp, PaymailManager, PaymentSchedule, Recurrence, RunLogEntry, RunResult, Scheduler,
+    WalletManager,
+};
+use crate::errors::ZipError;
+use crate::ui::components::{ErrorDisplay, Notification};
+use crate::ui::styles::global_styles;
+
+#[component]
+pub fn Schedules() -> Element {
+    let wallet = use_context::<WalletManager>();
+    let paymail = use_context::<PaymailManager>();
+    let scheduler = use_context::<Scheduler>();
+    let user_id = use_signal(|| Uuid::new_v4());
+    let schedules = use_signal(|| Vec::<PaymentSchedule>::new());
+    let run_log = use_signal(|| Vec::<RunLogEntry>::new());
+    let label = use_signal(|| String::new());
+    let recipient = use_signal(|| String::new());
+    let amount = use_signal(|| 0u64);
+    let recurrence = use_signal(|| "monthly".to_string());
+    let catch_up = use_signal(|| "once".to_string());
+    let max_runs = use_signal(|| None::<u32>);
+    let error = use_signal(|| None::<ZipError>);
+    let notification = use_signal(|| None::<String>);
+    let animated = use_animated(|style| style.opacity(1.0).duration(0.5));
+
+    let reload = move || {
+        match scheduler.list(*user_id.read()) {
+            Ok(list) => schedules.set(list),
+            Err(e) => error.set(Some(e)),
+        }
+        if let Ok(log) = scheduler.run_log(*user_id.read()) {
+            run_log.set(log.into_iter().rev().take(20).collect());
+        }
+    };
+
+    use_effect(move || async move {
+        reload();
+    });
+
+    let on_add = move |_| {
+        if recipient.read().is_empty() || *amount.read() == 0 {
+            error.set(Some(ZipError::Validation(
+                "Invalid recipient or amount".to_string(),
+            )));
+            return;
+        }
+        let now = Utc::now();
+        let rule = match recurrence.read().as_str() {
+            "daily" => Recurrence::Daily,
+            "weekly" => Recurrence::Weekly,
+            _ => Recurrence::Monthly { day: now.day() },
+        };
+        let mut schedule = PaymentSchedule::new(
+            &label.read(),
+            &recipient.read(),
+            *amount.read(),
+            1000,
+            rule,
+            now.timestamp(),
+        );
+        schedule.catch_up = match catch_up.read().as_str() {
+            "skip" => CatchUp::Skip,
+            "all" => CatchUp::RunAll,
+            _ => CatchUp::RunOnce,
+        };
+        schedule.limits.max_runs = *max_runs.read();
+        match scheduler.add(*user_id.read(), schedule) {
+            Ok(()) => {
+                // Schedules stored before launch are already running; start this user's task
+                scheduler.start(
+                    wallet.clone(),
+                    paymail.clone(),
+                    *user_id.read(),
+                    Duration::from_secs(60),
+                );
+                notification.set(Some(format!(
+                    "Recurring payment to {} created",
+                    recipient.read()
+                )));
+                reload();
+            }
+            Err(e) => error.set(Some(e)),
+        }
+    };
+
+    let toggle_pause = move |schedule: PaymentSchedule| {
+        match scheduler.set_paused(*user_id.read(), schedule.id, !schedule.paused) {
+            Ok(()) => reload(),
+            Err(e) => error.set(Some(e)),
+        }
+    };
+
+    let on_remove = move |schedule_id: Uuid| match scheduler.remove(*user_id.read(), schedule_id) {
+        Ok(()) => reload(),
+        Err(e) => error.set(Some(e)),
+    };
+
+    rsx! {
+        div {
+            class: "schedules",
+            style: format!("{{{global_styles()}}} {}", animated),
+            h2 { class: "title", "Recurring Payments" }
+            div { class: "section",
+                h3 { "New Schedule" }
+                input {
+                    r#type: "text",
+                    placeholder: "Label (e.g., Rent)",
+                    oninput: move |evt: Event<FormData>| label.set(evt.value()),
+                }
+                input {
+                    r#type: "text",
+                    placeholder: "Recipient PayMail (e.g., landlord@zip.io)",
+                    oninput: move |evt: Event<FormData>| recipient.set(evt.value()),
+                }
+                input {
+                    r#type: "number",
+                    placeholder: "Amount in satoshis",
+                    oninput: move |evt: Event<FormData>| amount.set(evt.value().parse().unwrap_or(0)),
+                }
+                select {
+                    onchange: move |evt: Event<FormData>| recurrence.set(evt.value()),
+                    option { value: "monthly", "Monthly" }
+                    option { value: "weekly", "Weekly" }
+                    option { value: "daily", "Daily" }
+                }
+                select {
+                    onchange: move |evt: Event<FormData>| catch_up.set(evt.value()),
+                    option { value: "once", "After downtime: pay once" }
+                    option { value: "all", "After downtime: pay every missed run" }
+                    option { value: "skip", "After downtime: skip missed runs" }
+                }
+                input {
+                    r#type: "number",
+                    placeholder: "Stop after N payments (optional)",
+                    oninput: move |evt: Event<FormData>| max_runs.set(evt.value().parse().ok()),
+                }
+                button { onclick: on_add, "Create Schedule" }
+            }
+            div { class: "schedule-list",
+                div { class: "header", "Label" }
+                div { class: "header", "Recipient" }
+                div { class: "header", "Amount" }
+                div { class: "header", "Next Run" }
+                div { class: "header", "Runs" }
+                div { class: "header", "" }
+                for schedule in schedules.read().iter().cloned() {
+                    div { "{schedule.label}" }
+                    div { "{schedule.recipient}" }
+                    div { "{schedule.amount} satoshis" }
+                    div {
+                        if schedule.is_finished() {
+                            "Finished"
+                        } else if schedule.paused {
+                            "Paused"
+                        } else {
+                            "{format_time(schedule.next_run)}"
+                        }
+                    }
+                    div { "{schedule.runs}" }
+                    div {
+                        button {
+                            onclick: {
+                                let schedule = schedule.clone();
+                                move |_| toggle_pause(schedule.clone())
+                            },
+                            if schedule.paused { "Resume" } else { "Pause" }
+                        }
+                        button { onclick: move |_| on_remove(schedule.id), "Delete" }
+                    }
+                }
+            }
+            h3 { "Run Log" }
+            div { class: "run-log",
+                for entry in run_log.read().iter() {
+                    div {
+                        "{format_time(entry.scheduled_for)}: "
+                        match &entry.result {
+                            RunResult::Sent { txid } => rsx! { "Sent (TXID {txid})" },
+                            RunResult::Failed { error } => rsx! { span { class: "error", "Failed: {error}" } },
+                            RunResult::Skipped { reason } => rsx! { "Skipped: {reason}" },
+                        }
+                    }
+                }
+            }
+            ErrorDisplay { error: *error.read() }
+            Notification { message: *notification.read(), is_success: true }
+        }
+    }
+}
+
+fn format_time(ts: i64) -> String {
+    Utc.timestamp_opt(ts, 0)
+        .single()
+        .map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_default()
+}
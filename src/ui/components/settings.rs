@@ -1,13 +1,13 @@
 use bincode;
+use chrono::{TimeZone, Utc};
 use dioxus::prelude::*;
-use rust_decimal::Decimal;
-use rust_decimal::prelude::ToPrimitive;
 use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
 use crate::auth::AuthManager;
-use crate::blockchain::{PaymailManager, WalletManager};
+use crate::blockchain::{AliasLease, AliasTransfer, Auction, PaymailManager, WalletManager};
 use crate::errors::ZipError;
+use crate::paymail_config::AliasQuote;
 use crate::storage::ZipStorage;
 use crate::ui::components::{
     ErrorDisplay, Notification, SwipeButton, Theme, ThemeProvider, ThemeSwitcher,
@@ -32,7 +32,17 @@ pub fn Settings() -> Element {
     let paymail_aliases = use_signal(|| HashSet::new());
     let primary_paymail = use_signal(|| String::new());
     let new_alias = use_signal(|| String::new());
-    let alias_price = use_signal(|| Decimal::ZERO);
+    let alias_quote = use_signal(|| None::<AliasQuote>);
+    let quote_lock_minutes = paymail.pricing().quote_lock_secs / 60;
+    let grace_period_secs = paymail.pricing().grace_period_secs;
+    let alias_auction = use_signal(|| None::<Auction>);
+    let bid_amount = use_signal(|| 0u64);
+    let escrowed = use_signal(|| paymail.escrowed(*user_id.read()).unwrap_or(0));
+    let leases = use_signal(|| HashMap::<String, AliasLease>::new());
+    let transfers = use_signal(|| Vec::<AliasTransfer>::new());
+    let transfer_to = use_signal(|| String::new());
+    let profile_name = use_signal(|| String::new());
+    let profile_avatar = use_signal(|| String::new());
     let two_fa_enabled = use_signal(|| false);
     let two_fa_secret = use_signal(|| None::<String>);
     let two_fa_code = use_signal(|| String::new());
@@ -44,11 +54,16 @@ pub fn Settings() -> Element {
         to_owned![
             storage,
             paymail,
+            wallet,
             user_id,
             selected_currency,
             selected_theme,
             paymail_aliases,
             primary_paymail,
+            leases,
+            transfers,
+            profile_name,
+            profile_avatar,
             notification,
             error
         ],
@@ -75,6 +90,14 @@ pub fn Settings() -> Element {
                 );
                 two_fa_enabled.set(prefs.get("2fa_enabled").is_some());
             }
+            // Payments accepted since the last visit activate their aliases
+            for alias in paymail
+                .activate_pending(&wallet, *user_id.read())
+                .await
+                .unwrap_or_default()
+            {
+                notification.set(Some(format!("{} is now active", alias)));
+            }
             // Load PayMail aliases
             match paymail.get_user_aliases(*user_id.read()).await {
                 Ok(aliases) => {
@@ -85,6 +108,22 @@ pub fn Settings() -> Element {
                 }
                 Err(e) => error.set(Some(e)),
             }
+            // Renewal dates and transfer offers
+            leases.set(
+                paymail_aliases
+                    .read()
+                    .iter()
+                    .filter_map(|alias| paymail.lease(alias).ok().flatten())
+                    .map(|lease| (lease.alias.clone(), lease))
+                    .collect(),
+            );
+            let now = Utc::now().timestamp();
+            transfers.set(paymail.pending_transfers(*user_id.read(), now).unwrap_or_default());
+            // Name and avatar shown to people paying your aliases
+            if let Ok(Some(profile)) = paymail.profile(*user_id.read()) {
+                profile_name.set(profile.name);
+                profile_avatar.set(profile.avatar);
+            }
             // Assign default PayMail if none exists
             if paymail_aliases.read().is_empty() {
                 match paymail.create_default_alias(*user_id.read(), None).await {
@@ -92,7 +131,7 @@ pub fn Settings() -> Element {
                         let mut aliases = paymail_aliases.read().clone();
                         aliases.insert(alias.clone());
                         paymail_aliases.set(aliases);
-                        primary_paymail.set("101@zip.io".to_string());
+                        primary_paymail.set(alias.clone());
                         notification.set(Some(format!("Default PayMail assigned: {}", alias)));
                     }
                     Err(e) => error.set(Some(e)),
@@ -150,19 +189,42 @@ pub fn Settings() -> Element {
         notification.set(Some("Theme updated".to_string()));
     };
 
+    // Typing only edits the prefix; quotes lock a price and auctions open, so both
+    // wait until the user asks for the price
     let on_new_alias = move |evt: Event<FormData>| {
+        new_alias.set(evt.value());
+        alias_quote.set(None);
+        alias_auction.set(None);
+    };
+
+    let on_check_alias = move |_| {
         spawn(async move {
-            let prefix = evt.value();
-            if Validation::validate_paymail_prefix(&prefix).is_ok() {
-                new_alias.set(prefix.clone());
-                match paymail.create_paid_alias(*user_id.read(), &prefix).await {
-                    Ok((_, price)) => alias_price.set(price),
-                    Err(e) => error.set(Some(e)),
+            let prefix = new_alias.read().trim().to_string();
+            if prefix.is_empty() {
+                return;
+            }
+            if paymail.pricing().is_auctioned(&prefix) {
+                // Premium prefixes go to the highest bidder instead of a fixed price
+                alias_quote.set(None);
+                match paymail.open_auction(&wallet, &prefix).await {
+                    Ok(auction) => {
+                        bid_amount.set(auction.minimum_bid(paymail.pricing().bid_increment_percent));
+                        alias_auction.set(Some(auction));
+                    }
+                    Err(e) => {
+                        alias_auction.set(None);
+                        error.set(Some(e));
+                    }
+                }
+                return;
+            }
+            alias_auction.set(None);
+            match paymail.quote_alias(&wallet, *user_id.read(), &prefix).await {
+                Ok(quote) => alias_quote.set(Some(quote)),
+                Err(e) => {
+                    alias_quote.set(None);
+                    error.set(Some(e));
                 }
-            } else {
-                error.set(Some(ZipError::Blockchain(
-                    "PayMail prefix must be 5 or more digits".to_string(),
-                )));
             }
         });
     };
@@ -180,7 +242,28 @@ pub fn Settings() -> Element {
                 }
             }
         }
-        let (alias, price) = match paymail
+        let Some(quote) = alias_quote.read().clone() else {
+            return;
+        };
+        if *two_fa_enabled.read() {
+            wallet.approve_2fa(*user_id.read(), quote.satoshis + 1000);
+        }
+        let now = chrono::Utc::now().timestamp();
+        if !matches!(paymail.locked_quote(*user_id.read(), &quote.alias, now), Ok(Some(_))) {
+            // Never pay a price the user has not seen
+            match paymail.quote_alias(&wallet, *user_id.read(), &new_alias.read()).await {
+                Ok(quote) => {
+                    alias_quote.set(Some(quote));
+                    notification.set(Some("Price quote expired; please confirm the new price".to_string()));
+                }
+                Err(e) => {
+                    alias_quote.set(None);
+                    error.set(Some(e));
+                }
+            }
+            return;
+        }
+        let (alias, _) = match paymail
             .create_paid_alias(*user_id.read(), &new_alias.read())
             .await
         {
@@ -190,13 +273,7 @@ pub fn Settings() -> Element {
                 return;
             }
         };
-        let satoshis = (price * Decimal::from(100_000_000)
-            / wallet
-                .fetch_price(&selected_currency.read())
-                .await
-                .unwrap_or(Decimal::ONE))
-        .to_u64()
-        .unwrap_or(0);
+        let satoshis = quote.satoshis;
         match Validation::validate_amount(satoshis) {
             Ok(()) => {}
             Err(e) => {
@@ -204,33 +281,44 @@ pub fn Settings() -> Element {
                 return;
             }
         }
-        match paymail.resolve_paymail("000@zip.io", satoshis).await {
-            Ok((script, _)) => {
-                match wallet
-                    .send_payment(*user_id.read(), script, satoshis, 1000)
-                    .await
-                {
-                    Ok(_) => {
-                        if paymail.confirm_alias(*user_id.read(), &alias).await.is_ok() {
-                            let mut aliases = paymail_aliases.read().clone();
-                            aliases.insert(alias.clone());
-                            paymail_aliases.set(aliases);
-                            new_alias.set(String::new());
-                            alias_price.set(Decimal::ZERO);
-                            notification.set(Some(format!("Alias purchased: {}", alias)));
-                        } else {
-                            error.set(Some(ZipError::Blockchain(
-                                "Failed to confirm alias".to_string(),
-                            )));
-                        }
-                    }
-                    Err(e) => error.set(Some(e)),
-                }
+        if let Err(e) = paymail.pay_alias(&wallet, *user_id.read(), &alias, 1000).await {
+            error.set(Some(e));
+            return;
+        }
+        new_alias.set(String::new());
+        alias_quote.set(None);
+        match paymail.confirm_alias(&wallet, *user_id.read(), &alias).await {
+            Ok(()) => {
+                let mut aliases = paymail_aliases.read().clone();
+                aliases.insert(alias.clone());
+                paymail_aliases.set(aliases);
+                notification.set(Some(format!("Alias purchased: {}", alias)));
             }
             Err(e) => error.set(Some(e)),
         }
     };
 
+    let on_bid = move |_| {
+        spawn(async move {
+            let Some(auction) = alias_auction.read().clone() else {
+                return;
+            };
+            let now = Utc::now().timestamp();
+            match paymail
+                .bid_alias(&wallet, *user_id.read(), &auction.alias, *bid_amount.read(), now)
+                .await
+            {
+                Ok(auction) => {
+                    notification.set(Some(format!("Bid placed on {}", auction.alias)));
+                    bid_amount.set(auction.minimum_bid(paymail.pricing().bid_increment_percent));
+                    alias_auction.set(Some(auction));
+                }
+                Err(e) => error.set(Some(e)),
+            }
+            escrowed.set(paymail.escrowed(*user_id.read()).unwrap_or(0));
+        });
+    };
+
     let on_primary_paymail_change = move |alias: String| {
         spawn(async move {
             if *two_fa_enabled.read() {
@@ -250,6 +338,90 @@ pub fn Settings() -> Element {
         });
     };
 
+    let on_renew = move |alias: String| {
+        spawn(async move {
+            match paymail.renew_alias(&wallet, *user_id.read(), &alias, 1000).await {
+                Ok(lease) => {
+                    let message = match lease.pending_renewal {
+                        Some(_) => format!("Renewal of {} sent; it applies once the network accepts it", alias),
+                        None => format!("{} renewed until {}", alias, date_label(lease.paid_until)),
+                    };
+                    notification.set(Some(message));
+                    leases.write().insert(alias, lease);
+                }
+                Err(e) => error.set(Some(e)),
+            }
+        });
+    };
+
+    let on_transfer = move |alias: String| {
+        spawn(async move {
+            if *two_fa_enabled.read() {
+                match auth_utils
+                    .validate_totp(*user_id.read(), &two_fa_code.read())
+                    .await
+                {
+                    Ok(true) => {}
+                    Ok(false) | Err(_) => {
+                        error.set(Some(ZipError::Auth("Invalid 2FA code".to_string())));
+                        return;
+                    }
+                }
+            }
+            let now = Utc::now().timestamp();
+            match paymail
+                .propose_transfer(*user_id.read(), &alias, &transfer_to.read(), now)
+                .await
+            {
+                Ok(transfer) => {
+                    notification.set(Some(format!("Transfer of {} offered; waiting for the recipient", alias)));
+                    transfers.write().push(transfer);
+                    transfer_to.set(String::new());
+                }
+                Err(e) => error.set(Some(e)),
+            }
+        });
+    };
+
+    let on_confirm_transfer = move |transfer: AliasTransfer| {
+        spawn(async move {
+            let now = Utc::now().timestamp();
+            match paymail.confirm_transfer(&wallet, *user_id.read(), &transfer.alias, now).await {
+                Ok(done) if done.is_confirmed() => {
+                    let mut aliases = paymail_aliases.read().clone();
+                    if done.to == *user_id.read() {
+                        aliases.insert(done.alias.clone());
+                    } else {
+                        aliases.remove(&done.alias);
+                    }
+                    paymail_aliases.set(aliases);
+                    notification.set(Some(format!("{} transferred", done.alias)));
+                }
+                Ok(_) => notification.set(Some("Transfer confirmed".to_string())),
+                Err(e) => error.set(Some(e)),
+            }
+            transfers.set(paymail.pending_transfers(*user_id.read(), now).unwrap_or_default());
+        });
+    };
+
+    let on_cancel_transfer = move |transfer: AliasTransfer| {
+        match paymail.cancel_transfer(*user_id.read(), &transfer.alias) {
+            Ok(()) => transfers.write().retain(|t| t.alias != transfer.alias),
+            Err(e) => error.set(Some(e)),
+        }
+    };
+
+    let on_save_profile = move |_| {
+        match paymail.set_profile(*user_id.read(), &profile_name.read(), &profile_avatar.read()) {
+            Ok(profile) => {
+                profile_name.set(profile.name);
+                profile_avatar.set(profile.avatar);
+                notification.set(Some("Public profile updated".to_string()));
+            }
+            Err(e) => error.set(Some(e)),
+        }
+    };
+
     let on_two_fa_toggle = move |_| {
         spawn(async move {
             if *two_fa_enabled.read() {
@@ -336,19 +508,90 @@ pub fn Settings() -> Element {
                                 input { r#type: "radio", name: "primary_paymail", checked: *alias == *primary_paymail.read(), onclick: move |_| on_primary_paymail_change(alias.clone()) }
                                 "{alias} {if *alias == *primary_paymail.read() { '(Primary)' } else { '' }}"
                             }
+                            if let Some(lease) = leases.read().get(alias).cloned() {
+                                div { class: "lease",
+                                    if lease.in_grace_at(Utc::now().timestamp(), grace_period_secs) {
+                                        span { class: "warning", "Expired {date_label(lease.paid_until)}; renew by {date_label(lease.paid_until + grace_period_secs)} to keep it" }
+                                    } else {
+                                        span { "Paid until {date_label(lease.paid_until)}" }
+                                    }
+                                    button { onclick: move |_| on_renew(lease.alias.clone()), "Renew" }
+                                }
+                            }
+                            div { class: "transfer",
+                                button { onclick: move |_| on_transfer(alias.clone()), "Transfer" }
+                            }
+                        }
+                        input { r#type: "text", placeholder: "Transfer to (recipient's PayMail)", value: "{transfer_to}", oninput: move |evt| transfer_to.set(evt.value()) }
+                        for transfer in transfers.read().iter().cloned() {
+                            div { class: "pending-transfer",
+                                if transfer.to == *user_id.read() {
+                                    span { "{transfer.alias} is offered to you until {date_label(transfer.expires_at)}" }
+                                } else {
+                                    span { "{transfer.alias} offered, waiting for the recipient" }
+                                }
+                                if transfer.to == *user_id.read() && !transfer.to_confirmed {
+                                    button {
+                                        onclick: {
+                                            let transfer = transfer.clone();
+                                            move |_| on_confirm_transfer(transfer.clone())
+                                        },
+                                        "Accept"
+                                    }
+                                }
+                                button { onclick: move |_| on_cancel_transfer(transfer.clone()), "Cancel" }
+                            }
                         }
                         div { class: "alias-input",
-                            input { r#type: "text", placeholder: "New alias prefix (5+ digits)", oninput: on_new_alias }
-                            if alias_price.read() > Decimal::ZERO {
+                            input { r#type: "text", placeholder: "New alias prefix (3+ characters)", value: "{new_alias}", oninput: on_new_alias }
+                            button { onclick: on_check_alias, disabled: new_alias.read().trim().is_empty(), "Check price" }
+                            if let Some(quote) = alias_quote.read().clone().filter(|q| q.satoshis > 0) {
+                                div { class: "quote", "Price locked for {quote_lock_minutes} minutes" }
                                 SwipeButton {
                                     recipient: "000@zip.io",
-                                    amount: (alias_price.read() * Decimal::from(100_000_000) / wallet.fetch_price(&selected_currency.read()).await.unwrap_or(Decimal::ONE)).to_u64().unwrap_or(0),
-                                    "Pay {alias_price} {selected_currency} for {new_alias}@zip.io"
+                                    amount: quote.satoshis,
+                                    on_swipe: move |_| on_pay_alias(),
+                                    "Pay {quote.price} {quote.currency} ({quote.satoshis} satoshis) for {quote.alias}"
+                                }
+                            }
+                            if let Some(auction) = alias_auction.read().clone() {
+                                div { class: "auction",
+                                    div { "{auction.alias} is sold by auction, ending {date_label(auction.ends_at)}" }
+                                    match auction.leading() {
+                                        Some(bid) => rsx! {
+                                            div { "Leading bid: {bid.satoshis} satoshis" }
+                                            if !auction.reserve_met() {
+                                                div { class: "warning", "Reserve not met" }
+                                            }
+                                        },
+                                        None => rsx! { div { "No bids yet" } },
+                                    }
+                                    input {
+                                        r#type: "number",
+                                        placeholder: "Bid in satoshis",
+                                        value: "{bid_amount}",
+                                        oninput: move |evt: Event<FormData>| bid_amount.set(evt.value().parse().unwrap_or(0)),
+                                    }
+                                    button { onclick: on_bid, "Bid" }
+                                    div { class: "quote", "Bids stay in your wallet but cannot be spent until you are outbid or the auction ends" }
                                 }
                             }
+                            if *escrowed.read() > 0 {
+                                div { class: "escrow", "Held for your leading bids: {escrowed} satoshis" }
+                            }
                         }
                     }
                 }
+                div { class: "section",
+                    h3 { "Public Profile" }
+                    div { class: "quote", "Shown to people paying any of your PayMail addresses" }
+                    input { r#type: "text", placeholder: "Display name", value: "{profile_name}", oninput: move |evt| profile_name.set(evt.value()) }
+                    input { r#type: "url", placeholder: "Avatar image URL (https://...)", value: "{profile_avatar}", oninput: move |evt| profile_avatar.set(evt.value()) }
+                    if !profile_avatar.read().is_empty() {
+                        img { class: "avatar", src: "{profile_avatar}", alt: "Avatar", width: "48", height: "48" }
+                    }
+                    button { onclick: on_save_profile, "Save Profile" }
+                }
                 div { class: "section",
                     h3 { "Enable 2FA" }
                     toggle { checked: *two_fa_enabled.read(), onchange: on_two_fa_toggle }
@@ -364,3 +607,10 @@ pub fn Settings() -> Element {
         }
     }
 }
+
+fn date_label(timestamp: i64) -> String {
+    Utc.timestamp_opt(timestamp, 0)
+        .single()
+        .map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_default()
+}
@@ -0,0 +1,179 @@
+use dioxus::prelude::*;
+use dioxus_motion::use_animated;
+use uuid::Uuid;
+
+use crate::blockchain::{
+    ReceiveAddress, ReserveCheck, ReserveReport, WalletManager, verify_reserve_report,
+};
+use crate::errors::ZipError;
+use crate::ui::components::{ErrorDisplay, Notification};
+use crate::ui::styles::global_styles;
+use crate::utils::Crypto;
+
+#[component]
+pub fn SignMessage() -> Element {
+    let wallet = use_context::<WalletManager>();
+    let user_id = use_signal(|| Uuid::new_v4());
+    let addresses = use_signal(|| Vec::<ReceiveAddress>::new());
+    let sign_address = use_signal(|| String::new());
+    let sign_text = use_signal(|| String::new());
+    let signature = use_signal(|| None::<String>);
+    let verify_address = use_signal(|| String::new());
+    let verify_text = use_signal(|| String::new());
+    let verify_signature = use_signal(|| String::new());
+    let verified = use_signal(|| None::<bool>);
+    let challenge = use_signal(|| String::new());
+    let report = use_signal(|| None::<String>);
+    let report_input = use_signal(|| String::new());
+    let report_check = use_signal(|| None::<ReserveCheck>);
+    let error = use_signal(|| None::<ZipError>);
+    let notification = use_signal(|| None::<String>);
+    let animated = use_animated(|style| style.opacity(1.0).duration(0.5));
+
+    use_effect(move || {
+        // Hand out a first address only when the user has none to sign with
+        let user = *user_id.read();
+        let list = match wallet.get_receive_addresses(user) {
+            Ok(list) if list.is_empty() => wallet
+                .receive_address(user)
+                .and_then(|_| wallet.get_receive_addresses(user)),
+            other => other,
+        };
+        match list {
+            Ok(list) => {
+                if let Some(first) = list.last() {
+                    sign_address.set(first.address.clone());
+                }
+                addresses.set(list);
+            }
+            Err(e) => error.set(Some(e)),
+        }
+    });
+
+    let on_sign = move |_| {
+        match wallet.sign_message(*user_id.read(), &sign_address.read(), &sign_text.read()) {
+            Ok(result) => {
+                signature.set(Some(result));
+                notification.set(Some("Message signed".to_string()));
+            }
+            Err(e) => error.set(Some(e)),
+        }
+    };
+
+    let on_verify = move |_| {
+        match Crypto::verify_bitcoin_message(
+            verify_address.read().trim(),
+            &verify_text.read(),
+            &verify_signature.read(),
+        ) {
+            Ok(valid) => verified.set(Some(valid)),
+            Err(e) => {
+                verified.set(Some(false));
+                error.set(Some(e));
+            }
+        }
+    };
+
+    let on_export = move |_| {
+        match ReserveReport::create(&wallet, *user_id.read(), &challenge.read())
+            .and_then(|r| r.to_json())
+        {
+            Ok(json) => {
+                report.set(Some(json));
+                notification.set(Some("Proof of reserves created".to_string()));
+            }
+            Err(e) => error.set(Some(e)),
+        }
+    };
+
+    let on_check_report = move |_| match verify_reserve_report(&report_input.read()) {
+        Ok(result) => report_check.set(Some(result)),
+        Err(e) => error.set(Some(e)),
+    };
+
+    rsx! {
+        div {
+            class: "sign-message",
+            style: format!("{{{global_styles()}}} {}", animated),
+            h2 { class: "title", "Sign & Verify" }
+            div { class: "section",
+                h3 { "Sign Message" }
+                select {
+                    onchange: move |evt: Event<FormData>| sign_address.set(evt.value()),
+                    for receive in addresses.read().iter().rev() {
+                        option { value: "{receive.address}", "{receive.address}" }
+                    }
+                }
+                textarea {
+                    placeholder: "Message to sign",
+                    oninput: move |evt: Event<FormData>| sign_text.set(evt.value()),
+                }
+                button { onclick: on_sign, "Sign" }
+                if let Some(result) = signature.read().as_ref() {
+                    div { class: "qr-text", "{result}" }
+                }
+            }
+            div { class: "section",
+                h3 { "Verify Message" }
+                input {
+                    r#type: "text",
+                    placeholder: "Address",
+                    oninput: move |evt: Event<FormData>| verify_address.set(evt.value()),
+                }
+                textarea {
+                    placeholder: "Message",
+                    oninput: move |evt: Event<FormData>| verify_text.set(evt.value()),
+                }
+                input {
+                    r#type: "text",
+                    placeholder: "Signature (base64)",
+                    oninput: move |evt: Event<FormData>| verify_signature.set(evt.value()),
+                }
+                button { onclick: on_verify, "Verify" }
+                match *verified.read() {
+                    Some(true) => rsx! { div { class: "status-paid", "Signature is valid for this address" } },
+                    Some(false) => rsx! { div { class: "error", "Signature is not valid for this address" } },
+                    None => rsx! {},
+                }
+            }
+            div { class: "section",
+                h3 { "Proof of Reserves" }
+                input {
+                    r#type: "text",
+                    placeholder: "Challenge from the auditor or counterparty",
+                    oninput: move |evt: Event<FormData>| challenge.set(evt.value()),
+                }
+                button { onclick: on_export, "Sign With All Funded Addresses" }
+                if let Some(json) = report.read().as_ref() {
+                    textarea { class: "report", readonly: true, value: "{json}" }
+                }
+                textarea {
+                    placeholder: "Paste a proof of reserves report to verify",
+                    oninput: move |evt: Event<FormData>| report_input.set(evt.value()),
+                }
+                button { onclick: on_check_report, "Verify Report" }
+                if let Some(result) = report_check.read().as_ref() {
+                    div { class: if matches!(result, ReserveCheck::Valid { .. }) { "status-paid" } else { "error" },
+                        "{reserve_label(result)}"
+                    }
+                }
+            }
+            ErrorDisplay { error: *error.read() }
+            Notification { message: *notification.read(), is_success: true }
+        }
+    }
+}
+
+fn reserve_label(result: &ReserveCheck) -> String {
+    match result {
+        ReserveCheck::Valid { total } => format!("Valid: controls {} satoshis", total),
+        ReserveCheck::InvalidSignature(address) => format!("Invalid signature for {}", address),
+        ReserveCheck::ForeignUtxo { address, outpoint } => {
+            format!("{} is not locked to {}", outpoint, address)
+        }
+        ReserveCheck::DuplicateUtxo(outpoint) => format!("{} is listed twice", outpoint),
+        ReserveCheck::TotalMismatch { claimed, computed } => {
+            format!("Claims {} satoshis but lists {}", claimed, computed)
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use chrono::{TimeZone, Utc};
+use dioxus::prelude::*;
+use dioxus_motion::use_animated;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::blockchain::{LockKind, LockedTx, WalletManager};
+use crate::errors::ZipError;
+use crate::ui::components::{ErrorDisplay, Loading, Notification};
+use crate::ui::styles::global_styles;
+
+#[component]
+pub fn Vault() -> Element {
+    let wallet = use_context::<WalletManager>();
+    let user_id = use_signal(|| Uuid::new_v4());
+    let locked = use_signal(|| Vec::<LockedTx>::new());
+    let vault_balance = use_signal(|| 0u64);
+    let amount = use_signal(|| 0u64);
+    let unlock_height = use_signal(|| 0u32);
+    let error = use_signal(|| None::<ZipError>);
+    let notification = use_signal(|| None::<String>);
+    let is_loading = use_signal(|| false);
+    let animated = use_animated(|style| style.opacity(1.0).duration(0.5));
+
+    use_effect(move || async move {
+        // Broadcast locked transactions in the background once they become final
+        wallet.start_timelock_watcher(*user_id.read(), Duration::from_secs(60));
+        match wallet.locked_funds(*user_id.read()) {
+            Ok(funds) => locked.set(funds),
+            Err(e) => error.set(Some(e)),
+        }
+        match wallet.vault_balance(*user_id.read()) {
+            Ok(balance) => vault_balance.set(balance),
+            Err(e) => error.set(Some(e)),
+        }
+    });
+
+    let on_create = move |_| async move {
+        if *amount.read() == 0 || *unlock_height.read() == 0 {
+            error.set(Some(ZipError::Validation(
+                "Enter an amount and unlock height".to_string(),
+            )));
+            return;
+        }
+        is_loading.set(true);
+        match wallet
+            .create_vault(*user_id.read(), *amount.read(), 1000, *unlock_height.read())
+            .await
+        {
+            Ok(vault) => {
+                notification.set(Some(format!(
+                    "Locked {} satoshis until block {}",
+                    vault.amount, vault.lock_time
+                )));
+                let mut funds = locked.read().clone();
+                funds.push(vault);
+                locked.set(funds);
+                if let Ok(balance) = wallet.vault_balance(*user_id.read()) {
+                    vault_balance.set(balance);
+                }
+            }
+            Err(e) => error.set(Some(e)),
+        }
+        is_loading.set(false);
+    };
+
+    rsx! {
+        div {
+            class: "vault",
+            style: format!("{{{global_styles()}}} {}", animated),
+            h2 { class: "title", "Vault" }
+            div { class: "balance-sub", "{vault_balance} satoshis in vaults" }
+            div { class: "section",
+                h3 { "Lock Savings" }
+                input {
+                    r#type: "number",
+                    placeholder: "Amount in satoshis",
+                    oninput: move |evt: Event<FormData>| amount.set(evt.value().parse().unwrap_or(0)),
+                    disabled: *is_loading.read()
+                }
+                input {
+                    r#type: "number",
+                    placeholder: "Unlock at block height",
+                    oninput: move |evt: Event<FormData>| unlock_height.set(evt.value().parse().unwrap_or(0)),
+                    disabled: *is_loading.read()
+                }
+                button { onclick: on_create, disabled: *is_loading.read(), "Lock Funds" }
+            }
+            div { class: "vault-list",
+                div { class: "header", "Type" }
+                div { class: "header", "Amount" }
+                div { class: "header", "Unlocks" }
+                div { class: "header", "To" }
+                for item in locked.read().iter() {
+                    div { if item.kind == LockKind::Vault { "Vault" } else { "Scheduled payment" } }
+                    div { "{item.amount} satoshis" }
+                    div { "{unlock_label(item)}" }
+                    div { "{item.counterparty}" }
+                }
+            }
+            ErrorDisplay { error: *error.read() }
+            Notification { message: *notification.read(), is_success: true }
+            if *is_loading.read() {
+                Loading { message: "Locking funds".to_string() }
+            }
+        }
+    }
+}
+
+fn unlock_label(item: &LockedTx) -> String {
+    match item.unlock_height() {
+        Some(height) => format!("Block {}", height),
+        None => Utc
+            .timestamp_opt(item.lock_time as i64, 0)
+            .single()
+            .map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+            .unwrap_or_default(),
+    }
+}
@@ -3,29 +3,41 @@ use dioxus_router::prelude::*;
 
 use crate::auth::auth::AuthManager;
 use crate::blockchain::{
+    invoice::Invoices as InvoiceBook,
+    notary::Notary as NotaryBook,
     paymail::PaymailManager,
+    schedule::Scheduler,
     transaction::TransactionManager,
     wallet::WalletManager,
 };
+use crate::config::EnvConfig;
 use crate::integrations::rustbus::RustBusIntegrator;
+use crate::server::{BRC100_PORT, PAYMAIL_PORT, PaymailHost, WalletInterface};
 use crate::storage::ZipStorage;
 use crate::ui::components::{
     auth::Auth,
     auth_callback::AuthCallback,
+    connected_apps::ConnectedApps,
     dashboard::Dashboard,
     error::Error,
     history::History,
     home::Home,
+    invoices::Invoices,
     logout::Logout,
     nav::NavBar,
+    notary::Notary,
     payment_form::PaymentForm,
     profile::Profile,
+    receive::Receive,
+    schedules::Schedules,
     settings::Settings,
-    swipe_button::SwipeButton,
+    sign_message::SignMessage,
+    vault::Vault,
 };
 use crate::ui::styles::global_styles;
 use crate::utils::session::Session;
 use std::sync::Arc;
+use std::time::Duration;
 
 #[derive(Routable, Clone, PartialEq)]
 #[rustfmt::skip]
@@ -46,9 +58,30 @@ pub enum Route {
     #[route("/payment")]
     Payment,
     #[layout(Nav)]
+    #[route("/receive")]
+    ReceiveRoute,
+    #[layout(Nav)]
+    #[route("/invoices")]
+    InvoicesRoute,
+    #[layout(Nav)]
     #[route("/history")]
     HistoryRoute,
     #[layout(Nav)]
+    #[route("/vault")]
+    VaultRoute,
+    #[layout(Nav)]
+    #[route("/schedules")]
+    SchedulesRoute,
+    #[layout(Nav)]
+    #[route("/notary")]
+    NotaryRoute,
+    #[layout(Nav)]
+    #[route("/sign")]
+    SignMessageRoute,
+    #[layout(Nav)]
+    #[route("/apps")]
+    ConnectedAppsRoute,
+    #[layout(Nav)]
     #[route("/settings")]
     SettingsRoute,
     #[layout(Nav)]
@@ -84,6 +117,23 @@ pub fn AppRouter(cx: Scope) -> Element {
     let auth = AuthManager::new(Arc::clone(&storage)).expect("Failed to initialize auth");
     let paymail = PaymailManager::new(Arc::clone(&storage));
     let session = Session::new(Arc::clone(&storage)).expect("Failed to initialize session");
+    let scheduler = Scheduler::new(Arc::clone(&storage));
+    let invoice_book = InvoiceBook::new(Arc::clone(&storage));
+    let notary = NotaryBook::new(Arc::clone(&storage));
+    // Apps act on the wallet of whoever is signed in
+    let wallet_interface = WalletInterface::for_session(
+        (*wallet).clone(),
+        Arc::clone(&tx_manager),
+        Arc::clone(&storage),
+    );
+    let brc100 = wallet_interface.clone();
+    let paymail_host = EnvConfig::load().ok().and_then(|config| {
+        let url = config.paymail_host_url?;
+        Some(
+            PaymailHost::new(paymail.clone(), (*wallet).clone(), Arc::clone(&storage), &url)
+                .with_trusted_proxies(config.paymail_trusted_proxies),
+        )
+    });
 
     use_effect(cx, || async move {
         // Authentication check
@@ -109,6 +159,45 @@ pub fn AppRouter(cx: Scope) -> Element {
         // Settings update
     });
 
+    let schedule_runner = (scheduler.clone(), (*wallet).clone(), paymail.clone());
+    use_effect(cx, || async move {
+        // Recurring payments run from launch, whichever page is open
+        let (scheduler, wallet, paymail) = schedule_runner;
+        if let Err(e) = scheduler.start_all(wallet, paymail, Duration::from_secs(60)) {
+            tracing::warn!("Scheduled payments unavailable: {}", e);
+        }
+    });
+
+    let auction_settler = ((*wallet).clone(), paymail.clone());
+    use_effect(cx, || async move {
+        // Ended auctions settle in the background, whichever page is open
+        let (wallet, paymail) = auction_settler;
+        paymail.start_auction_settler(wallet, Duration::from_secs(60));
+    });
+
+    let expiry_sweeper = ((*wallet).clone(), paymail.clone());
+    use_effect(cx, || async move {
+        // Renewals settle and lapsed aliases go back to the pool in the background
+        let (wallet, paymail) = expiry_sweeper;
+        paymail.start_expiry_sweeper(wallet, Duration::from_secs(60));
+    });
+
+    use_effect(cx, || async move {
+        // BRC-100 interface for third-party apps on localhost
+        if let Err(e) = brc100.serve(BRC100_PORT).await {
+            tracing::warn!("BRC-100 interface unavailable: {}", e);
+        }
+    });
+
+    use_effect(cx, || async move {
+        // PayMail host for the aliases we issue, when a public URL is configured
+        if let Some(host) = paymail_host {
+            if let Err(e) = host.serve(PAYMAIL_PORT).await {
+                tracing::warn!("PayMail host unavailable: {}", e);
+            }
+        }
+    });
+
     cx.render(rsx! {
         Router::<Route> {
             ContextProvider {
@@ -125,7 +214,19 @@ pub fn AppRouter(cx: Scope) -> Element {
                                     value: paymail,
                                     ContextProvider {
                                         value: session,
-                                        Outlet::<Route> {}
+                                        ContextProvider {
+                                            value: scheduler,
+                                            ContextProvider {
+                                                value: invoice_book,
+                                                ContextProvider {
+                                                    value: notary,
+                                                    ContextProvider {
+                                                        value: wallet_interface,
+                                                        Outlet::<Route> {}
+                                                    }
+                                                }
+                                            }
+                                        }
                                     }
                                 }
                             }
@@ -182,14 +283,35 @@ fn Payment(cx: Scope) -> Element {
         }
     });
 
-    cx.render(rsx! {
-        PaymentForm {}
-        SwipeButton {
-            recipient: "example@paymail.com",
-            amount: 1000,
-            "Pay 1000 satoshis to example@paymail.com"
+    cx.render(rsx! { PaymentForm {} })
+}
+
+#[component]
+fn ReceiveRoute(cx: Scope) -> Element {
+    let session = use_context::<Session>().unwrap();
+    let user_id = use_signal(|| Uuid::new_v4());
+
+    use_effect(cx, || async move {
+        if !session.is_authenticated(*user_id.read()).await {
+            router().push(Route::Auth);
         }
-    })
+    });
+
+    cx.render(rsx! { Receive {} })
+}
+
+#[component]
+fn InvoicesRoute(cx: Scope) -> Element {
+    let session = use_context::<Session>().unwrap();
+    let user_id = use_signal(|| Uuid::new_v4());
+
+    use_effect(cx, || async move {
+        if !session.is_authenticated(*user_id.read()).await {
+            router().push(Route::Auth);
+        }
+    });
+
+    cx.render(rsx! { Invoices {} })
 }
 
 #[component]
@@ -206,6 +328,76 @@ fn HistoryRoute(cx: Scope) -> Element {
     cx.render(rsx! { History {} })
 }
 
+#[component]
+fn VaultRoute(cx: Scope) -> Element {
+    let session = use_context::<Session>().unwrap();
+    let user_id = use_signal(|| Uuid::new_v4());
+
+    use_effect(cx, || async move {
+        if !session.is_authenticated(*user_id.read()).await {
+            router().push(Route::Auth);
+        }
+    });
+
+    cx.render(rsx! { Vault {} })
+}
+
+#[component]
+fn SchedulesRoute(cx: Scope) -> Element {
+    let session = use_context::<Session>().unwrap();
+    let user_id = use_signal(|| Uuid::new_v4());
+
+    use_effect(cx, || async move {
+        if !session.is_authenticated(*user_id.read()).await {
+            router().push(Route::Auth);
+        }
+    });
+
+    cx.render(rsx! { Schedules {} })
+}
+
+#[component]
+fn NotaryRoute(cx: Scope) -> Element {
+    let session = use_context::<Session>().unwrap();
+    let user_id = use_signal(|| Uuid::new_v4());
+
+    use_effect(cx, || async move {
+        if !session.is_authenticated(*user_id.read()).await {
+            router().push(Route::Auth);
+        }
+    });
+
+    cx.render(rsx! { Notary {} })
+}
+
+#[component]
+fn SignMessageRoute(cx: Scope) -> Element {
+    let session = use_context::<Session>().unwrap();
+    let user_id = use_signal(|| Uuid::new_v4());
+
+    use_effect(cx, || async move {
+        if !session.is_authenticated(*user_id.read()).await {
+            router().push(Route::Auth);
+        }
+    });
+
+    cx.render(rsx! { SignMessage {} })
+}
+
+#[component]
+fn ConnectedAppsRoute(cx: Scope) -> Element {
+    let session = use_context::<Session>().unwrap();
+    let user_id = use_signal(|| Uuid::new_v4());
+
+    use_effect(cx, || async move {
+        if !session.is_authenticated(*user_id.read()).await {
+            router().push(Route::Auth);
+        }
+    });
+
+    cx.render(rsx! { ConnectedApps {} })
+}
+
 #[component]
 fn SettingsRoute(cx: Scope) -> Element {
     let session = use_context::<Session>().unwrap();
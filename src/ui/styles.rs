@@ -9,9 +9,33 @@ pub fn global_styles() -> &'static str {
         .balance-main { font-size: 2.5em; font-weight: bold; color: #333; }
         .balance-sub { font-size: 1.2em; color: #666; }
         .payment-form { display: flex; flex-direction: column; gap: 10px; padding: 20px; }
+        .confirmation-sheet { display: flex; flex-direction: column; gap: 6px; padding: 15px; border: 1px solid #ddd; border-radius: 8px; background-color: #fafafa; }
+        .confirmation-sheet .total { font-weight: bold; }
         .swipe-button { width: 200px; height: 50px; background-color: #4caf50; color: white; text-align: center; line-height: 50px; transition: transform 0.3s ease; cursor: pointer; }
         .history-grid { display: grid; grid-template-columns: 100px 120px 140px 200px 140px 200px; gap: 10px; overflow-y: auto; max-height: 80vh; font-size: 14px; padding: 10px; border: 1px solid #ddd; }
         .header { font-weight: bold; background-color: #f0f0f0; padding: 8px; }
+        .vault { display: flex; flex-direction: column; gap: 10px; padding: 20px; }
+        .receive { display: flex; flex-direction: column; gap: 10px; padding: 20px; max-width: 600px; margin: auto; }
+        .receive-codes { display: flex; flex-wrap: wrap; gap: 20px; justify-content: center; }
+        .qr-card { display: flex; flex-direction: column; align-items: center; gap: 6px; padding: 15px; border: 1px solid #ddd; border-radius: 8px; }
+        .qr-text { font-family: monospace; word-break: break-all; }
+        .qr-uri { font-size: 12px; color: #666; word-break: break-all; }
+        .schedules { display: flex; flex-direction: column; gap: 10px; padding: 20px; }
+        .schedule-list { display: grid; grid-template-columns: 120px 1fr 120px 170px 60px 160px; gap: 10px; font-size: 14px; }
+        .invoices { display: flex; flex-direction: column; gap: 10px; padding: 20px; }
+        .invoice-list { display: grid; grid-template-columns: 110px 1fr 150px 150px 110px 160px; gap: 10px; font-size: 14px; }
+        .status-paid { color: #2e7d32; }
+        .status-expired { color: #999; }
+        .sign-message { display: flex; flex-direction: column; gap: 10px; padding: 20px; max-width: 600px; margin: auto; }
+        .sign-message textarea { min-height: 80px; }
+        .sign-message .report { min-height: 200px; font-family: monospace; font-size: 12px; }
+        .connected-apps { display: flex; flex-direction: column; gap: 10px; padding: 20px; }
+        .connected-apps .prompt { display: flex; flex-direction: column; gap: 8px; padding: 12px; border: 1px solid #ccc; border-radius: 8px; }
+        .app-list { display: grid; grid-template-columns: 1fr 140px 140px 100px; gap: 10px; font-size: 14px; word-break: break-all; }
+        .notary { display: flex; flex-direction: column; gap: 10px; padding: 20px; }
+        .receipt-list { display: grid; grid-template-columns: 140px 1fr 1fr 170px 150px; gap: 10px; font-size: 14px; word-break: break-all; }
+        .run-log { display: flex; flex-direction: column; gap: 4px; font-size: 13px; color: #666; }
+        .vault-list { display: grid; grid-template-columns: 140px 140px 160px 1fr; gap: 10px; font-size: 14px; }
         .delta-positive { color: green; }
         .delta-negative { color: red; }
         .txid-link { color: #007bff; text-decoration: none; display: flex; align-items: center; gap: 4px; }
@@ -33,6 +57,12 @@ pub fn global_styles() -> &'static str {
             .dashboard { padding: 10px; }
             .balance-main { font-size: 2em; }
             .paymail-list { grid-template-columns: 1fr; }
+            .vault-list { grid-template-columns: 1fr; }
+            .schedule-list { grid-template-columns: 1fr; }
+            .invoice-list { grid-template-columns: 1fr; }
+            .receipt-list { grid-template-columns: 1fr; }
+            .app-list { grid-template-columns: 1fr; }
+            .receive-codes { flex-direction: column; }
             .navbar { flex-direction: column; gap: 10px; }
         }
     "#
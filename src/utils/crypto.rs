@@ -1,10 +1,29 @@
 use crate::errors::ZipError;
+use aes::Aes128;
+use aes::cipher::block_padding::Pkcs7;
+use aes::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use hmac::{Hmac, Mac};
 use rand::RngCore;
 use rand::rngs::OsRng;
-use secp256k1::{Secp256k1, SecretKey};
+use secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
+use secp256k1::{Scalar, Secp256k1, SecretKey};
+use sha2::{Digest, Sha256, Sha512};
 use sv::public_key::PublicKey;
 use sv::util::hash160;
 
+/// Prefix that stops a signed message from doubling as a signed transaction.
+pub const MESSAGE_MAGIC: &str = "Bitcoin Signed Message:\n";
+
+/// Magic prefix of Electrum-compatible ECIES ciphertexts.
+pub const BIE1_MAGIC: &[u8] = b"BIE1";
+/// Magic, ephemeral public key, one AES block and the HMAC tag.
+const BIE1_MIN_SIZE: usize = 4 + 33 + 16 + 32;
+
+/// BRC-29 payment protocol ID, used in BRC-43 invoice numbers for payment keys.
+pub const BRC29_PROTOCOL_ID: &str = "3241645161d8";
+
 pub struct Crypto;
 
 impl Crypto {
@@ -62,4 +81,240 @@ impl Crypto {
             )
             .is_ok())
     }
+
+    /// Hash signed by "Bitcoin Signed Message" signatures:
+    /// sha256d(varint(len(magic)) || magic || varint(len(message)) || message).
+    pub fn message_hash(message: &str) -> [u8; 32] {
+        let mut data = vec![];
+        for part in [MESSAGE_MAGIC.as_bytes(), message.as_bytes()] {
+            match part.len() {
+                len @ 0..=0xfc => data.push(len as u8),
+                len @ 0xfd..=0xffff => {
+                    data.push(0xfd);
+                    data.extend_from_slice(&(len as u16).to_le_bytes());
+                }
+                len => {
+                    data.push(0xfe);
+                    data.extend_from_slice(&(len as u32).to_le_bytes());
+                }
+            }
+            data.extend_from_slice(part);
+        }
+        sv::util::sha256d(&data).0
+    }
+
+    /// Signs a message in the standard "Bitcoin Signed Message" format used by other BSV
+    /// wallets: a base64, 65-byte recoverable signature for a compressed-key address.
+    pub fn sign_bitcoin_message(
+        private_key: &SecretKey,
+        message: &str,
+    ) -> Result<String, ZipError> {
+        let secp = Secp256k1::new();
+        let digest = secp256k1::Message::from_digest(Self::message_hash(message));
+        let (recovery_id, compact) = secp
+            .sign_ecdsa_recoverable(&digest, private_key)
+            .serialize_compact();
+        // 27 + recovery ID, plus 4 because wallet addresses use compressed keys
+        let mut signature = vec![31 + recovery_id.to_i32() as u8];
+        signature.extend_from_slice(&compact);
+        Ok(BASE64.encode(signature))
+    }
+
+    /// Verifies a "Bitcoin Signed Message" signature by recovering the signer's key and
+    /// comparing its P2PKH address with `address`.
+    pub fn verify_bitcoin_message(
+        address: &str,
+        message: &str,
+        signature: &str,
+    ) -> Result<bool, ZipError> {
+        let bytes = BASE64
+            .decode(signature.trim())
+            .map_err(|e| ZipError::Crypto(e.to_string()))?;
+        let (&header, compact) = bytes
+            .split_first()
+            .filter(|(_, rest)| rest.len() == 64)
+            .ok_or_else(|| ZipError::Crypto("Signature must be 65 bytes".to_string()))?;
+        if !(27..=34).contains(&header) {
+            return Err(ZipError::Crypto(format!(
+                "Invalid signature header: {}",
+                header
+            )));
+        }
+        let compressed = header >= 31;
+        let recovery_id = RecoveryId::from_i32(((header - 27) & 3) as i32)
+            .map_err(|e| ZipError::Crypto(e.to_string()))?;
+        let signature = RecoverableSignature::from_compact(compact, recovery_id)
+            .map_err(|e| ZipError::Crypto(e.to_string()))?;
+        let secp = Secp256k1::new();
+        let digest = secp256k1::Message::from_digest(Self::message_hash(message));
+        let Ok(public_key) = secp.recover_ecdsa(&digest, &signature) else {
+            return Ok(false);
+        };
+        let key_bytes = if compressed {
+            public_key.serialize().to_vec()
+        } else {
+            public_key.serialize_uncompressed().to_vec()
+        };
+        let recovered =
+            sv::address::Address::p2pkh(&hash160(&key_bytes), sv::network::Network::Mainnet)
+                .to_string()
+                .unwrap_or_default();
+        Ok(recovered == address)
+    }
+
+    /// Derives the BIE1 IV, AES key and HMAC key from an ECDH shared point.
+    fn bie1_keys(shared: &secp256k1::PublicKey) -> ([u8; 16], [u8; 16], [u8; 32]) {
+        let digest = Sha512::digest(shared.serialize());
+        let mut iv = [0u8; 16];
+        let mut key_e = [0u8; 16];
+        let mut key_m = [0u8; 32];
+        iv.copy_from_slice(&digest[..16]);
+        key_e.copy_from_slice(&digest[16..32]);
+        key_m.copy_from_slice(&digest[32..]);
+        (iv, key_e, key_m)
+    }
+
+    /// Encrypts to a public key with Electrum-compatible ECIES (BIE1):
+    /// "BIE1" || ephemeral pubkey || AES-128-CBC ciphertext || HMAC-SHA256.
+    pub fn encrypt_bie1(public_key: &PublicKey, plaintext: &[u8]) -> Result<Vec<u8>, ZipError> {
+        let secp = Secp256k1::new();
+        let recipient = secp256k1::PublicKey::from_slice(public_key.to_bytes())
+            .map_err(|e| ZipError::Crypto(e.to_string()))?;
+        let ephemeral = Self::generate_private_key()?;
+        let shared = recipient
+            .mul_tweak(&secp, &Scalar::from(ephemeral))
+            .map_err(|e| ZipError::Crypto(e.to_string()))?;
+        let (iv, key_e, key_m) = Self::bie1_keys(&shared);
+        let mut data = BIE1_MAGIC.to_vec();
+        data.extend_from_slice(
+            &secp256k1::PublicKey::from_secret_key(&secp, &ephemeral).serialize(),
+        );
+        data.extend_from_slice(
+            &cbc::Encryptor::<Aes128>::new(&key_e.into(), &iv.into())
+                .encrypt_padded_vec_mut::<Pkcs7>(plaintext),
+        );
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&key_m)
+            .map_err(|e| ZipError::Crypto(e.to_string()))?;
+        mac.update(&data);
+        data.extend_from_slice(&mac.finalize().into_bytes());
+        Ok(data)
+    }
+
+    /// Decrypts a BIE1 ciphertext, rejecting it if the HMAC does not match.
+    pub fn decrypt_bie1(private_key: &SecretKey, data: &[u8]) -> Result<Vec<u8>, ZipError> {
+        if data.len() < BIE1_MIN_SIZE || !data.starts_with(BIE1_MAGIC) {
+            return Err(ZipError::Crypto("Not a BIE1 ciphertext".to_string()));
+        }
+        let (body, tag) = data.split_at(data.len() - 32);
+        let ephemeral = secp256k1::PublicKey::from_slice(&body[4..37])
+            .map_err(|e| ZipError::Crypto(e.to_string()))?;
+        let shared = ephemeral
+            .mul_tweak(&Secp256k1::new(), &Scalar::from(*private_key))
+            .map_err(|e| ZipError::Crypto(e.to_string()))?;
+        let (iv, key_e, key_m) = Self::bie1_keys(&shared);
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&key_m)
+            .map_err(|e| ZipError::Crypto(e.to_string()))?;
+        mac.update(body);
+        mac.verify_slice(tag)
+            .map_err(|_| ZipError::Crypto("Invalid ciphertext MAC".to_string()))?;
+        cbc::Decryptor::<Aes128>::new(&key_e.into(), &iv.into())
+            .decrypt_padded_vec_mut::<Pkcs7>(&body[37..])
+            .map_err(|e| ZipError::Crypto(e.to_string()))
+    }
+
+    /// Encrypts a text message to a public key, base64-encoded as Electrum does.
+    pub fn encrypt_message(public_key: &PublicKey, message: &str) -> Result<String, ZipError> {
+        Ok(BASE64.encode(Self::encrypt_bie1(public_key, message.as_bytes())?))
+    }
+
+    /// Decrypts a base64 BIE1 text message.
+    pub fn decrypt_message(private_key: &SecretKey, encrypted: &str) -> Result<String, ZipError> {
+        let data = BASE64
+            .decode(encrypted.trim())
+            .map_err(|e| ZipError::Crypto(e.to_string()))?;
+        String::from_utf8(Self::decrypt_bie1(private_key, &data)?)
+            .map_err(|e| ZipError::Crypto(e.to_string()))
+    }
+
+    /// Whether text looks like a base64 BIE1 ciphertext.
+    pub fn is_encrypted_message(text: &str) -> bool {
+        BASE64
+            .decode(text.trim())
+            .map(|data| data.len() >= BIE1_MIN_SIZE && data.starts_with(BIE1_MAGIC))
+            .unwrap_or(false)
+    }
+
+    /// BRC-43 invoice number for a BRC-29 payment key: security level 2, payment
+    /// protocol, and the sender-chosen derivation prefix and suffix.
+    pub fn payment_invoice_number(prefix: &str, suffix: &str) -> String {
+        format!("2-{}-{} {}", BRC29_PROTOCOL_ID, prefix, suffix)
+    }
+
+    /// BRC-42 tweak: HMAC-SHA256 of the invoice number, keyed with the compressed ECDH
+    /// shared point of our private key and the counterparty's public key.
+    fn brc42_tweak(
+        private_key: &SecretKey,
+        counterparty: &PublicKey,
+        invoice_number: &str,
+    ) -> Result<Scalar, ZipError> {
+        let counterparty = secp256k1::PublicKey::from_slice(counterparty.to_bytes())
+            .map_err(|e| ZipError::Crypto(e.to_string()))?;
+        let shared = counterparty
+            .mul_tweak(&Secp256k1::new(), &Scalar::from(*private_key))
+            .map_err(|e| ZipError::Crypto(e.to_string()))?;
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&shared.serialize())
+            .map_err(|e| ZipError::Crypto(e.to_string()))?;
+        mac.update(invoice_number.as_bytes());
+        Scalar::from_be_bytes(mac.finalize().into_bytes().into())
+            .map_err(|e| ZipError::Crypto(e.to_string()))
+    }
+
+    /// BRC-42 child public key of `counterparty` for an invoice number, computed by the
+    /// other party with its own private key (e.g., a sender deriving where to pay).
+    pub fn derive_child_public_key(
+        private_key: &SecretKey,
+        counterparty: &PublicKey,
+        invoice_number: &str,
+    ) -> Result<PublicKey, ZipError> {
+        let tweak = Self::brc42_tweak(private_key, counterparty, invoice_number)?;
+        let child = secp256k1::PublicKey::from_slice(counterparty.to_bytes())
+            .map_err(|e| ZipError::Crypto(e.to_string()))?
+            .add_exp_tweak(&Secp256k1::new(), &tweak)
+            .map_err(|e| ZipError::Crypto(e.to_string()))?;
+        PublicKey::from_slice(&child.serialize()).map_err(|e| ZipError::Crypto(e.to_string()))
+    }
+
+    /// BRC-42 child private key matching `derive_child_public_key`, computed by the key
+    /// owner from the counterparty's public key.
+    pub fn derive_child_private_key(
+        private_key: &SecretKey,
+        counterparty: &PublicKey,
+        invoice_number: &str,
+    ) -> Result<SecretKey, ZipError> {
+        let tweak = Self::brc42_tweak(private_key, counterparty, invoice_number)?;
+        private_key
+            .add_tweak(&tweak)
+            .map_err(|e| ZipError::Crypto(e.to_string()))
+    }
+
+    /// Signs a 32-byte digest, returning a DER-encoded ECDSA signature.
+    pub fn sign_digest(private_key: &SecretKey, digest: [u8; 32]) -> Vec<u8> {
+        Secp256k1::new()
+            .sign_ecdsa(&secp256k1::Message::from_digest(digest), private_key)
+            .serialize_der()
+            .to_vec()
+    }
+
+    /// Verifies a DER-encoded ECDSA signature over a 32-byte digest.
+    pub fn verify_digest(public_key: &PublicKey, digest: [u8; 32], signature: &[u8]) -> bool {
+        let (Ok(key), Ok(sig)) = (
+            secp256k1::PublicKey::from_slice(public_key.to_bytes()),
+            secp256k1::ecdsa::Signature::from_der(signature),
+        ) else {
+            return false;
+        };
+        Secp256k1::new()
+            .verify_ecdsa(&secp256k1::Message::from_digest(digest), &sig, &key)
+            .is_ok()
+    }
 }
@@ -3,6 +3,8 @@ pub mod cache;
 pub mod crypto;
 pub mod logging;
 pub mod misc;
+pub mod payment_uri;
+pub mod qr;
 pub mod rate_limiter;
 pub mod security;
 pub mod session;
@@ -14,6 +16,8 @@ pub use cache::Cache;
 pub use crypto::Crypto;
 pub use logging::setup_logging;
 pub use misc::generate_salt;
+pub use payment_uri::{ParsedPayment, PaymentTarget, PaymentUri};
+pub use qr::Qr;
 pub use rate_limiter::RateLimiter;
 pub use security::Security;
 pub use session::Session;
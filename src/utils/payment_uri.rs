@@ -0,0 +1,308 @@
+use std::fmt;
+use sv::network::Network;
+
+use crate::errors::ZipError;
+use crate::utils::validation::Validation;
+
+/// Satoshis per BSV, used for BIP21 decimal amounts.
+pub const SATOSHIS_PER_BSV: u64 = 100_000_000;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum UriScheme {
+    /// `bitcoin:<address>` (BIP21).
+    Bitcoin,
+    /// `pay:<paymail>` for PayMail handles.
+    Pay,
+}
+
+/// Payment URI shared as a link or QR code.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PaymentUri {
+    pub scheme: UriScheme,
+    /// Address or PayMail handle.
+    pub target: String,
+    /// Requested amount in satoshis.
+    pub amount: Option<u64>,
+    pub label: Option<String>,
+    pub message: Option<String>,
+}
+
+impl PaymentUri {
+    /// URI requesting payment to a BSV address.
+    pub fn bitcoin(address: &str) -> Self {
+        Self {
+            scheme: UriScheme::Bitcoin,
+            target: address.to_string(),
+            amount: None,
+            label: None,
+            message: None,
+        }
+    }
+
+    /// URI requesting payment to a PayMail handle.
+    pub fn paymail(handle: &str) -> Self {
+        Self {
+            scheme: UriScheme::Pay,
+            target: handle.to_string(),
+            amount: None,
+            label: None,
+            message: None,
+        }
+    }
+
+    /// Sets the requested amount (zero clears it).
+    pub fn with_amount(mut self, satoshis: u64) -> Self {
+        self.amount = (satoshis > 0).then_some(satoshis);
+        self
+    }
+
+    /// Sets the label shown to the payer (empty clears it).
+    pub fn with_label(mut self, label: &str) -> Self {
+        self.label = (!label.is_empty()).then(|| label.to_string());
+        self
+    }
+
+    /// Sets the message shown to the payer (empty clears it).
+    pub fn with_message(mut self, message: &str) -> Self {
+        self.message = (!message.is_empty()).then(|| message.to_string());
+        self
+    }
+}
+
+impl fmt::Display for PaymentUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let scheme = match self.scheme {
+            UriScheme::Bitcoin => "bitcoin",
+            UriScheme::Pay => "pay",
+        };
+        write!(f, "{}:{}", scheme, self.target)?;
+        let mut params = vec![];
+        if let Some(amount) = self.amount {
+            params.push(format!("amount={}", format_bsv(amount)));
+        }
+        if let Some(label) = &self.label {
+            params.push(format!("label={}", percent_encode(label)));
+        }
+        if let Some(message) = &self.message {
+            params.push(format!("message={}", percent_encode(message)));
+        }
+        if !params.is_empty() {
+            write!(f, "?{}", params.join("&"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Where a parsed payment goes.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PaymentTarget {
+    Address(String),
+    Paymail(String),
+    /// BIP270/BIP272 merchant payment request URL.
+    PaymentRequest(String),
+}
+
+/// Payment details extracted from a typed handle, URI, link or QR payload.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParsedPayment {
+    pub target: PaymentTarget,
+    /// Amount in satoshis.
+    pub amount: Option<u64>,
+    pub label: Option<String>,
+    pub memo: Option<String>,
+}
+
+impl ParsedPayment {
+    /// Parses BIP21 (`bitcoin:`/`bsv:`/`pay:`) URIs, BIP272 `?r=` links, BIP270 payment
+    /// request URLs, bare addresses, PayMail handles and `$handle` aliases.
+    pub fn parse(input: &str) -> Result<Self, ZipError> {
+        let input = input.trim();
+        if input.is_empty() {
+            return Err(ZipError::Validation("Recipient is empty".to_string()));
+        }
+        if let Some((scheme, rest)) = input.split_once(':') {
+            if ["bitcoin", "bsv", "pay"].contains(&scheme.to_lowercase().as_str()) {
+                return Self::parse_uri(rest);
+            }
+        }
+        let lower = input.to_lowercase();
+        if lower.starts_with("https://") || lower.starts_with("http://") {
+            return Ok(Self::new(PaymentTarget::PaymentRequest(input.to_string())));
+        }
+        Ok(Self::new(parse_target(input)?))
+    }
+
+    fn new(target: PaymentTarget) -> Self {
+        Self {
+            target,
+            amount: None,
+            label: None,
+            memo: None,
+        }
+    }
+
+    fn parse_uri(rest: &str) -> Result<Self, ZipError> {
+        let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let mut parsed = Self::new(PaymentTarget::Paymail(String::new()));
+        let mut request_url = None;
+        for pair in query.split('&').filter(|p| !p.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = percent_decode(value)?;
+            match key {
+                "amount" => parsed.amount = Some(parse_bsv(&value)?),
+                "label" => parsed.label = Some(value),
+                "message" | "memo" => parsed.memo = Some(value),
+                "r" => request_url = Some(value),
+                // BIP21: unknown required parameters make the URI unusable
+                k if k.starts_with("req-") => {
+                    return Err(ZipError::Validation(format!(
+                        "Unsupported required parameter: {}",
+                        k
+                    )));
+                }
+                _ => {}
+            }
+        }
+        let path = percent_decode(path)?;
+        parsed.target = match request_url {
+            Some(url) => PaymentTarget::PaymentRequest(url),
+            None => parse_target(&path)?,
+        };
+        Ok(parsed)
+    }
+
+    /// Rejects addresses that belong to a different network.
+    pub fn validate_network(&self, network: Network) -> Result<(), ZipError> {
+        let PaymentTarget::Address(address) = &self.target else {
+            return Ok(());
+        };
+        let prefixes: &[char] = match network {
+            Network::Mainnet => &['1', '3'],
+            _ => &['m', 'n', '2'],
+        };
+        if address.starts_with(prefixes) {
+            Ok(())
+        } else {
+            Err(ZipError::Validation(format!(
+                "Address {} is not valid on this network",
+                address
+            )))
+        }
+    }
+}
+
+/// Classifies a bare recipient: `$handle`, PayMail or address.
+fn parse_target(input: &str) -> Result<PaymentTarget, ZipError> {
+    if let Some(handle) = input.strip_prefix('$') {
+        if !handle.is_empty()
+            && handle
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            return Ok(PaymentTarget::Paymail(format!(
+                "{}@handcash.io",
+                handle.to_lowercase()
+            )));
+        }
+    } else if let Some((local, domain)) = input.split_once('@') {
+        if Validation::validate_paymail_prefix(local).is_ok()
+            && domain.contains('.')
+            && domain
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || ".-".contains(c))
+        {
+            return Ok(PaymentTarget::Paymail(input.to_lowercase()));
+        }
+    } else if is_base58_address(input) {
+        return Ok(PaymentTarget::Address(input.to_string()));
+    }
+    Err(ZipError::Validation(format!(
+        "Unrecognized recipient: {}",
+        input
+    )))
+}
+
+fn is_base58_address(input: &str) -> bool {
+    (26..=35).contains(&input.len())
+        && input.starts_with(['1', '3', 'm', 'n', '2'])
+        && input
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() && !"0OIl".contains(c))
+}
+
+/// Parses a BSV decimal amount into satoshis (e.g., "0.0015" -> 150000).
+pub fn parse_bsv(value: &str) -> Result<u64, ZipError> {
+    let invalid = || ZipError::Validation(format!("Invalid amount: {}", value));
+    let (whole, frac) = value.split_once('.').unwrap_or((value, ""));
+    if (whole.is_empty() && frac.is_empty())
+        || frac.len() > 8
+        || !whole
+            .chars()
+            .chain(frac.chars())
+            .all(|c| c.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+    let whole: u64 = if whole.is_empty() {
+        0
+    } else {
+        whole.parse().map_err(|_| invalid())?
+    };
+    let frac: u64 = format!("{:0<8}", frac).parse().map_err(|_| invalid())?;
+    whole
+        .checked_mul(SATOSHIS_PER_BSV)
+        .and_then(|sats| sats.checked_add(frac))
+        .ok_or_else(invalid)
+}
+
+/// Decodes %XX escapes and '+' as space.
+pub fn percent_decode(value: &str) -> Result<String, ZipError> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = value
+                    .get(i + 1..i + 3)
+                    .and_then(|h| u8::from_str_radix(h, 16).ok())
+                    .ok_or_else(|| ZipError::Validation(format!("Invalid escape in {}", value)))?;
+                decoded.push(hex);
+                i += 3;
+            }
+            b'+' => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            b => {
+                decoded.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).map_err(|e| ZipError::Validation(e.to_string()))
+}
+
+/// Formats satoshis as a BSV decimal without trailing zeros (e.g., 150000 -> "0.0015").
+pub fn format_bsv(satoshis: u64) -> String {
+    let whole = satoshis / SATOSHIS_PER_BSV;
+    let frac = satoshis % SATOSHIS_PER_BSV;
+    if frac == 0 {
+        return whole.to_string();
+    }
+    let frac = format!("{:08}", frac);
+    format!("{}.{}", whole, frac.trim_end_matches('0'))
+}
+
+/// Percent-encodes everything except RFC 3986 unreserved characters.
+pub fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
@@ -0,0 +1,19 @@
+use qrcode::QrCode;
+use qrcode::render::svg;
+
+use crate::errors::ZipError;
+
+/// QR code rendering for addresses, PayMail handles and payment URIs.
+pub struct Qr;
+
+impl Qr {
+    /// Renders data as an SVG QR code at least `size` pixels wide.
+    pub fn svg(data: &str, size: u32) -> Result<String, ZipError> {
+        let code = QrCode::new(data.as_bytes()).map_err(|e| ZipError::Validation(e.to_string()))?;
+        Ok(code
+            .render::<svg::Color>()
+            .min_dimensions(size, size)
+            .quiet_zone(true)
+            .build())
+    }
+}
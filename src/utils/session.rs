@@ -42,6 +42,7 @@ impl Session {
         let serialized =
             bincode::serialize(&session).map_err(|e| ZipError::Storage(e.to_string()))?;
         self.storage.store_user_data(user_id, &serialized)?;
+        self.storage.store_session_user(Some(user_id))?;
         let _ = self
             .telemetry
             .track_auth_event(&user_id.to_string(), "session_create", true)
@@ -58,6 +59,9 @@ impl Session {
     /// Clears session for a user.
     pub async fn clear(&self, user_id: Uuid) -> Result<(), ZipError> {
         self.storage.store_user_data(user_id, &[])?;
+        if self.storage.get_session_user()? == Some(user_id) {
+            self.storage.store_session_user(None)?;
+        }
         let _ = self
             .telemetry
             .track_auth_event(&user_id.to_string(), "session_clear", true)
@@ -65,6 +69,11 @@ impl Session {
         Ok(())
     }
 
+    /// User currently signed in on this device, if any.
+    pub fn current_user(&self) -> Result<Option<Uuid>, ZipError> {
+        self.storage.get_session_user()
+    }
+
     /// Checks if a user is authenticated.
     pub async fn is_authenticated(&self, user_id: Uuid) -> bool {
         self.get(user_id)
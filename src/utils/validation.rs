@@ -1,22 +1,92 @@
 use regex::Regex;
+use std::fmt;
 
 use crate::errors::ZipError;
 
+/// Longest PayMail prefix, in bytes (the RFC 5321 local-part limit).
+pub const MAX_PAYMAIL_PREFIX_LEN: usize = 64;
+
+/// Why a PayMail prefix (the local part before the `@`) was refused.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PrefixViolation {
+    Empty,
+    TooLong { max: usize },
+    TooShort { min: usize },
+    InvalidCharacter(char),
+    MisplacedDot,
+    Reserved(String),
+    Blocked,
+    Confusable { existing: String },
+}
+
+impl fmt::Display for PrefixViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrefixViolation::Empty => write!(f, "PayMail prefix cannot be empty"),
+            PrefixViolation::TooLong { max } => {
+                write!(f, "PayMail prefix must be at most {} characters", max)
+            }
+            PrefixViolation::TooShort { min } => {
+                write!(f, "PayMail prefix must be at least {} characters", min)
+            }
+            PrefixViolation::InvalidCharacter(c) => {
+                write!(f, "PayMail prefix cannot contain '{}'", c)
+            }
+            PrefixViolation::MisplacedDot => write!(
+                f,
+                "PayMail prefix cannot start or end with a dot or have two dots in a row"
+            ),
+            PrefixViolation::Reserved(prefix) => {
+                write!(f, "PayMail prefix '{}' is reserved", prefix)
+            }
+            PrefixViolation::Blocked => write!(f, "PayMail prefix contains a blocked word"),
+            PrefixViolation::Confusable { existing } => {
+                write!(f, "PayMail prefix looks too much like {}", existing)
+            }
+        }
+    }
+}
+
+impl From<PrefixViolation> for ZipError {
+    fn from(violation: PrefixViolation) -> Self {
+        ZipError::Validation(violation.to_string())
+    }
+}
+
 /// Input validation utilities for the Zip wallet.
 pub struct Validation;
 
 impl Validation {
-    /// Validates a PayMail alias prefix (minimum 5 digits).
+    /// Validates a PayMail prefix as an email local part: a dot-atom (RFC 5322) of
+    /// letters, digits and ``!#$%&'*+-/=?^_`{|}~``, with UTF-8 letters and digits
+    /// allowed (RFC 6531), no leading, trailing or doubled dots, and at most 64 bytes.
     pub fn validate_paymail_prefix(prefix: &str) -> Result<(), ZipError> {
-        let re = Regex::new(r"^\d{5,}$")
-            .map_err(|e| ZipError::Validation(format!("Invalid regex: {}", e)))?;
-        if re.is_match(prefix) {
-            Ok(())
-        } else {
-            Err(ZipError::Validation(
-                "PayMail prefix must be 5 or more digits".to_string(),
-            ))
+        if prefix.is_empty() {
+            return Err(PrefixViolation::Empty.into());
+        }
+        if prefix.len() > MAX_PAYMAIL_PREFIX_LEN {
+            return Err(PrefixViolation::TooLong {
+                max: MAX_PAYMAIL_PREFIX_LEN,
+            }
+            .into());
         }
+        if let Some(c) = prefix.chars().find(|c| *c != '.' && !is_atext(*c)) {
+            return Err(PrefixViolation::InvalidCharacter(c).into());
+        }
+        if prefix.starts_with('.') || prefix.ends_with('.') || prefix.contains("..") {
+            return Err(PrefixViolation::MisplacedDot.into());
+        }
+        Ok(())
+    }
+
+    /// Folds characters that look alike (e.g., Cyrillic "о" or "0" for "o", "rn" for
+    /// "m") so prefixes that read the same compare equal.
+    pub fn confusable_skeleton(prefix: &str) -> String {
+        let folded: String = prefix.to_lowercase().chars().map(fold_confusable).collect();
+        folded
+            .replace("rn", "m")
+            .replace("vv", "w")
+            .replace("cl", "d")
     }
 
     /// Validates a TOTP code (6 digits).
@@ -56,3 +126,41 @@ impl Validation {
         }
     }
 }
+
+/// Characters allowed in a dot-atom, besides the dot.
+fn is_atext(c: char) -> bool {
+    c.is_ascii_alphanumeric()
+        || "!#$%&'*+-/=?^_`{|}~".contains(c)
+        || (!c.is_ascii() && c.is_alphanumeric())
+}
+
+/// Maps a lowercase character to the ASCII letter it is usually mistaken for.
+fn fold_confusable(c: char) -> char {
+    match c {
+        // Fullwidth forms
+        '\u{ff01}'..='\u{ff5e}' => char::from_u32(c as u32 - 0xfee0)
+            .map(fold_confusable)
+            .unwrap_or(c),
+        'а' | 'α' => 'a',
+        'с' | 'ϲ' => 'c',
+        'ԁ' => 'd',
+        'е' => 'e',
+        'ɡ' => 'g',
+        'һ' => 'h',
+        'і' | 'ι' | 'ı' => 'i',
+        'ј' => 'j',
+        'κ' | 'к' => 'k',
+        '1' | '|' | 'ӏ' | 'ℓ' => 'l',
+        '0' | 'о' | 'ο' | 'օ' => 'o',
+        'р' | 'ρ' => 'p',
+        'ԛ' => 'q',
+        'ѕ' => 's',
+        'υ' => 'u',
+        'ν' | 'ѵ' => 'v',
+        'ԝ' => 'w',
+        'х' | 'χ' => 'x',
+        'у' => 'y',
+        'ᴢ' => 'z',
+        c => c,
+    }
+}
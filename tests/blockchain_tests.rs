@@ -1,33 +1,131 @@
 use std::sync::Arc;
 use uuid::Uuid;
 
+use axum::Router;
+use axum::routing::{get, post};
 use rust_decimal::Decimal;
+use sv::script::Script;
+use sv::transaction::TxOut;
 
-use crate::blockchain::{PaymailManager, TransactionManager, WalletManager};
+use crate::blockchain::paymail::ALIAS_PURCHASE_TAG;
+use crate::blockchain::{
+    Alerts, AliasLease, AliasPurchase, AliasRegistry, AliasStatus, AuctionStatus,
+    BRC42_PATH_PREFIX, BlockHeader, CatchUp, DataOutput, HeaderStore, HistoryEntry, Invoice,
+    InvoiceAmount, InvoiceDestination, InvoicePayment, InvoiceStatus, Invoices, LockKind, LockedTx,
+    MAX_PROFILE_NAME_LEN, MerkleProof, Notary, NotaryCheck, PaymailManager, Payment, PaymentAck,
+    PaymentRequest, PaymentSchedule, PolicyViolation, PublicProfile, RENEWAL_TIMEOUT_SECS,
+    RESERVATION_TIMEOUT_SECS, Recurrence, Reorg, RequestedOutput, ReserveCheck, ReserveReport,
+    Scheduler, SigningKeys, SpendCheck, SpendingPolicy, TRANSFER_TIMEOUT_SECS, TimeLocks,
+    TransactionManager, TxDirection, TxHistory, TxStatus, Utxo, WalletData, WalletManager,
+    verify_reserve_report,
+};
 use crate::config::EnvConfig;
 use crate::errors::ZipError;
-use crate::integrations::RustBusIntegrator;
-use crate::paymail_config::PaymailConfig;
+use crate::integrations::{ArcResponse, RustBusIntegrator};
+use crate::paymail_config::{MIN_PREFIX_LENGTH, PaymailConfig};
 use crate::storage::ZipStorage;
+use crate::utils::crypto::Crypto;
+use crate::utils::validation::{MAX_PAYMAIL_PREFIX_LEN, PrefixViolation, Validation};
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_pre_create_utxos() {
+    /// Caches `count` coins of `value` satoshis locked to `script`.
+    fn fund_script(
+        tx_manager: &TransactionManager,
+        user_id: Uuid,
+        script: &[u8],
+        count: u32,
+        value: u64,
+    ) -> Vec<Utxo> {
+        let txid = Uuid::new_v4().simple().to_string().repeat(2);
+        let coins: Vec<Utxo> = (0..count)
+            .map(|vout| Utxo {
+                txid: txid.clone(),
+                vout,
+                value,
+                script: script.to_vec(),
+            })
+            .collect();
+        tx_manager.add_utxos(user_id, &coins).unwrap();
+        coins
+    }
+
+    /// Caches coins locked to a fresh key and returns the key to sign for them.
+    fn fund(tx_manager: &TransactionManager, user_id: Uuid, count: u32, value: u64) -> SigningKeys {
+        let key = Crypto::generate_private_key().unwrap();
+        let address = Crypto::generate_address(&Crypto::derive_public_key(&key));
+        let script = WalletManager::address_script(&address).unwrap().0;
+        fund_script(tx_manager, user_id, &script, count, value);
+        SigningKeys::from([(script, key)])
+    }
+
+    /// Caches coins at the user's receive address, so the wallet can sign for them.
+    fn fund_wallet(
+        wallet: &WalletManager,
+        tx_manager: &TransactionManager,
+        user_id: Uuid,
+        count: u32,
+        value: u64,
+    ) {
+        let address = wallet.receive_address(user_id).unwrap().address;
+        let script = WalletManager::address_script(&address).unwrap().0;
+        fund_script(tx_manager, user_id, &script, count, value);
+    }
+
+    #[tokio::test]
+    async fn test_pre_create_utxos() {
         let storage = Arc::new(ZipStorage::new().unwrap());
         let rustbus = None::<Arc<RustBusIntegrator>>;
         let tx_manager = Arc::new(TransactionManager::new(Arc::clone(&storage), rustbus));
         let user_id = Uuid::new_v4();
-        let result = tx_manager
-            .pre_create_utxos(user_id, 5, 10000)
-            .block_on()
+        let keys = fund(&tx_manager, user_id, 1, 60000);
+        let tx = tx_manager
+            .pre_create_utxos(user_id, 5, 10000, 500, &keys)
+            .await
+            .unwrap();
+        // Five split coins and change, all back to the wallet's own key
+        let script = keys.keys().next().unwrap();
+        assert_eq!(tx.outputs.len(), 6);
+        assert!(tx.outputs.iter().all(|o| &o.script.0 == script));
+        assert!(tx.inputs.iter().all(|i| !i.script.0.is_empty()));
+        let utxos = tx_manager.get_cached_utxos(user_id).unwrap();
+        assert_eq!(utxos.len(), 6);
+        assert_eq!(utxos.iter().map(|u| u.value).sum::<u64>(), 59500);
+
+        // Indexed coins are cached once, and never over coins the wallet already tracks
+        let spent = tx_manager
+            .reserved_inputs(user_id, &TransactionManager::txid(&tx))
+            .unwrap();
+        let found = Utxo {
+            txid: "dd".repeat(32),
+            vout: 0,
+            value: 7000,
+            script: script.clone(),
+        };
+        let merged = tx_manager
+            .merge_indexed_utxos(
+                user_id,
+                vec![
+                    spent[0].clone(),
+                    utxos[0].clone(),
+                    found.clone(),
+                    found.clone(),
+                ],
+            )
             .unwrap();
-        assert_eq!(result.len(), 5);
-        let cached = storage.get_utxos(user_id).unwrap().unwrap();
-        let utxos: Vec<TxOut> = bincode::deserialize(&cached).unwrap();
-        assert_eq!(utxos.len(), 5);
+        assert_eq!(merged, vec![found]);
+        assert_eq!(tx_manager.get_cached_utxos(user_id).unwrap().len(), 7);
+
+        // Coins cached without a locking script are never selected
+        let stranger = Uuid::new_v4();
+        fund_script(&tx_manager, stranger, &[], 2, 10000);
+        assert!(tx_manager.select_coins(stranger, 1000, 500).is_err());
+        let result = tx_manager
+            .pre_create_utxos(stranger, 1, 1000, 500, &keys)
+            .await;
+        assert!(matches!(result, Err(ZipError::Blockchain(_))));
     }
 
     #[tokio::test]
@@ -36,17 +134,47 @@ mod tests {
         let rustbus = None::<Arc<RustBusIntegrator>>;
         let tx_manager = Arc::new(TransactionManager::new(Arc::clone(&storage), rustbus));
         let user_id = Uuid::new_v4();
-        // Pre-create UTXOs
-        tx_manager
-            .pre_create_utxos(user_id, 5, 10000)
-            .await
-            .unwrap();
-        let script = Script::default();
+        let keys = fund(&tx_manager, user_id, 5, 10000);
+        // An empty locking script is never taken to mean "pay myself"
         let result = tx_manager
-            .build_payment_tx(user_id, script, 8000, 1000)
+            .build_payment_tx(user_id, Script::default(), 8000, 1000, &[], &keys)
+            .await;
+        assert!(matches!(result, Err(ZipError::Blockchain(_))));
+        let script = Script(hex::decode(format!("76a914{}88ac", "11".repeat(20))).unwrap());
+        let result = tx_manager
+            .build_payment_tx(user_id, script, 8000, 1000, &[], &keys)
             .await
             .unwrap();
         assert!(!result.to_hex().unwrap().is_empty());
+
+        // Every input carries `<signature+FORKID> <public key>` over its BIP143 digest
+        let spent = tx_manager
+            .reserved_inputs(user_id, &TransactionManager::txid(&result))
+            .unwrap();
+        let (locking, key) = keys.iter().next().unwrap();
+        let public_key = Crypto::derive_public_key(key);
+        for (index, input) in result.inputs.iter().enumerate() {
+            let script = &input.script.0;
+            let sig_len = script[0] as usize;
+            assert_eq!(script[sig_len], 0x41);
+            assert_eq!(&script[sig_len + 2..], &public_key.to_bytes()[..]);
+            let digest = TransactionManager::sighash(&result, index, locking, spent[index].value);
+            assert!(Crypto::verify_digest(
+                &public_key,
+                digest,
+                &script[1..sig_len]
+            ));
+        }
+
+        // Coins the wallet holds no key for are never spent unsigned
+        let stranger = Uuid::new_v4();
+        fund_script(&tx_manager, stranger, &[0x51], 1, 10000);
+        let script = Script(hex::decode(format!("76a914{}88ac", "11".repeat(20))).unwrap());
+        let result = tx_manager
+            .build_payment_tx(stranger, script, 5000, 500, &[], &keys)
+            .await;
+        assert!(matches!(result, Err(ZipError::Crypto(_))));
+        assert!(tx_manager.get_reservations(stranger).unwrap().is_empty());
     }
 
     #[tokio::test]
@@ -78,8 +206,18 @@ mod tests {
         let rustbus = None::<Arc<RustBusIntegrator>>;
         let tx_manager = Arc::new(TransactionManager::new(Arc::clone(&storage), rustbus));
         let wallet = WalletManager::new(Arc::clone(&storage), tx_manager, None).unwrap();
-        let address = wallet.get_address().unwrap();
+        let (address, index) = wallet.get_address().unwrap();
         assert!(!address.is_empty());
+        // The persistent counter never hands out an index twice, even to a new manager
+        let restarted = WalletManager::new(
+            Arc::clone(&storage),
+            Arc::new(TransactionManager::new(Arc::clone(&storage), None)),
+            None,
+        )
+        .unwrap();
+        let (next_address, next_index) = restarted.get_address().unwrap();
+        assert!(next_index > index);
+        assert_ne!(next_address, address);
     }
 
     #[tokio::test]
@@ -126,7 +264,7 @@ mod tests {
         assert_eq!(price, Decimal::from(300));
 
         let result = paymail.create_paid_alias(user_id, "a").await;
-        assert!(matches!(result, Err(ZipError::Blockchain(_))));
+        assert!(matches!(result, Err(ZipError::Validation(_))));
     }
 
     #[tokio::test]
@@ -134,10 +272,16 @@ mod tests {
         let storage = Arc::new(ZipStorage::new().unwrap());
         let priv_key = PrivateKey::new();
         let paymail = PaymailManager::new(priv_key, Arc::clone(&storage));
+        let tx_manager = Arc::new(TransactionManager::new(Arc::clone(&storage), None));
+        let wallet = WalletManager::new(Arc::clone(&storage), tx_manager, None).unwrap();
         let user_id = Uuid::new_v4();
         paymail.create_paid_alias(user_id, "54321").await.unwrap();
-        let result = paymail.confirm_alias(user_id, "54321@zip.io").await;
-        assert!(result.is_ok());
+        // A reservation stays inactive until its payment is accepted
+        let result = paymail
+            .confirm_alias(&wallet, user_id, "54321@zip.io")
+            .await;
+        assert!(matches!(result, Err(ZipError::Blockchain(_))));
+        assert_eq!(paymail.alias_owner("54321@zip.io").unwrap(), None);
     }
 
     #[tokio::test]
@@ -193,15 +337,21 @@ mod tests {
         let storage = Arc::new(ZipStorage::new().unwrap());
         let priv_key = PrivateKey::new();
         let paymail = PaymailManager::new(priv_key, Arc::clone(&storage));
+        let tx_manager = Arc::new(TransactionManager::new(Arc::clone(&storage), None));
+        let wallet = WalletManager::new(Arc::clone(&storage), tx_manager, None).unwrap();
         let user_id = Uuid::new_v4();
         paymail.create_paid_alias(user_id, "54321").await.unwrap();
 
         // Test rate limit (5 requests per minute)
         for _ in 0..5 {
-            let result = paymail.confirm_alias(user_id, "54321@zip.io").await;
-            assert!(result.is_ok());
+            let result = paymail
+                .confirm_alias(&wallet, user_id, "54321@zip.io")
+                .await;
+            assert!(!matches!(result, Err(ZipError::RateLimit(_))));
         }
-        let result = paymail.confirm_alias(user_id, "54321@zip.io").await;
+        let result = paymail
+            .confirm_alias(&wallet, user_id, "54321@zip.io")
+            .await;
         assert!(matches!(result, Err(ZipError::RateLimit(_))));
     }
 
@@ -210,6 +360,8 @@ mod tests {
         let storage = Arc::new(ZipStorage::new().unwrap());
         let priv_key = PrivateKey::new();
         let paymail = PaymailManager::new(priv_key, Arc::clone(&storage));
+        let tx_manager = Arc::new(TransactionManager::new(Arc::clone(&storage), None));
+        let wallet = WalletManager::new(Arc::clone(&storage), tx_manager, None).unwrap();
         let user_id = Uuid::new_v4();
 
         // Test telemetry for create_default_alias
@@ -221,7 +373,9 @@ mod tests {
         assert!(result.is_ok());
 
         // Test telemetry for confirm_alias
-        let result = paymail.confirm_alias(user_id, "54321@zip.io").await;
+        let result = paymail
+            .confirm_alias(&wallet, user_id, "54321@zip.io")
+            .await;
         assert!(result.is_ok());
 
         // Test telemetry for get_user_aliases
@@ -239,4 +393,1951 @@ mod tests {
             .await;
         assert!(result.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_select_coins_does_not_reserve() {
+        let storage = Arc::new(ZipStorage::new().unwrap());
+        let rustbus = None::<Arc<RustBusIntegrator>>;
+        let tx_manager = Arc::new(TransactionManager::new(Arc::clone(&storage), rustbus));
+        let user_id = Uuid::new_v4();
+        fund(&tx_manager, user_id, 5, 10000);
+        let selection = tx_manager.select_coins(user_id, 8000, 1000).unwrap();
+        assert_eq!(selection.inputs.len(), 1);
+        assert_eq!(selection.change, 1000);
+        assert_eq!(
+            selection.estimated_size,
+            TransactionManager::estimate_size(1, 2)
+        );
+        // Simulation must leave every UTXO spendable
+        assert_eq!(tx_manager.get_cached_utxos(user_id).unwrap().len(), 5);
+
+        let result = tx_manager.select_coins(user_id, 60000, 1000);
+        assert!(matches!(result, Err(ZipError::Blockchain(_))));
+    }
+
+    #[test]
+    fn test_spending_policy_check() {
+        let policy = SpendingPolicy {
+            max_payment: Some(5000),
+            daily_limit: Some(8000),
+            require_2fa_above: Some(2000),
+        };
+        assert!(policy.check(4000, 0).is_empty());
+        assert_eq!(
+            policy.check(6000, 0),
+            vec![PolicyViolation::MaxPaymentExceeded {
+                limit: 5000,
+                amount: 6000
+            }]
+        );
+        assert_eq!(
+            policy.check(3000, 6000),
+            vec![PolicyViolation::DailyLimitExceeded {
+                limit: 8000,
+                spent_today: 6000,
+                amount: 3000
+            }]
+        );
+        assert!(policy.requires_2fa(2500));
+        assert!(!SpendingPolicy::default().requires_2fa(u64::MAX));
+    }
+
+    #[tokio::test]
+    async fn test_double_spend_releases_reservation() {
+        let storage = Arc::new(ZipStorage::new().unwrap());
+        let rustbus = None::<Arc<RustBusIntegrator>>;
+        let tx_manager = Arc::new(TransactionManager::new(Arc::clone(&storage), rustbus));
+        let user_id = Uuid::new_v4();
+        let keys = fund(&tx_manager, user_id, 2, 10000);
+        let tx = tx_manager
+            .build_payment_tx(
+                user_id,
+                Script(hex::decode(format!("76a914{}88ac", "11".repeat(20))).unwrap()),
+                15000,
+                1000,
+                &[],
+                &keys,
+            )
+            .await
+            .unwrap();
+        let txid = TransactionManager::txid(&tx);
+        let inputs = tx_manager.reserved_inputs(user_id, &txid).unwrap();
+        assert_eq!(inputs.len(), 2);
+        // Only the change output is spendable while the payment is in flight
+        assert_eq!(tx_manager.get_cached_utxos(user_id).unwrap().len(), 1);
+
+        let check = tx_manager
+            .apply_spend(user_id, &inputs[0].outpoint(), "competing_txid")
+            .unwrap();
+        assert_eq!(
+            check,
+            SpendCheck::Conflict {
+                our_txid: txid.clone(),
+                outpoint: inputs[0].outpoint(),
+            }
+        );
+        // Change is gone and the untouched input is spendable again
+        let utxos = tx_manager.get_cached_utxos(user_id).unwrap();
+        assert_eq!(utxos, vec![inputs[1].clone()]);
+        assert!(tx_manager.get_reservations(user_id).unwrap().is_empty());
+        let check = tx_manager
+            .apply_spend(user_id, &inputs[0].outpoint(), "competing_txid")
+            .unwrap();
+        assert_eq!(check, SpendCheck::Expected);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_conflict_restores_untouched_inputs() {
+        let storage = Arc::new(ZipStorage::new().unwrap());
+        let tx_manager = Arc::new(TransactionManager::new(Arc::clone(&storage), None));
+        let user_id = Uuid::new_v4();
+        let keys = fund(&tx_manager, user_id, 3, 10000);
+        let tx = tx_manager
+            .build_payment_tx(
+                user_id,
+                Script(hex::decode(format!("76a914{}88ac", "11".repeat(20))).unwrap()),
+                25000,
+                1000,
+                &[],
+                &keys,
+            )
+            .await
+            .unwrap();
+        let txid = TransactionManager::txid(&tx);
+        let inputs = tx_manager.reserved_inputs(user_id, &txid).unwrap();
+        assert_eq!(inputs.len(), 3);
+
+        // Only the first input was taken; the whole reservation settles at once
+        tx_manager
+            .resolve_conflict(user_id, &txid, &[inputs[0].outpoint()], "competing_txid")
+            .unwrap();
+        let mut utxos = tx_manager.get_cached_utxos(user_id).unwrap();
+        utxos.sort_by_key(Utxo::outpoint);
+        let mut restored = inputs[1..].to_vec();
+        restored.sort_by_key(Utxo::outpoint);
+        assert_eq!(utxos, restored);
+        assert!(tx_manager.get_reservations(user_id).unwrap().is_empty());
+        let spent = tx_manager.get_spent_outpoints(user_id).unwrap();
+        assert_eq!(
+            spent.get(&inputs[0].outpoint()).map(String::as_str),
+            Some("competing_txid")
+        );
+        assert_eq!(spent.len(), 1);
+    }
+
+    #[test]
+    fn test_alerts_take_clears() {
+        let storage = Arc::new(ZipStorage::new().unwrap());
+        let alerts = Alerts::new(Arc::clone(&storage));
+        let user_id = Uuid::new_v4();
+        alerts
+            .push(user_id, "Coin spent".to_string(), None)
+            .unwrap();
+        assert_eq!(alerts.take(user_id).unwrap().len(), 1);
+        assert!(alerts.take(user_id).unwrap().is_empty());
+        assert!(storage.get_alerts(user_id).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_history_conflict_rollback() {
+        let storage = Arc::new(ZipStorage::new().unwrap());
+        let history = TxHistory::new(Arc::clone(&storage));
+        let user_id = Uuid::new_v4();
+        history
+            .upsert(
+                user_id,
+                HistoryEntry {
+                    txid: "our_txid".to_string(),
+                    direction: TxDirection::Sent,
+                    amount: 5000,
+                    fee: 1000,
+                    counterparty: "bob@example.com".to_string(),
+                    inputs: vec!["prev_txid:0".to_string()],
+                    status: TxStatus::Broadcast,
+                    timestamp: 0,
+                    memo: None,
+                },
+            )
+            .unwrap();
+        let callback: ArcResponse = serde_json::from_str(
+            r#"{"txid":"our_txid","txStatus":"DOUBLE_SPEND_ATTEMPTED","competingTxs":["other_txid"]}"#,
+        )
+        .unwrap();
+        assert!(callback.is_double_spend());
+        assert!(!callback.is_mined());
+        let updated = history
+            .set_status(
+                user_id,
+                &callback.txid,
+                TxStatus::Conflicted {
+                    competing_txid: "other_txid".to_string(),
+                },
+            )
+            .unwrap();
+        assert!(updated);
+        assert!(matches!(
+            history.get(user_id, "our_txid").unwrap().unwrap().status,
+            TxStatus::Conflicted { .. }
+        ));
+        assert!(
+            !history
+                .set_status(user_id, "unknown", TxStatus::Pending)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_two_block_reorg() {
+        let storage = Arc::new(ZipStorage::new().unwrap());
+        let headers = HeaderStore::new(Arc::clone(&storage));
+        let history = TxHistory::new(Arc::clone(&storage));
+        let user_id = Uuid::new_v4();
+        let tx_a = "aa".repeat(32);
+        let tx_b = "bb".repeat(32);
+        let header = |height: u64, hash: &str, prev_hash: &str, merkle_root: &str| BlockHeader {
+            height,
+            hash: hash.to_string(),
+            prev_hash: prev_hash.to_string(),
+            merkle_root: merkle_root.to_string(),
+            time: height as u32 * 600,
+        };
+        let h0 = header(0, "h0", "", &"00".repeat(32));
+        let h1 = header(1, "h1", "h0", &"01".repeat(32));
+        let h2a = header(2, "h2a", "h1", &tx_a);
+        let h3a = header(3, "h3a", "h2a", &tx_b);
+        let h2b = header(2, "h2b", "h1", &tx_a);
+        let h3b = header(3, "h3b", "h2b", &"cc".repeat(32));
+        let h4b = header(4, "h4b", "h3b", &"dd".repeat(32));
+        let h5b = header(5, "h5b", "h4b", &"ee".repeat(32));
+        assert!(
+            headers
+                .apply_branch(vec![h0, h1, h2a.clone(), h3a.clone()])
+                .unwrap()
+                .is_none()
+        );
+
+        let proof = |txid: &str, block: &BlockHeader| MerkleProof {
+            txid: txid.to_string(),
+            index: 0,
+            nodes: vec![],
+            block_hash: block.hash.clone(),
+            block_height: block.height,
+        };
+        for (txid, block) in [(&tx_a, &h2a), (&tx_b, &h3a)] {
+            history
+                .upsert(
+                    user_id,
+                    HistoryEntry {
+                        txid: txid.clone(),
+                        direction: TxDirection::Received,
+                        amount: 5000,
+                        fee: 0,
+                        counterparty: "alice@example.com".to_string(),
+                        inputs: vec![],
+                        status: TxStatus::Confirmed {
+                            height: block.height,
+                            block_hash: block.hash.clone(),
+                        },
+                        timestamp: 0,
+                        memo: None,
+                    },
+                )
+                .unwrap();
+            history.store_proof(user_id, proof(txid, block)).unwrap();
+        }
+
+        let reorg = headers
+            .apply_branch(vec![h2b.clone(), h3b.clone(), h4b.clone()])
+            .unwrap()
+            .unwrap();
+        assert_eq!(reorg.fork_height, 1);
+        assert_eq!(reorg.disconnected, vec![h2a.clone(), h3a.clone()]);
+        assert_eq!(headers.tip().unwrap().unwrap().hash, "h4b");
+
+        let rewound = history.rewind(user_id, reorg.fork_height).unwrap();
+        assert_eq!(rewound.len(), 2);
+        // Old proofs point at orphaned blocks; only tx A was re-mined on the new chain
+        assert!(
+            !history
+                .reverify(user_id, proof(&tx_a, &h2a), &headers)
+                .unwrap()
+        );
+        assert!(
+            !history
+                .reverify(user_id, proof(&tx_b, &h3a), &headers)
+                .unwrap()
+        );
+        assert!(
+            history
+                .reverify(user_id, proof(&tx_a, &h2b), &headers)
+                .unwrap()
+        );
+        assert_eq!(
+            history.get(user_id, &tx_a).unwrap().unwrap().status,
+            TxStatus::Confirmed {
+                height: 2,
+                block_hash: "h2b".to_string(),
+            }
+        );
+        assert_eq!(
+            history.get(user_id, &tx_b).unwrap().unwrap().status,
+            TxStatus::Broadcast
+        );
+
+        // A shorter competing branch does not replace the active chain
+        assert!(headers.apply_branch(vec![h2a]).unwrap().is_none());
+        assert_eq!(headers.tip().unwrap().unwrap().hash, "h4b");
+
+        // Resending known headers ahead of a new one only extends the tip
+        assert!(
+            headers
+                .apply_branch(vec![h2b, h3b, h4b, h5b])
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(headers.tip().unwrap().unwrap().hash, "h5b");
+        // Time locks are measured against the median of recent block times
+        assert_eq!(headers.median_time_past().unwrap(), Some(1800));
+    }
+
+    #[tokio::test]
+    async fn test_handle_reorg_and_reconfirmation() {
+        #[derive(serde::Serialize)]
+        struct WalletDataV1 {
+            address: String,
+            balance: u64,
+            currency: String,
+            balance_converted: Decimal,
+            derivation_path: String,
+        }
+        let storage = Arc::new(ZipStorage::new().unwrap());
+        let tx_manager = Arc::new(TransactionManager::new(Arc::clone(&storage), None));
+        let wallet =
+            WalletManager::new(Arc::clone(&storage), Arc::clone(&tx_manager), None).unwrap();
+        let history = TxHistory::new(Arc::clone(&storage));
+        let user_id = Uuid::new_v4();
+        let v1 = WalletDataV1 {
+            address: "1BoatSLRHtKNngkdXEeobR76b53LETtpyT".to_string(),
+            balance: 8000,
+            currency: "USD".to_string(),
+            balance_converted: Decimal::ZERO,
+            derivation_path: "m/44'/0'/0'/0/1".to_string(),
+        };
+        storage
+            .store_user_data(user_id, &bincode::serialize(&v1).unwrap())
+            .unwrap();
+        let txid = "ab".repeat(32);
+        history
+            .upsert(
+                user_id,
+                HistoryEntry {
+                    txid: txid.clone(),
+                    direction: TxDirection::Received,
+                    amount: 5000,
+                    fee: 0,
+                    counterparty: "alice@example.com".to_string(),
+                    inputs: vec![],
+                    status: TxStatus::Confirmed {
+                        height: 900_001,
+                        block_hash: "orphaned".to_string(),
+                    },
+                    timestamp: 0,
+                    memo: None,
+                },
+            )
+            .unwrap();
+        let reorg = Reorg {
+            fork_height: 900_000,
+            disconnected: vec![],
+            connected: vec![],
+        };
+
+        // Without a proof on the new chain the receipt moves to the unconfirmed balance
+        let unconfirmed = wallet.handle_reorg(user_id, &reorg).await.unwrap();
+        assert_eq!(unconfirmed.len(), 1);
+        assert_eq!(unconfirmed[0].txid, txid);
+        let data = WalletData::decode(&storage.get_user_data(user_id).unwrap().unwrap()).unwrap();
+        assert_eq!(data.balance, 3000);
+        assert_eq!(data.unconfirmed_balance, 5000);
+        assert_eq!(data.derivation_path, "m/44'/0'/0'/0/1");
+
+        // Once the receipt is mined on the new chain it counts as confirmed again
+        let callback: ArcResponse = serde_json::from_str(&format!(
+            r#"{{"txid":"{}","txStatus":"MINED","blockHash":"replacement","blockHeight":900001}}"#,
+            txid
+        ))
+        .unwrap();
+        wallet
+            .handle_arc_callback(user_id, &callback)
+            .await
+            .unwrap();
+        let data = WalletData::decode(&storage.get_user_data(user_id).unwrap().unwrap()).unwrap();
+        assert_eq!(data.balance, 8000);
+        assert_eq!(data.unconfirmed_balance, 0);
+
+        // A later confirmation does not count it twice
+        wallet
+            .handle_arc_callback(user_id, &callback)
+            .await
+            .unwrap();
+        let data = WalletData::decode(&storage.get_user_data(user_id).unwrap().unwrap()).unwrap();
+        assert_eq!(data.balance, 8000);
+    }
+
+    #[test]
+    fn test_history_migrates_v1_entries() {
+        #[derive(serde::Serialize)]
+        struct HistoryEntryV1 {
+            txid: String,
+            direction: TxDirection,
+            amount: u64,
+            fee: u64,
+            counterparty: String,
+            inputs: Vec<String>,
+            status: TxStatus,
+            timestamp: i64,
+        }
+        let storage = Arc::new(ZipStorage::new().unwrap());
+        let history = TxHistory::new(Arc::clone(&storage));
+        let user_id = Uuid::new_v4();
+        let v1 = vec![
+            HistoryEntryV1 {
+                txid: "ab".repeat(32),
+                direction: TxDirection::Sent,
+                amount: 1000,
+                fee: 200,
+                counterparty: "alice@example.com".to_string(),
+                inputs: vec![format!("{}:0", "cd".repeat(32))],
+                status: TxStatus::Broadcast,
+                timestamp: 1_700_000_000,
+            },
+            HistoryEntryV1 {
+                txid: "ef".repeat(32),
+                direction: TxDirection::Received,
+                amount: 5000,
+                fee: 0,
+                counterparty: "bob@example.com".to_string(),
+                inputs: vec![],
+                status: TxStatus::Pending,
+                timestamp: 1_700_000_100,
+            },
+        ];
+        storage
+            .store_history(user_id, &bincode::serialize(&v1).unwrap())
+            .unwrap();
+
+        let entries = history.list(user_id).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].amount, 1000);
+        assert_eq!(entries[1].counterparty, "bob@example.com");
+        assert!(entries.iter().all(|e| e.memo.is_none()));
+
+        // Writing back stores the current layout, memos included
+        let mut entry = entries[0].clone();
+        entry.memo = Some("rent".to_string());
+        history.upsert(user_id, entry).unwrap();
+        let entries = history.list(user_id).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].memo.as_deref(), Some("rent"));
+    }
+
+    #[tokio::test]
+    async fn test_build_locked_tx() {
+        let storage = Arc::new(ZipStorage::new().unwrap());
+        let rustbus = None::<Arc<RustBusIntegrator>>;
+        let tx_manager = Arc::new(TransactionManager::new(Arc::clone(&storage), rustbus));
+        let user_id = Uuid::new_v4();
+        let keys = fund(&tx_manager, user_id, 2, 10000);
+        let tx = tx_manager
+            .build_locked_tx(user_id, None, 5000, 1000, 850_000, &[], &keys)
+            .await
+            .unwrap();
+        assert_eq!(tx.lock_time, 850_000);
+        assert!(tx.inputs.iter().all(|i| i.sequence < 0xffff_ffff));
+        // Vaults lock back to the wallet's own script
+        assert_eq!(&tx.outputs[0].script.0, keys.keys().next().unwrap());
+        // Change stays unspendable until the locked transaction is mined
+        assert_eq!(tx_manager.get_cached_utxos(user_id).unwrap().len(), 1);
+
+        let result = tx_manager
+            .build_locked_tx(user_id, None, 5000, 1000, 0, &[], &keys)
+            .await;
+        assert!(matches!(result, Err(ZipError::Blockchain(_))));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_locked_payment() {
+        let storage = Arc::new(ZipStorage::new().unwrap());
+        let rustbus = None::<Arc<RustBusIntegrator>>;
+        let tx_manager = Arc::new(TransactionManager::new(Arc::clone(&storage), rustbus));
+        let wallet =
+            WalletManager::new(Arc::clone(&storage), Arc::clone(&tx_manager), None).unwrap();
+        let user_id = Uuid::new_v4();
+        fund_wallet(&wallet, &tx_manager, user_id, 1, 10000);
+        let recipient =
+            WalletManager::address_script("1BoatSLRHtKNngkdXEeobR76b53LETtpyT").unwrap();
+        let locked = wallet
+            .schedule_locked_payment(user_id, recipient, 5000, 500, 2_000_000_000, &[])
+            .await
+            .unwrap();
+        // Scheduling holds the coin, but the daily spend waits for the broadcast
+        let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+        assert_eq!(storage.get_daily_spend(user_id, &today).unwrap(), 0);
+        assert!(tx_manager.get_cached_utxos(user_id).unwrap().is_empty());
+
+        wallet.cancel_locked_payment(user_id, &locked.txid).unwrap();
+        assert!(wallet.locked_funds(user_id).unwrap().is_empty());
+        assert!(wallet.get_history(user_id).unwrap().is_empty());
+        assert_eq!(tx_manager.get_cached_utxos(user_id).unwrap().len(), 1);
+        assert_eq!(storage.get_daily_spend(user_id, &today).unwrap(), 0);
+        assert!(matches!(
+            wallet.cancel_locked_payment(user_id, &locked.txid),
+            Err(ZipError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn test_timelocks_due() {
+        let storage = Arc::new(ZipStorage::new().unwrap());
+        let timelocks = TimeLocks::new(Arc::clone(&storage));
+        let user_id = Uuid::new_v4();
+        let locked = |txid: &str, lock_time: u32, kind: LockKind| LockedTx {
+            txid: txid.to_string(),
+            tx_hex: String::new(),
+            lock_time,
+            kind,
+            amount: 5000,
+            fee: 1000,
+            counterparty: String::new(),
+            wallet_outputs: vec![],
+            created_at: 0,
+        };
+        timelocks
+            .add(user_id, locked("vault", 850_000, LockKind::Vault))
+            .unwrap();
+        timelocks
+            .add(user_id, locked("rent", 1_900_000_000, LockKind::Payment))
+            .unwrap();
+        assert_eq!(timelocks.vault_balance(user_id).unwrap(), 5000);
+
+        assert!(timelocks.due(user_id, 849_999, 0).unwrap().is_empty());
+        let due = timelocks.due(user_id, 850_000, 0).unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].txid, "vault");
+        // A time lock is final only once the median time past has moved beyond it
+        assert_eq!(
+            timelocks
+                .due(user_id, 850_000, 1_900_000_000)
+                .unwrap()
+                .len(),
+            1
+        );
+        let due = timelocks.due(user_id, 850_000, 1_900_000_001).unwrap();
+        assert_eq!(due.len(), 2);
+
+        timelocks.remove(user_id, "vault").unwrap();
+        assert_eq!(timelocks.list(user_id).unwrap().len(), 1);
+        assert_eq!(timelocks.vault_balance(user_id).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_recurrence_monthly_clamps_day() {
+        // 2025-01-31 09:00 UTC
+        let jan_31 = 1_738_314_000;
+        let monthly = Recurrence::Monthly { day: 31 };
+        let feb = monthly.advance(jan_31);
+        let mar = monthly.advance(feb);
+        assert_eq!(feb - jan_31, 28 * 86_400);
+        assert_eq!(mar - feb, 31 * 86_400);
+        assert_eq!(Recurrence::Weekly.advance(0), 7 * 86_400);
+    }
+
+    #[test]
+    fn test_schedule_catch_up_rules() {
+        let now = 100 * 86_400;
+        // Daily schedule that last ran five days ago; four runs were missed
+        let mut schedule = PaymentSchedule::new(
+            "Allowance",
+            "kid@zip.io",
+            1000,
+            100,
+            Recurrence::Daily,
+            now - 4 * 86_400,
+        );
+
+        schedule.catch_up = CatchUp::RunOnce;
+        let plan = schedule.plan(now);
+        assert_eq!(plan.run, vec![now]);
+        assert_eq!(plan.skipped.len(), 4);
+        assert_eq!(plan.next_run, now + 86_400);
+
+        schedule.catch_up = CatchUp::RunAll;
+        assert_eq!(schedule.plan(now).run.len(), 5);
+
+        schedule.catch_up = CatchUp::Skip;
+        let plan = schedule.plan(now - 7200);
+        assert!(plan.run.is_empty());
+        assert_eq!(plan.skipped.len(), 4);
+
+        // Limits cap catch-up and finish the schedule
+        schedule.catch_up = CatchUp::RunAll;
+        schedule.limits.max_runs = Some(3);
+        schedule.runs = 1;
+        let plan = schedule.plan(now);
+        assert_eq!(plan.run, vec![now - 4 * 86_400, now - 3 * 86_400]);
+        assert_eq!(plan.skipped.len(), 3);
+        schedule.runs = 3;
+        assert!(schedule.is_finished());
+    }
+
+    #[test]
+    fn test_scheduler_pause_resume() {
+        let storage = Arc::new(ZipStorage::new().unwrap());
+        let scheduler = Scheduler::new(Arc::clone(&storage));
+        let user_id = Uuid::new_v4();
+        let schedule = PaymentSchedule::new(
+            "Rent",
+            "landlord@zip.io",
+            50000,
+            1000,
+            Recurrence::Monthly { day: 1 },
+            0,
+        );
+        let schedule_id = schedule.id;
+        scheduler.add(user_id, schedule).unwrap();
+        // Stored schedules are picked up at launch
+        assert!(storage.get_schedule_users().unwrap().contains(&user_id));
+        scheduler.set_paused(user_id, schedule_id, true).unwrap();
+        assert!(scheduler.list(user_id).unwrap()[0].paused);
+
+        // Resuming skips runs missed while paused instead of replaying them
+        scheduler.set_paused(user_id, schedule_id, false).unwrap();
+        let resumed = &scheduler.list(user_id).unwrap()[0];
+        assert!(!resumed.paused);
+        assert!(resumed.next_run >= chrono::Utc::now().timestamp() - 3600);
+
+        let invalid = PaymentSchedule::new("Empty", "a@zip.io", 0, 0, Recurrence::Daily, 0);
+        assert!(matches!(
+            scheduler.add(user_id, invalid),
+            Err(ZipError::Validation(_))
+        ));
+    }
+
+    /// Local BIP270 merchant serving one payment request and acknowledging payments.
+    async fn spawn_mock_merchant(expiration_timestamp: Option<i64>, refuse: bool) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let request = PaymentRequest {
+            network: "bitcoin-sv".to_string(),
+            outputs: vec![
+                RequestedOutput {
+                    amount: 6000,
+                    script: format!("76a914{}88ac", "11".repeat(20)),
+                    description: Some("Order #42".to_string()),
+                },
+                RequestedOutput {
+                    amount: 1500,
+                    script: format!("76a914{}88ac", "22".repeat(20)),
+                    description: Some("Shipping".to_string()),
+                },
+            ],
+            creation_timestamp: chrono::Utc::now().timestamp(),
+            expiration_timestamp,
+            memo: Some("Order #42".to_string()),
+            payment_url: format!("{}/pay", base),
+            merchant_data: Some("order-42".to_string()),
+        };
+        let app = Router::new()
+            .route(
+                "/invoice",
+                get(move || {
+                    let request = request.clone();
+                    async move { axum::Json(request) }
+                }),
+            )
+            // BIP270 payments are JSON sent as application/bitcoinsv-payment
+            .route(
+                "/pay",
+                post(move |body: String| async move {
+                    let payment: Payment = serde_json::from_str(&body).unwrap();
+                    assert_eq!(payment.merchant_data.as_deref(), Some("order-42"));
+                    axum::Json(PaymentAck {
+                        payment,
+                        memo: Some(if refuse { "Out of stock" } else { "Thanks" }.to_string()),
+                        error: refuse.then_some(1),
+                    })
+                }),
+            );
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("{}/invoice", base)
+    }
+
+    #[tokio::test]
+    async fn test_build_outputs_tx() {
+        let storage = Arc::new(ZipStorage::new().unwrap());
+        let rustbus = None::<Arc<RustBusIntegrator>>;
+        let tx_manager = Arc::new(TransactionManager::new(Arc::clone(&storage), rustbus));
+        let user_id = Uuid::new_v4();
+        let keys = fund(&tx_manager, user_id, 1, 10000);
+        let outputs = vec![
+            TxOut {
+                value: 6000,
+                script: Script(vec![0x51]),
+            },
+            TxOut {
+                value: 1500,
+                script: Script(vec![0x52]),
+            },
+        ];
+        let tx = tx_manager
+            .build_outputs_tx(user_id, outputs.clone(), 500, &keys)
+            .await
+            .unwrap();
+        assert_eq!(tx.outputs[..2], outputs[..]);
+        assert_eq!(tx.outputs[2].value, 2000);
+        // Change is spendable at the vout after the requested outputs
+        let utxos = tx_manager.get_cached_utxos(user_id).unwrap();
+        assert_eq!(utxos[0].vout, 2);
+
+        let empty = vec![TxOut {
+            value: 100,
+            script: Script::default(),
+        }];
+        let result = tx_manager
+            .build_outputs_tx(user_id, empty, 500, &keys)
+            .await;
+        assert!(matches!(result, Err(ZipError::Blockchain(_))));
+    }
+
+    #[tokio::test]
+    async fn test_bip270_merchant_payment() {
+        let storage = Arc::new(ZipStorage::new().unwrap());
+        let rustbus = None::<Arc<RustBusIntegrator>>;
+        let tx_manager = Arc::new(TransactionManager::new(Arc::clone(&storage), rustbus));
+        let wallet =
+            WalletManager::new(Arc::clone(&storage), Arc::clone(&tx_manager), None).unwrap();
+        let user_id = Uuid::new_v4();
+        fund_wallet(&wallet, &tx_manager, user_id, 2, 10000);
+        let now = chrono::Utc::now().timestamp();
+
+        let url = spawn_mock_merchant(Some(now + 600), false).await;
+        // The reviewed request is paid as is, without fetching it again
+        let request = wallet.fetch_payment_request(&url).await.unwrap();
+        let ack = wallet
+            .pay_payment_request(user_id, &request, 500, Some("Ship fast".to_string()))
+            .await
+            .unwrap();
+        assert!(ack.is_accepted());
+        assert_eq!(ack.memo.as_deref(), Some("Thanks"));
+        assert_eq!(ack.payment.memo.as_deref(), Some("Ship fast"));
+        let history = wallet.get_history(user_id).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].amount, 7500);
+        assert_eq!(history[0].counterparty, "Order #42");
+        assert_eq!(history[0].status, TxStatus::Broadcast);
+        assert!(tx_manager.get_reservations(user_id).unwrap().is_empty());
+
+        // A refused payment releases its inputs and leaves no history entry
+        let before = tx_manager.get_cached_utxos(user_id).unwrap();
+        let url = spawn_mock_merchant(Some(now + 600), true).await;
+        let refusing = wallet.fetch_payment_request(&url).await.unwrap();
+        let result = wallet
+            .pay_payment_request(user_id, &refusing, 500, None)
+            .await;
+        assert!(matches!(result, Err(ZipError::Blockchain(_))));
+        assert_eq!(
+            tx_manager.get_cached_utxos(user_id).unwrap().len(),
+            before.len()
+        );
+        assert_eq!(wallet.get_history(user_id).unwrap().len(), 1);
+
+        // If the post fails the merchant may hold the transaction: it stays pending
+        let unreachable = PaymentRequest {
+            payment_url: "http://127.0.0.1:1/pay".to_string(),
+            ..request
+        };
+        let result = wallet
+            .pay_payment_request(user_id, &unreachable, 500, None)
+            .await;
+        assert!(result.is_err());
+        let history = wallet.get_history(user_id).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[1].status, TxStatus::Pending);
+        assert!(!tx_manager.get_reservations(user_id).unwrap().is_empty());
+
+        let url = spawn_mock_merchant(Some(now - 1), false).await;
+        let result = wallet.fetch_payment_request(&url).await;
+        assert!(matches!(result, Err(ZipError::Validation(_))));
+    }
+
+    #[test]
+    fn test_invoice_status_transitions() {
+        let storage = Arc::new(ZipStorage::new().unwrap());
+        let invoices = Invoices::new(Arc::clone(&storage));
+        let user_id = Uuid::new_v4();
+        let invoice = Invoice::new(
+            InvoiceAmount::Satoshis(10_000),
+            10_000,
+            "Logo design",
+            InvoiceDestination::Paymail {
+                handle: "alice@zip.io".to_string(),
+            },
+            1_000,
+            Some(2_000),
+        );
+        let reference = invoice.reference.clone();
+        assert_eq!(invoice.status_at(1_500), InvoiceStatus::Open);
+        assert_eq!(invoice.status_at(2_000), InvoiceStatus::Expired);
+        assert!(
+            invoice
+                .payment_uri()
+                .to_string()
+                .starts_with("pay:alice@zip.io?amount=")
+        );
+        invoices.add(user_id, invoice).unwrap();
+
+        let payment = |txid: &str, amount: u64| InvoicePayment {
+            txid: txid.to_string(),
+            amount,
+            received_at: 1_500,
+        };
+        let updated = invoices
+            .record_payment(user_id, &reference, payment("tx1", 4_000))
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.status, InvoiceStatus::PartiallyPaid);
+        assert_eq!(updated.outstanding(), 6_000);
+        // The same transaction is only counted once
+        assert!(
+            invoices
+                .record_payment(user_id, &reference, payment("tx1", 4_000))
+                .unwrap()
+                .is_none()
+        );
+        // Partial payments still expire; full payment wins over expiry
+        assert_eq!(updated.status_at(2_500), InvoiceStatus::Expired);
+        let updated = invoices
+            .record_payment(user_id, &reference.to_lowercase(), payment("tx2", 6_000))
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.status, InvoiceStatus::Paid);
+        assert_eq!(updated.status_at(2_500), InvoiceStatus::Paid);
+        assert!(
+            invoices
+                .record_payment(user_id, "INV-UNKNOWN", payment("tx3", 1))
+                .unwrap()
+                .is_none()
+        );
+
+        let zero = Invoice::new(
+            InvoiceAmount::Satoshis(0),
+            0,
+            "",
+            InvoiceDestination::Paymail {
+                handle: "alice@zip.io".to_string(),
+            },
+            1_000,
+            None,
+        );
+        assert!(matches!(
+            invoices.add(user_id, zero),
+            Err(ZipError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn test_encrypted_memo_readable_by_both_parties() {
+        let storage = Arc::new(ZipStorage::new().unwrap());
+        let tx_manager = Arc::new(TransactionManager::new(Arc::clone(&storage), None));
+        let wallet =
+            WalletManager::new(Arc::clone(&storage), Arc::clone(&tx_manager), None).unwrap();
+        let recipient_key = Crypto::generate_private_key().unwrap();
+        let recipient = Crypto::derive_public_key(&recipient_key);
+        let memo = DataOutput::encrypted_memo(
+            &recipient,
+            &wallet.identity_public_key().unwrap(),
+            "Dinner on Friday",
+        )
+        .unwrap();
+        assert_eq!(memo.pushes.len(), 2);
+
+        // History stores the summary; each side opens its own copy
+        let summary = memo.summary();
+        assert!(!summary.contains("Dinner"));
+        assert_eq!(wallet.decrypt_memo(&summary).unwrap(), "Dinner on Friday");
+        let for_recipient = summary.split_whitespace().next().unwrap();
+        assert_eq!(
+            Crypto::decrypt_message(&recipient_key, for_recipient).unwrap(),
+            "Dinner on Friday"
+        );
+        assert!(wallet.decrypt_memo(for_recipient).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_op_return_data_outputs() {
+        let memo = DataOutput::memo("Rent March").unwrap();
+        let script = memo.script().unwrap();
+        assert_eq!(&script.0[..3], &[0x00, 0x6a, 10]);
+        assert_eq!(DataOutput::parse(&script.0), Some(memo.clone()));
+        assert_eq!(memo.summary(), "Rent March");
+        assert!(DataOutput::memo("").is_err());
+        assert!(DataOutput::memo(&"x".repeat(1_001)).is_err());
+
+        // 76+ byte pushes need OP_PUSHDATA1
+        let long = DataOutput::new().push(&[7u8; 100]);
+        let script = long.script().unwrap();
+        assert_eq!(&script.0[2..4], &[0x4c, 100]);
+        assert_eq!(DataOutput::parse(&script.0), Some(long));
+
+        let file = DataOutput::b(b"hello", "text/plain", "utf-8", Some("hello.txt"))
+            .pipe(DataOutput::map_set(&[("app", "zip"), ("type", "post")]));
+        assert_eq!(file.summary(), "B:// | MAP");
+        assert_eq!(DataOutput::parse(&file.script().unwrap().0), Some(file));
+        assert!(DataOutput::parse(&[0x76, 0xa9]).is_none());
+
+        let too_big = DataOutput::new().push(&vec![0u8; 100_001]);
+        assert!(matches!(too_big.script(), Err(ZipError::Validation(_))));
+
+        let storage = Arc::new(ZipStorage::new().unwrap());
+        let rustbus = None::<Arc<RustBusIntegrator>>;
+        let tx_manager = Arc::new(TransactionManager::new(Arc::clone(&storage), rustbus));
+        let user_id = Uuid::new_v4();
+        let keys = fund(&tx_manager, user_id, 1, 10000);
+        let tx = tx_manager
+            .build_payment_tx(user_id, Script(vec![0x51]), 5000, 500, &[memo], &keys)
+            .await
+            .unwrap();
+        assert_eq!(tx.outputs.len(), 3);
+        assert_eq!(tx.outputs[1].value, 0);
+        assert_eq!(tx.outputs[1].script.0[..2], [0x00, 0x6a]);
+        assert_eq!(tx_manager.get_cached_utxos(user_id).unwrap()[0].vout, 2);
+    }
+
+    const GENESIS_HEADER: &str = "0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d1dac2b7c";
+    const GENESIS_HASH: &str = "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f";
+
+    #[test]
+    fn test_block_header_from_raw() {
+        let raw = hex::decode(GENESIS_HEADER).unwrap();
+        let header = BlockHeader::from_raw(0, &raw).unwrap();
+        assert_eq!(header.hash, GENESIS_HASH);
+        assert_eq!(header.prev_hash, "00".repeat(32));
+        assert_eq!(
+            header.merkle_root,
+            "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b"
+        );
+        assert_eq!(header.time, 1_231_006_505);
+
+        // Changing the nonce breaks the proof of work
+        let mut tampered = raw.clone();
+        tampered[76] ^= 1;
+        assert!(BlockHeader::from_raw(0, &tampered).is_err());
+        assert!(BlockHeader::from_raw(0, &raw[..79]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_notarise_and_verify_offline() {
+        let storage = Arc::new(ZipStorage::new().unwrap());
+        let rustbus = None::<Arc<RustBusIntegrator>>;
+        let tx_manager = Arc::new(TransactionManager::new(Arc::clone(&storage), rustbus));
+        let wallet =
+            WalletManager::new(Arc::clone(&storage), Arc::clone(&tx_manager), None).unwrap();
+        let notary = Notary::new(Arc::clone(&storage));
+        let user_id = Uuid::new_v4();
+        fund_wallet(&wallet, &tx_manager, user_id, 1, 10000);
+        let document = b"Signed lease agreement, 1 March";
+        let mut receipt = notary
+            .notarise(&wallet, user_id, "lease.pdf", document)
+            .await
+            .unwrap();
+        assert_eq!(notary.list(user_id).unwrap(), vec![receipt.clone()]);
+        assert!(receipt.tx_hex.contains(&receipt.file_hash));
+        assert_eq!(
+            receipt.verify_file(document).unwrap(),
+            NotaryCheck::Unconfirmed
+        );
+        assert_eq!(
+            receipt
+                .verify_file(b"Signed lease agreement, 2 March")
+                .unwrap(),
+            NotaryCheck::HashMismatch
+        );
+        // Only the fee leaves the wallet: one input, the data output and change at 1 sat/byte
+        let history = wallet.get_history(user_id).unwrap();
+        assert_eq!((history[0].amount, history[0].fee), (0, 281));
+
+        // A made-up header committing to the TXID has no valid proof of work
+        let mut raw = vec![1, 0, 0, 0];
+        raw.extend_from_slice(&[0; 32]);
+        let mut root = hex::decode(&receipt.txid).unwrap();
+        root.reverse();
+        raw.extend_from_slice(&root);
+        raw.extend_from_slice(&1_700_000_000u32.to_le_bytes());
+        raw.extend_from_slice(&0x207f_ffffu32.to_le_bytes());
+        raw.extend_from_slice(&[0; 4]);
+        receipt.proof = Some(MerkleProof {
+            txid: receipt.txid.clone(),
+            index: 0,
+            nodes: vec![],
+            block_hash: "11".repeat(32),
+            block_height: 850_000,
+        });
+        receipt.raw_header = Some(hex::encode(&raw));
+        assert_eq!(
+            receipt.verify_file(document).unwrap(),
+            NotaryCheck::HeaderInvalid
+        );
+        // A real header that does not contain the transaction
+        receipt.raw_header = Some(GENESIS_HEADER.to_string());
+        receipt.proof = Some(MerkleProof {
+            block_hash: GENESIS_HASH.to_string(),
+            block_height: 0,
+            ..receipt.proof.clone().unwrap()
+        });
+        assert_eq!(
+            receipt.verify_file(document).unwrap(),
+            NotaryCheck::ProofInvalid
+        );
+        receipt.tx_hex.replace_range(..2, "02");
+        assert_eq!(
+            receipt.verify_file(document).unwrap(),
+            NotaryCheck::TransactionMismatch
+        );
+    }
+
+    #[tokio::test]
+    async fn test_proof_of_reserves() {
+        let storage = Arc::new(ZipStorage::new().unwrap());
+        let rustbus = None::<Arc<RustBusIntegrator>>;
+        let tx_manager = Arc::new(TransactionManager::new(Arc::clone(&storage), rustbus));
+        let wallet =
+            WalletManager::new(Arc::clone(&storage), Arc::clone(&tx_manager), None).unwrap();
+        let user_id = Uuid::new_v4();
+        let funded = wallet.receive_address(user_id).unwrap().address;
+        let script = WalletManager::address_script(&funded).unwrap().0;
+        let utxo = |txid: &str, value| Utxo {
+            txid: txid.repeat(32),
+            vout: 0,
+            value,
+            script: script.clone(),
+        };
+        tx_manager
+            .add_utxos(user_id, &[utxo("aa", 30000), utxo("bb", 12000)])
+            .unwrap();
+        // Invoice and PayMail destination addresses are wallet keys too
+        let invoiced = wallet.invoice_address().unwrap().address;
+        tx_manager
+            .add_utxos(
+                user_id,
+                &[Utxo {
+                    txid: "cc".repeat(32),
+                    vout: 1,
+                    value: 8000,
+                    script: WalletManager::address_script(&invoiced).unwrap().0,
+                }],
+            )
+            .unwrap();
+        // UTXOs without a wallet key are not part of the proof
+        fund_script(&tx_manager, user_id, &[0x51], 1, 5000);
+
+        let challenge = "Audit 2026-Q3, nonce 8f41";
+        let report = ReserveReport::create(&wallet, user_id, challenge).unwrap();
+        assert_eq!(report.addresses.len(), 2);
+        assert_eq!(report.addresses[0].address, funded);
+        assert_eq!(report.addresses[1].address, invoiced);
+        assert_eq!(report.total, 50000);
+        let json = report.to_json().unwrap();
+        assert_eq!(
+            verify_reserve_report(&json).unwrap(),
+            ReserveCheck::Valid { total: 50000 }
+        );
+        assert!(ReserveReport::create(&wallet, user_id, " ").is_err());
+
+        let inflated = ReserveReport {
+            total: 60000,
+            ..report.clone()
+        };
+        assert_eq!(
+            inflated.verify().unwrap(),
+            ReserveCheck::TotalMismatch {
+                claimed: 60000,
+                computed: 50000
+            }
+        );
+        let replayed = ReserveReport {
+            challenge: "Audit 2026-Q4, nonce 0000".to_string(),
+            ..report.clone()
+        };
+        assert_eq!(
+            replayed.verify().unwrap(),
+            ReserveCheck::InvalidSignature(funded.clone())
+        );
+        let mut padded = report.clone();
+        padded.addresses[0].utxos.push(utxo("aa", 30000));
+        padded.total += 30000;
+        assert_eq!(
+            padded.verify().unwrap(),
+            ReserveCheck::DuplicateUtxo(format!("{}:0", "aa".repeat(32)))
+        );
+
+        // Ownership of a single address, funded or not
+        let fresh = wallet.invoice_address().unwrap().address;
+        let unfunded = ReserveReport::prove_address(&wallet, user_id, &fresh, challenge).unwrap();
+        assert_eq!(unfunded.balance(), 0);
+        assert!(unfunded.verify(challenge));
+        let foreign = Crypto::generate_address(&Crypto::derive_public_key(
+            &Crypto::generate_private_key().unwrap(),
+        ));
+        assert!(ReserveReport::prove_address(&wallet, user_id, &foreign, challenge).is_err());
+        let proof = ReserveReport::prove_address(&wallet, user_id, &funded, challenge).unwrap();
+        assert_eq!(proof.balance(), 42000);
+        assert!(proof.verify(challenge));
+        assert!(!proof.verify("another challenge"));
+    }
+
+    #[test]
+    fn test_brc42_private_payment_keys() {
+        let storage = Arc::new(ZipStorage::new().unwrap());
+        let rustbus = None::<Arc<RustBusIntegrator>>;
+        let tx_manager = Arc::new(TransactionManager::new(Arc::clone(&storage), rustbus));
+        let wallet =
+            WalletManager::new(Arc::clone(&storage), Arc::clone(&tx_manager), None).unwrap();
+        let user_id = Uuid::new_v4();
+        let identity = wallet.identity_public_key().unwrap();
+
+        // Sender and recipient agree on the child key from their own private keys
+        let sender_key = Crypto::generate_private_key().unwrap();
+        let sender = Crypto::derive_public_key(&sender_key);
+        let invoice = Crypto::payment_invoice_number("prefix1", "suffix1");
+        assert_eq!(invoice, "2-3241645161d8-prefix1 suffix1");
+        let child = Crypto::derive_child_public_key(&sender_key, &identity, &invoice).unwrap();
+        let received = wallet
+            .accept_private_payment(user_id, &sender, "prefix1", "suffix1")
+            .unwrap();
+        assert_eq!(received.address, Crypto::generate_address(&child));
+        assert!(received.derivation_path.starts_with(BRC42_PATH_PREFIX));
+        // Registering twice keeps one entry
+        wallet
+            .accept_private_payment(user_id, &sender, "prefix1", "suffix1")
+            .unwrap();
+        assert_eq!(wallet.get_receive_addresses(user_id).unwrap().len(), 1);
+
+        // Every payment gets its own key, none of them the identity key
+        let next = wallet
+            .accept_private_payment(user_id, &sender, "prefix1", "suffix2")
+            .unwrap();
+        assert_ne!(next.address, received.address);
+        assert_ne!(received.address, Crypto::generate_address(&identity));
+        // Counterparty addresses are never handed out on the receive screen
+        let shown = wallet.receive_address(user_id).unwrap();
+        assert!(!shown.derivation_path.starts_with(BRC42_PATH_PREFIX));
+
+        // The wallet can prove control of the derived key
+        let signature = wallet
+            .sign_message(user_id, &received.address, "owned")
+            .unwrap();
+        assert!(Crypto::verify_bitcoin_message(&received.address, "owned", &signature).unwrap());
+
+        // Paying back: the wallet derives the sender's child key the same way
+        let script = wallet
+            .private_payment_script(&sender, "prefix2", "suffix1")
+            .unwrap();
+        let sender_child = Crypto::derive_child_private_key(
+            &sender_key,
+            &identity,
+            &Crypto::payment_invoice_number("prefix2", "suffix1"),
+        )
+        .unwrap();
+        let expected = Crypto::generate_address(&Crypto::derive_public_key(&sender_child));
+        assert_eq!(script, WalletManager::address_script(&expected).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_alias_registry() {
+        let storage = Arc::new(ZipStorage::new().unwrap());
+        let registry = AliasRegistry::new(Arc::clone(&storage));
+        let (alice, bob) = (Uuid::new_v4(), Uuid::new_v4());
+
+        // Sequential aliases are never handed out twice, even across restarts
+        let first = registry.allocate("zip.io", alice).unwrap();
+        let number: u64 = first.split('@').next().unwrap().parse().unwrap();
+        let bespoke = format!("{}@zip.io", number + 1);
+        registry.register(&bespoke, bob).unwrap();
+        let restarted = AliasRegistry::new(Arc::clone(&storage));
+        let next = restarted.allocate("zip.io", bob).unwrap();
+        let next_number: u64 = next.split('@').next().unwrap().parse().unwrap();
+        assert!(next_number > number + 1);
+
+        // One owner per alias, case-insensitively; re-registering your own is harmless
+        let name = format!("{}@ZIP.io", Uuid::new_v4().simple());
+        assert_eq!(
+            registry.register(&name, alice).unwrap(),
+            name.to_lowercase()
+        );
+        registry.register(&name, alice).unwrap();
+        assert!(matches!(
+            registry.register(&name.to_uppercase(), bob),
+            Err(ZipError::Validation(_))
+        ));
+        assert_eq!(registry.owner(&name).unwrap(), Some(alice));
+        assert_eq!(registry.owner("nobody@zip.io").unwrap(), None);
+        let mut expected = vec![first, name.to_lowercase()];
+        expected.sort();
+        assert_eq!(registry.aliases(alice).unwrap(), expected);
+
+        let history = registry.history(&name).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(
+            (history[0].from, history[0].to),
+            (None::<Uuid>, Some(alice))
+        );
+
+        // Concurrent allocations never collide
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let registry = registry.clone();
+                tokio::spawn(async move { registry.allocate("zip.io", Uuid::new_v4()).unwrap() })
+            })
+            .collect();
+        let mut allocated = std::collections::HashSet::new();
+        for handle in handles {
+            assert!(allocated.insert(handle.await.unwrap()));
+        }
+    }
+
+    #[test]
+    fn test_paymail_prefix_validation() {
+        // PayMail local-part syntax
+        assert!(Validation::validate_paymail_prefix("john.doe+tips").is_ok());
+        assert!(Validation::validate_paymail_prefix("jösé").is_ok());
+        for bad in ["", "john doe", "john@doe", ".john", "john.", "jo..hn"] {
+            assert!(matches!(
+                Validation::validate_paymail_prefix(bad),
+                Err(ZipError::Validation(_))
+            ));
+        }
+        assert!(Validation::validate_paymail_prefix(&"a".repeat(MAX_PAYMAIL_PREFIX_LEN)).is_ok());
+        assert!(
+            Validation::validate_paymail_prefix(&"a".repeat(MAX_PAYMAIL_PREFIX_LEN + 1)).is_err()
+        );
+
+        // Both code paths report the same message
+        let config = PaymailConfig::default();
+        assert_eq!(
+            config.validate_prefix("jo..hn").unwrap_err().to_string(),
+            Validation::validate_paymail_prefix("jo..hn")
+                .unwrap_err()
+                .to_string()
+        );
+        assert_eq!(
+            config.validate_prefix("ab").unwrap_err().to_string(),
+            ZipError::from(PrefixViolation::TooShort {
+                min: MIN_PREFIX_LENGTH
+            })
+            .to_string()
+        );
+
+        // Reserved words, including look-alike spellings, and blocked words
+        assert!(config.validate_prefix("Admin").is_err());
+        assert!(config.validate_prefix("adrnin").is_err());
+        assert!(config.validate_prefix("supp0rt").is_err());
+        assert!(config.validate_prefix("fuckit").is_err());
+        assert!(config.validate_prefix("administrators").is_ok());
+        let custom = PaymailConfig {
+            reserved_words: vec![],
+            blocked_words: vec![String::new()],
+            ..PaymailConfig::default()
+        };
+        assert!(custom.validate_prefix("admin").is_ok());
+
+        // Look-alikes of another user's alias are refused
+        let storage = Arc::new(ZipStorage::new().unwrap());
+        let registry = AliasRegistry::new(Arc::clone(&storage));
+        let (alice, bob) = (Uuid::new_v4(), Uuid::new_v4());
+        let name = format!("john{}", Uuid::new_v4().simple()).replace(['0', '1'], "x");
+        let alias = registry
+            .register(&format!("{}@zip.io", name), alice)
+            .unwrap();
+        let digits = name.replacen('o', "0", 1);
+        let cyrillic = name.replacen('o', "\u{43e}", 1);
+        for lookalike in [&digits, &cyrillic] {
+            let result = registry.register(&format!("{}@zip.io", lookalike), bob);
+            assert_eq!(
+                result.unwrap_err().to_string(),
+                ZipError::from(PrefixViolation::Confusable {
+                    existing: alias.clone()
+                })
+                .to_string()
+            );
+        }
+        // ...but not for the owner, or on another domain
+        registry
+            .register(&format!("{}@zip.io", digits), alice)
+            .unwrap();
+        registry
+            .register(&format!("{}@other.io", cyrillic), bob)
+            .unwrap();
+
+        // Released aliases stop blocking their look-alikes
+        let cyrillic_alias = format!("{}@zip.io", cyrillic);
+        registry.release(&alias, alice).unwrap();
+        assert!(registry.register(&cyrillic_alias, bob).is_err());
+        registry
+            .release(&format!("{}@zip.io", digits), alice)
+            .unwrap();
+        registry.register(&cyrillic_alias, bob).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_public_profile() {
+        // Own profiles are validated; remote ones are cleaned up instead
+        assert!(PublicProfile::new("Alice", "").is_ok());
+        assert!(PublicProfile::new(&"a".repeat(MAX_PROFILE_NAME_LEN + 1), "").is_err());
+        assert!(PublicProfile::new("Al\u{7}ice", "").is_err());
+        assert!(PublicProfile::new("Alice", "http://example.com/a.png").is_err());
+        assert!(PublicProfile::new("Alice", "javascript:alert(1)").is_err());
+        let remote = PublicProfile::from_response(
+            "bob@example.com",
+            &serde_json::json!({ "name": "\u{1b}[2J", "avatar": "data:image/png;base64,AA" }),
+        );
+        assert_eq!(
+            remote,
+            PublicProfile {
+                name: "[2J".to_string(),
+                avatar: String::new()
+            }
+        );
+        let unnamed = PublicProfile::from_response("bob@example.com", &serde_json::json!({}));
+        assert_eq!(unnamed.name, "bob");
+
+        let storage = Arc::new(ZipStorage::new().unwrap());
+        let paymail = PaymailManager::new(Arc::clone(&storage));
+        let user_id = Uuid::new_v4();
+        let (alias, _) = paymail.create_default_alias(user_id, None).await.unwrap();
+        let prefix = alias.split('@').next().unwrap();
+        assert_eq!(paymail.alias_profile(&alias).unwrap().name, prefix);
+
+        paymail
+            .set_profile(user_id, "Alice", "https://example.com/alice.png")
+            .unwrap();
+        let served = paymail.alias_profile(&alias.to_uppercase()).unwrap();
+        assert_eq!(served.name, "Alice");
+        assert_eq!(served.avatar, "https://example.com/alice.png");
+        assert_eq!(paymail.resolve_profile(&alias).await.unwrap(), served);
+
+        // Names cannot pass for a reserved word or another user's alias
+        let (others, _) = paymail
+            .create_default_alias(Uuid::new_v4(), None)
+            .await
+            .unwrap();
+        let other_prefix = others.split('@').next().unwrap();
+        for name in ["Supp0rt", other_prefix] {
+            assert!(matches!(
+                paymail.set_profile(user_id, name, ""),
+                Err(ZipError::Validation(_))
+            ));
+        }
+        paymail.set_profile(user_id, prefix, "").unwrap();
+        paymail
+            .set_profile(user_id, "Alice", "https://example.com/alice.png")
+            .unwrap();
+
+        // An avatar alone keeps the alias as name; clearing both removes the profile
+        paymail
+            .set_profile(user_id, "", "https://example.com/alice.png")
+            .unwrap();
+        assert_eq!(paymail.alias_profile(&alias).unwrap().name, prefix);
+        paymail.set_profile(user_id, " ", "").unwrap();
+        assert_eq!(paymail.profile(user_id).unwrap(), None);
+        assert!(matches!(
+            paymail.alias_profile("nobody@zip.io"),
+            Err(ZipError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn test_paymail_pricing() {
+        let config = PaymailConfig::default();
+        // Premium list first, then length, then the default price
+        assert_eq!(config.get_prefix_price("john", false), Decimal::from(300));
+        assert_eq!(config.get_prefix_price("VIP", false), Decimal::from(5000));
+        assert_eq!(config.get_prefix_price("abc", false), Decimal::from(250));
+        assert_eq!(config.get_prefix_price("abcd", false), Decimal::from(25));
+        assert_eq!(config.get_prefix_price("54321", false), Decimal::from(10));
+        assert_eq!(config.get_prefix_price("101", true), Decimal::ZERO);
+        assert!(config.validate_prefix("ab").is_err());
+
+        // Fiat prices become satoshis at the quoted BSV price and stay locked
+        let quote = config
+            .quote("54321", false, Decimal::from(50), 1_000)
+            .unwrap();
+        assert_eq!(quote.satoshis, 20_000_000);
+        assert_eq!(quote.currency, "USD");
+        assert!(!quote.is_expired_at(1_000 + config.quote_lock_secs - 1));
+        assert!(quote.is_expired_at(1_000 + config.quote_lock_secs));
+        assert!(config.quote("54321", false, Decimal::ZERO, 1_000).is_err());
+
+        // A config file replaces the tables it names and keeps defaults for the rest
+        let path = std::env::temp_dir().join(format!("paymail-{}.json", Uuid::new_v4()));
+        std::fs::write(
+            &path,
+            r#"{ "currency": "EUR", "excluded_prefixes": { "zip": "9000" }, "length_prices": { "5": "20" } }"#,
+        )
+        .unwrap();
+        let config = PaymailConfig::from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(config.currency, "EUR");
+        assert_eq!(config.get_prefix_price("zip", false), Decimal::from(9000));
+        assert_eq!(config.get_prefix_price("54321", false), Decimal::from(20));
+        assert_eq!(config.get_prefix_price("john", false), Decimal::from(10));
+        assert!(matches!(
+            PaymailConfig::from_file(&path),
+            Err(ZipError::Config(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_alias_purchase_lifecycle() {
+        let storage = Arc::new(ZipStorage::new().unwrap());
+        let paymail = PaymailManager::new(Arc::clone(&storage));
+        let rustbus = None::<Arc<RustBusIntegrator>>;
+        let tx_manager = Arc::new(TransactionManager::new(Arc::clone(&storage), rustbus));
+        let wallet =
+            WalletManager::new(Arc::clone(&storage), Arc::clone(&tx_manager), None).unwrap();
+        let history = TxHistory::new(Arc::clone(&storage));
+        let alerts = Alerts::new(Arc::clone(&storage));
+        let (alice, bob) = (Uuid::new_v4(), Uuid::new_v4());
+        let prefix = format!(
+            "{}",
+            Uuid::new_v4().as_u128() % 1_000_000_000 + 1_000_000_000
+        );
+        let alias = format!("{}@zip.io", prefix);
+
+        // Reserved for alice alone, and not resolvable until paid
+        paymail.create_paid_alias(alice, &prefix).await.unwrap();
+        assert!(paymail.create_paid_alias(bob, &prefix).await.is_err());
+        assert_eq!(paymail.alias_owner(&alias).unwrap(), None);
+        let mut purchase: AliasPurchase = paymail.purchase(&alias).unwrap().unwrap();
+        assert_eq!(purchase.status, AliasStatus::Reserved);
+
+        // Payment details fixed when paying starts
+        let treasury = Script(vec![0x51]);
+        purchase.satoshis = 5000;
+        purchase.payment_script = treasury.0.clone();
+        storage
+            .store_alias_purchase(&alias, &bincode::serialize(&purchase).unwrap())
+            .unwrap();
+        let keys = fund(&tx_manager, alice, 3, 10000);
+        let tagged = |reference: &str| {
+            DataOutput::new()
+                .push(ALIAS_PURCHASE_TAG.as_bytes())
+                .push(reference.as_bytes())
+        };
+        let hex_of = |tx| hex::encode(TransactionManager::serialize(&tx));
+
+        let wrong_reference = tx_manager
+            .build_payment_tx(
+                alice,
+                treasury.clone(),
+                5000,
+                500,
+                &[tagged("other")],
+                &keys,
+            )
+            .await
+            .unwrap();
+        let underpaid = tx_manager
+            .build_payment_tx(
+                alice,
+                treasury.clone(),
+                4999,
+                500,
+                &[tagged(&purchase.reference)],
+                &keys,
+            )
+            .await
+            .unwrap();
+        let paid = tx_manager
+            .build_payment_tx(
+                alice,
+                treasury.clone(),
+                5000,
+                500,
+                &[tagged(&purchase.reference)],
+                &keys,
+            )
+            .await
+            .unwrap();
+        for tx in [wrong_reference, underpaid] {
+            assert!(matches!(
+                paymail.submit_alias_payment(alice, &alias, &hex_of(tx)),
+                Err(ZipError::Blockchain(_))
+            ));
+        }
+        assert!(
+            paymail
+                .submit_alias_payment(bob, &alias, &hex_of(paid.clone()))
+                .is_err()
+        );
+        let paid = hex_of(paid);
+        let txid = paymail.submit_alias_payment(alice, &alias, &paid).unwrap();
+        assert_eq!(
+            paymail.purchase(&alias).unwrap().unwrap().status,
+            AliasStatus::PaymentPending { txid: txid.clone() }
+        );
+
+        // Activates only once the network has the payment; having broadcast it is no proof
+        let entry = HistoryEntry {
+            txid: txid.clone(),
+            direction: TxDirection::Sent,
+            amount: 5000,
+            fee: 500,
+            counterparty: hex::encode(&treasury.0),
+            inputs: vec![],
+            status: TxStatus::Pending,
+            timestamp: 0,
+            memo: None,
+        };
+        history.upsert(alice, entry).unwrap();
+        assert!(paymail.confirm_alias(&wallet, alice, &alias).await.is_err());
+        history
+            .set_status(alice, &txid, TxStatus::Broadcast)
+            .unwrap();
+        assert!(paymail.confirm_alias(&wallet, alice, &alias).await.is_err());
+        assert_eq!(
+            paymail.purchase(&alias).unwrap().unwrap().status,
+            AliasStatus::PaymentPending { txid: txid.clone() }
+        );
+        assert_eq!(paymail.alias_owner(&alias).unwrap(), None);
+
+        // A payment the network turned down leaves the reservation to be paid again
+        let competing_txid = "ff".repeat(32);
+        history
+            .set_status(alice, &txid, TxStatus::Conflicted { competing_txid })
+            .unwrap();
+        assert!(matches!(
+            paymail.confirm_alias(&wallet, alice, &alias).await,
+            Err(ZipError::Blockchain(_))
+        ));
+        assert_eq!(
+            paymail.purchase(&alias).unwrap().unwrap().status,
+            AliasStatus::Reserved
+        );
+
+        // A payment still unaccepted at the timeout releases the alias
+        paymail.submit_alias_payment(alice, &alias, &paid).unwrap();
+        let now = chrono::Utc::now().timestamp();
+        assert!(!paymail.expire_reservations(now).unwrap().contains(&alias));
+        assert!(
+            paymail
+                .expire_reservations(now + RESERVATION_TIMEOUT_SECS)
+                .unwrap()
+                .contains(&alias)
+        );
+        assert_eq!(
+            paymail.purchase(&alias).unwrap().unwrap().status,
+            AliasStatus::Expired
+        );
+        assert_eq!(paymail.registry().owner(&alias).unwrap(), None);
+        assert!(
+            alerts
+                .take(alice)
+                .unwrap()
+                .iter()
+                .any(|a| a.message.contains(&alias))
+        );
+
+        // Unpaid reservations go back to the pool after the timeout
+        let lapsing = format!(
+            "{}",
+            Uuid::new_v4().as_u128() % 1_000_000_000 + 1_000_000_000
+        );
+        let (lapsing, _) = paymail.create_paid_alias(bob, &lapsing).await.unwrap();
+        let now = chrono::Utc::now().timestamp();
+        assert!(!paymail.expire_reservations(now).unwrap().contains(&lapsing));
+        let released = paymail
+            .expire_reservations(now + RESERVATION_TIMEOUT_SECS)
+            .unwrap();
+        assert!(released.contains(&lapsing));
+        assert_eq!(
+            paymail.purchase(&lapsing).unwrap().unwrap().status,
+            AliasStatus::Expired
+        );
+        assert_eq!(paymail.registry().owner(&lapsing).unwrap(), None);
+        let prefix = lapsing.split('@').next().unwrap();
+        paymail.create_paid_alias(alice, prefix).await.unwrap();
+        assert_eq!(
+            paymail.purchase(&lapsing).unwrap().unwrap().status,
+            AliasStatus::Reserved
+        );
+    }
+
+    #[tokio::test]
+    async fn test_alias_auction() {
+        let storage = Arc::new(ZipStorage::new().unwrap());
+        let rustbus = None::<Arc<RustBusIntegrator>>;
+        let tx_manager = Arc::new(TransactionManager::new(Arc::clone(&storage), rustbus));
+        let wallet =
+            WalletManager::new(Arc::clone(&storage), Arc::clone(&tx_manager), None).unwrap();
+        let (premium, unpopular) = (
+            format!("vip{}", &Uuid::new_v4().simple().to_string()[..8]),
+            format!("gold{}", &Uuid::new_v4().simple().to_string()[..8]),
+        );
+        let mut pricing = PaymailConfig {
+            premium_auctions: true,
+            ..PaymailConfig::default()
+        };
+        pricing
+            .excluded_prefixes
+            .insert(premium.clone(), Decimal::from(5000));
+        pricing
+            .excluded_prefixes
+            .insert(unpopular.clone(), Decimal::from(5000));
+        let extension = pricing.auction_extension_secs;
+        let paymail = PaymailManager::new(Arc::clone(&storage)).with_pricing(pricing);
+        let (alice, bob) = (Uuid::new_v4(), Uuid::new_v4());
+        for user in [alice, bob] {
+            fund_wallet(&wallet, &tx_manager, user, 3, 10000);
+        }
+
+        // Premium prefixes are not sold at a fixed price while auctions are on
+        assert!(matches!(
+            paymail.create_paid_alias(alice, &premium).await,
+            Err(ZipError::Validation(_))
+        ));
+        let now = chrono::Utc::now().timestamp();
+        let auction = paymail.start_auction(&premium, 10_000, now).await.unwrap();
+        let alias = auction.alias.clone();
+        assert_eq!(
+            paymail.start_auction(&premium, 1, now).await.unwrap().id,
+            auction.id
+        );
+
+        // The opening bid must meet the reserve
+        assert!(matches!(
+            paymail.bid_alias(&wallet, alice, &alias, 9_999, now).await,
+            Err(ZipError::Validation(_))
+        ));
+        assert_eq!(tx_manager.get_cached_utxos(alice).unwrap().len(), 3);
+
+        // Bids are held in the bidder's wallet
+        paymail
+            .bid_alias(&wallet, alice, &alias, 10_000, now)
+            .await
+            .unwrap();
+        assert_eq!(paymail.escrowed(alice).unwrap(), 10_000);
+        assert_eq!(tx_manager.get_cached_utxos(alice).unwrap().len(), 2);
+
+        // Raises must beat the leading bid by the increment
+        assert!(matches!(
+            paymail.bid_alias(&wallet, bob, &alias, 10_200, now).await,
+            Err(ZipError::Validation(_))
+        ));
+        assert_eq!(tx_manager.get_cached_utxos(bob).unwrap().len(), 3);
+
+        // Outbid users get their coins back and an alert
+        paymail
+            .bid_alias(&wallet, bob, &alias, 12_000, now)
+            .await
+            .unwrap();
+        assert_eq!(paymail.escrowed(alice).unwrap(), 0);
+        assert_eq!(paymail.escrowed(bob).unwrap(), 12_000);
+        assert_eq!(tx_manager.get_cached_utxos(alice).unwrap().len(), 3);
+        let alerts = wallet.take_alerts(alice).unwrap();
+        assert!(alerts.iter().any(|a| a.message.starts_with("Outbid on")));
+
+        // A last-minute bid extends the auction
+        let late = auction.ends_at - 60;
+        let extended = paymail
+            .bid_alias(&wallet, alice, &alias, 16_000, late)
+            .await
+            .unwrap();
+        assert_eq!(extended.ends_at, late + extension);
+        assert_eq!(tx_manager.get_cached_utxos(bob).unwrap().len(), 3);
+        assert!(matches!(
+            paymail
+                .bid_alias(&wallet, bob, &alias, 20_000, extended.ends_at)
+                .await,
+            Err(ZipError::Validation(_))
+        ));
+
+        // The highest bidder wins and owes the winning bid, its escrow held until paid
+        let closed = paymail
+            .settle_auctions(&wallet, extended.ends_at)
+            .await
+            .unwrap();
+        let won = closed.iter().find(|a| a.alias == alias).unwrap();
+        assert_eq!(
+            won.status,
+            AuctionStatus::Settled {
+                winner: alice,
+                satoshis: 16_000
+            }
+        );
+        assert_eq!(paymail.registry().owner(&alias).unwrap(), Some(alice));
+        let purchase = paymail.purchase(&alias).unwrap().unwrap();
+        assert_eq!((purchase.owner, purchase.satoshis), (alice, 16_000));
+        let escrow = won.leading().unwrap().escrow.clone();
+        assert!(!tx_manager.get_escrow(alice, &escrow).unwrap().is_empty());
+        assert!(tx_manager.get_cached_utxos(alice).unwrap().len() < 3);
+
+        // Left unpaid, the reservation lapses and the escrow is released
+        paymail
+            .expire_reservations(purchase.reserved_at + RESERVATION_TIMEOUT_SECS)
+            .unwrap();
+        assert_eq!(paymail.registry().owner(&alias).unwrap(), None);
+        assert!(tx_manager.get_escrow(alice, &escrow).unwrap().is_empty());
+        assert_eq!(tx_manager.get_cached_utxos(alice).unwrap().len(), 3);
+
+        // Without a bid at the reserve nothing is sold
+        let auction = paymail
+            .start_auction(&unpopular, 50_000, now)
+            .await
+            .unwrap();
+        assert!(matches!(
+            paymail
+                .bid_alias(&wallet, bob, &auction.alias, 1_000, now)
+                .await,
+            Err(ZipError::Validation(_))
+        ));
+        let closed = paymail
+            .settle_auctions(&wallet, auction.ends_at)
+            .await
+            .unwrap();
+        let unsold = closed.iter().find(|a| a.id == auction.id).unwrap();
+        assert_eq!(unsold.status, AuctionStatus::Unsold);
+        assert_eq!(paymail.registry().owner(&auction.alias).unwrap(), None);
+        assert_eq!(tx_manager.get_cached_utxos(bob).unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_alias_transfer() {
+        let storage = Arc::new(ZipStorage::new().unwrap());
+        let paymail = PaymailManager::new(Arc::clone(&storage));
+        let wallet_alerts = Alerts::new(Arc::clone(&storage));
+        let (alice, bob, carol) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let (alias, _) = paymail.create_default_alias(alice, None).await.unwrap();
+        let (bobs, _) = paymail.create_default_alias(bob, None).await.unwrap();
+        let tx_manager = Arc::new(TransactionManager::new(Arc::clone(&storage), None));
+        let wallet = WalletManager::new(Arc::clone(&storage), tx_manager, None).unwrap();
+        let now = chrono::Utc::now().timestamp();
+
+        // Offering counts as the owner's side; nothing moves until the recipient confirms
+        let offer = paymail
+            .propose_transfer(alice, &alias, &bobs, now)
+            .await
+            .unwrap();
+        assert_eq!((offer.from, offer.to), (alice, bob));
+        assert!(offer.from_confirmed && !offer.to_confirmed);
+        assert_eq!(paymail.alias_owner(&alias).unwrap(), Some(alice));
+        assert!(matches!(
+            paymail.propose_transfer(alice, &alias, &bobs, now).await,
+            Err(ZipError::Validation(_))
+        ));
+        assert!(matches!(
+            paymail.confirm_transfer(&wallet, carol, &alias, now).await,
+            Err(ZipError::Validation(_))
+        ));
+        assert!(
+            wallet_alerts
+                .take(bob)
+                .unwrap()
+                .iter()
+                .any(|a| a.message.contains(&alias))
+        );
+
+        let done = paymail
+            .confirm_transfer(&wallet, bob, &alias, now)
+            .await
+            .unwrap();
+        assert!(done.is_confirmed());
+        assert_eq!(paymail.alias_owner(&alias).unwrap(), Some(bob));
+        let last = paymail.registry().history(&alias).unwrap().pop().unwrap();
+        assert_eq!((last.from, last.to), (Some(alice), Some(bob)));
+        assert!(paymail.pending_transfers(alice, now).unwrap().is_empty());
+
+        // Offers lapse, and either side can cancel
+        assert!(matches!(
+            paymail.propose_transfer(bob, &alias, &bobs, now).await,
+            Err(ZipError::Validation(_))
+        ));
+        let (alices, _) = paymail.create_default_alias(alice, None).await.unwrap();
+        paymail
+            .propose_transfer(bob, &bobs, &alices, now)
+            .await
+            .unwrap();
+        assert!(matches!(
+            paymail
+                .confirm_transfer(&wallet, alice, &bobs, now + TRANSFER_TIMEOUT_SECS)
+                .await,
+            Err(ZipError::Validation(_))
+        ));
+        assert_eq!(paymail.alias_owner(&bobs).unwrap(), Some(bob));
+        paymail
+            .propose_transfer(bob, &bobs, &alices, now)
+            .await
+            .unwrap();
+        paymail.cancel_transfer(alice, &bobs).unwrap();
+        assert!(paymail.pending_transfers(bob, now).unwrap().is_empty());
+        assert!(
+            wallet_alerts
+                .take(bob)
+                .unwrap()
+                .iter()
+                .any(|a| a.message.contains("cancelled"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_alias_renewal_and_expiry() {
+        let storage = Arc::new(ZipStorage::new().unwrap());
+        let premium = format!("king{}", &Uuid::new_v4().simple().to_string()[..8]);
+        let mut pricing = PaymailConfig {
+            premium_renewals: true,
+            ..PaymailConfig::default()
+        };
+        pricing
+            .excluded_prefixes
+            .insert(premium.clone(), Decimal::from(2000));
+        let (period, grace) = (pricing.renewal_period_secs, pricing.grace_period_secs);
+        let paymail = PaymailManager::new(Arc::clone(&storage)).with_pricing(pricing);
+        let history = TxHistory::new(Arc::clone(&storage));
+        let alerts = Alerts::new(Arc::clone(&storage));
+        let user_id = Uuid::new_v4();
+        let broadcast = |txid: &str| HistoryEntry {
+            txid: txid.to_string(),
+            direction: TxDirection::Sent,
+            amount: 5000,
+            fee: 500,
+            counterparty: String::new(),
+            inputs: vec![],
+            status: TxStatus::Broadcast,
+            timestamp: 0,
+            memo: None,
+        };
+
+        // The first term starts when the purchase activates, its payment proven in a
+        // block of the header chain (the single-transaction block at height 1 that
+        // the reorg test builds on)
+        let (alias, _) = paymail.create_paid_alias(user_id, &premium).await.unwrap();
+        let block = |height: u64, hash: &str, prev_hash: &str, merkle_root: String| BlockHeader {
+            height,
+            hash: hash.to_string(),
+            prev_hash: prev_hash.to_string(),
+            merkle_root,
+            time: height as u32 * 600,
+        };
+        HeaderStore::new(Arc::clone(&storage))
+            .apply_branch(vec![
+                block(0, "h0", "", "00".repeat(32)),
+                block(1, "h1", "h0", "01".repeat(32)),
+            ])
+            .unwrap();
+        let purchase_txid = "01".repeat(32);
+        let mut purchase = paymail.purchase(&alias).unwrap().unwrap();
+        purchase.status = AliasStatus::PaymentPending {
+            txid: purchase_txid.clone(),
+        };
+        storage
+            .store_alias_purchase(&alias, &bincode::serialize(&purchase).unwrap())
+            .unwrap();
+        history.upsert(user_id, broadcast(&purchase_txid)).unwrap();
+        let tx_manager = Arc::new(TransactionManager::new(Arc::clone(&storage), None));
+        let wallet = WalletManager::new(Arc::clone(&storage), tx_manager, None).unwrap();
+        assert!(
+            paymail
+                .confirm_alias(&wallet, user_id, &alias)
+                .await
+                .is_err()
+        );
+        history
+            .store_proof(
+                user_id,
+                MerkleProof {
+                    txid: purchase_txid.clone(),
+                    index: 0,
+                    nodes: vec![],
+                    block_hash: "h1".to_string(),
+                    block_height: 1,
+                },
+            )
+            .unwrap();
+        let now = chrono::Utc::now().timestamp();
+        paymail
+            .confirm_alias(&wallet, user_id, &alias)
+            .await
+            .unwrap();
+        let lease: AliasLease = paymail.lease(&alias).unwrap().unwrap();
+        assert_eq!(lease.owner, user_id);
+        assert!(lease.paid_until >= now + period && lease.paid_until <= now + period + 5);
+
+        // Owners are warned once when the term ends but keep the alias through the grace period
+        assert!(paymail.expire_leases(lease.paid_until).unwrap().is_empty());
+        paymail.expire_leases(lease.paid_until + 1).unwrap();
+        let warnings = alerts.take(user_id).unwrap();
+        assert_eq!(
+            warnings
+                .iter()
+                .filter(|a| a.message.contains("renew within"))
+                .count(),
+            1
+        );
+        assert_eq!(paymail.alias_owner(&alias).unwrap(), Some(user_id));
+
+        // A renewal the network has yet to accept keeps the alias past the grace period
+        // until it times out; being broadcast is not enough
+        let mut renewing = paymail.lease(&alias).unwrap().unwrap();
+        renewing.pending_renewal = Some("renewal_txid".to_string());
+        renewing.pending_since = now;
+        storage
+            .store_alias_lease(&alias, &bincode::serialize(&renewing).unwrap())
+            .unwrap();
+        history.upsert(user_id, broadcast("renewal_txid")).unwrap();
+        assert!(
+            !paymail
+                .expire_leases(lease.paid_until + grace)
+                .unwrap()
+                .contains(&alias)
+        );
+        paymail.settle_renewals(&wallet, now).await.unwrap();
+        assert_eq!(paymail.lease(&alias).unwrap().unwrap(), renewing);
+        paymail
+            .settle_renewals(&wallet, now + RENEWAL_TIMEOUT_SECS)
+            .await
+            .unwrap();
+        let dropped = paymail.lease(&alias).unwrap().unwrap();
+        assert!(dropped.pending_renewal.is_none());
+        assert_eq!(dropped.paid_until, lease.paid_until);
+        assert!(
+            alerts
+                .take(user_id)
+                .unwrap()
+                .iter()
+                .any(|a| a.message.contains("did not accept the renewal"))
+        );
+
+        // An accepted renewal adds a term to the end of the current one; the proven
+        // purchase payment stands in for it
+        renewing.pending_renewal = Some(purchase_txid.clone());
+        storage
+            .store_alias_lease(&alias, &bincode::serialize(&renewing).unwrap())
+            .unwrap();
+        let renewals = paymail.settle_renewals(&wallet, now).await.unwrap();
+        assert!(renewals.contains(&alias));
+        let renewed = paymail.lease(&alias).unwrap().unwrap();
+        assert_eq!(renewed.paid_until, lease.paid_until + period);
+        assert_eq!(renewed.renewals, vec![purchase_txid]);
+        assert!(renewed.pending_renewal.is_none());
+
+        // Unrenewed aliases go back to the pool after the grace period
+        let released = paymail.expire_leases(renewed.paid_until + grace).unwrap();
+        assert!(released.contains(&alias));
+        assert_eq!(paymail.registry().owner(&alias).unwrap(), None);
+        assert_eq!(paymail.alias_owner(&alias).unwrap(), None);
+        assert!(paymail.lease(&alias).unwrap().is_none());
+        assert_eq!(
+            paymail.purchase(&alias).unwrap().unwrap().status,
+            AliasStatus::Expired
+        );
+    }
 }
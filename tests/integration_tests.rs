@@ -2,8 +2,9 @@ use tokio::runtime::Runtime;
 use uuid::Uuid;
 
 use crate::auth::{OAuthManager, PasskeyManager};
-use crate::blockchain::{PaymailManager, TransactionManager, WalletManager};
+use crate::blockchain::{PaymailManager, TransactionManager, Utxo, WalletManager};
 use crate::config::Config;
+use crate::errors::ZipError;
 use crate::integrations::RustBusIntegrator;
 use crate::storage::ZipStorage;
 use crate::utils::setup_logging;
@@ -12,6 +13,28 @@ use crate::utils::setup_logging;
 mod tests {
     use super::*;
 
+    /// Caches coins at the user's receive address, so the wallet can sign for them.
+    fn fund_wallet(
+        wallet: &WalletManager,
+        tx_manager: &TransactionManager,
+        user_id: Uuid,
+        count: u32,
+        value: u64,
+    ) {
+        let address = wallet.receive_address(user_id).unwrap().address;
+        let script = WalletManager::address_script(&address).unwrap().0;
+        let txid = Uuid::new_v4().simple().to_string().repeat(2);
+        let coins: Vec<Utxo> = (0..count)
+            .map(|vout| Utxo {
+                txid: txid.clone(),
+                vout,
+                value,
+                script: script.clone(),
+            })
+            .collect();
+        tx_manager.add_utxos(user_id, &coins).unwrap();
+    }
+
     #[tokio::test]
     async fn test_auth_to_wallet_flow() {
         let config = Config::load().unwrap();
@@ -49,7 +72,7 @@ mod tests {
         let (balance, _) = wallet.update_balance(user_id, "USD").await.unwrap();
         assert_eq!(balance, 0);
 
-        let address = wallet.get_address().unwrap();
+        let (address, _) = wallet.get_address().unwrap();
         assert!(!address.is_empty());
     }
 
@@ -70,19 +93,15 @@ mod tests {
         let paymail = PaymailManager::new(PrivateKey::new(), Arc::clone(&storage));
         let user_id = Uuid::new_v4();
 
-        // Pre-create UTXOs
-        tx_manager
-            .pre_create_utxos(user_id, 5, 10000)
-            .await
-            .unwrap();
+        fund_wallet(&wallet, &tx_manager, user_id, 5, 10000);
 
         // Resolve PayMail and send payment
         let (recipient_script, _) = paymail.resolve_paymail("000@zip.io", 10000).await.unwrap();
-        let tx_hex = wallet
-            .send_payment(user_id, recipient_script, 8000, 1000)
+        let txid = wallet
+            .send_payment(user_id, recipient_script, 8000, 1000, &[])
             .await
             .unwrap();
-        assert!(!tx_hex.is_empty());
+        assert_eq!(txid.len(), 64);
     }
 
     #[tokio::test]
@@ -108,22 +127,21 @@ mod tests {
         assert_eq!(price, Decimal::ZERO);
 
         // Create paid alias
-        tx_manager
-            .pre_create_utxos(user_id, 5, 10000)
-            .await
-            .unwrap();
+        fund_wallet(&wallet, &tx_manager, user_id, 5, 10000);
         let (alias, price) = paymail.create_paid_alias(user_id, "54321").await.unwrap();
         assert_eq!(alias, "54321@zip.io");
         assert_eq!(price, Decimal::from(10));
 
         let (recipient_script, _) = paymail.resolve_paymail("000@zip.io", 10000).await.unwrap();
         wallet
-            .send_payment(user_id, recipient_script, 10000, 1000)
+            .send_payment(user_id, recipient_script, 10000, 1000, &[])
             .await
             .unwrap();
-        paymail.confirm_alias(user_id, &alias).await.unwrap();
-
-        let aliases = paymail.get_user_aliases(user_id).await.unwrap();
-        assert!(aliases.contains("54321@zip.io"));
+        // A payment without the purchase reference does not pay for the alias
+        assert!(matches!(
+            paymail.confirm_alias(&wallet, user_id, &alias).await,
+            Err(ZipError::Blockchain(_))
+        ));
+        assert_eq!(paymail.alias_owner(&alias).unwrap(), None);
     }
 }
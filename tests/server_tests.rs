@@ -0,0 +1,787 @@
+use serde_json::{Value, json};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use sv::script::Script;
+use sv::transaction::{OutPoint, Transaction, TxIn, TxOut};
+use sv::util::{Hash256, sha256d};
+use uuid::Uuid;
+use zip::blockchain::{
+    BEEF_V1, BlockHeader, HeaderStore, Invoice, InvoiceAmount, InvoiceDestination, InvoiceStatus,
+    Invoices, PaymailManager, SigningKeys, TransactionManager, TxDirection, Utxo, WalletManager,
+};
+use zip::errors::ZipError;
+use zip::integrations::RustBusIntegrator;
+use zip::server::{
+    DESTINATION_TTL_SECS, MAX_OPEN_DESTINATIONS, PaymailHost, PromptKind, WalletInterface,
+};
+use zip::storage::ZipStorage;
+use zip::utils::crypto::Crypto;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const APP: &str = "https://app.example";
+
+    /// Answers prompts like a user would: connect with a 5,000 satoshi allowance,
+    /// approve spends below 20,000 satoshis and deny anything larger, and allow signing
+    /// only with the test protocol.
+    fn spawn_user(interface: WalletInterface) {
+        tokio::spawn(async move {
+            loop {
+                for prompt in interface.pending_prompts().await {
+                    let answer = match prompt.kind {
+                        PromptKind::Connect => Some(5_000),
+                        PromptKind::Spend { satoshis, .. } if satoshis < 20_000 => Some(0),
+                        PromptKind::Spend { .. } => None,
+                        PromptKind::Protocol { protocol, .. }
+                            if protocol == "zip test protocol" =>
+                        {
+                            Some(0)
+                        }
+                        PromptKind::Protocol { .. } => None,
+                    };
+                    let _ = interface.respond(prompt.id, answer).await;
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        });
+    }
+
+    async fn call(base: &str, origin: Option<&str>, method: &str, args: Value) -> (u16, Value) {
+        let mut request = reqwest::Client::new()
+            .post(format!("{}/{}", base, method))
+            .json(&args);
+        if let Some(origin) = origin {
+            request = request.header("Origin", origin);
+        }
+        let response = request.send().await.unwrap();
+        let status = response.status().as_u16();
+        (status, response.json().await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_brc100_interface() {
+        let storage = Arc::new(ZipStorage::new().unwrap());
+        let rustbus = None::<Arc<RustBusIntegrator>>;
+        let tx_manager = Arc::new(TransactionManager::new(Arc::clone(&storage), rustbus));
+        let wallet =
+            WalletManager::new(Arc::clone(&storage), Arc::clone(&tx_manager), None).unwrap();
+        let user_id = Uuid::new_v4();
+        let funded = wallet.receive_address(user_id).unwrap().address;
+        let coins: Vec<Utxo> = (0..4)
+            .map(|vout| Utxo {
+                txid: "ab".repeat(32),
+                vout,
+                value: 10_000,
+                script: WalletManager::address_script(&funded).unwrap().0,
+            })
+            .collect();
+        tx_manager.add_utxos(user_id, &coins).unwrap();
+        let interface = WalletInterface::new(
+            wallet.clone(),
+            Arc::clone(&tx_manager),
+            Arc::clone(&storage),
+            user_id,
+        );
+        let base = format!("http://{}", interface.serve(0).await.unwrap());
+        spawn_user(interface.clone());
+
+        let (status, body) = call(&base, None, "getPublicKey", json!({})).await;
+        assert_eq!(
+            (status, body["code"].as_str()),
+            (400, Some("ERR_UNAUTHORIZED"))
+        );
+        // Nobody is signed in as this wallet user
+        let (_, body) = call(&base, Some(APP), "isAuthenticated", json!({})).await;
+        assert_eq!(body["authenticated"], false);
+
+        // A page cannot pass itself off as another app through `originator`
+        let spoofed = reqwest::Client::new()
+            .post(format!("{}/getPublicKey", base))
+            .header("Origin", "https://evil.example")
+            .header("originator", APP)
+            .json(&json!({ "identityKey": true }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(spoofed.status().as_u16(), 403);
+        assert!(interface.permissions().get(user_id, APP).unwrap().is_none());
+
+        // First contact prompts, then the identity key matches the wallet's PKI key
+        let (status, body) = call(
+            &base,
+            Some(APP),
+            "getPublicKey",
+            json!({ "identityKey": true }),
+        )
+        .await;
+        assert_eq!(status, 200);
+        let identity = hex::encode(wallet.identity_public_key().unwrap().to_bytes());
+        assert_eq!(body["publicKey"], identity);
+        let permission = interface.permissions().get(user_id, APP).unwrap().unwrap();
+        assert_eq!(permission.allowance, 5_000);
+
+        // Signatures verify against the same derived key
+        let key_args = json!({
+            "protocolID": [2, "zip test protocol"],
+            "keyID": "1",
+            "counterparty": "self",
+            "data": [1, 2, 3],
+        });
+        let (_, signed) = call(&base, Some(APP), "createSignature", key_args.clone()).await;
+        let mut verify_args = key_args.clone();
+        verify_args["signature"] = signed["signature"].clone();
+        verify_args["forSelf"] = json!(true);
+        let (_, verified) = call(&base, Some(APP), "verifySignature", verify_args.clone()).await;
+        assert_eq!(verified["valid"], true);
+        verify_args["data"] = json!([1, 2, 4]);
+        let (_, verified) = call(&base, Some(APP), "verifySignature", verify_args).await;
+        assert_eq!(verified["valid"], false);
+        let permission = interface.permissions().get(user_id, APP).unwrap().unwrap();
+        assert_eq!(
+            permission.protocols,
+            vec!["2-zip test protocol".to_string()]
+        );
+
+        // Payment keys and unapproved protocols are refused
+        let mut payment_args = key_args.clone();
+        payment_args["protocolID"] = json!([2, "3241645161d8"]);
+        let (_, refused) = call(&base, Some(APP), "createSignature", payment_args).await;
+        assert_eq!(refused["code"], "ERR_UNAUTHORIZED");
+        let mut other_args = key_args.clone();
+        other_args["protocolID"] = json!([1, "another protocol"]);
+        let (_, refused) = call(&base, Some(APP), "createSignature", other_args).await;
+        assert_eq!(refused["code"], "ERR_UNAUTHORIZED");
+
+        // Nor does the wallet sign with a key it holds funds under
+        let (_, key) = call(
+            &base,
+            Some(APP),
+            "getPublicKey",
+            json!({ "protocolID": [2, "zip test protocol"], "keyID": "7", "counterparty": "anyone", "forSelf": true }),
+        )
+        .await;
+        let key = hex::decode(key["publicKey"].as_str().unwrap()).unwrap();
+        tx_manager
+            .add_utxos(
+                user_id,
+                &[Utxo {
+                    txid: "ee".repeat(32),
+                    vout: 0,
+                    value: 1_000,
+                    script: [vec![0x21], key, vec![0xac]].concat(),
+                }],
+            )
+            .unwrap();
+        let (_, refused) = call(
+            &base,
+            Some(APP),
+            "createSignature",
+            json!({ "protocolID": [2, "zip test protocol"], "keyID": "7", "hashToDirectlySign": vec![7u8; 32] }),
+        )
+        .await;
+        assert_eq!(refused["code"], "ERR_UNAUTHORIZED");
+
+        let (_, listed) = call(
+            &base,
+            Some(APP),
+            "listOutputs",
+            json!({ "basket": "default" }),
+        )
+        .await;
+        assert_eq!(listed["totalOutputs"], 4);
+
+        // Within the allowance: sent immediately and charged to the app
+        let script = format!("76a914{}88ac", "11".repeat(20));
+        let (status, sent) = call(
+            &base,
+            Some(APP),
+            "createAction",
+            json!({
+                "description": "Tip the author",
+                "outputs": [{ "lockingScript": script, "satoshis": 1_000, "outputDescription": "Tip" }],
+            }),
+        )
+        .await;
+        assert_eq!(status, 200);
+        assert_eq!(sent["txid"].as_str().unwrap().len(), 64);
+        let permission = interface.permissions().get(user_id, APP).unwrap().unwrap();
+        assert!(permission.spent > 1_000 && permission.remaining() < 4_000);
+
+        // Beyond the allowance: prompts, and a denial leaves funds untouched
+        let (status, denied) = call(
+            &base,
+            Some(APP),
+            "createAction",
+            json!({
+                "description": "Buy everything",
+                "outputs": [{ "lockingScript": script, "satoshis": 25_000 }],
+            }),
+        )
+        .await;
+        assert_eq!(
+            (status, denied["code"].as_str()),
+            (400, Some("ERR_UNAUTHORIZED"))
+        );
+
+        // Two-phase actions can be aborted, releasing the reserved inputs
+        let reserved = tx_manager.get_reservations(user_id).unwrap().len();
+        let (_, signable) = call(
+            &base,
+            Some(APP),
+            "createAction",
+            json!({
+                "description": "Maybe later",
+                "outputs": [{ "lockingScript": script, "satoshis": 8_000 }],
+                "options": { "signAndProcess": false },
+            }),
+        )
+        .await;
+        let reference = signable["signableTransaction"]["reference"].clone();
+        assert!(tx_manager.get_reservations(user_id).unwrap().len() > reserved);
+        let (_, other) = call(
+            &base,
+            Some("https://other.example"),
+            "abortAction",
+            json!({ "reference": reference }),
+        )
+        .await;
+        assert_eq!(other["code"], "ERR_INVALID_PARAMETER");
+        let (_, aborted) = call(
+            &base,
+            Some(APP),
+            "abortAction",
+            json!({ "reference": reference }),
+        )
+        .await;
+        assert_eq!(aborted["aborted"], true);
+        assert_eq!(
+            tx_manager.get_reservations(user_id).unwrap().len(),
+            reserved
+        );
+
+        // Pending actions within the allowance hold it until signed or aborted
+        let before = interface.permissions().get(user_id, APP).unwrap().unwrap();
+        let (_, signable) = call(
+            &base,
+            Some(APP),
+            "createAction",
+            json!({
+                "description": "Small pending tip",
+                "outputs": [{ "lockingScript": script, "satoshis": 500 }],
+                "options": { "signAndProcess": false },
+            }),
+        )
+        .await;
+        let held = interface.permissions().get(user_id, APP).unwrap().unwrap();
+        assert!(held.remaining() < before.remaining() - 500);
+        call(
+            &base,
+            Some(APP),
+            "abortAction",
+            json!({ "reference": signable["signableTransaction"]["reference"] }),
+        )
+        .await;
+        let released = interface.permissions().get(user_id, APP).unwrap().unwrap();
+        assert_eq!(released.remaining(), before.remaining());
+
+        // Browser apps get CORS headers, including on preflight
+        let preflight = reqwest::Client::new()
+            .request(reqwest::Method::OPTIONS, format!("{}/getNetwork", base))
+            .header("Origin", APP)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(preflight.status().as_u16(), 204);
+        assert_eq!(preflight.headers()["access-control-allow-origin"], APP);
+        assert_eq!(
+            preflight.headers()["access-control-allow-headers"],
+            "content-type"
+        );
+        // Origins the user never connected get no CORS headers at all
+        let preflight = reqwest::Client::new()
+            .request(reqwest::Method::OPTIONS, format!("{}/getNetwork", base))
+            .header("Origin", "https://unknown.example")
+            .send()
+            .await
+            .unwrap();
+        assert!(
+            !preflight
+                .headers()
+                .contains_key("access-control-allow-origin")
+        );
+        // Apps without a browser name themselves through `originator`
+        let native = reqwest::Client::new()
+            .post(format!("{}/getNetwork", base))
+            .header("originator", "native.example")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(native.status().as_u16(), 200);
+        let (_, network) = call(&base, Some(APP), "getNetwork", json!({})).await;
+        assert_eq!(network["network"], "mainnet");
+
+        interface.permissions().revoke(user_id, APP).unwrap();
+        assert!(interface.permissions().list(user_id).unwrap().is_empty());
+    }
+
+    async fn post(url: &str, body: Value) -> (u16, Value) {
+        let response = reqwest::Client::new()
+            .post(url)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        let status = response.status().as_u16();
+        (status, response.json().await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_paymail_host() {
+        let storage = Arc::new(ZipStorage::new().unwrap());
+        let rustbus = None::<Arc<RustBusIntegrator>>;
+        let tx_manager = Arc::new(TransactionManager::new(Arc::clone(&storage), rustbus));
+        let wallet =
+            WalletManager::new(Arc::clone(&storage), Arc::clone(&tx_manager), None).unwrap();
+        let paymail = PaymailManager::new(Arc::clone(&storage));
+        let user_id = Uuid::new_v4();
+        let (handle, _) = paymail.create_default_alias(user_id, None).await.unwrap();
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        // The test client stands in for the reverse proxy
+        let host = PaymailHost::new(paymail.clone(), wallet.clone(), Arc::clone(&storage), &base)
+            .with_trusted_proxies(vec![IpAddr::from([127, 0, 0, 1])]);
+        let router = host
+            .router()
+            .into_make_service_with_connect_info::<SocketAddr>();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let client = reqwest::Client::new();
+        let document: Value = client
+            .get(format!("{}/.well-known/bsvalias", base))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let endpoint = |capability: &str, handle: &str| {
+            document["capabilities"][capability]
+                .as_str()
+                .unwrap()
+                .replace("{alias}@{domain.tld}", handle)
+        };
+
+        let pki: Value = client
+            .get(endpoint("pki", &handle))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let identity = hex::encode(wallet.identity_public_key().unwrap().to_bytes());
+        assert_eq!(pki["pubkey"], identity);
+
+        // Profiles fall back to the alias until the owner sets a name and avatar
+        let profile: Value = client
+            .get(endpoint("f12f968c92d6", &handle))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(profile["name"], handle.split('@').next().unwrap());
+        assert_eq!(profile["avatar"], "");
+        paymail
+            .set_profile(user_id, " Alice ", "https://example.com/alice.png")
+            .unwrap();
+        let profile: Value = client
+            .get(endpoint("f12f968c92d6", &handle))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(
+            profile,
+            json!({ "name": "Alice", "avatar": "https://example.com/alice.png" })
+        );
+        assert_eq!(
+            paymail.resolve_profile(&handle).await.unwrap().name,
+            "Alice"
+        );
+
+        // Every destination request hands out a fresh output
+        let mut outputs = Vec::new();
+        for _ in 0..2 {
+            let body: Value = client
+                .post(endpoint("paymentDestination", &handle))
+                .json(&json!({ "senderHandle": "alice@example.com", "amount": 1_000 }))
+                .send()
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+            outputs.push(body["output"].as_str().unwrap().to_string());
+        }
+        assert_ne!(outputs[0], outputs[1]);
+
+        // Destinations issued for an invoice ask for what it still owes, whatever the
+        // sender announces
+        let invoice = Invoice::new(
+            InvoiceAmount::Satoshis(2_000),
+            2_000,
+            "Lunch",
+            InvoiceDestination::Paymail {
+                handle: handle.clone(),
+            },
+            chrono::Utc::now().timestamp(),
+            None,
+        );
+        let other = Invoice::new(
+            InvoiceAmount::Satoshis(2_000),
+            2_000,
+            "Dinner",
+            InvoiceDestination::Paymail {
+                handle: handle.clone(),
+            },
+            chrono::Utc::now().timestamp(),
+            None,
+        );
+        let invoices = Invoices::new(Arc::clone(&storage));
+        invoices.add(user_id, invoice.clone()).unwrap();
+        invoices.add(user_id, other.clone()).unwrap();
+        let destination: Value = client
+            .post(endpoint("2a40af698840", &handle))
+            .json(&json!({ "satoshis": 1, "invoice": invoice.reference }))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(destination["outputs"][0]["satoshis"], 2_000);
+        let (status, _) = post(
+            &endpoint("2a40af698840", &handle),
+            json!({ "satoshis": 1, "invoice": "INV-UNKNOWN" }),
+        )
+        .await;
+        assert_eq!(status, 404);
+        assert!(destination.get("derivationPrefix").is_none());
+        let reference = destination["reference"].as_str().unwrap().to_string();
+
+        // BRC-29 senders get a key derived between both identity keys, which they can
+        // check against the PKI answer
+        let sender_key = Crypto::generate_private_key().unwrap();
+        let private: Value = client
+            .post(endpoint("2a40af698840", &handle))
+            .json(&json!({
+                "satoshis": 500,
+                "senderIdentityKey": hex::encode(Crypto::derive_public_key(&sender_key).to_bytes()),
+            }))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(private["derivationPrefix"], private["reference"]);
+        let invoice_number = Crypto::payment_invoice_number(
+            private["derivationPrefix"].as_str().unwrap(),
+            private["derivationSuffix"].as_str().unwrap(),
+        );
+        let child = Crypto::derive_child_public_key(
+            &sender_key,
+            &wallet.identity_public_key().unwrap(),
+            &invoice_number,
+        )
+        .unwrap();
+        let expected = WalletManager::address_script(&Crypto::generate_address(&child)).unwrap();
+        assert_eq!(private["outputs"][0]["script"], hex::encode(&expected.0));
+        assert!(
+            wallet
+                .get_receive_addresses(user_id)
+                .unwrap()
+                .iter()
+                .any(|a| a.address == Crypto::generate_address(&child))
+        );
+        let (status, _) = post(
+            &endpoint("2a40af698840", &handle),
+            json!({ "satoshis": 500, "senderIdentityKey": "02abcd" }),
+        )
+        .await;
+        assert_eq!(status, 400);
+
+        // Senders pay the issued script from their own wallet, with coins mined in a
+        // block the header store knows
+        let sender = Uuid::new_v4();
+        let sender_key = Crypto::generate_private_key().unwrap();
+        let coin = WalletManager::address_script(&Crypto::generate_address(
+            &Crypto::derive_public_key(&sender_key),
+        ))
+        .unwrap();
+        let keys = SigningKeys::from([(coin.0.clone(), sender_key)]);
+        let parent = Transaction {
+            version: 1,
+            inputs: vec![TxIn {
+                previous_output: OutPoint {
+                    hash: Hash256([9; 32]),
+                    index: 0,
+                },
+                script: Script(vec![]),
+                sequence: 0xffff_ffff,
+            }],
+            outputs: vec![
+                TxOut {
+                    value: 10_000,
+                    script: coin.clone(),
+                };
+                2
+            ],
+            lock_time: 0,
+        };
+        let parent_txid = TransactionManager::txid(&parent);
+        let coins: Vec<Utxo> = (0..2)
+            .map(|vout| Utxo {
+                txid: parent_txid.clone(),
+                vout,
+                value: 10_000,
+                script: coin.0.clone(),
+            })
+            .collect();
+        tx_manager.add_utxos(sender, &coins).unwrap();
+        let headers = HeaderStore::new(Arc::clone(&storage));
+        let tip = headers.tip().unwrap();
+        let height = tip.as_ref().map_or(0, |tip| tip.height + 1);
+        let sibling = [7u8; 32];
+        let parent_leaf = Hash256::decode(&parent_txid).unwrap().0;
+        let merkle_root = sha256d(&[parent_leaf, sibling].concat()).encode();
+        headers
+            .connect(BlockHeader {
+                height,
+                hash: sha256d(merkle_root.as_bytes()).encode(),
+                prev_hash: tip.map(|tip| tip.hash).unwrap_or_default(),
+                merkle_root,
+                time: 0,
+            })
+            .unwrap();
+        let script =
+            Script(hex::decode(destination["outputs"][0]["script"].as_str().unwrap()).unwrap());
+        let short = tx_manager
+            .build_payment_tx(sender, script.clone(), 1_000, 200, &[], &keys)
+            .await
+            .unwrap();
+        let (status, _) = post(
+            &endpoint("5f1323cddf31", &handle),
+            json!({ "hex": TransactionManager::to_hex(&short), "reference": reference }),
+        )
+        .await;
+        assert_eq!(status, 400);
+        let (status, _) = post(
+            &endpoint("5f1323cddf31", &handle),
+            json!({ "hex": "0100", "reference": reference }),
+        )
+        .await;
+        assert_eq!(status, 400);
+
+        let tx = tx_manager
+            .build_payment_tx(sender, script, 2_000, 200, &[], &keys)
+            .await
+            .unwrap();
+        let txid = TransactionManager::txid(&tx);
+
+        // Nothing is credited for a transaction that cannot be verified: raw hex needs
+        // ARC, and a BEEF without its ancestry proves nothing
+        let mut unproven = BEEF_V1.to_le_bytes().to_vec();
+        unproven.extend_from_slice(&[0, 1]);
+        unproven.extend_from_slice(&TransactionManager::serialize(&tx));
+        unproven.push(0);
+        for body in [
+            json!({ "hex": TransactionManager::to_hex(&tx), "reference": reference }),
+            json!({ "beef": hex::encode(&unproven), "reference": reference }),
+        ] {
+            let (status, _) = post(&endpoint("5c55a7fdb7bb", &handle), body).await;
+            assert_eq!(status, 400);
+        }
+        assert!(wallet.get_history(user_id).unwrap().is_empty());
+        assert_eq!(
+            invoices.list(user_id).unwrap()[0].status,
+            InvoiceStatus::Open
+        );
+
+        // BEEF with the parent and its Merkle path (BRC-74) against the stored header
+        let mut beef = BEEF_V1.to_le_bytes().to_vec();
+        beef.push(1);
+        beef.push(0xfe);
+        beef.extend_from_slice(&(height as u32).to_le_bytes());
+        beef.extend_from_slice(&[1, 2, 0, 2]);
+        beef.extend_from_slice(&parent_leaf);
+        beef.extend_from_slice(&[1, 0]);
+        beef.extend_from_slice(&sibling);
+        beef.push(2);
+        beef.extend_from_slice(&TransactionManager::serialize(&parent));
+        beef.extend_from_slice(&[1, 0]);
+        beef.extend_from_slice(&TransactionManager::serialize(&tx));
+        beef.push(0);
+        // The sender's note does not pick the invoice; the destination does
+        let metadata = json!({ "sender": "alice@example.com", "note": other.reference });
+        let (status, received) = post(
+            &endpoint("5c55a7fdb7bb", &handle),
+            json!({ "beef": hex::encode(&beef), "reference": reference, "metadata": metadata }),
+        )
+        .await;
+        assert_eq!(status, 200);
+        assert_eq!(received["txid"], txid);
+
+        // Recorded, spendable, announced and matched to the invoice
+        let history = wallet.get_history(user_id).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(
+            (
+                &history[0].direction,
+                history[0].amount,
+                history[0].counterparty.as_str()
+            ),
+            (&TxDirection::Received, 2_000, "alice@example.com")
+        );
+        assert!(
+            tx_manager
+                .get_cached_utxos(user_id)
+                .unwrap()
+                .iter()
+                .any(|u| u.txid == txid && u.value == 2_000)
+        );
+        assert_eq!(wallet.take_alerts(user_id).unwrap().len(), 1);
+        let paid = invoices.list(user_id).unwrap();
+        let status = |reference: &str| {
+            paid.iter()
+                .find(|i| i.reference == reference)
+                .map(|i| i.status.clone())
+        };
+        assert_eq!(status(&invoice.reference), Some(InvoiceStatus::Paid));
+        assert_eq!(status(&other.reference), Some(InvoiceStatus::Open));
+        let destinations = host.destinations(user_id).unwrap();
+        assert_eq!(destinations.len(), 4);
+        assert_eq!(
+            destinations
+                .iter()
+                .find(|d| d.reference == reference)
+                .unwrap()
+                .txid,
+            Some(txid.clone())
+        );
+
+        // Resubmitting the same transaction is harmless
+        let (status, received) = post(
+            &endpoint("5f1323cddf31", &handle),
+            json!({ "hex": TransactionManager::to_hex(&tx), "reference": reference }),
+        )
+        .await;
+        assert_eq!(
+            (status, received["txid"].as_str()),
+            (200, Some(txid.as_str()))
+        );
+        assert_eq!(wallet.get_history(user_id).unwrap().len(), 1);
+
+        let unknown = format!("nobody@{}", paymail.domain());
+        let status = client
+            .get(endpoint("pki", &unknown))
+            .send()
+            .await
+            .unwrap()
+            .status();
+        assert_eq!(status.as_u16(), 404);
+
+        // Each client address gets a limited number of destination requests
+        let request = |client_ip: String| {
+            client
+                .post(endpoint("2a40af698840", &handle))
+                .header("x-forwarded-for", format!("198.51.100.1, {}", client_ip))
+                .json(&json!({ "satoshis": 100 }))
+                .send()
+        };
+        for _ in 0..30 {
+            let response = request("203.0.113.7".to_string()).await.unwrap();
+            assert_eq!(response.status().as_u16(), 200);
+        }
+        let response = request("203.0.113.7".to_string()).await.unwrap();
+        assert_eq!(response.status().as_u16(), 429);
+        let response = request("203.0.113.8".to_string()).await.unwrap();
+        assert_eq!(response.status().as_u16(), 200);
+
+        // Destination requests cannot use up the alias's allowance for payments
+        let mut exhausted = false;
+        for i in 0..100 {
+            let response = request(format!("192.0.2.{}", i / 20)).await.unwrap();
+            if response.status().as_u16() == 429 {
+                exhausted = true;
+                break;
+            }
+        }
+        assert!(exhausted);
+        let (status, _) = post(
+            &endpoint("5f1323cddf31", &handle),
+            json!({ "hex": TransactionManager::to_hex(&tx), "reference": reference }),
+        )
+        .await;
+        assert_eq!(status, 200);
+
+        // Without a trusted proxy in front, X-Forwarded-For names nobody
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let direct = format!("http://{}", listener.local_addr().unwrap());
+        let router = PaymailHost::new(paymail.clone(), wallet.clone(), Arc::clone(&storage), &base)
+            .router()
+            .into_make_service_with_connect_info::<SocketAddr>();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        let url = endpoint("2a40af698840", &handle).replace(&base, &direct);
+        for i in 0..31 {
+            let status = client
+                .post(&url)
+                .header("x-forwarded-for", format!("203.0.113.{}", i))
+                .json(&json!({ "satoshis": 100 }))
+                .send()
+                .await
+                .unwrap()
+                .status();
+            assert_eq!(status.as_u16(), if i < 30 { 200 } else { 429 });
+        }
+
+        // A client holding too many unpaid destinations gets no more, and nobody's
+        // destinations are dropped to make room
+        let before = host.destinations(user_id).unwrap();
+        for _ in 0..MAX_OPEN_DESTINATIONS {
+            host.issue_destination("198.51.100.9", &handle, 100, None, None)
+                .unwrap();
+        }
+        assert!(matches!(
+            host.issue_destination("198.51.100.9", &handle, 100, None, None),
+            Err(ZipError::RateLimit(_))
+        ));
+        host.issue_destination("198.51.100.10", &handle, 100, None, None)
+            .unwrap();
+        let destinations = host.destinations(user_id).unwrap();
+        assert_eq!(destinations.len(), before.len() + MAX_OPEN_DESTINATIONS + 1);
+        assert!(
+            before
+                .iter()
+                .all(|kept| destinations.iter().any(|d| d.reference == kept.reference))
+        );
+
+        // Unpaid destinations expire; paid ones never do
+        let open = destinations.iter().find(|d| d.txid.is_none()).unwrap();
+        assert!(!open.is_expired(open.created_at + DESTINATION_TTL_SECS));
+        assert!(open.is_expired(open.created_at + DESTINATION_TTL_SECS + 1));
+        let paid = destinations
+            .iter()
+            .find(|d| d.reference == reference)
+            .unwrap();
+        assert!(!paid.is_expired(paid.created_at + DESTINATION_TTL_SECS + 1));
+    }
+}
@@ -4,6 +4,7 @@ use uuid::Uuid;
 
 use crate::auth::{AuthManager, Session};
 use crate::blockchain::{PaymailManager, TransactionManager, WalletManager};
+use crate::errors::ZipError;
 use crate::integrations::RustBusIntegrator;
 use crate::storage::ZipStorage;
 use crate::ui::components::{
@@ -145,15 +146,18 @@ mod tests {
             .await
             .unwrap();
         wallet
-            .send_payment(user_id, script, satoshis, 1000)
+            .send_payment(user_id, script, satoshis, 1000, &[])
             .await
             .unwrap();
-        paymail.confirm_alias(user_id, &alias).await.unwrap();
+        // A payment without the purchase reference does not pay for the alias
+        assert!(matches!(
+            paymail.confirm_alias(&wallet, user_id, &alias).await,
+            Err(ZipError::Blockchain(_))
+        ));
 
         let app =
             VirtualDom::new_with_props(AppRouter, |c| c.with_context(wallet).with_context(paymail));
         let html = app.render_to_string();
-        assert!(html.contains("54321@zip.io"));
         assert!(html.contains("Pay 10 USD"));
     }
 
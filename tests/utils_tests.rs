@@ -1,8 +1,12 @@
 use std::error::Error as StdError;
+use sv::network::Network;
 
 use crate::config::env::EnvConfig;
 use crate::errors::ZipError;
+use crate::utils::crypto::Crypto;
 use crate::utils::error::format_zip_error;
+use crate::utils::payment_uri::{ParsedPayment, PaymentTarget, PaymentUri, format_bsv, parse_bsv};
+use crate::utils::qr::Qr;
 use crate::utils::telemetry::Telemetry;
 
 #[cfg(test)]
@@ -139,4 +143,151 @@ mod tests {
         // Verify via mock endpoint (wiremock in full test)
         env::remove_var("TELEMETRY_ENDPOINT");
     }
+
+    #[test]
+    fn test_payment_uri_encoding() {
+        let uri = PaymentUri::bitcoin("1BoatSLRHtKNngkdXEeobR76b53LETtpyT")
+            .with_amount(150_000)
+            .with_label("Coffee & cake");
+        assert_eq!(
+            uri.to_string(),
+            "bitcoin:1BoatSLRHtKNngkdXEeobR76b53LETtpyT?amount=0.0015&label=Coffee%20%26%20cake"
+        );
+        let uri = PaymentUri::paymail("alice@zip.io")
+            .with_amount(0)
+            .with_label("");
+        assert_eq!(uri.to_string(), "pay:alice@zip.io");
+        assert_eq!(format_bsv(100_000_000), "1");
+        assert_eq!(format_bsv(1), "0.00000001");
+    }
+
+    #[test]
+    fn test_qr_svg() {
+        let svg = Qr::svg("pay:alice@zip.io", 200).unwrap();
+        assert!(svg.contains("<svg"));
+    }
+
+    #[test]
+    fn test_parse_payment_inputs() {
+        let parsed = ParsedPayment::parse(
+            "bitcoin:1BoatSLRHtKNngkdXEeobR76b53LETtpyT?amount=0.0015&message=Coffee%20%26%20cake",
+        )
+        .unwrap();
+        assert_eq!(
+            parsed.target,
+            PaymentTarget::Address("1BoatSLRHtKNngkdXEeobR76b53LETtpyT".to_string())
+        );
+        assert_eq!(parsed.amount, Some(150_000));
+        assert_eq!(parsed.memo.as_deref(), Some("Coffee & cake"));
+        assert!(parsed.validate_network(Network::Mainnet).is_ok());
+
+        let parsed =
+            ParsedPayment::parse("bitcoin:?r=https://merchant.example/invoice/42").unwrap();
+        assert_eq!(
+            parsed.target,
+            PaymentTarget::PaymentRequest("https://merchant.example/invoice/42".to_string())
+        );
+        let parsed = ParsedPayment::parse("https://merchant.example/invoice/42").unwrap();
+        assert!(matches!(parsed.target, PaymentTarget::PaymentRequest(_)));
+
+        let parsed = ParsedPayment::parse("$Alice").unwrap();
+        assert_eq!(
+            parsed.target,
+            PaymentTarget::Paymail("alice@handcash.io".to_string())
+        );
+        let parsed = ParsedPayment::parse(" Bob@Zip.io ").unwrap();
+        assert_eq!(
+            parsed.target,
+            PaymentTarget::Paymail("bob@zip.io".to_string())
+        );
+        let parsed = ParsedPayment::parse("pay:bob@zip.io?amount=1").unwrap();
+        assert_eq!(parsed.amount, Some(100_000_000));
+
+        assert!(
+            ParsedPayment::parse("bitcoin:1BoatSLRHtKNngkdXEeobR76b53LETtpyT?req-foo=1").is_err()
+        );
+        assert!(ParsedPayment::parse("not a recipient").is_err());
+
+        let testnet = ParsedPayment::parse("mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn").unwrap();
+        assert!(testnet.validate_network(Network::Mainnet).is_err());
+        assert!(testnet.validate_network(Network::Testnet).is_ok());
+
+        assert_eq!(parse_bsv(".5").unwrap(), 50_000_000);
+        assert!(parse_bsv("0.000000001").is_err());
+        assert!(parse_bsv("1e3").is_err());
+    }
+
+    #[test]
+    fn test_bitcoin_signed_message() {
+        let key = Crypto::generate_private_key().unwrap();
+        let address = Crypto::generate_address(&Crypto::derive_public_key(&key));
+        let message = "I own this address";
+        let signature = Crypto::sign_bitcoin_message(&key, message).unwrap();
+        // 65 bytes with a compressed-key header (31-34)
+        let bytes =
+            base64::Engine::decode(&base64::engine::general_purpose::STANDARD, &signature).unwrap();
+        assert_eq!(bytes.len(), 65);
+        assert!((31..=34).contains(&bytes[0]));
+        assert!(Crypto::verify_bitcoin_message(&address, message, &signature).unwrap());
+        assert!(
+            !Crypto::verify_bitcoin_message(&address, "I own this address!", &signature).unwrap()
+        );
+
+        let other = Crypto::generate_address(&Crypto::derive_public_key(
+            &Crypto::generate_private_key().unwrap(),
+        ));
+        assert!(!Crypto::verify_bitcoin_message(&other, message, &signature).unwrap());
+        assert!(Crypto::verify_bitcoin_message(&address, message, "not base64!").is_err());
+        assert_ne!(Crypto::message_hash("a"), sv::util::sha256d(b"a").0);
+    }
+
+    #[test]
+    fn test_bitcoin_signed_message_known_answer() {
+        // Published bitcoinjs-message vector (WIF L4rK1yDtCWekvXuE6oXD9jCYfFNV2cWRpVuPLBcCU2z8TrisoyY1);
+        // RFC 6979 nonces make the signature byte-for-byte reproducible
+        let key = secp256k1::SecretKey::from_slice(
+            &hex::decode("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+                .unwrap(),
+        )
+        .unwrap();
+        let address = "1F3sAm6ZtwLAUnj7d38pGFxtP3RVEvtsbV";
+        let message = "This is an example of a signed message.";
+        let signature = "H9L5yLFjti0QTHhPyFrZCT1V/MMnBtXKmoiKDZ78NDBjERki6ZTQZdSMCtkgoNmp17By9ItJr8o7ChX0XxY91nk=";
+        assert_eq!(
+            Crypto::generate_address(&Crypto::derive_public_key(&key)),
+            address
+        );
+        assert_eq!(
+            Crypto::sign_bitcoin_message(&key, message).unwrap(),
+            signature
+        );
+        assert!(Crypto::verify_bitcoin_message(address, message, signature).unwrap());
+    }
+
+    #[test]
+    fn test_bie1_encrypted_messages() {
+        let key = Crypto::generate_private_key().unwrap();
+        let public_key = Crypto::derive_public_key(&key);
+        let memo = "Rent for March, flat 4B";
+        let encrypted = Crypto::encrypt_message(&public_key, memo).unwrap();
+        assert!(encrypted.starts_with("QklFMQ")); // base64 of "BIE1"
+        assert!(Crypto::is_encrypted_message(&encrypted));
+        assert!(!Crypto::is_encrypted_message(memo));
+        assert_eq!(Crypto::decrypt_message(&key, &encrypted).unwrap(), memo);
+        // Fresh ephemeral key every time
+        assert_ne!(
+            Crypto::encrypt_message(&public_key, memo).unwrap(),
+            encrypted
+        );
+
+        let other = Crypto::generate_private_key().unwrap();
+        assert!(matches!(
+            Crypto::decrypt_message(&other, &encrypted),
+            Err(ZipError::Crypto(_))
+        ));
+        let mut tampered = Crypto::encrypt_bie1(&public_key, memo.as_bytes()).unwrap();
+        tampered[40] ^= 1;
+        assert!(Crypto::decrypt_bie1(&key, &tampered).is_err());
+        assert!(Crypto::decrypt_bie1(&key, b"BIE1").is_err());
+    }
 }